@@ -0,0 +1,100 @@
+//! `--alt-report`: audit how a recorded file's txs resolve their account keys.
+//!
+//! One jsonl line per tx with its `TxFacts::resolution_report`, so fixtures
+//! and pulls can be checked for actually exercising the lookup-table path.
+
+use anyhow::{Result, anyhow};
+use log::warn;
+use schema::{KeyResolutionReport, TxFacts};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+
+#[derive(Debug, Serialize)]
+struct ReportLine<'a> {
+    signature: &'a str,
+    #[serde(flatten)]
+    report: KeyResolutionReport,
+}
+
+/// Write one report line per recorded tx; returns (txs, txs that needed an ALT)
+pub fn write_alt_report(records: impl BufRead, mut out: impl Write) -> Result<(usize, usize)> {
+    let (mut txs, mut alt_required) = (0usize, 0usize);
+
+    for line in records.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let v: Value = serde_json::from_str(&line)?;
+        let sig = v.get("signature").and_then(|x| x.as_str()).unwrap_or("");
+        let tx = v.get("tx").ok_or_else(|| anyhow!("missing tx field"))?;
+        if sig.is_empty() {
+            warn!("alt-report: skipping record without signature");
+            continue;
+        }
+
+        let slot = tx.get("slot").and_then(|v| v.as_u64()).unwrap_or(0);
+        let report = TxFacts::from_json(tx, sig, slot).resolution_report();
+        txs += 1;
+        if report.alt_required {
+            alt_required += 1;
+        }
+        let line = ReportLine {
+            signature: sig,
+            report,
+        };
+        writeln!(out, "{}", serde_json::to_string(&line)?)?;
+    }
+
+    out.flush()?;
+    Ok((txs, alt_required))
+}
+
+/// `--from-file <jsonl> --alt-report`: report to stdout
+pub fn print_alt_report(jsonl_path: &Path) -> Result<(usize, usize)> {
+    let records = BufReader::new(File::open(jsonl_path)?);
+    write_alt_report(records, std::io::stdout().lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alt_report_flags_programs_from_lookup_tables() {
+        let fixture = |name: &str| -> Value {
+            let path = format!(
+                "{}/../../crates/schema/tests/fixtures/{name}.json",
+                env!("CARGO_MANIFEST_DIR")
+            );
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+        };
+        let jsonl = format!(
+            "{}\n{}\n",
+            serde_json::json!({"signature": "SigV0", "tx": fixture("v0_raydium_swap_full")}),
+            serde_json::json!({"signature": "SigLegacy", "tx": fixture("legacy_raydium_swap_full")}),
+        );
+
+        let mut out = Vec::new();
+        assert_eq!(write_alt_report(jsonl.as_bytes(), &mut out).unwrap(), (2, 1));
+
+        let lines: Vec<Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines[0]["signature"], "SigV0");
+        assert_eq!(lines[0]["static_keys"], 4);
+        assert_eq!(lines[0]["loaded_writable"], 2);
+        assert_eq!(lines[0]["loaded_readonly"], 2);
+        assert_eq!(lines[0]["alt_required"], true);
+        assert!(!lines[0]["loaded_programs"].as_array().unwrap().is_empty());
+        assert_eq!(lines[1]["alt_required"], false);
+    }
+}
@@ -24,17 +24,71 @@ pub struct Cli {
     #[arg(long)]
     pub from_file: Option<PathBuf>,
 
+    /// Replay mode: publish at most this many recorded txs per second
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub replay_rate: Option<u32>,
+
+    /// Replay mode: stop after publishing this many recorded txs
+    #[arg(long)]
+    pub replay_limit: Option<usize>,
+
+    /// With --from-file: write the recorded txs' Raydium v4 swaps to this CSV
+    /// instead of publishing anything (no Kafka needed)
+    #[arg(long)]
+    pub export_swaps: Option<PathBuf>,
+
+    /// With --from-file: print each recorded tx's account key resolution (static
+    /// vs lookup-table keys) as jsonl instead of publishing anything
+    #[arg(long, default_value_t = false)]
+    pub alt_report: bool,
+
+    /// Per-signature backfill: stop paging at signatures older than this slot
+    #[arg(long)]
+    pub since_slot: Option<u64>,
+
+    /// Per-signature backfill: skip signatures newer than this slot
+    #[arg(long)]
+    pub until_slot: Option<u64>,
+
+    /// Before fetching, skip signatures already recorded in --out (and in
+    /// --dlq-file, if given) by a previous run
+    #[arg(long, default_value_t = false)]
+    pub resume: bool,
+
+    /// Also append DLQ events to this jsonl file (per-signature backfill)
+    #[arg(long)]
+    pub dlq_file: Option<PathBuf>,
+
     /// Concurrency for getTransaction calls
     #[arg(long, default_value_t = 8)]
     pub concurrency: usize,
+
+    /// Run the swap detectors and emit DexSwapV1 to KAFKA_SWAPS_TOPIC instead of RawTxEvent
+    #[arg(long, default_value_t = false)]
+    pub decode_swaps: bool,
+
+    /// Page whole blocks with getBlock over --start-slot..=--end-slot instead of
+    /// one getTransaction per signature
+    #[arg(long, default_value_t = false)]
+    pub by_block: bool,
+
+    /// First slot of the --by-block range
+    #[arg(long)]
+    pub start_slot: Option<u64>,
+
+    /// Last slot (inclusive) of the --by-block range
+    #[arg(long)]
+    pub end_slot: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub rpc_url: String,
+    pub rpc_max_backoff_ms: u64,
     pub kafka_broker: String,
     pub kafka_topic: String,
     pub dlq_topic: String,
+    pub swaps_topic: String,
     pub chain: String,
 }
 
@@ -45,9 +99,16 @@ pub fn load(cli: &Cli) -> Result<Config> {
         .or_else(|| env::var("RPC_URL").ok())
         .unwrap_or_else(|| "https://api.mainnet-beta.solana.com".to_string());
 
+    // Cap for exponential retry backoff (client retries and 429 re-fetches)
+    let rpc_max_backoff_ms = env::var("RPC_MAX_BACKOFF_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(5000);
+
     let kafka_broker = env::var("KAFKA_BROKER").unwrap_or_else(|_| "127.0.0.1:19092".to_string());
     let kafka_topic = env::var("KAFKA_TOPIC").unwrap_or_else(|_| "sol_raw_txs".to_string());
     let dlq_topic = env::var("KAFKA_DLQ_TOPIC").unwrap_or_else(|_| "sol_raw_txs_dlq".to_string());
+    let swaps_topic = env::var("KAFKA_SWAPS_TOPIC").unwrap_or_else(|_| "sol_swaps_v2".to_string());
 
     // keep consistent with your existing schema
     let chain = env::var("CHAIN").unwrap_or_else(|_| "solana-mainnet".to_string());
@@ -58,12 +119,67 @@ pub fn load(cli: &Cli) -> Result<Config> {
             "Choose a mode: either --out <file> (backfill/record) or --from-file <file> (replay)"
         ));
     }
+    if cli.export_swaps.is_some() && cli.from_file.is_none() {
+        return Err(anyhow!(
+            "--export-swaps reads a recorded file: use it with --from-file <file>"
+        ));
+    }
+    if cli.alt_report && cli.from_file.is_none() {
+        return Err(anyhow!(
+            "--alt-report reads a recorded file: use it with --from-file <file>"
+        ));
+    }
+    if (cli.replay_rate.is_some() || cli.replay_limit.is_some())
+        && (cli.from_file.is_none() || cli.export_swaps.is_some() || cli.alt_report)
+    {
+        return Err(anyhow!(
+            "--replay-rate and --replay-limit pace a replay: use them with --from-file \
+             and without --export-swaps or --alt-report"
+        ));
+    }
+    if (cli.resume || cli.dlq_file.is_some()) && (cli.from_file.is_some() || cli.by_block) {
+        return Err(anyhow!(
+            "--resume and --dlq-file apply to the per-signature backfill: use them with --out \
+             and without --by-block or --from-file"
+        ));
+    }
+    if (cli.since_slot.is_some() || cli.until_slot.is_some())
+        && (cli.from_file.is_some() || cli.by_block)
+    {
+        return Err(anyhow!(
+            "--since-slot and --until-slot window the per-signature backfill: use them with \
+             --out and without --by-block or --from-file"
+        ));
+    }
+    if let (Some(since), Some(until)) = (cli.since_slot, cli.until_slot)
+        && since > until
+    {
+        return Err(anyhow!(
+            "--since-slot must be <= --until-slot, got {}..={}",
+            since,
+            until
+        ));
+    }
+    if cli.by_block {
+        match (cli.start_slot, cli.end_slot) {
+            (Some(start), Some(end)) if start <= end => {}
+            _ => {
+                return Err(anyhow!(
+                    "--by-block needs --start-slot <= --end-slot, got {:?}..={:?}",
+                    cli.start_slot,
+                    cli.end_slot
+                ));
+            }
+        }
+    }
 
     Ok(Config {
         rpc_url,
+        rpc_max_backoff_ms,
         kafka_broker,
         kafka_topic,
         dlq_topic,
+        swaps_topic,
         chain,
     })
 }
@@ -1,12 +1,16 @@
 use anyhow::Result;
 use clap::Parser;
 use log::info;
+use std::collections::HashSet;
 
+mod alt_report;
 mod config;
 mod kafka;
 mod pipeline;
 mod replay;
+mod resume;
 mod rpc;
+mod swaps;
 mod types;
 
 fn setup_logging() {
@@ -22,6 +26,24 @@ async fn main() -> Result<()> {
     let cli = config::Cli::parse();
     let cfg = config::load(&cli)?;
 
+    // CSV export: offline conversion of a recorded file, no Kafka involved
+    if let (Some(csv), Some(from)) = (&cli.export_swaps, &cli.from_file) {
+        info!("mode: export-swaps {} -> {}", from.display(), csv.display());
+        let n = swaps::export_swaps_csv(from, csv, &cfg.chain)?;
+        info!("exported {} swaps to {}", n, csv.display());
+        return Ok(());
+    }
+
+    // Key resolution audit of a recorded file, no Kafka involved
+    if let (true, Some(from)) = (cli.alt_report, &cli.from_file) {
+        let (txs, alt_required) = alt_report::print_alt_report(from)?;
+        info!(
+            "alt-report: {} of {} txs invoke a program through a lookup table",
+            alt_required, txs
+        );
+        return Ok(());
+    }
+
     let producer = kafka::create_producer(&cfg.kafka_broker)?;
 
     // Ensure data dir exists if using --out data/...
@@ -33,40 +55,88 @@ async fn main() -> Result<()> {
     info!("using rpc_url={}", cfg.rpc_url);
 
     info!(
-        "mode: {}",
+        "mode: {}{}",
         if cli.from_file.is_some() {
             "replay"
+        } else if cli.by_block {
+            "backfill (by-block)"
         } else {
             "backfill"
-        }
+        },
+        if cli.decode_swaps { " (decode-swaps)" } else { "" }
     );
 
+    // Decode-only: emit DexSwapV1 to the swaps topic instead of RawTxEvent
+    let swaps_topic = cli.decode_swaps.then_some(cfg.swaps_topic.as_str());
+
     if let Some(from) = cli.from_file {
         replay::replay_file(
             &producer,
             &cfg.kafka_topic,
             &cfg.dlq_topic,
             &cfg.chain,
+            swaps_topic,
             &from,
+            replay::ReplayLimits {
+                rate: cli.replay_rate,
+                limit: cli.replay_limit,
+            },
         )
         .await?;
         return Ok(());
     }
 
     // backfill/record mode
-    let rpc = rpc::RpcClient::new(cfg.rpc_url.clone());
+    let rpc = rpc::RpcClient::new(cfg.rpc_url.clone(), cfg.rpc_max_backoff_ms);
 
     let out = cli.out.expect("--out required in backfill mode");
+    if cli.by_block {
+        // Range validated in config::load
+        pipeline::backfill_by_block(
+            &rpc,
+            &producer,
+            &cfg.kafka_topic,
+            &cfg.dlq_topic,
+            &cfg.chain,
+            swaps_topic,
+            &cli.address,
+            cli.start_slot.unwrap_or_default()..=cli.end_slot.unwrap_or_default(),
+            cli.concurrency,
+            &out,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    // Signatures a previous run recorded or dead-lettered are not refetched
+    let skip = if cli.resume {
+        let mut skip = resume::recorded_signatures(&out)?;
+        if let Some(dlq_file) = &cli.dlq_file {
+            skip.extend(resume::recorded_signatures(dlq_file)?);
+        }
+        info!("resume: {} signatures already handled", skip.len());
+        skip
+    } else {
+        HashSet::new()
+    };
+
     pipeline::backfill_record(
         &rpc,
         &producer,
         &cfg.kafka_topic,
         &cfg.dlq_topic,
         &cfg.chain,
+        swaps_topic,
         &cli.address,
         cli.limit,
+        pipeline::SlotWindow {
+            since: cli.since_slot,
+            until: cli.until_slot,
+        },
         cli.concurrency,
         &out,
+        cli.dlq_file.as_deref(),
+        &skip,
     )
     .await?;
 
@@ -1,56 +1,118 @@
 use crate::{
     kafka,
     rpc::RpcClient,
+    swaps,
     types::{DlqEvent, RawTxEvent},
 };
 use anyhow::{Result, anyhow};
+use decoder::retry::{RetryError, RetryPolicy, retry};
+use decoder::rpc_error::RpcError;
 use futures::{StreamExt, stream};
 use log::{info, warn};
 use rdkafka::producer::FutureProducer;
 use serde_json::{Value, json};
 use std::{
-    collections::hash_map::DefaultHasher,
+    collections::HashSet,
     fs::OpenOptions,
-    hash::{Hash, Hasher},
     io::Write,
+    ops::RangeInclusive,
     path::Path,
+    sync::atomic::Ordering,
     time::Duration,
 };
-use tokio::time::sleep;
 
 // Import ALT-aware helpers from schema crate
-use schema::{extract_program_ids_from_transaction, pick_main_program};
+use schema::{ChainPrograms, extract_program_ids_from_transaction, resolve_full_account_keys};
 
 // Note: extract_program_ids_from_tx and pick_main_program moved to schema crate
 // to support Address Lookup Table (ALT) resolution for v0 transactions.
 
-fn is_rate_limited_429(err_dbg: &str) -> bool {
-    // Your logs show: "status=429 Too Many Requests"
-    err_dbg.contains("status=429")
-        || err_dbg.contains("Too Many Requests")
-        || err_dbg.contains("\"code\":429")
+/// Message recentBlockhash from a getTransaction response (json or jsonParsed)
+pub fn extract_recent_blockhash(tx: &Value) -> Option<String> {
+    tx.pointer("/transaction/message/recentBlockhash")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
 }
 
-fn jitter_ms(sig: &str, attempt: usize) -> u64 {
-    // deterministic jitter (no extra deps)
-    let mut h = DefaultHasher::new();
-    sig.hash(&mut h);
-    attempt.hash(&mut h);
-    h.finish() % 200 // 0..199ms
+/// getBlocks rejects ranges wider than this many slots
+const GET_BLOCKS_MAX_RANGE: u64 = 500_000;
+
+/// RawTxEvent for a getTransaction-shaped response
+fn raw_tx_event(
+    tx: &Value,
+    sig: &str,
+    chain: &str,
+    programs: &ChainPrograms,
+    index_in_block: u32,
+) -> RawTxEvent {
+    // Use ALT-aware extraction from schema crate
+    let program_ids = extract_program_ids_from_transaction(tx);
+    let main_program = programs.pick_main_program(&program_ids);
+
+    RawTxEvent {
+        schema_version: 1,
+        chain: chain.to_string(),
+        slot: tx.get("slot").and_then(|v| v.as_u64()).unwrap_or(0),
+        block_time: tx.get("blockTime").and_then(|v| v.as_i64()),
+        signature: sig.to_string(),
+        index_in_block,
+        tx_version: None,
+        is_success: tx.pointer("/meta/err").is_none(),
+        fee_lamports: tx
+            .pointer("/meta/fee")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0),
+        compute_units_consumed: None,
+        main_program,
+        program_ids,
+        recent_blockhash: extract_recent_blockhash(tx),
+    }
 }
 
+/// Transactions of a getBlock response (`transactionDetails=full`) whose
+/// accounts, lookup-table addresses included, contain `address`.
+///
+/// Each is reshaped like a getTransaction response (`slot` and `blockTime`
+/// added) and paired with its position in the block.
+pub fn block_transactions_for_address(
+    block: &Value,
+    slot: u64,
+    address: &str,
+) -> Vec<(u32, Value)> {
+    let Some(txs) = block.get("transactions").and_then(|v| v.as_array()) else {
+        return vec![];
+    };
+    let block_time = block.get("blockTime").cloned().unwrap_or(Value::Null);
+
+    txs.iter()
+        .enumerate()
+        .filter(|(_, tx)| tx.is_object())
+        .filter_map(|(index, tx)| {
+            let mut tx = tx.clone();
+            tx["slot"] = json!(slot);
+            tx["blockTime"] = block_time.clone();
+            resolve_full_account_keys(&tx)
+                .iter()
+                .any(|k| k == address)
+                .then_some((index as u32, tx))
+        })
+        .collect()
+}
+
+/// Re-fetch a transaction while the client keeps failing with 429s; returns it
+/// with the number of 429 re-fetches
 async fn get_transaction_with_retry(
     rpc: &RpcClient,
     sig: &str,
-    max_retries: usize,
-    mut backoff: Duration,
-    max_backoff: Duration,
+    policy: &RetryPolicy,
 ) -> Result<(Value, usize)> {
     let mut retries_429 = 0usize;
 
-    for attempt in 0..=max_retries {
-        let res = rpc
-            .call(
+    let tx = retry(
+        policy,
+        |_| async {
+            rpc.call(
                 "getTransaction",
                 json!([
                     sig,
@@ -60,36 +122,65 @@ async fn get_transaction_with_retry(
                     }
                 ]),
             )
-            .await;
-
-        match res {
-            Ok(v) => return Ok((v, retries_429)),
-            Err(e) => {
-                let dbg = format!("{e:?}");
-                let is_429 = is_rate_limited_429(&dbg);
-
-                if !is_429 || attempt == max_retries {
-                    return Err(anyhow!("{dbg}"));
+            .await
+            .map_err(|e| match e {
+                RpcError::RateLimited { retry_after: Some(delay) } => {
+                    RetryError::RetryAfter(e, delay.min(policy.max_backoff))
                 }
+                RpcError::RateLimited { retry_after: None } => RetryError::Transient(e),
+                e => RetryError::Permanent(e),
+            })
+        },
+        |attempt, _, sleep_for| {
+            retries_429 += 1;
+            warn!(
+                "rate-limited (429) sig={} attempt={} sleeping={:?}",
+                sig, attempt, sleep_for
+            );
+            rpc.record_backoff(sleep_for);
+        },
+    )
+    .await?;
 
-                retries_429 += 1;
-                let j = Duration::from_millis(jitter_ms(sig, attempt));
-                let sleep_for = (backoff + j).min(max_backoff);
+    Ok((tx, retries_429))
+}
 
-                warn!(
-                    "rate-limited (429) sig={} attempt={} sleeping={:?}",
-                    sig,
-                    attempt + 1,
-                    sleep_for
-                );
+/// Slot window of a per-signature backfill (`--since-slot` / `--until-slot`)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SlotWindow {
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+}
 
-                sleep(sleep_for).await;
-                backoff = (backoff * 2).min(max_backoff);
-            }
+/// Collect one `getSignaturesForAddress` page (newest first) into `signatures`,
+/// up to `limit`, skipping those after `window.until`. Returns false once the
+/// page reaches before `window.since`: every older page would too
+fn collect_signature_page(
+    page: &[Value],
+    window: SlotWindow,
+    limit: usize,
+    signatures: &mut Vec<String>,
+) -> bool {
+    for item in page {
+        let slot = item.get("slot").and_then(|v| v.as_u64());
+        if let (Some(since), Some(slot)) = (window.since, slot)
+            && slot < since
+        {
+            return false;
+        }
+        if let (Some(until), Some(slot)) = (window.until, slot)
+            && slot > until
+        {
+            continue;
+        }
+        if signatures.len() >= limit {
+            break;
+        }
+        if let Some(sig) = item.get("signature").and_then(|v| v.as_str()) {
+            signatures.push(sig.to_string());
         }
     }
-
-    unreachable!()
+    true
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -99,28 +190,42 @@ pub async fn backfill_record(
     kafka_topic: &str,
     dlq_topic: &str,
     chain: &str,
+    swaps_topic: Option<&str>,
     address: &str,
     limit: usize,
+    window: SlotWindow,
     concurrency: usize,
     out_path: &Path,
+    dlq_path: Option<&Path>,
+    skip: &HashSet<String>,
 ) -> Result<()> {
     let mut f = OpenOptions::new()
         .create(true)
         .append(true)
         .open(out_path)?;
+    let mut dlq_file = dlq_path
+        .map(|path| OpenOptions::new().create(true).append(true).open(path))
+        .transpose()?;
 
     info!(
         "backfill: address={} limit={} concurrency={} rpc={}",
         address, limit, concurrency, "public"
     );
     info!("recording raw tx responses to {}", out_path.display());
+    if window.since.is_some() || window.until.is_some() {
+        info!("slot window: since={:?} until={:?}", window.since, window.until);
+    }
 
     // Step A: page signatures
     let mut signatures: Vec<String> = Vec::with_capacity(limit);
     let mut before: Option<String> = None;
 
     while signatures.len() < limit {
-        let page_size = std::cmp::min(1000, limit - signatures.len());
+        // Full pages while skipping past --until-slot, where most may be dropped
+        let page_size = match window.until {
+            Some(_) => 1000,
+            None => std::cmp::min(1000, limit - signatures.len()),
+        };
 
         let mut opts = json!({ "limit": page_size });
         if let Some(b) = &before {
@@ -139,10 +244,10 @@ pub async fn backfill_record(
             break;
         }
 
-        for item in arr {
-            if let Some(sig) = item.get("signature").and_then(|v| v.as_str()) {
-                signatures.push(sig.to_string());
-            }
+        let in_window = collect_signature_page(arr, window, limit, &mut signatures);
+        if !in_window {
+            info!("collected signatures: {} (reached since-slot)", signatures.len());
+            break;
         }
 
         before = arr
@@ -154,22 +259,35 @@ pub async fn backfill_record(
         info!("collected signatures: {}", signatures.len());
     }
 
+    if !skip.is_empty() {
+        let before = signatures.len();
+        signatures.retain(|sig| !skip.contains(sig));
+        info!("resume: skipping {} already handled signatures", before - signatures.len());
+    }
+
     info!("fetching {} transactions…", signatures.len());
 
     // Step B: fetch transactions concurrently
     let rpc2 = rpc.clone();
+    let programs = ChainPrograms::for_chain(chain);
+    let registry = swaps::swap_registry(chain);
     let chain = chain.to_string();
 
     // counters (for visibility)
     let mut ok = 0usize;
     let mut err = 0usize;
     let mut retries_429_total = 0usize;
+    let mut swaps_emitted = 0usize;
     let mut logged_schema = false; // schema validation flag
 
-    // tune these if needed
-    let max_retries = 6usize;
-    let base_backoff = Duration::from_millis(250);
-    let max_backoff = Duration::from_secs(5);
+    // tune these if needed: 6 re-fetches after the first 429, jittered so
+    // concurrent fetches don't retry in lockstep
+    let policy = RetryPolicy {
+        max_attempts: 7,
+        base_backoff: Duration::from_millis(250),
+        max_backoff: rpc.max_backoff(),
+        jitter_ms: 200,
+    };
 
     let mut stream = stream::iter(signatures.into_iter())
         .map(move |sig| {
@@ -177,9 +295,7 @@ pub async fn backfill_record(
             let sig2 = sig.clone();
             let chain = chain.clone();
             async move {
-                let tx =
-                    get_transaction_with_retry(&rpc, &sig2, max_retries, base_backoff, max_backoff)
-                        .await;
+                let tx = get_transaction_with_retry(&rpc, &sig2, &policy).await;
                 (sig, chain, tx)
             }
         })
@@ -196,21 +312,10 @@ pub async fn backfill_record(
                 writeln!(f, "{line}")?;
 
                 // build RawTxEvent (best-effort)
-                let slot = tx.get("slot").and_then(|v| v.as_u64()).unwrap_or(0);
-                let block_time = tx.get("blockTime").and_then(|v| v.as_i64());
-
-                let fee = tx
-                    .pointer("/meta/fee")
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0);
-                let is_success = tx.pointer("/meta/err").is_none();
-
-                // Use ALT-aware extraction from schema crate
-                let program_ids = extract_program_ids_from_transaction(&tx);
-                let main_program = pick_main_program(&program_ids);
+                let event = raw_tx_event(&tx, &sig, &chain, &programs, 0);
 
                 // guard: never emit empty signature
-                if sig.is_empty() || slot == 0 {
+                if sig.is_empty() || event.slot == 0 {
                     let dlq = DlqEvent {
                         source: "backfill".to_string(),
                         step: "parse".to_string(),
@@ -218,24 +323,18 @@ pub async fn backfill_record(
                         error: "empty signature or slot=0".to_string(),
                     };
                     let j = serde_json::to_string(&dlq)?;
+                    if let Some(dlq_file) = dlq_file.as_mut() {
+                        writeln!(dlq_file, "{j}")?;
+                    }
                     kafka::send_json(producer, dlq_topic, None, &j).await?;
                     continue;
                 }
 
-                let event = RawTxEvent {
-                    schema_version: 1,
-                    chain,
-                    slot,
-                    block_time,
-                    signature: sig.clone(),
-                    index_in_block: 0,
-                    tx_version: None,
-                    is_success,
-                    fee_lamports: fee,
-                    compute_units_consumed: None,
-                    main_program,
-                    program_ids,
-                };
+                if let Some(swaps_topic) = swaps_topic {
+                    let found = swaps::decode_swaps(&registry, &tx, &sig, &chain);
+                    swaps_emitted += swaps::emit_swaps(producer, swaps_topic, &found).await?;
+                    continue;
+                }
 
                 let json_event = serde_json::to_string(&event)?;
 
@@ -262,6 +361,9 @@ pub async fn backfill_record(
                     error: format!("{e:?}"),
                 };
                 let j = serde_json::to_string(&dlq)?;
+                if let Some(dlq_file) = dlq_file.as_mut() {
+                    writeln!(dlq_file, "{j}")?;
+                }
                 kafka::send_json(producer, dlq_topic, None, &j).await?;
             }
         }
@@ -276,12 +378,249 @@ pub async fn backfill_record(
         }
     }
 
+    let rpc_stats = rpc.retry_stats();
     info!(
-        "backfill done. fetched={} ok={} err={} retries_429_total={}",
+        "backfill done. fetched={} ok={} err={} retries_429_total={} swaps_emitted={} rpc_retries={} rpc_backoff_ms={}",
         ok + err,
         ok,
         err,
-        retries_429_total
+        retries_429_total,
+        swaps_emitted,
+        rpc_stats.retries.load(Ordering::Relaxed),
+        rpc_stats.backoff_ms_total.load(Ordering::Relaxed)
+    );
+    Ok(())
+}
+
+/// Backfill `address` by paging whole blocks: one getBlock per produced slot in
+/// `slots` instead of one getTransaction per signature. Records and emits the
+/// same lines and events as `backfill_record`, with the real `index_in_block`.
+#[allow(clippy::too_many_arguments)]
+pub async fn backfill_by_block(
+    rpc: &RpcClient,
+    producer: &FutureProducer,
+    kafka_topic: &str,
+    dlq_topic: &str,
+    chain: &str,
+    swaps_topic: Option<&str>,
+    address: &str,
+    slots: RangeInclusive<u64>,
+    concurrency: usize,
+    out_path: &Path,
+) -> Result<()> {
+    let mut f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(out_path)?;
+
+    info!(
+        "backfill by block: address={} slots={}..={} concurrency={}",
+        address,
+        slots.start(),
+        slots.end(),
+        concurrency
+    );
+    info!("recording raw tx responses to {}", out_path.display());
+
+    // Step A: list produced slots (skipped slots have no block to fetch)
+    let mut produced: Vec<u64> = Vec::new();
+    let mut from = *slots.start();
+    while from <= *slots.end() {
+        let to = (*slots.end()).min(from.saturating_add(GET_BLOCKS_MAX_RANGE - 1));
+        let res = rpc
+            .call("getBlocks", json!([from, to]))
+            .await
+            .map_err(|e| anyhow!("getBlocks failed: {e:?}"))?;
+        let arr = res
+            .as_array()
+            .ok_or_else(|| anyhow!("unexpected getBlocks result"))?;
+        produced.extend(arr.iter().filter_map(|v| v.as_u64()));
+        info!("collected blocks: {}", produced.len());
+        if to == u64::MAX {
+            break;
+        }
+        from = to + 1;
+    }
+
+    info!("fetching {} blocks…", produced.len());
+
+    // Step B: fetch blocks concurrently, handled in slot order
+    let programs = ChainPrograms::for_chain(chain);
+    let registry = swaps::swap_registry(chain);
+    let mut blocks_ok = 0usize;
+    let mut blocks_err = 0usize;
+    let mut txs_matched = 0usize;
+    let mut swaps_emitted = 0usize;
+
+    let mut stream = stream::iter(produced)
+        .map(|slot| async move {
+            let params = json!([
+                slot,
+                {
+                    "encoding": "json",
+                    "transactionDetails": "full",
+                    "maxSupportedTransactionVersion": 0,
+                    "rewards": false
+                }
+            ]);
+            (slot, rpc.call("getBlock", params).await)
+        })
+        .buffered(concurrency);
+
+    while let Some((slot, block_res)) = stream.next().await {
+        match block_res {
+            Ok(block) => {
+                blocks_ok += 1;
+                for (index_in_block, tx) in block_transactions_for_address(&block, slot, address) {
+                    let Some(sig) = tx
+                        .pointer("/transaction/signatures/0")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string())
+                    else {
+                        continue;
+                    };
+                    txs_matched += 1;
+
+                    // record in the getTransaction-shaped line replay expects
+                    let line = serde_json::to_string(&json!({ "signature": sig, "tx": tx }))?;
+                    writeln!(f, "{line}")?;
+
+                    if let Some(swaps_topic) = swaps_topic {
+                        let found = swaps::decode_swaps(&registry, &tx, &sig, chain);
+                        swaps_emitted += swaps::emit_swaps(producer, swaps_topic, &found).await?;
+                        continue;
+                    }
+
+                    let event = raw_tx_event(&tx, &sig, chain, &programs, index_in_block);
+                    let json_event = serde_json::to_string(&event)?;
+                    kafka::send_json(producer, kafka_topic, Some(&sig), &json_event).await?;
+                }
+            }
+            Err(e) => {
+                blocks_err += 1;
+                warn!("getBlock failed slot={slot}: {e:?}");
+
+                let dlq = DlqEvent {
+                    source: "backfill".to_string(),
+                    step: "getBlock".to_string(),
+                    signature: None,
+                    error: format!("slot={slot}: {e:?}"),
+                };
+                let j = serde_json::to_string(&dlq)?;
+                kafka::send_json(producer, dlq_topic, None, &j).await?;
+            }
+        }
+
+        let done = blocks_ok + blocks_err;
+        if done.is_multiple_of(100) {
+            info!(
+                "progress blocks={} ok={} err={} txs_matched={}",
+                done, blocks_ok, blocks_err, txs_matched
+            );
+        }
+    }
+
+    let rpc_stats = rpc.retry_stats();
+    info!(
+        "backfill by block done. blocks={} ok={} err={} txs_matched={} swaps_emitted={} rpc_retries={} rpc_backoff_ms={}",
+        blocks_ok + blocks_err,
+        blocks_ok,
+        blocks_err,
+        txs_matched,
+        swaps_emitted,
+        rpc_stats.retries.load(Ordering::Relaxed),
+        rpc_stats.backoff_ms_total.load(Ordering::Relaxed)
     );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_recent_blockhash() {
+        let tx = json!({
+            "slot": 250000000,
+            "transaction": {
+                "message": {
+                    "accountKeys": ["FeePayer111"],
+                    "recentBlockhash": "9sHcv6xwn9YkB8nxTUGKDwPwNnmqVp5oAXxU8Fdkm4J6",
+                    "instructions": []
+                }
+            }
+        });
+        assert_eq!(
+            extract_recent_blockhash(&tx).as_deref(),
+            Some("9sHcv6xwn9YkB8nxTUGKDwPwNnmqVp5oAXxU8Fdkm4J6")
+        );
+
+        let missing = json!({"transaction": {"message": {"accountKeys": []}}});
+        assert_eq!(extract_recent_blockhash(&missing), None);
+    }
+
+    #[test]
+    fn test_block_transactions_for_address() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/legacy_raydium_swap_full.json"
+        );
+        let recorded: Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        // getBlock entries carry only transaction/meta/version
+        let entry = |tx: &Value| json!({"transaction": tx["transaction"], "meta": tx["meta"]});
+        let unrelated = json!({
+            "transaction": {
+                "message": {"accountKeys": ["Payer111", "11111111111111111111111111111111"]},
+                "signatures": ["UnrelatedSig"]
+            },
+            "meta": {"err": null, "fee": 5000}
+        });
+        let block = json!({
+            "blockTime": 1703001999,
+            "transactions": [unrelated, entry(&recorded)]
+        });
+
+        let raydium = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+        let txs = block_transactions_for_address(&block, 250000999, raydium);
+        assert_eq!(txs.len(), 1);
+        let (index_in_block, tx) = &txs[0];
+        assert_eq!(*index_in_block, 1);
+        assert_eq!(tx["slot"], 250000999);
+        assert_eq!(tx["blockTime"], 1703001999);
+
+        // Same RawTxEvent as the per-signature path, plus the block position
+        let programs = ChainPrograms::for_chain("solana-mainnet");
+        let event = raw_tx_event(tx, "sig", "solana-mainnet", &programs, *index_in_block);
+        assert_eq!(event.slot, 250000999);
+        assert_eq!(event.index_in_block, 1);
+        assert!(event.program_ids.iter().any(|p| p == raydium));
+
+        assert!(block_transactions_for_address(&json!(null), 1, raydium).is_empty());
+    }
+
+    #[test]
+    fn test_signature_pages_respect_slot_window() {
+        let page: Vec<Value> = [("S5", 500), ("S4", 400), ("S3", 300), ("S2", 200)]
+            .iter()
+            .map(|(sig, slot)| json!({"signature": sig, "slot": slot}))
+            .collect();
+        let window = SlotWindow {
+            since: Some(300),
+            until: Some(400),
+        };
+
+        // S5 is after the window, S2 ends paging
+        let mut signatures = vec![];
+        assert!(!collect_signature_page(&page, window, 10, &mut signatures));
+        assert_eq!(signatures, vec!["S4", "S3"]);
+
+        // The limit counts kept signatures only; no window keeps paging
+        let mut signatures = vec![];
+        assert!(collect_signature_page(&page, window, 1, &mut signatures));
+        assert_eq!(signatures, vec!["S4"]);
+        let mut signatures = vec![];
+        assert!(collect_signature_page(&page, SlotWindow::default(), 10, &mut signatures));
+        assert_eq!(signatures.len(), 4);
+    }
+}
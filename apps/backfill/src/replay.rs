@@ -1,5 +1,7 @@
 use crate::{
     kafka,
+    pipeline::extract_recent_blockhash,
+    swaps,
     types::{DlqEvent, RawTxEvent},
 };
 use anyhow::{Result, anyhow};
@@ -10,27 +12,80 @@ use std::{
     fs::File,
     io::{BufRead, BufReader},
     path::Path,
+    time::Duration,
 };
+use tokio::time::Instant;
 
 // Import ALT-aware helpers from schema crate
 use schema::extract_program_ids_from_transaction;
 
+/// Pacing and cap for a replay (`--replay-rate`, `--replay-limit`)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReplayLimits {
+    /// Recorded txs published per second (`None` = as fast as Kafka takes them)
+    pub rate: Option<u32>,
+    /// Stop after this many recorded txs have been published
+    pub limit: Option<usize>,
+}
+
+/// Fixed-interval pacing: the n-th tx is due `n / rate` seconds after the
+/// first, so a slow send is caught up on instead of drifting the rate down
+struct Throttle {
+    start: Instant,
+    interval: Duration,
+}
+
+impl Throttle {
+    fn new(rate: u32) -> Self {
+        Self {
+            start: Instant::now(),
+            interval: Duration::from_secs(1) / rate,
+        }
+    }
+
+    /// When the `n`-th (0-based) tx may be published
+    fn due(&self, n: usize) -> Instant {
+        self.start + self.interval * u32::try_from(n).unwrap_or(u32::MAX)
+    }
+
+    async fn wait(&self, n: usize) {
+        tokio::time::sleep_until(self.due(n)).await;
+    }
+}
+
 pub async fn replay_file(
     producer: &FutureProducer,
     kafka_topic: &str,
     dlq_topic: &str,
     chain: &str,
+    swaps_topic: Option<&str>,
     path: &Path,
+    limits: ReplayLimits,
 ) -> Result<()> {
     info!("replay from {}", path.display());
+    if let Some(rate) = limits.rate {
+        info!("replay rate: {} txs/s", rate);
+    }
+    if let Some(limit) = limits.limit {
+        info!("replay limit: {} txs", limit);
+    }
 
     let f = File::open(path)?;
     let r = BufReader::new(f);
 
+    let throttle = limits.rate.map(Throttle::new);
+    let registry = swaps::swap_registry(chain);
     let mut count = 0usize;
+    let mut replayed = 0usize; // recorded txs published (one event, or one tx's swaps)
+    let mut dlq = 0usize;
     let mut logged_schema = false; // schema validation flag
 
     for line in r.lines() {
+        if limits.limit.is_some_and(|limit| replayed >= limit) {
+            info!("replay limit reached after {} txs", replayed);
+            break;
+        }
+
         let line = line?;
         if line.trim().is_empty() {
             continue;
@@ -49,14 +104,32 @@ pub async fn replay_file(
 
         let slot = tx.get("slot").and_then(|v| v.as_u64()).unwrap_or(0);
         if sig.is_empty() || slot == 0 {
-            let dlq = DlqEvent {
+            let dlq_event = DlqEvent {
                 source: "backfill".to_string(),
                 step: "replay-parse".to_string(),
                 signature: Some(sig),
                 error: "empty signature or slot=0".to_string(),
             };
-            let j = serde_json::to_string(&dlq)?;
+            let j = serde_json::to_string(&dlq_event)?;
             kafka::send_json(producer, dlq_topic, None, &j).await?;
+            dlq += 1;
+            continue;
+        }
+
+        if let Some(throttle) = &throttle {
+            throttle.wait(replayed).await;
+        }
+        replayed += 1;
+        if replayed.is_multiple_of(1000) {
+            info!(
+                "progress replayed={} published={} dlq={}",
+                replayed, count, dlq
+            );
+        }
+
+        if let Some(swaps_topic) = swaps_topic {
+            let found = swaps::decode_swaps(&registry, &tx, &sig, chain);
+            count += swaps::emit_swaps(producer, swaps_topic, &found).await?;
             continue;
         }
 
@@ -70,6 +143,7 @@ pub async fn replay_file(
         // Use ALT-aware extraction from schema crate
         let program_ids = extract_program_ids_from_transaction(&tx);
         let main_program = program_ids.first().cloned();
+        let recent_blockhash = extract_recent_blockhash(&tx);
 
         // Keep replay simple: reuse same extraction as backfill by emitting only core fields
         let event = RawTxEvent {
@@ -85,6 +159,7 @@ pub async fn replay_file(
             compute_units_consumed: None,
             main_program,
             program_ids,
+            recent_blockhash,
         };
 
         let json_event = serde_json::to_string(&event)?;
@@ -103,9 +178,26 @@ pub async fn replay_file(
         count += 1;
     }
 
-    info!("replay published {} events", count);
+    info!(
+        "replay published {} events (txs={} dlq={})",
+        count, replayed, dlq
+    );
     Ok(())
 }
 
 // Note: extract_program_ids_from_tx moved to schema crate as extract_program_ids_from_transaction
 // to support Address Lookup Table (ALT) resolution for v0 transactions.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_schedules_on_fixed_interval() {
+        let throttle = Throttle::new(200);
+        assert_eq!(throttle.due(0), throttle.start);
+        assert_eq!(throttle.due(1) - throttle.start, Duration::from_millis(5));
+        // Due times come from the start, not the previous send: no drift
+        assert_eq!(throttle.due(1000) - throttle.start, Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,80 @@
+//! `--resume`: skip signatures a previous run already handled.
+//!
+//! Both the `--out` recording and the `--dlq-file` are jsonl with a top-level
+//! `signature`, so one loader reads either. A run killed mid-write can leave a
+//! truncated last line; it is skipped and terminated so the resumed run's
+//! first appended line starts on its own line.
+
+use anyhow::Result;
+use log::warn;
+use serde_json::Value;
+use std::{
+    collections::HashSet,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+/// Signatures recorded in the jsonl file at `path` (none if it does not exist)
+pub fn recorded_signatures(path: &Path) -> Result<HashSet<String>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut signatures = HashSet::new();
+    let mut unreadable = 0usize;
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        let sig = serde_json::from_str::<Value>(line).ok().and_then(|v| {
+            v.get("signature")
+                .and_then(|s| s.as_str())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+        });
+        match sig {
+            Some(sig) => {
+                signatures.insert(sig);
+            }
+            None => unreadable += 1,
+        }
+    }
+    if unreadable > 0 {
+        warn!(
+            "resume: {} unreadable lines in {} (their signatures are refetched)",
+            unreadable,
+            path.display()
+        );
+    }
+
+    if !content.is_empty() && !content.ends_with('\n') {
+        writeln!(OpenOptions::new().append(true).open(path)?)?;
+    }
+    Ok(signatures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorded_signatures_skips_truncated_line() {
+        let dir = std::env::temp_dir().join(format!("backfill_resume_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.jsonl");
+        // Killed while writing the third line
+        fs::write(
+            &path,
+            "{\"signature\":\"SigA\",\"tx\":{}}\n\n{\"signature\":\"SigB\",\"tx\":{}}\n{\"signa",
+        )
+        .unwrap();
+
+        let signatures = recorded_signatures(&path).unwrap();
+        assert_eq!(signatures.len(), 2);
+        assert!(signatures.contains("SigA") && signatures.contains("SigB"));
+        assert!(fs::read_to_string(&path).unwrap().ends_with('\n'));
+
+        assert!(recorded_signatures(&dir.join("missing.jsonl")).unwrap().is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
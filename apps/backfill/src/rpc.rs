@@ -1,82 +1,105 @@
-use anyhow::{Result, anyhow};
+use decoder::retry::{RetryError, RetryPolicy, retry};
+use decoder::rpc_error::RpcError;
 use reqwest::Client;
 use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
-use tokio::time::sleep;
+
+/// Retry counters shared by all clones of an RpcClient
+#[derive(Debug, Default)]
+pub struct RpcRetryStats {
+    pub retries: AtomicU64,
+    pub backoff_ms_total: AtomicU64,
+}
 
 #[derive(Clone)]
 pub struct RpcClient {
     http: Client,
     url: String,
+    retry_policy: RetryPolicy,
+    stats: Arc<RpcRetryStats>,
 }
 
 impl RpcClient {
-    pub fn new(url: String) -> Self {
+    pub fn new(url: String, max_backoff_ms: u64) -> Self {
         let http = Client::builder()
             .timeout(Duration::from_secs(20))
             .build()
             .expect("reqwest client");
-        Self { http, url }
+        Self {
+            http,
+            url,
+            // simple retry with exponential backoff (public RPC friendly)
+            retry_policy: RetryPolicy {
+                max_attempts: 6,
+                base_backoff: Duration::from_millis(250),
+                max_backoff: Duration::from_millis(max_backoff_ms),
+                jitter_ms: 0,
+            },
+            stats: Arc::new(RpcRetryStats::default()),
+        }
     }
 
-    pub async fn call(&self, method: &str, params: Value) -> Result<Value> {
-        // simple retry with exponential backoff (public RPC friendly)
-        let mut backoff = Duration::from_millis(250);
-
-        for attempt in 1..=6 {
-            let body = json!({
-                "jsonrpc": "2.0",
-                "id": 1,
-                "method": method,
-                "params": params
-            });
+    pub fn max_backoff(&self) -> Duration {
+        self.retry_policy.max_backoff
+    }
 
-            let resp = self.http.post(&self.url).json(&body).send().await;
+    pub fn retry_stats(&self) -> &RpcRetryStats {
+        &self.stats
+    }
 
-            match resp {
-                Ok(r) => {
-                    let status = r.status();
-                    let v: Value = r
-                        .json()
-                        .await
-                        .map_err(|e| anyhow!("rpc decode error: {e:?}"))?;
+    /// Account for a retry sleep (also used by callers with their own retry loop)
+    pub fn record_backoff(&self, slept: Duration) {
+        self.stats.retries.fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .backoff_ms_total
+            .fetch_add(slept.as_millis() as u64, Ordering::Relaxed);
+    }
 
-                    if !status.is_success() {
-                        // usually 429/5xx
-                        if attempt < 6 {
-                            sleep(backoff).await;
-                            backoff = (backoff * 2).min(Duration::from_secs(5));
-                            continue;
-                        }
-                        return Err(anyhow!("rpc http error status={status} body={v}"));
-                    }
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params
+        });
 
-                    if let Some(err) = v.get("error") {
-                        // data-level or transient, still retry a bit
-                        if attempt < 6 {
-                            sleep(backoff).await;
-                            backoff = (backoff * 2).min(Duration::from_secs(5));
-                            continue;
-                        }
-                        return Err(anyhow!("rpc returned error: {err}"));
-                    }
+        retry(
+            &self.retry_policy,
+            |_| async {
+                let r = self
+                    .http
+                    .post(&self.url)
+                    .json(&body)
+                    .send()
+                    .await
+                    .map_err(|e| RetryError::Transient(RpcError::Transport(e)))?;
+                let status = r.status();
+                if status.as_u16() == 429 {
+                    // Backoff here; the Retry-After is left to callers with their own loop
+                    return Err(RetryError::Transient(RpcError::rate_limited(r.headers())));
+                }
+                let v: Value = r.json().await.map_err(|e| {
+                    RetryError::Permanent(RpcError::Decode(format!("{e:?}")))
+                })?;
 
-                    return v
-                        .get("result")
-                        .cloned()
-                        .ok_or_else(|| anyhow!("missing result field"));
+                if !status.is_success() {
+                    // usually 5xx
+                    return Err(RetryError::Transient(RpcError::ServerError(status.as_u16())));
                 }
-                Err(e) => {
-                    if attempt < 6 {
-                        sleep(backoff).await;
-                        backoff = (backoff * 2).min(Duration::from_secs(5));
-                        continue;
-                    }
-                    return Err(anyhow!("rpc request failed: {e:?}"));
+
+                if let Some(err) = v.get("error") {
+                    // data-level or transient, still retry a bit
+                    return Err(RetryError::Transient(RpcError::rpc_level(err)));
                 }
-            }
-        }
 
-        Err(anyhow!("unreachable"))
+                v.get("result").cloned().ok_or_else(|| {
+                    RetryError::Permanent(RpcError::Decode("missing result field".into()))
+                })
+            },
+            |_, _, delay| self.record_backoff(delay),
+        )
+        .await
     }
 }
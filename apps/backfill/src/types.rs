@@ -14,6 +14,8 @@ pub struct RawTxEvent {
     pub compute_units_consumed: Option<u64>,
     pub main_program: Option<String>,
     pub program_ids: Vec<String>,
+    /// Message recentBlockhash (base58), for correlating txs to fork points
+    pub recent_blockhash: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
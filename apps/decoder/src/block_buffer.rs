@@ -0,0 +1,297 @@
+//! Per-slot buffering: collect what the decoder produces for the current slot
+//! and hand it back in one batch once a different slot shows up (slot rollover).
+//!
+//! The input topic is ordered by slot in practice, so a rollover means the
+//! previous block is complete. A late tx for an older slot also rolls over and
+//! comes out as its own small batch.
+//!
+//! `BlockCounter` uses the same rollover to report how many swaps each block
+//! yielded, for spotting parser regressions or RPC gaps per block.
+//!
+//! `SwapOrderBuffer` holds a slot's swaps for block-order emission, together
+//! with the input messages whose offsets must not be committed before it.
+
+use schema::DexSwapV1;
+use serde::Serialize;
+
+/// Items of the slot currently being decoded
+#[derive(Debug)]
+pub struct BlockBuffer<T> {
+    slot: Option<u64>,
+    items: Vec<T>,
+}
+
+impl<T> Default for BlockBuffer<T> {
+    fn default() -> Self {
+        Self {
+            slot: None,
+            items: Vec::new(),
+        }
+    }
+}
+
+impl<T> BlockBuffer<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one tx's items (possibly none) seen in `slot`. On rollover, returns
+    /// the previous slot with everything buffered for it.
+    pub fn push(
+        &mut self,
+        slot: u64,
+        items: impl IntoIterator<Item = T>,
+    ) -> Option<(u64, Vec<T>)> {
+        let done = match self.slot {
+            Some(current) if current != slot => {
+                Some((current, std::mem::take(&mut self.items)))
+            }
+            _ => None,
+        };
+        self.slot = Some(slot);
+        self.items.extend(items);
+        done
+    }
+
+    /// Slot currently being buffered
+    pub fn slot(&self) -> Option<u64> {
+        self.slot
+    }
+
+    /// Hand back the current slot without waiting for a rollover (idle or
+    /// shutdown); a later tx of the same slot then starts a new batch
+    pub fn take(&mut self) -> Option<(u64, Vec<T>)> {
+        let slot = self.slot.take()?;
+        Some((slot, std::mem::take(&mut self.items)))
+    }
+}
+
+/// Swaps detected in one slot, over all the txs decoded for it (with or
+/// without swaps)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BlockSwapCounts {
+    pub slot: u64,
+    pub swap_count: usize,
+    pub tx_count: usize,
+}
+
+/// Per-slot swap and tx counts, released on slot rollover
+#[derive(Debug, Default)]
+pub struct BlockCounter {
+    /// One entry per decoded tx: the swaps it yielded
+    swaps_per_tx: BlockBuffer<usize>,
+}
+
+impl BlockCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count one decoded tx of `slot` and its swaps. On rollover, returns the
+    /// previous slot's counts.
+    pub fn record(&mut self, slot: u64, swaps: usize) -> Option<BlockSwapCounts> {
+        let (slot, txs) = self.swaps_per_tx.push(slot, [swaps])?;
+        Some(BlockSwapCounts {
+            slot,
+            swap_count: txs.iter().sum(),
+            tx_count: txs.len(),
+        })
+    }
+}
+
+/// Input message position: (partition, offset)
+pub type MessagePosition = (i32, i64);
+
+/// A slot handed back by `SwapOrderBuffer`: its swaps in block order and the
+/// input messages to commit once they are emitted
+#[derive(Debug)]
+pub struct ReleasedSlot {
+    pub slot: u64,
+    pub swaps: Vec<DexSwapV1>,
+    pub held: Vec<MessagePosition>,
+}
+
+/// Swaps of the slot being decoded, emitted in block order on rollover.
+///
+/// A Kafka commit covers every earlier offset of its partition, so while a
+/// slot is buffered no finished message may be committed: each one is held
+/// here and committed after the slot's swaps are emitted.
+#[derive(Debug, Default)]
+pub struct SwapOrderBuffer {
+    swaps: BlockBuffer<DexSwapV1>,
+    held: Vec<MessagePosition>,
+}
+
+impl SwapOrderBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one tx's swaps seen in `slot`. On rollover, returns the previous
+    /// slot with the messages held for it; the tx itself is held by the
+    /// caller afterwards, since its swaps are now buffered.
+    pub fn push(&mut self, slot: u64, swaps: Vec<DexSwapV1>) -> Option<ReleasedSlot> {
+        let (slot, swaps) = self.swaps.push(slot, swaps)?;
+        Some(self.release(slot, swaps))
+    }
+
+    /// Slot currently being buffered
+    pub fn slot(&self) -> Option<u64> {
+        self.swaps.slot()
+    }
+
+    /// Hold a finished message until the buffered slot is emitted. Returns
+    /// false if no slot is buffered: the message can be committed right away.
+    pub fn hold(&mut self, position: MessagePosition) -> bool {
+        if self.swaps.slot().is_none() {
+            return false;
+        }
+        self.held.push(position);
+        true
+    }
+
+    /// Hand back the current slot without waiting for a rollover (idle,
+    /// revoke or shutdown)
+    pub fn take(&mut self) -> Option<ReleasedSlot> {
+        let (slot, swaps) = self.swaps.take()?;
+        Some(self.release(slot, swaps))
+    }
+
+    fn release(&mut self, slot: u64, mut swaps: Vec<DexSwapV1>) -> ReleasedSlot {
+        sort_block_order(&mut swaps);
+        ReleasedSlot {
+            slot,
+            swaps,
+            held: std::mem::take(&mut self.held),
+        }
+    }
+}
+
+/// Sort one block's swaps into on-chain order: tx position in the block, then
+/// instruction within the tx, then hop within a route
+pub fn sort_block_order(swaps: &mut [DexSwapV1]) {
+    swaps.sort_by_key(|s| (s.index_in_block, s.index_in_tx, s.hop_index));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::DexSwapV1Builder;
+
+    fn swap(index_in_block: u32, index_in_tx: u16, hop_index: u8) -> DexSwapV1 {
+        DexSwapV1Builder::new()
+            .slot(100)
+            .signature(format!("sig{index_in_block}"))
+            .index_in_block(index_in_block)
+            .index_in_tx(index_in_tx)
+            .hop_index(hop_index)
+            .in_token("SOL", 1_000_000_000u128)
+            .out_token("USDC", 50_000_000u128)
+            .build()
+    }
+
+    fn order(swaps: &[DexSwapV1]) -> Vec<(u32, u16, u8)> {
+        swaps
+            .iter()
+            .map(|s| (s.index_in_block, s.index_in_tx, s.hop_index))
+            .collect()
+    }
+
+    #[test]
+    fn test_slot_swaps_emitted_in_block_order_on_rollover() {
+        let mut buffer = BlockBuffer::new();
+
+        // Detections within slot 100 arrive out of order (batched RPC, retries)
+        assert!(buffer.push(100, [swap(7, 2000, 0)]).is_none());
+        assert!(buffer.push(100, [swap(3, 1000, 1), swap(3, 1000, 0)]).is_none());
+        assert!(buffer.push(100, Vec::new()).is_none());
+        assert!(buffer.push(100, [swap(7, 1000, 0), swap(0, 0, 0)]).is_none());
+
+        // First tx of slot 101 releases slot 100
+        let (slot, mut swaps) = buffer.push(101, [swap(1, 0, 0)]).unwrap();
+        assert_eq!(slot, 100);
+        sort_block_order(&mut swaps);
+        assert_eq!(
+            order(&swaps),
+            vec![(0, 0, 0), (3, 1000, 0), (3, 1000, 1), (7, 1000, 0), (7, 2000, 0)]
+        );
+        assert_eq!(buffer.slot(), Some(101));
+
+        // A tx-less rollover still releases the buffered slot
+        let (slot, swaps) = buffer.push(102, Vec::new()).unwrap();
+        assert_eq!((slot, order(&swaps)), (101, vec![(1, 0, 0)]));
+    }
+
+    #[test]
+    fn test_take_releases_last_slot_without_rollover() {
+        let mut buffer = BlockBuffer::new();
+        assert!(buffer.take().is_none());
+
+        // Idle or shutdown: the last slot comes out without a next slot
+        assert!(buffer.push(100, [swap(0, 0, 0)]).is_none());
+        let (slot, swaps) = buffer.take().unwrap();
+        assert_eq!((slot, order(&swaps)), (100, vec![(0, 0, 0)]));
+        assert_eq!(buffer.slot(), None);
+        assert!(buffer.take().is_none());
+
+        // A straggler of the taken slot starts a new batch, not a rollover
+        assert!(buffer.push(100, [swap(5, 0, 0)]).is_none());
+        let (slot, swaps) = buffer.take().unwrap();
+        assert_eq!((slot, order(&swaps)), (100, vec![(5, 0, 0)]));
+    }
+
+    #[test]
+    fn test_swap_order_buffer_holds_messages_until_slot_release() {
+        let mut buffer = SwapOrderBuffer::new();
+
+        // Nothing buffered: a finished message is committed right away
+        assert!(!buffer.hold((0, 10)));
+
+        // Swaps of slot 100 buffered: this and later messages are held
+        assert!(buffer.push(100, vec![swap(3, 0, 0)]).is_none());
+        assert!(buffer.hold((0, 11)));
+        assert!(buffer.push(100, vec![swap(1, 0, 0)]).is_none());
+        assert!(buffer.hold((1, 4)));
+
+        // Rollover releases slot 100 with its held messages, in block order
+        let released = buffer.push(101, vec![swap(0, 0, 0)]).unwrap();
+        assert_eq!(released.slot, 100);
+        assert_eq!(order(&released.swaps), vec![(1, 0, 0), (3, 0, 0)]);
+        assert_eq!(released.held, vec![(0, 11), (1, 4)]);
+
+        // The rollover tx belongs to slot 101
+        assert!(buffer.hold((0, 12)));
+        let released = buffer.take().unwrap();
+        assert_eq!((released.slot, released.held), (101, vec![(0, 12)]));
+        assert!(!buffer.hold((0, 13)));
+        assert_eq!(buffer.slot(), None);
+    }
+
+    #[test]
+    fn test_block_counter_reports_slot_on_rollover() {
+        let mut counter = BlockCounter::new();
+
+        // Slot 100: three txs, one without swaps
+        assert!(counter.record(100, 2).is_none());
+        assert!(counter.record(100, 0).is_none());
+        assert!(counter.record(100, 1).is_none());
+
+        let counts = counter.record(101, 0).unwrap();
+        assert_eq!(
+            counts,
+            BlockSwapCounts {
+                slot: 100,
+                swap_count: 3,
+                tx_count: 3
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&counts).unwrap(),
+            r#"{"slot":100,"swap_count":3,"tx_count":3}"#
+        );
+
+        // A block whose txs all yielded nothing still reports
+        let counts = counter.record(102, 4).unwrap();
+        assert_eq!((counts.slot, counts.swap_count, counts.tx_count), (101, 0, 1));
+    }
+}
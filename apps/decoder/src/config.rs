@@ -1,5 +1,21 @@
 use anyhow::{Result, anyhow};
 use std::env;
+use schema::{ChainPrograms, JITO_TIP_ACCOUNTS};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::kafka::FetchTuning;
+use crate::rpc::{self, Commitment, GetTxMethod};
+use crate::sinks::SinkKind;
+use crate::throttle::ThrottleMode;
+use crate::types::{SchemaMismatchMode, SchemaVersionRange};
+use decoder::detectors::raydium_v4_gold::parse_raydium_pool_vaults;
+use decoder::detectors::stable_pool::parse_stable_pool_programs;
+use decoder::detectors::{AmountSources, IndexInTxMode, MultihopPolicy};
+use decoder::mint_registry::{MintInfo, parse_mint_metadata};
+use decoder::notional::PriceTable;
 
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -8,17 +24,86 @@ pub struct Config {
     pub rpc_concurrency: u32,
     pub rpc_min_delay_ms: u64,
     pub rpc_max_tx_version: u8,
+    pub rpc_get_tx_method: GetTxMethod,
+    pub rpc_commitment: Option<Commitment>,
+    pub rpc_max_backoff_ms: u64,
+    pub rpc_max_retry_after_ms: u64,
+    pub rpc_timeout_ms: u64,
+    pub rpc_batch_size: usize,
+    pub resolve_alt_via_rpc: bool,
+    pub startup_warmup: bool,
     pub kafka_broker: String,
+    pub sink_kind: SinkKind,
     pub in_topic: String,
     pub out_sol_deltas_topic: String,
     pub out_token_deltas_topic: String,
+    pub emit_combined_facts: bool,
+    pub out_facts_topic: String,
     pub out_swaps_topic: String,
+    pub emit_tx_summary: bool,
+    pub out_tx_summary_topic: String,
+    pub confirm_retractions: bool,
+    pub confirm_delay_ms: u64,
+    pub out_retractions_topic: String,
+    pub out_swaps_v2_topic: String,
+    pub swaps_dual_write: bool,
+    /// Gold detector registry on (DexSwapV1); see `gold_venue_gates`
+    pub gold_swaps: bool,
+    /// Gold venues left out because their program ID variable is unset
+    pub disabled_venues: Vec<String>,
+    pub swaps_order_by_block: bool,
+    pub swaps_order_idle_flush: Duration,
     pub swaps_explain: bool,
     pub swaps_explain_limit: u32,
+    pub swaps_explain_log_lines: usize,
+    pub swaps_explain_max_confidence: Option<u8>,
+    pub swaps_allow_same_mint_arb: bool,
+    pub swaps_min_confidence: u8,
+    pub swaps_max_eps: u32,
+    pub throttle_mode: ThrottleMode,
+    pub swap_prices: PriceTable,
+    pub swaps_min_notional_usd: f64,
+    pub swaps_drop_unknown_notional: bool,
+    pub pool_mints_cache_size: usize,
+    pub mint_metadata: HashMap<String, MintInfo>,
+    pub mint_metadata_fetch: bool,
+    pub mint_cache_size: usize,
+    pub mint_cache_ttl: Duration,
+    pub index_in_tx_mode: IndexInTxMode,
+    pub multihop_policy: MultihopPolicy,
+    pub amount_sources: AmountSources,
+    pub stable_pool_programs: Vec<(String, String)>,
+    pub raydium_pool_vaults: Arc<HashMap<String, String>>,
+    pub swap_venues: Option<Vec<String>>,
+    pub out_stable_swaps_topic: String,
+    pub emit_parse_diagnostics: bool,
+    pub out_diagnostics_topic: String,
+    pub block_swap_counts: bool,
+    pub out_block_counts_topic: Option<String>,
+    pub out_liquidity_topic: Option<String>,
+    pub detector_timings: bool,
+    pub metrics_prom_file: Option<PathBuf>,
+    pub metrics_port: Option<u16>,
     pub raydium_amm_v4_program_id: String,
+    pub chain_programs: ChainPrograms,
+    pub trader_exclude: Vec<String>,
+    pub jito_tip_accounts: Vec<String>,
+    pub liquidity_allow_zero_amount: bool,
+    pub normalize_wsol: bool,
     pub dlq_topic: Option<String>,
     pub consumer_group: String,
+    pub fetch_tuning: FetchTuning,
     pub include_failed: bool,
+    pub prefer_event_metadata: bool,
+    pub raw_schema_versions: SchemaVersionRange,
+    pub raw_schema_mismatch: SchemaMismatchMode,
+    pub strict_balance_lengths: bool,
+    pub max_message_bytes: usize,
+    pub dlq_max_field_bytes: usize,
+    pub retry_max_block_ms: u64,
+    pub producer_flush_ms: u64,
+    pub schema_dump_count: usize,
+    pub schema_dump_dir: Option<PathBuf>,
 }
 
 fn parse_bool(v: Option<String>, default: bool) -> bool {
@@ -30,12 +115,81 @@ fn parse_bool(v: Option<String>, default: bool) -> bool {
     }
 }
 
+/// Consumer group when `KAFKA_GROUP` is unset: the production decoder's
+const DEFAULT_CONSUMER_GROUP: &str = "decoder_v1";
+
+/// A dry run still commits offsets, so on the production group it would mark
+/// messages consumed that were never produced; it needs a group of its own
+fn validate_dry_run_group(dry_run: bool, consumer_group: &str) -> Result<()> {
+    if dry_run && consumer_group == DEFAULT_CONSUMER_GROUP {
+        return Err(anyhow!(
+            "DECODER_DRY_RUN=true needs a dedicated KAFKA_GROUP (got the default {}, whose offsets it would advance)",
+            DEFAULT_CONSUMER_GROUP
+        ));
+    }
+    Ok(())
+}
+
+/// Which swap path runs. The gold registry (DexSwapV1) runs under
+/// `SWAPS_DUAL_WRITE` or as soon as one of the `opt_in` venues (name, program ID
+/// variable's value) has its program ID set; each of those venues runs only then.
+/// Raydium joins the gold path under `SWAPS_DUAL_WRITE` or with
+/// `RAYDIUM_AMM_V4_PROGRAM_ID`. Returns whether the gold path is on and the
+/// venues it leaves out.
+fn gold_venue_gates(
+    swaps_dual_write: bool,
+    raydium_amm_v4_program_id: &str,
+    opt_in: &[(&str, &str)],
+) -> (bool, Vec<String>) {
+    let mut disabled: Vec<String> = opt_in
+        .iter()
+        .filter(|(_, program_id)| program_id.trim().is_empty())
+        .map(|(venue, _)| venue.to_string())
+        .collect();
+    let gold_swaps = swaps_dual_write || disabled.len() < opt_in.len();
+    if !swaps_dual_write && raydium_amm_v4_program_id.is_empty() {
+        disabled.push("raydium".to_string());
+    }
+    (gold_swaps, disabled)
+}
+
+/// Swaps are only emitted with detection enabled (`RAYDIUM_AMM_V4_PROGRAM_ID`
+/// set, or the gold path on); then an empty swaps topic would lose every
+/// swap, otherwise it is unused
+fn validate_swaps_topic(
+    raydium_amm_v4_program_id: &str,
+    gold_swaps: bool,
+    out_swaps_topic: &str,
+) -> Result<()> {
+    let detection = !raydium_amm_v4_program_id.is_empty() || gold_swaps;
+    if detection && out_swaps_topic.trim().is_empty() {
+        return Err(anyhow!(
+            "KAFKA_OUT_SWAPS_TOPIC is empty (required when RAYDIUM_AMM_V4_PROGRAM_ID, a venue program ID or SWAPS_DUAL_WRITE=true is set)"
+        ));
+    }
+    Ok(())
+}
+
+/// Parse an address list: comma/newline separated, `#` starts a comment
+fn parse_address_list(s: &str) -> Vec<String> {
+    s.lines()
+        .map(|line| line.split('#').next().unwrap_or(""))
+        .flat_map(|line| line.split(','))
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect()
+}
+
 pub fn load() -> Result<Config> {
     let include_failed = env::var("INCLUDE_FAILED")
         .ok()
         .map(|s| matches!(s.as_str(), "1" | "true" | "TRUE" | "yes" | "YES"))
         .unwrap_or(false);
 
+    // Swap/summary block_time from the RawTxEvent (streamer block-meta) rather
+    // than the fetched tx JSON, so emitted events match the raw event
+    let prefer_event_metadata = parse_bool(env::var("PREFER_EVENT_METADATA").ok(), true);
+
     // RPC URL precedence: RPC_PRIMARY_URL > RPC_URL > default mainnet
     let rpc_primary_url = env::var("RPC_PRIMARY_URL")
         .or_else(|_| env::var("RPC_URL"))
@@ -66,28 +220,422 @@ pub fn load() -> Result<Config> {
         .and_then(|s| s.parse().ok())
         .unwrap_or(1);
 
+    // Deprecated getConfirmedTransaction for archival providers that reject getTransaction
+    let rpc_get_tx_method = match env::var("RPC_GET_TX_METHOD") {
+        Ok(s) => GetTxMethod::parse(&s).ok_or_else(|| {
+            anyhow!(
+                "RPC_GET_TX_METHOD must be getTransaction|getConfirmedTransaction, got {}",
+                s
+            )
+        })?,
+        Err(_) => GetTxMethod::GetTransaction,
+    };
+
+    // Commitment for getTransaction; unset leaves it to the node. getTransaction
+    // only serves confirmed or finalized data
+    let rpc_commitment = match env::var("RPC_COMMITMENT") {
+        Ok(s) => match Commitment::parse(&s) {
+            Some(Commitment::Processed) => {
+                return Err(anyhow!(
+                    "RPC_COMMITMENT=processed is not supported by getTransaction, \
+                     use confirmed|finalized"
+                ));
+            }
+            Some(commitment) => Some(commitment),
+            None => {
+                return Err(anyhow!(
+                    "RPC_COMMITMENT must be processed|confirmed|finalized, got {}",
+                    s
+                ));
+            }
+        },
+        Err(_) => None,
+    };
+
+    // Cap for exponential retry backoff (429/5xx/transport errors)
+    let rpc_max_backoff_ms = env::var("RPC_MAX_BACKOFF_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(8000);
+
+    // Cap for a 429's Retry-After, which replaces the backoff when present
+    let rpc_max_retry_after_ms = env::var("RPC_MAX_RETRY_AFTER_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(rpc::DEFAULT_MAX_RETRY_AFTER_MS);
+
+    // Per-request HTTP timeout; a timed-out request moves on to the next URL
+    let rpc_timeout_ms = env::var("RPC_TIMEOUT_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(rpc::DEFAULT_TIMEOUT_MS);
+
+    // getTransaction calls per JSON-RPC batch request (1 = no batching)
+    let rpc_batch_size = env::var("RPC_BATCH_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1usize)
+        .max(1);
+
+    // Resolve address lookup tables via getAccountInfo when the provider omits
+    // meta.loadedAddresses (off by default: costs one RPC call per table)
+    let resolve_alt_via_rpc = parse_bool(env::var("RESOLVE_ALT_VIA_RPC").ok(), false);
+
+    // Fetch and decode a couple of recent venue txs at startup; exit on failure
+    let startup_warmup = parse_bool(env::var("STARTUP_WARMUP").ok(), false);
+
     let kafka_broker = env::var("KAFKA_BROKER").unwrap_or_else(|_| "localhost:19092".to_string());
+    // Output sink: kafka (default), stdout (NDJSON for piping; logs go to stderr)
+    // or log (events as log lines)
+    let sink_kind = match env::var("SINK_KIND") {
+        Ok(s) => SinkKind::parse(&s)
+            .ok_or_else(|| anyhow!("SINK_KIND must be kafka|stdout|log, got {}", s))?,
+        Err(_) => SinkKind::Kafka,
+    };
+    // Dry run: full decode and detection, but every event is logged instead of
+    // produced (the `log` sink, overriding SINK_KIND); offsets are still committed,
+    // so the default KAFKA_GROUP is refused
+    let dry_run = parse_bool(env::var("DECODER_DRY_RUN").ok(), false);
+    let sink_kind = if dry_run { SinkKind::Log } else { sink_kind };
     let in_topic = env::var("KAFKA_IN_TOPIC").unwrap_or_else(|_| "sol_raw_txs".to_string());
     let out_sol_deltas_topic =
         env::var("KAFKA_OUT_SOL_DELTAS_TOPIC").unwrap_or_else(|_| "sol_balance_deltas".to_string());
     let out_token_deltas_topic = env::var("KAFKA_OUT_TOKEN_DELTAS_TOPIC")
         .unwrap_or_else(|_| "sol_token_balance_deltas".to_string());
 
+    // Both delta kinds of a tx in one RawFacts message (the per-delta topics stay)
+    let emit_combined_facts = parse_bool(env::var("EMIT_COMBINED_FACTS").ok(), false);
+    let out_facts_topic =
+        env::var("KAFKA_OUT_FACTS_TOPIC").unwrap_or_else(|_| "sol_tx_facts".to_string());
+
     let out_swaps_topic =
         env::var("KAFKA_OUT_SWAPS_TOPIC").unwrap_or_else(|_| "sol_swaps".to_string());
 
+    // One compact TxSummaryV1 per processed tx (monitoring heartbeat)
+    let emit_tx_summary = parse_bool(env::var("EMIT_TX_SUMMARY").ok(), false);
+    let out_tx_summary_topic = env::var("KAFKA_OUT_TX_SUMMARY_TOPIC")
+        .unwrap_or_else(|_| "sol_tx_summaries".to_string());
+    let out_swaps_v2_topic =
+        env::var("KAFKA_OUT_SWAPS_V2_TOPIC").unwrap_or_else(|_| "sol_swaps_v2".to_string());
+
+    // Re-check emitted swaps at `confirmed` after a delay and retract those whose
+    // tx was rolled back (for inputs read at `processed` commitment)
+    let confirm_retractions = parse_bool(env::var("CONFIRM_RETRACTIONS").ok(), false);
+    let confirm_delay_ms = env::var("CONFIRM_DELAY_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(30000);
+    let out_retractions_topic = env::var("KAFKA_OUT_RETRACTIONS_TOPIC")
+        .unwrap_or_else(|_| "sol_swap_retractions".to_string());
+
+    // Migration mode: emit legacy SwapEvent and gold DexSwapV1 side by side
+    let swaps_dual_write = parse_bool(env::var("SWAPS_DUAL_WRITE").ok(), false);
+    // Buffer each slot's gold swaps and emit them sorted on slot rollover
+    let swaps_order_by_block = parse_bool(env::var("SWAPS_ORDER_BY_BLOCK").ok(), false);
+    // ...or once no message has arrived for this long, so the last slot is not
+    // held until traffic resumes
+    let swaps_order_idle_flush = Duration::from_millis(
+        env::var("SWAPS_ORDER_IDLE_FLUSH_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1000),
+    );
+
     let swaps_explain = parse_bool(env::var("SWAPS_EXPLAIN").ok(), false);
     let swaps_explain_limit = env::var("SWAPS_EXPLAIN_LIMIT")
         .ok()
         .and_then(|s| s.parse().ok())
         .unwrap_or(20);
+    // Venue program log lines appended to low-confidence explains (0 = off)
+    let swaps_explain_log_lines = env::var("SWAPS_EXPLAIN_LOG_LINES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
+    // Explain only swaps at or below this confidence (unset = all)
+    let swaps_explain_max_confidence = env::var("SWAPS_EXPLAIN_MAX_CONFIDENCE")
+        .ok()
+        .map(|s| {
+            s.trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|max| *max <= 100)
+                .ok_or_else(|| anyhow!("SWAPS_EXPLAIN_MAX_CONFIDENCE must be 0-100, got {}", s))
+        })
+        .transpose()?;
+
+    // Swaps with in_mint == out_mint are parse errors, except legs of a cyclic
+    // arbitrage route when this is set
+    let swaps_allow_same_mint_arb =
+        parse_bool(env::var("SWAPS_ALLOW_SAME_MINT_ARB").ok(), false);
+    // Gold swaps below this confidence are dropped before emission (0 = keep all)
+    let swaps_min_confidence = env::var("SWAPS_MIN_CONFIDENCE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
+
+    // Streamer -> decoder contract: RawTxEvent schema versions this build decodes
+    let raw_schema_versions = match env::var("RAW_SCHEMA_VERSIONS") {
+        Ok(s) => SchemaVersionRange::parse(&s).ok_or_else(|| {
+            anyhow!("RAW_SCHEMA_VERSIONS must be a version or min-max range, got {}", s)
+        })?,
+        Err(_) => SchemaVersionRange::CURRENT,
+    };
+    let raw_schema_mismatch = match env::var("RAW_SCHEMA_MISMATCH") {
+        Ok(s) => SchemaMismatchMode::parse(&s)
+            .ok_or_else(|| anyhow!("RAW_SCHEMA_MISMATCH must be warn|dlq, got {}", s))?,
+        Err(_) => SchemaMismatchMode::Warn,
+    };
+
+    // Emission throttle: 0 disables; over-rate events are dropped or delayed
+    let swaps_max_eps = env::var("SWAPS_MAX_EPS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
+    let throttle_mode = match env::var("THROTTLE_MODE") {
+        Ok(s) => ThrottleMode::parse(&s)
+            .ok_or_else(|| anyhow!("THROTTLE_MODE must be drop|block, got {}", s))?,
+        Err(_) => ThrottleMode::Drop,
+    };
+
+    // USD notional enrichment: static `mint:decimals:usd` price table. With a
+    // non-zero SWAPS_MIN_NOTIONAL_USD, swaps below the threshold are dropped;
+    // swaps with unknown notional pass unless SWAPS_DROP_UNKNOWN_NOTIONAL is set.
+    let swap_prices = match env::var("SWAPS_PRICES") {
+        Ok(s) => PriceTable::parse(&s)?,
+        Err(_) => PriceTable::default(),
+    };
+    let swaps_min_notional_usd = env::var("SWAPS_MIN_NOTIONAL_USD")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0.0);
+    let swaps_drop_unknown_notional =
+        parse_bool(env::var("SWAPS_DROP_UNKNOWN_NOTIONAL").ok(), false);
+
+    // Pools whose mint pair is remembered from high-confidence swaps, to vouch
+    // for later low-confidence swaps on the same pool (0 = off)
+    let pool_mints_cache_size = env::var("POOL_MINTS_CACHE_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(10000);
+
+    // Symbol / ui amount enrichment of gold swaps: a static `mint:decimals:symbol`
+    // table, plus (MINT_METADATA_FETCH) background getAccountInfo lookups of
+    // other mints kept in an LRU cache of MINT_CACHE_SIZE for MINT_CACHE_TTL_SECS
+    let mint_metadata = match env::var("MINT_METADATA") {
+        Ok(s) => parse_mint_metadata(&s)?,
+        Err(_) => HashMap::new(),
+    };
+    let mint_metadata_fetch = parse_bool(env::var("MINT_METADATA_FETCH").ok(), false);
+    let mint_cache_size = env::var("MINT_CACHE_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(10000);
+    let mint_cache_ttl = Duration::from_secs(
+        env::var("MINT_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(3600),
+    );
+
+    // How `index_in_tx` is derived: the outer ix index alone, or combined with
+    // the inner ix index so several CPI swaps under one outer ix stay distinct
+    let index_in_tx_mode = match env::var("INDEX_IN_TX_MODE") {
+        Ok(s) => IndexInTxMode::parse(&s)
+            .ok_or_else(|| anyhow!("INDEX_IN_TX_MODE must be outer|composite, got {}", s))?,
+        Err(_) => IndexInTxMode::Outer,
+    };
+
+    // Multi-hop routes with an unparseable leg: DLQ the tx (all_or_nothing) or
+    // emit the parsed legs tagged `partial` (best_effort)
+    let multihop_policy = match env::var("MULTIHOP_POLICY") {
+        Ok(s) => MultihopPolicy::parse(&s).ok_or_else(|| {
+            anyhow!("MULTIHOP_POLICY must be all_or_nothing|best_effort, got {}", s)
+        })?,
+        Err(_) => MultihopPolicy::BestEffort,
+    };
+
+    // Swap amounts from balance deltas, instruction data, or by discriminator;
+    // either one mode for all venues or a `venue:mode` list
+    let amount_sources = match env::var("AMOUNT_SOURCE") {
+        Ok(s) => AmountSources::parse(&s).ok_or_else(|| {
+            anyhow!(
+                "AMOUNT_SOURCE must be delta|instruction|auto or venue:mode list, got {}",
+                s
+            )
+        })?,
+        Err(_) => AmountSources::default(),
+    };
+
+    // Multi-asset (3+ token) pools: `venue:program_id` list, emitted as StableSwapV1
+    // to their own topic (gold path only; empty disables)
+    let stable_pool_programs = match env::var("STABLE_POOL_PROGRAMS") {
+        Ok(s) => parse_stable_pool_programs(&s).ok_or_else(|| {
+            anyhow!("STABLE_POOL_PROGRAMS must be a venue:program_id list, got {}", s)
+        })?,
+        Err(_) => Vec::new(),
+    };
+    // Raydium's pool list (liquidity/mainnet.json): vault -> pool, for pool ids of
+    // swaps whose pool account is not at its usual position
+    let raydium_pool_vaults = match env::var("RAYDIUM_POOLS_FILE") {
+        Ok(path) => {
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| anyhow!("RAYDIUM_POOLS_FILE {}: {}", path, e))?;
+            let list: serde_json::Value = serde_json::from_str(&contents)
+                .map_err(|e| anyhow!("RAYDIUM_POOLS_FILE {}: {}", path, e))?;
+            Arc::new(parse_raydium_pool_vaults(&list))
+        }
+        Err(_) => Arc::default(),
+    };
+
+    // Gold detector venues to run, comma-separated (`raydium,orca`); unset runs every
+    // registered venue. Names are checked against the registry at startup
+    let swap_venues = env::var("SWAP_VENUES").ok().map(|s| {
+        s.split(',')
+            .map(|v| v.trim().to_ascii_lowercase())
+            .filter(|v| !v.is_empty())
+            .collect()
+    });
+    let out_stable_swaps_topic = env::var("KAFKA_OUT_STABLE_SWAPS_TOPIC")
+        .unwrap_or_else(|_| "sol_stable_swaps".to_string());
+
+    // One ParseDiagnostic per venue present in a tx that yielded no swap (gold path)
+    let emit_parse_diagnostics = parse_bool(env::var("EMIT_PARSE_DIAGNOSTICS").ok(), false);
+    let out_diagnostics_topic = env::var("KAFKA_OUT_DIAGNOSTICS_TOPIC")
+        .unwrap_or_else(|_| "sol_parse_diagnostics".to_string());
+
+    // Per-slot {slot, swap_count, tx_count} on slot rollover, over every decoded tx: a gauge
+    // set, plus a message per block when the topic is set
+    let block_swap_counts = parse_bool(env::var("BLOCK_SWAP_COUNTS").ok(), false);
+    let out_block_counts_topic = env::var("KAFKA_OUT_BLOCK_COUNTS_TOPIC").ok();
+
+    // Raydium AMM v4 deposits/withdrawals as LiquidityEventV1 (gold path; unset disables)
+    let out_liquidity_topic = env::var("KAFKA_OUT_LIQUIDITY_TOPIC")
+        .ok()
+        .filter(|t| !t.trim().is_empty());
+
+    // Per-venue parse timings, logged with the stats line and optionally written
+    // as a Prometheus textfile (node_exporter textfile collector)
+    let detector_timings = parse_bool(env::var("DETECTOR_TIMINGS").ok(), false);
+    let metrics_prom_file = env::var("METRICS_PROM_FILE").ok().map(PathBuf::from);
+    // Prometheus scrape endpoint (`GET /metrics`); off unless a port is set
+    let metrics_port = env::var("METRICS_PORT")
+        .ok()
+        .map(|s| {
+            s.parse::<u16>()
+                .map_err(|_| anyhow!("METRICS_PORT must be a port number, got {}", s))
+        })
+        .transpose()?;
 
     // Raydium AMM v4 program id must be provided when you enable swap detection.
     // Keep it empty by default so current decoder flows keep working.
     let raydium_amm_v4_program_id =
         env::var("RAYDIUM_AMM_V4_PROGRAM_ID").unwrap_or_else(|_| "".to_string());
+
+    // Venue program IDs and skip lists are selected by CHAIN (mainnet defaults);
+    // an explicit RAYDIUM_AMM_V4_PROGRAM_ID overrides the chain's AMM id.
+    // PHOENIX_PROGRAM_ID and ORCA_WHIRLPOOL_PROGRAM_ID likewise set the
+    // Phoenix order-book and Orca Whirlpool programs, LIFINITY_PROGRAM_ID the
+    // Lifinity v2 PMM, METEORA_DLMM_PROGRAM_ID the Meteora DLMM and
+    // JUPITER_PROGRAM_ID the Jupiter v6 aggregator whose routes group venue hops.
+    // Each venue variable also enables its detector (`gold_venue_gates`).
+    let chain = env::var("CHAIN").unwrap_or_else(|_| "solana-mainnet".to_string());
+    let phoenix_program_id = env::var("PHOENIX_PROGRAM_ID").unwrap_or_default();
+    let orca_whirlpool_program_id = env::var("ORCA_WHIRLPOOL_PROGRAM_ID").unwrap_or_default();
+    let lifinity_program_id = env::var("LIFINITY_PROGRAM_ID").unwrap_or_default();
+    let meteora_dlmm_program_id = env::var("METEORA_DLMM_PROGRAM_ID").unwrap_or_default();
+    let chain_programs = ChainPrograms::for_chain(&chain)
+        .with_raydium_amm_v4(&raydium_amm_v4_program_id)
+        .with_phoenix(&phoenix_program_id)
+        .with_orca_whirlpool(&orca_whirlpool_program_id)
+        .with_lifinity_v2(&lifinity_program_id)
+        .with_meteora_dlmm(&meteora_dlmm_program_id)
+        .with_jupiter_v6(&env::var("JUPITER_PROGRAM_ID").unwrap_or_default());
+    let (gold_swaps, disabled_venues) = gold_venue_gates(
+        swaps_dual_write,
+        &raydium_amm_v4_program_id,
+        &[
+            ("phoenix", &phoenix_program_id),
+            ("orca", &orca_whirlpool_program_id),
+            ("lifinity", &lifinity_program_id),
+            ("meteora", &meteora_dlmm_program_id),
+        ],
+    );
+
+    // Known routers/aggregators that must never be reported as the trader.
+    // TRADER_EXCLUDE takes a comma-separated list, TRADER_EXCLUDE_FILE one address per line.
+    let mut trader_exclude = env::var("TRADER_EXCLUDE")
+        .map(|s| parse_address_list(&s))
+        .unwrap_or_default();
+    if let Ok(path) = env::var("TRADER_EXCLUDE_FILE") {
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("TRADER_EXCLUDE_FILE {}: {}", path, e))?;
+        trader_exclude.extend(parse_address_list(&contents));
+    }
+    trader_exclude.sort();
+    trader_exclude.dedup();
+
+    // Jito tip accounts (defaults to the known mainnet set; empty string disables)
+    let jito_tip_accounts = env::var("JITO_TIP_ACCOUNTS")
+        .map(|s| parse_address_list(&s))
+        .unwrap_or_else(|_| JITO_TIP_ACCOUNTS.iter().map(|a| a.to_string()).collect());
+
+    // Strict mode: txs whose pre/post balance arrays don't match the account keys
+    // are sent to the DLQ instead of being decoded from the truncated arrays
+    let strict_balance_lengths = parse_bool(env::var("STRICT_BALANCE_LENGTHS").ok(), false);
+
+    // Liquidity events may carry a zero leg (single-sided add/remove); swaps never do
+    let liquidity_allow_zero_amount =
+        parse_bool(env::var("LIQUIDITY_ALLOW_ZERO_AMOUNT").ok(), false);
+
+    // Report WSOL legs wrapped from / unwrapped into the trader's SOL as native SOL
+    let normalize_wsol = parse_bool(env::var("NORMALIZE_WSOL").ok(), false);
+
     let dlq_topic = env::var("KAFKA_DLQ_TOPIC").ok();
-    let consumer_group = env::var("KAFKA_GROUP").unwrap_or_else(|_| "decoder_v1".to_string());
+    let consumer_group =
+        env::var("KAFKA_GROUP").unwrap_or_else(|_| DEFAULT_CONSUMER_GROUP.to_string());
+
+    // Consumer fetch tuning for high-throughput reprocessing (unset = librdkafka default)
+    let fetch_tuning = FetchTuning {
+        fetch_min_bytes: env::var("KAFKA_FETCH_MIN_BYTES").ok().and_then(|s| s.parse().ok()),
+        fetch_max_bytes: env::var("KAFKA_FETCH_MAX_BYTES").ok().and_then(|s| s.parse().ok()),
+        queued_max_messages_kbytes: env::var("KAFKA_QUEUED_MAX_MESSAGES_KBYTES")
+            .ok()
+            .and_then(|s| s.parse().ok()),
+    };
+
+    // Schema samples: first N of each message type logged (and written to SCHEMA_DUMP_DIR)
+    let schema_dump_count = env::var("SCHEMA_DUMP_COUNT")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1);
+    let schema_dump_dir = env::var("SCHEMA_DUMP_DIR").ok().map(PathBuf::from);
+
+    // Input payloads above this size go to DLQ unparsed (0 disables)
+    let max_message_bytes = env::var("MAX_MESSAGE_BYTES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1_048_576);
+
+    // DLQ error/context fields above this size are cut with a `...[truncated]`
+    // suffix so entries stay within broker limits (0 disables)
+    let dlq_max_field_bytes = env::var("DLQ_MAX_FIELD_BYTES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(65_536);
+
+    // Total time one signature's fetch retries may sleep the consume loop before
+    // it is sent to the DLQ and committed, even with attempts left
+    let retry_max_block_ms = env::var("RETRY_MAX_BLOCK_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(600);
+
+    // Background producer flush interval, bounding latency of buffered messages
+    // on quiet streams (0 = rely on linger.ms / per-send awaits only)
+    let producer_flush_ms = env::var("PRODUCER_FLUSH_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
 
     if kafka_broker.trim().is_empty() {
         return Err(anyhow!("KAFKA_BROKER is empty"));
@@ -95,8 +643,32 @@ pub fn load() -> Result<Config> {
     if in_topic.trim().is_empty() {
         return Err(anyhow!("KAFKA_IN_TOPIC is empty"));
     }
-    if out_swaps_topic.trim().is_empty() {
-        return Err(anyhow!("KAFKA_OUT_SWAPS_TOPIC is empty"));
+    validate_swaps_topic(&raydium_amm_v4_program_id, gold_swaps, &out_swaps_topic)?;
+    validate_dry_run_group(dry_run, &consumer_group)?;
+    if emit_combined_facts && out_facts_topic.trim().is_empty() {
+        return Err(anyhow!(
+            "KAFKA_OUT_FACTS_TOPIC is empty (required when EMIT_COMBINED_FACTS=true)"
+        ));
+    }
+    if emit_parse_diagnostics && out_diagnostics_topic.trim().is_empty() {
+        return Err(anyhow!(
+            "KAFKA_OUT_DIAGNOSTICS_TOPIC is empty (required when EMIT_PARSE_DIAGNOSTICS=true)"
+        ));
+    }
+    if emit_tx_summary && out_tx_summary_topic.trim().is_empty() {
+        return Err(anyhow!(
+            "KAFKA_OUT_TX_SUMMARY_TOPIC is empty (required when EMIT_TX_SUMMARY=true)"
+        ));
+    }
+    if confirm_retractions && out_retractions_topic.trim().is_empty() {
+        return Err(anyhow!(
+            "KAFKA_OUT_RETRACTIONS_TOPIC is empty (required when CONFIRM_RETRACTIONS=true)"
+        ));
+    }
+    if gold_swaps && out_swaps_v2_topic.trim().is_empty() {
+        return Err(anyhow!(
+            "KAFKA_OUT_SWAPS_V2_TOPIC is empty (required when SWAPS_DUAL_WRITE=true or a venue program ID is set)"
+        ));
     }
 
     Ok(Config {
@@ -105,16 +677,138 @@ pub fn load() -> Result<Config> {
         rpc_concurrency,
         rpc_min_delay_ms,
         rpc_max_tx_version,
+        rpc_get_tx_method,
+        rpc_commitment,
+        rpc_max_backoff_ms,
+        rpc_max_retry_after_ms,
+        rpc_timeout_ms,
+        rpc_batch_size,
+        resolve_alt_via_rpc,
+        startup_warmup,
         kafka_broker,
+        sink_kind,
         in_topic,
         out_sol_deltas_topic,
         out_token_deltas_topic,
+        emit_combined_facts,
+        out_facts_topic,
         out_swaps_topic,
+        emit_tx_summary,
+        out_tx_summary_topic,
+        confirm_retractions,
+        confirm_delay_ms,
+        out_retractions_topic,
+        out_swaps_v2_topic,
+        swaps_dual_write,
+        gold_swaps,
+        disabled_venues,
+        swaps_order_by_block,
+        swaps_order_idle_flush,
         swaps_explain,
         swaps_explain_limit,
+        swaps_explain_log_lines,
+        swaps_explain_max_confidence,
+        swaps_allow_same_mint_arb,
+        swaps_min_confidence,
+        swaps_max_eps,
+        throttle_mode,
+        swap_prices,
+        swaps_min_notional_usd,
+        swaps_drop_unknown_notional,
+        pool_mints_cache_size,
+        mint_metadata,
+        mint_metadata_fetch,
+        mint_cache_size,
+        mint_cache_ttl,
+        index_in_tx_mode,
+        multihop_policy,
+        amount_sources,
+        stable_pool_programs,
+        raydium_pool_vaults,
+        swap_venues,
+        out_stable_swaps_topic,
+        emit_parse_diagnostics,
+        out_diagnostics_topic,
+        block_swap_counts,
+        out_block_counts_topic,
+        out_liquidity_topic,
+        detector_timings,
+        metrics_prom_file,
+        metrics_port,
         raydium_amm_v4_program_id,
+        chain_programs,
+        trader_exclude,
+        jito_tip_accounts,
+        liquidity_allow_zero_amount,
+        normalize_wsol,
         dlq_topic,
         consumer_group,
+        fetch_tuning,
         include_failed,
+        prefer_event_metadata,
+        raw_schema_versions,
+        raw_schema_mismatch,
+        strict_balance_lengths,
+        max_message_bytes,
+        dlq_max_field_bytes,
+        retry_max_block_ms,
+        producer_flush_ms,
+        schema_dump_count,
+        schema_dump_dir,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swaps_topic_required_only_with_detection() {
+        let amm = schema::RAYDIUM_AMM_V4_PROGRAM_ID;
+
+        // Detection enabled: the topic must be set
+        assert!(validate_swaps_topic(amm, false, "sol_swaps").is_ok());
+        let err = validate_swaps_topic(amm, false, " ").unwrap_err();
+        assert!(err.to_string().contains("KAFKA_OUT_SWAPS_TOPIC"));
+
+        // The gold registry runs without the Raydium program id
+        assert!(validate_swaps_topic("", true, "").is_err());
+
+        // Detection disabled: nothing is emitted, so an empty topic is fine
+        assert!(validate_swaps_topic("", false, "").is_ok());
+        assert!(validate_swaps_topic("", false, "sol_swaps").is_ok());
+    }
+
+    #[test]
+    fn test_venue_program_id_enables_its_detector() {
+        let orca = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+        let unset = [("phoenix", ""), ("orca", "")];
+        let orca_set = [("phoenix", ""), ("orca", orca)];
+
+        // ORCA_WHIRLPOOL_PROGRAM_ID alone turns the gold path on, for Orca only
+        let (gold, disabled) = gold_venue_gates(false, "", &orca_set);
+        assert!(gold);
+        assert_eq!(disabled, ["phoenix", "raydium"]);
+        assert!(validate_swaps_topic("", gold, "").is_err());
+
+        // Dual write runs Raydium, but no venue whose program ID is unset
+        let (gold, disabled) = gold_venue_gates(true, "", &unset);
+        assert!(gold);
+        assert_eq!(disabled, ["phoenix", "orca"]);
+
+        // Raydium's own variable keeps it next to an opted-in venue
+        let amm = schema::RAYDIUM_AMM_V4_PROGRAM_ID;
+        assert_eq!(gold_venue_gates(false, amm, &orca_set).1, ["phoenix"]);
+
+        // Nothing set: legacy path (or no detection) only
+        assert!(!gold_venue_gates(false, amm, &unset).0);
+    }
+
+    #[test]
+    fn test_dry_run_refuses_the_default_group() {
+        let err = validate_dry_run_group(true, DEFAULT_CONSUMER_GROUP).unwrap_err();
+        assert!(err.to_string().contains("KAFKA_GROUP"));
+        assert!(validate_dry_run_group(true, "decoder_dry_run").is_ok());
+        assert!(validate_dry_run_group(false, DEFAULT_CONSUMER_GROUP).is_ok());
+    }
+}
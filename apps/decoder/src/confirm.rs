@@ -0,0 +1,191 @@
+//! Confirmation pass for swaps emitted from `processed` data.
+//!
+//! Emitted swaps are queued per signature. Once `CONFIRM_DELAY_MS` has passed,
+//! their signatures are re-checked in batches with `getSignatureStatuses`; a
+//! transaction the cluster no longer knows was rolled back, and each of its
+//! swaps is retracted.
+
+use schema::SwapRetraction;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// `getSignatureStatuses` accepts at most 256 signatures per call
+pub const MAX_STATUS_BATCH: usize = 256;
+
+/// Outcome of re-checking one signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    /// Reached `confirmed` (or `finalized`): nothing to undo
+    Confirmed,
+    /// Still only `processed`: check again later
+    Pending,
+    /// Unknown to the cluster: the tx was rolled back
+    RolledBack,
+}
+
+/// Classify one entry of a `getSignatureStatuses` result (`null` = not found)
+pub fn classify_status(status: &Value) -> Confirmation {
+    if status.is_null() {
+        return Confirmation::RolledBack;
+    }
+    match status.get("confirmationStatus").and_then(|v| v.as_str()) {
+        Some("processed") => Confirmation::Pending,
+        _ => Confirmation::Confirmed,
+    }
+}
+
+/// Emitted swaps of one transaction awaiting confirmation
+#[derive(Debug, Clone)]
+pub struct PendingTx {
+    pub signature: String,
+    due: Instant,
+    swaps: Vec<SwapRetraction>,
+    rechecked: bool,
+}
+
+/// FIFO of emitted swaps; the delay is fixed, so entries come due in order
+#[derive(Debug)]
+pub struct ConfirmQueue {
+    delay: Duration,
+    queue: VecDeque<PendingTx>,
+}
+
+impl ConfirmQueue {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            queue: VecDeque::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Queue an emitted swap (hops of the same tx share one entry)
+    pub fn track(&mut self, swap: SwapRetraction, now: Instant) {
+        if let Some(last) = self.queue.back_mut()
+            && last.signature == swap.signature
+        {
+            last.swaps.push(swap);
+            return;
+        }
+        self.queue.push_back(PendingTx {
+            signature: swap.signature.clone(),
+            due: now + self.delay,
+            swaps: vec![swap],
+            rechecked: false,
+        });
+    }
+
+    /// Entries whose delay has passed, at most one status batch
+    pub fn take_due(&mut self, now: Instant) -> Vec<PendingTx> {
+        let mut due = Vec::new();
+        while due.len() < MAX_STATUS_BATCH && self.queue.front().is_some_and(|p| p.due <= now) {
+            due.extend(self.queue.pop_front());
+        }
+        due
+    }
+
+    /// Every entry, due or not (shutdown: their last check)
+    pub fn take_all(&mut self) -> Vec<PendingTx> {
+        self.queue.drain(..).collect()
+    }
+
+    /// Put entries back unchanged (e.g. the status call failed)
+    pub fn requeue(&mut self, pending: Vec<PendingTx>, now: Instant) {
+        for mut p in pending {
+            p.due = now + self.delay;
+            self.queue.push_back(p);
+        }
+    }
+
+    /// Decide each due entry from its status (same order as `take_due` returned).
+    ///
+    /// Rolled-back txs yield their retractions. Txs still at `processed` get one
+    /// more delay; if they are still not confirmed then, they are kept as is
+    /// rather than retracted on uncertain evidence.
+    pub fn resolve(
+        &mut self,
+        due: Vec<PendingTx>,
+        statuses: &[Value],
+        now: Instant,
+    ) -> Vec<SwapRetraction> {
+        let mut retractions = Vec::new();
+        for (mut pending, status) in due.into_iter().zip(statuses) {
+            match classify_status(status) {
+                Confirmation::Confirmed => {}
+                Confirmation::RolledBack => retractions.extend(pending.swaps),
+                Confirmation::Pending if !pending.rechecked => {
+                    pending.rechecked = true;
+                    pending.due = now + self.delay;
+                    self.queue.push_back(pending);
+                }
+                Confirmation::Pending => {}
+            }
+        }
+        retractions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn retraction(sig: &str, hop: u8) -> SwapRetraction {
+        SwapRetraction {
+            signature: sig.to_string(),
+            index_in_tx: 0,
+            hop_index: hop,
+        }
+    }
+
+    #[test]
+    fn test_missing_tx_is_retracted() {
+        let delay = Duration::from_millis(100);
+        let start = Instant::now();
+        let mut queue = ConfirmQueue::new(delay);
+
+        // Two hops of a route, then two single swaps
+        queue.track(retraction("sigRolledBack", 0), start);
+        queue.track(retraction("sigRolledBack", 1), start);
+        queue.track(retraction("sigConfirmed", 0), start);
+        queue.track(retraction("sigProcessed", 0), start);
+        assert_eq!(queue.len(), 3);
+
+        // Nothing due before the delay
+        assert!(queue.take_due(start).is_empty());
+
+        let later = start + delay;
+        let due = queue.take_due(later);
+        let statuses = [
+            Value::Null,
+            json!({"slot": 250000000, "confirmations": 10, "err": null, "confirmationStatus": "confirmed"}),
+            json!({"slot": 250000001, "confirmations": 0, "err": null, "confirmationStatus": "processed"}),
+        ];
+        let retractions = queue.resolve(due, &statuses, later);
+        assert_eq!(
+            retractions,
+            vec![
+                retraction("sigRolledBack", 0),
+                retraction("sigRolledBack", 1)
+            ]
+        );
+
+        // Still-processed tx gets one recheck; gone by then, so it is retracted
+        assert_eq!(queue.len(), 1);
+        let due = queue.take_due(later + delay);
+        assert_eq!(due[0].signature, "sigProcessed");
+        let retractions = queue.resolve(due, &[Value::Null], later + delay);
+        assert_eq!(retractions, vec![retraction("sigProcessed", 0)]);
+        assert_eq!(queue.len(), 0);
+
+        // Shutdown takes entries before they are due
+        queue.track(retraction("sigLate", 0), later);
+        let all = queue.take_all();
+        assert_eq!(all[0].signature, "sigLate");
+        assert_eq!(queue.len(), 0);
+    }
+}
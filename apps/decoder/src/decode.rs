@@ -115,8 +115,8 @@ pub fn decode_token_deltas(
 
     // key = (account_index, mint)
     // value = (decimals, amount_base_units)
-    let mut pre_map: HashMap<(u32, String), (Option<u8>, u64)> = HashMap::new();
-    let mut post_map: HashMap<(u32, String), (Option<u8>, u64)> = HashMap::new();
+    let mut pre_map: HashMap<(u32, String), (Option<u8>, u128)> = HashMap::new();
+    let mut post_map: HashMap<(u32, String), (Option<u8>, u128)> = HashMap::new();
 
     let pre = tx
         .pointer("/meta/preTokenBalances")
@@ -147,13 +147,13 @@ pub fn decode_token_deltas(
         }
     }
 
-    let parse_amount_u64 = |b: &Value| -> u64 {
+    let parse_amount = |b: &Value| -> u128 {
         // uiTokenAmount.amount is a string integer in base units
         let s = b
             .pointer("/uiTokenAmount/amount")
             .and_then(|v| v.as_str())
             .unwrap_or("0");
-        s.parse::<u64>().unwrap_or(0)
+        s.parse::<u128>().unwrap_or(0)
     };
 
     let parse_decimals = |b: &Value| -> Option<u8> {
@@ -173,7 +173,7 @@ pub fn decode_token_deltas(
             continue;
         }
 
-        let amt = parse_amount_u64(b);
+        let amt = parse_amount(b);
         let decimals = parse_decimals(b);
         pre_map.insert((idx, mint), (decimals, amt));
     }
@@ -189,7 +189,7 @@ pub fn decode_token_deltas(
             continue;
         }
 
-        let amt = parse_amount_u64(b);
+        let amt = parse_amount(b);
         let decimals = parse_decimals(b);
         post_map.insert((idx, mint), (decimals, amt));
     }
@@ -219,8 +219,8 @@ pub fn decode_token_deltas(
 
         let decimals = dec_post.or(dec_pre);
 
-        let delta_i128 = post_amt as i128 - pre_amt as i128;
-        let delta = delta_i128.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+        // Same arithmetic as TxFacts: exact for balances up to i128::MAX
+        let delta = post_amt as i128 - pre_amt as i128;
 
         out.push(TokenBalanceDelta {
             slot,
@@ -389,6 +389,44 @@ mod tests {
         assert_eq!(deltas.len(), 0);
     }
 
+    #[test]
+    fn test_decode_token_deltas_beyond_u64() {
+        // Fixture: high-supply token whose balances don't fit in u64
+        let pre = u64::MAX as u128 + 1;
+        let post = u64::MAX as u128 * 4;
+        let tx = json!({
+            "meta": {
+                "preTokenBalances": [
+                    {
+                        "accountIndex": 3,
+                        "mint": "MemeMint111",
+                        "uiTokenAmount": {"amount": pre.to_string(), "decimals": 6}
+                    }
+                ],
+                "postTokenBalances": [
+                    {
+                        "accountIndex": 3,
+                        "mint": "MemeMint111",
+                        "uiTokenAmount": {"amount": post.to_string(), "decimals": 6}
+                    }
+                ]
+            }
+        });
+
+        let deltas = decode_token_deltas(123456, Some(1734643200), "test_sig_big", &tx);
+
+        // Exact amounts and delta, no truncation or clamping to i64
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].pre_amount, pre);
+        assert_eq!(deltas[0].post_amount, post);
+        assert_eq!(deltas[0].delta, (post - pre) as i128);
+        assert!(deltas[0].delta > i64::MAX as i128);
+
+        // And they reach the wire as exact integers
+        let json = serde_json::to_string(&deltas[0]).unwrap();
+        assert!(json.contains(&format!("\"post_amount\":{post},")));
+    }
+
     #[test]
     fn test_inspect_token_balances() {
         let tx = json!({
@@ -0,0 +1,213 @@
+//! Parser rollout check: run two detector versions over a directory of recorded
+//! transactions and report every swap they disagree on.
+//!
+//! Both sides are compared as legacy `SwapEvent`s (gold swaps convert via
+//! `From<&DexSwapV1>`), hop by hop in emission order.
+
+use schema::SwapEvent;
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+
+/// What differs between the old and new detector for one fixture
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffKind {
+    /// Number of swaps emitted
+    Count {
+        old: usize,
+        new: usize,
+    },
+    /// `(in_mint, out_mint)` of the hop
+    Direction {
+        old: (String, String),
+        new: (String, String),
+    },
+    /// `(in_amount, out_amount)` of the hop
+    Amounts {
+        old: (String, String),
+        new: (String, String),
+    },
+    Confidence {
+        old: u8,
+        new: u8,
+    },
+}
+
+/// One disagreement; `hop` is the swap's position in the emitted list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diff {
+    pub fixture: String,
+    pub hop: Option<usize>,
+    pub kind: DiffKind,
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.hop {
+            Some(hop) => write!(f, "{} hop={}: {:?}", self.fixture, hop, self.kind),
+            None => write!(f, "{}: {:?}", self.fixture, self.kind),
+        }
+    }
+}
+
+/// Compare the swaps two detectors emit for one transaction
+pub fn diff_swaps(fixture: &str, old: &[SwapEvent], new: &[SwapEvent]) -> Vec<Diff> {
+    let diff = |hop, kind| Diff {
+        fixture: fixture.to_string(),
+        hop,
+        kind,
+    };
+    let mut diffs = Vec::new();
+    if old.len() != new.len() {
+        diffs.push(diff(
+            None,
+            DiffKind::Count {
+                old: old.len(),
+                new: new.len(),
+            },
+        ));
+    }
+    for (hop, (o, n)) in old.iter().zip(new).enumerate() {
+        let (old_dir, new_dir) = (
+            (o.in_mint.clone(), o.out_mint.clone()),
+            (n.in_mint.clone(), n.out_mint.clone()),
+        );
+        if old_dir != new_dir {
+            // Amounts of opposite directions are not comparable
+            diffs.push(diff(
+                Some(hop),
+                DiffKind::Direction {
+                    old: old_dir,
+                    new: new_dir,
+                },
+            ));
+            continue;
+        }
+        let (old_amounts, new_amounts) = (
+            (o.in_amount.clone(), o.out_amount.clone()),
+            (n.in_amount.clone(), n.out_amount.clone()),
+        );
+        if old_amounts != new_amounts {
+            diffs.push(diff(
+                Some(hop),
+                DiffKind::Amounts {
+                    old: old_amounts,
+                    new: new_amounts,
+                },
+            ));
+        }
+        if o.confidence != n.confidence {
+            diffs.push(diff(
+                Some(hop),
+                DiffKind::Confidence {
+                    old: o.confidence,
+                    new: n.confidence,
+                },
+            ));
+        }
+    }
+    diffs
+}
+
+/// Run `old` and `new` over every recorded tx (`*.json` with a `transaction`
+/// object) in `fixtures`, in file name order
+pub fn diff_detectors<O, N>(old: O, new: N, fixtures: &Path) -> Vec<Diff>
+where
+    O: Fn(&Value) -> Vec<SwapEvent>,
+    N: Fn(&Value) -> Vec<SwapEvent>,
+{
+    let mut paths: Vec<_> = std::fs::read_dir(fixtures)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", fixtures.display(), e))
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    let mut diffs = Vec::new();
+    for path in paths {
+        let tx: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap())
+            .unwrap_or_else(|e| panic!("Failed to parse {}: {}", path.display(), e));
+        // Expected-output snapshots live alongside the txs
+        if tx.get("transaction").is_none() {
+            continue;
+        }
+        let name = path.file_stem().unwrap().to_string_lossy();
+        diffs.extend(diff_swaps(&name, &old(&tx), &new(&tx)));
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::DetectorOptions;
+    use crate::detectors::raydium_v4::detect_raydium_v4_swap;
+    use crate::detectors::raydium_v4_gold::parse_raydium_v4_swaps;
+    use schema::{RAYDIUM_AMM_V4_PROGRAM_ID, TxFacts, extract_program_ids_from_transaction};
+
+    fn signature(tx: &Value) -> &str {
+        tx.pointer("/transaction/signatures/0")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+    }
+
+    fn slot(tx: &Value) -> u64 {
+        tx.get("slot").and_then(|v| v.as_u64()).unwrap_or(0)
+    }
+
+    #[test]
+    fn test_legacy_vs_gold_raydium() {
+        let legacy = |tx: &Value| -> Vec<SwapEvent> {
+            detect_raydium_v4_swap(
+                "solana-mainnet",
+                slot(tx),
+                tx.get("blockTime").and_then(|v| v.as_i64()),
+                signature(tx),
+                &extract_program_ids_from_transaction(tx),
+                RAYDIUM_AMM_V4_PROGRAM_ID,
+                tx,
+                false,
+            )
+            .into_iter()
+            .collect()
+        };
+        let gold = |tx: &Value| -> Vec<SwapEvent> {
+            let facts = TxFacts::from_json(tx, signature(tx), slot(tx));
+            let opts = DetectorOptions::default();
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &opts)
+                .iter()
+                .map(SwapEvent::from)
+                .collect()
+        };
+
+        let fixtures = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures"
+        ));
+        let diffs = diff_detectors(legacy, gold, fixtures);
+
+        // Legacy nets the trader's deltas, so a two-hop route collapses into one swap
+        let count = Diff {
+            fixture: "jupiter_two_hop_inner".to_string(),
+            hop: None,
+            kind: DiffKind::Count { old: 1, new: 2 },
+        };
+        assert!(diffs.contains(&count));
+        assert_eq!(count.to_string(), "jupiter_two_hop_inner: Count { old: 1, new: 2 }");
+        // Gold scores confidence from reasons; legacy always reports 80
+        let confidence = diffs
+            .iter()
+            .find(|d| {
+                d.fixture == "jito_tip_swap"
+                    && d.kind == DiffKind::Confidence { old: 80, new: 100 }
+            })
+            .unwrap();
+        assert_eq!(
+            confidence.to_string(),
+            "jito_tip_swap hop=0: Confidence { old: 80, new: 100 }"
+        );
+
+        // Identical detectors never differ
+        assert!(diff_detectors(gold, gold, fixtures).is_empty());
+    }
+}
@@ -0,0 +1,190 @@
+//! Jupiter v6 aggregator routes.
+//!
+//! Jupiter does not swap itself: its route instruction CPIs into one AMM per
+//! hop, and each hop is parsed by that venue's own detector. This module finds
+//! which of those swaps ran directly under a Jupiter route instruction and ties
+//! them into one route: a shared `route_id`, `hop_index` in CPI order across
+//! venues, and the trader resolved once from the route instruction's signer
+//! (a venue parser sees one hop and may settle on another account owner).
+//!
+//! A route's net in/out is carried by its hops: hop 0's input is what the
+//! trader paid, the highest `hop_index`'s output what they received.
+
+use schema::{ConfidenceReasons, DexSwapV1, ParsedInstruction, TxFacts};
+
+use super::{DetectorOptions, venue_programs};
+
+/// Swaps executed by one Jupiter route instruction, in hop order
+#[derive(Debug, Clone)]
+pub struct JupiterRoute {
+    pub route_id: String,
+    pub trader: String,
+    /// Venue CPIs under the route instruction that produced no swap
+    pub missing_legs: usize,
+    pub hops: Vec<DexSwapV1>,
+}
+
+/// First signer among the route instruction's accounts (fee payer's signer
+/// set as fallback), skipping `trader_exclude`
+fn route_trader(
+    facts: &TxFacts,
+    route_ix: &ParsedInstruction,
+    opts: &DetectorOptions,
+) -> Option<String> {
+    route_ix
+        .accounts
+        .iter()
+        .filter_map(|&i| facts.account_at(i))
+        .chain(facts.signers.iter().map(String::as_str))
+        .find(|a| facts.is_signer(a) && !opts.trader_exclude.contains(*a))
+        .map(str::to_string)
+}
+
+/// Group the venue swaps executed by Jupiter route instructions into routes.
+///
+/// This is a pure function - no RPC calls, no side effects.
+///
+/// Each direct venue CPI of a route instruction takes its swap (same venue and
+/// `index_in_tx`) out of `swaps`; swaps outside any route are left in place.
+pub fn assemble_jupiter_routes(
+    facts: &TxFacts,
+    swaps: &mut Vec<DexSwapV1>,
+    opts: &DetectorOptions,
+) -> Vec<JupiterRoute> {
+    let jupiter = &opts.programs.jupiter_v6;
+    if !facts.has_program(jupiter) {
+        return vec![];
+    }
+    let venues = venue_programs(&opts.programs);
+
+    let mut routes = Vec::new();
+    for route_ix in facts
+        .outer_instructions
+        .iter()
+        .filter(|ix| ix.program_id == *jupiter)
+    {
+        let outer = route_ix.outer_ix_index.unwrap_or(0);
+        let children = facts.cpi_children_of(outer);
+        let legs: Vec<(&str, u16)> = children
+            .get(&None)
+            .into_iter()
+            .flatten()
+            .filter_map(|ix| {
+                let (venue, _) = venues.iter().find(|(_, p)| **p == ix.program_id)?;
+                Some((*venue, opts.index_in_tx_mode.index_in_tx(ix)))
+            })
+            .collect();
+
+        // In CPI order; under outer-only indexes a venue's hops match in turn
+        let mut hops = Vec::new();
+        for (venue, index) in &legs {
+            if let Some(pos) = swaps
+                .iter()
+                .position(|s| s.venue == *venue && s.index_in_tx == *index)
+            {
+                hops.push(swaps.remove(pos));
+            }
+        }
+        if hops.is_empty() {
+            continue;
+        }
+
+        let trader = route_trader(facts, route_ix, opts).unwrap_or_else(|| hops[0].trader.clone());
+        let route_id = format!(
+            "{}:{}",
+            &facts.signature[..16.min(facts.signature.len())],
+            outer
+        );
+        let missing_legs = legs.len() - hops.len();
+        for (hop_index, hop) in hops.iter_mut().enumerate() {
+            hop.hop_index = hop_index as u8;
+            hop.route_id = Some(route_id.clone());
+            hop.partial |= missing_legs > 0;
+            if legs.len() > 1 {
+                hop.remove_confidence_reason(ConfidenceReasons::SINGLE_HOP);
+            }
+            if hop.trader != trader {
+                hop.trader = trader.clone();
+                hop.sol_cost_lamports = Some(facts.sol_cost(&trader, &opts.jito_tip_accounts));
+                hop.remove_confidence_reason(ConfidenceReasons::TRADER_FROM_OWNER);
+                hop.add_confidence_reason(ConfidenceReasons::TRADER_IS_SIGNER);
+            }
+        }
+
+        routes.push(JupiterRoute {
+            route_id,
+            trader,
+            missing_legs,
+            hops,
+        });
+    }
+    routes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::IndexInTxMode;
+    use crate::detectors::raydium_v4_gold::parse_raydium_v4_swaps;
+
+    const MSOL: &str = "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So";
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    #[test]
+    fn test_route_hops_share_route_id_and_trader() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/jupiter_two_hop_inner.json"
+        );
+        let tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let facts = TxFacts::from_json(&tx, "JupiterTwoHopSig", 250000000);
+        // Composite indexes tell the two hops under outer ix 0 apart
+        let opts = DetectorOptions {
+            index_in_tx_mode: IndexInTxMode::Composite,
+            ..Default::default()
+        };
+
+        // Venue output out of order and with a per-hop trader guess
+        let mut swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &opts);
+        swaps.reverse();
+        swaps[0].trader = "IntermediateOwner111".to_string();
+        swaps[0].route_id = None;
+
+        let routes = assemble_jupiter_routes(&facts, &mut swaps, &opts);
+        assert!(swaps.is_empty());
+        assert_eq!(routes.len(), 1);
+        let route = &routes[0];
+        assert_eq!(route.route_id, "JupiterTwoHopSig:0");
+        assert_eq!(route.trader, "RouteTrader1111111111111111111111111111111");
+        assert_eq!(route.missing_legs, 0);
+        // Net in/out: the first hop's input, the last hop's output
+        let (first, last) = (&route.hops[0], route.hops.last().unwrap());
+        assert_eq!(first.in_mint, schema::WSOL_MINT);
+        assert_eq!(first.in_amount, "5000000000");
+        assert_eq!(last.out_mint, USDC);
+        assert_eq!(last.out_amount, "485000000");
+
+        let hops: Vec<_> = route
+            .hops
+            .iter()
+            .map(|h| (h.hop_index, h.index_in_tx, h.out_mint.as_str()))
+            .collect();
+        assert_eq!(hops, vec![(0, 1, MSOL), (1, 2, USDC)]);
+        for hop in &route.hops {
+            assert_eq!(hop.route_id.as_deref(), Some("JupiterTwoHopSig:0"));
+            assert_eq!(hop.trader, route.trader);
+            assert!(!ConfidenceReasons(hop.confidence_reasons).has(ConfidenceReasons::SINGLE_HOP));
+            assert!(!hop.partial);
+        }
+
+        // Another aggregator program: hops stay with their venue
+        let other = DetectorOptions {
+            programs: opts.programs.clone().with_jupiter_v6("OtherRouter111"),
+            ..Default::default()
+        };
+        let mut swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &other);
+        assert!(assemble_jupiter_routes(&facts, &mut swaps, &other).is_empty());
+        assert_eq!(swaps.len(), 2);
+    }
+}
@@ -0,0 +1,196 @@
+//! Lifinity v2 (proactive market maker) swaps.
+//!
+//! Lifinity prices from an oracle and rebalances its pools proactively, so the
+//! vault side of a swap need not mirror the trader's legs (fees and
+//! rebalancing stay in or leave the pool). The pool (AMM) account, trader and
+//! their source/destination token accounts are read from the instruction and
+//! in/out come from the trader's deltas only; VAULT_MATCH is never set. The
+//! amounts are confirmed against the instruction's `amount_in` and
+//! `minimum_amount_out`.
+
+use schema::{
+    ConfidenceReasons, DexSwapV1, DexSwapV1Builder, InOut, PairingStrategy, ParsedInstruction,
+    TxFacts, pair_in_out,
+};
+
+use super::DetectorOptions;
+
+/// Anchor discriminator of `swap` (sha256("global:swap")[..8])
+const SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+
+mod lifinity_accounts {
+    /// AMM (pool) account (index 1 in swap instruction)
+    pub const AMM: usize = 1;
+    /// User transfer authority (index 2)
+    pub const TRADER: usize = 2;
+    /// User source token account (index 3)
+    pub const SOURCE_ACCOUNT: usize = 3;
+    /// User destination token account (index 4)
+    pub const DESTINATION_ACCOUNT: usize = 4;
+}
+
+/// `(amount_in, minimum_amount_out)` of a swap instruction with its full
+/// account list; `None` for any other Lifinity instruction
+fn swap_args(ix: &ParsedInstruction) -> Option<(u64, u64)> {
+    let data = bs58::decode(ix.data.as_deref()?).into_vec().ok()?;
+    if data.get(..8)? != SWAP_DISCRIMINATOR
+        || ix.accounts.len() <= lifinity_accounts::DESTINATION_ACCOUNT
+    {
+        return None;
+    }
+    let amount_in = u64::from_le_bytes(data.get(8..16)?.try_into().ok()?);
+    let minimum_amount_out = u64::from_le_bytes(data.get(16..24)?.try_into().ok()?);
+    Some((amount_in, minimum_amount_out))
+}
+
+/// Parse Lifinity v2 swaps from TxFacts.
+///
+/// This is a pure function - no RPC calls, no side effects.
+///
+/// Returns one DexSwapV1 per swap instruction.
+pub fn parse_lifinity_swaps(
+    facts: &TxFacts,
+    chain: &str,
+    index_in_block: u32,
+    explain_enabled: bool,
+    opts: &DetectorOptions,
+) -> Vec<DexSwapV1> {
+    if !facts.has_program(&opts.programs.lifinity_v2) {
+        return vec![];
+    }
+
+    let swap_ixs: Vec<(&ParsedInstruction, (u64, u64))> = facts
+        .instructions_for_program(&opts.programs.lifinity_v2)
+        .into_iter()
+        .filter_map(|ix| Some((ix, swap_args(ix)?)))
+        .collect();
+    if swap_ixs.is_empty() {
+        return vec![];
+    }
+
+    let is_multi_hop = swap_ixs.len() > 1;
+    let route_id = is_multi_hop.then(|| {
+        let first_ix = swap_ixs[0].0.outer_ix_index.unwrap_or(0);
+        format!(
+            "{}:{}",
+            &facts.signature[..16.min(facts.signature.len())],
+            first_ix
+        )
+    });
+    let jito_bundle = facts.tip_lamports(&opts.jito_tip_accounts) > 0;
+
+    swap_ixs
+        .iter()
+        .enumerate()
+        .filter_map(|(hop_idx, (ix, (amount_in, minimum_amount_out)))| {
+            let account = |i: usize| facts.account_at(ix.accounts[i]);
+            let pool = account(lifinity_accounts::AMM)?;
+            let trader = account(lifinity_accounts::TRADER)?;
+
+            let user_accounts = [
+                ix.accounts[lifinity_accounts::SOURCE_ACCOUNT],
+                ix.accounts[lifinity_accounts::DESTINATION_ACCOUNT],
+            ];
+            let deltas: Vec<_> = facts
+                .token_balance_deltas
+                .iter()
+                .filter(|d| user_accounts.contains(&(d.account_index as usize)))
+                .collect();
+            let InOut {
+                in_delta,
+                out_delta,
+            } = pair_in_out(&deltas, PairingStrategy::FirstMatch, &[])?;
+            let (in_amount, out_amount) = ((-in_delta.delta) as u128, out_delta.delta as u128);
+
+            let mut builder = DexSwapV1Builder::new()
+                .chain(chain)
+                .slot(facts.slot)
+                .block_time(facts.block_time)
+                .signature(&facts.signature)
+                .index_in_block(index_in_block)
+                .index_in_tx(opts.index_in_tx_mode.index_in_tx(ix))
+                .hop_index(hop_idx as u8)
+                .venue("lifinity")
+                .pool_id(Some(pool.to_string()))
+                .trader(trader)
+                .in_token(&in_delta.mint, in_amount)
+                .out_token(&out_delta.mint, out_amount)
+                .route_id(route_id.clone())
+                .sol_cost_lamports(Some(facts.sol_cost(trader, &opts.jito_tip_accounts)))
+                .jito_bundle(jito_bundle)
+                .explain_enabled(explain_enabled)
+                .with_confidence_reason(ConfidenceReasons::PROGRAM_GATE)
+                .with_confidence_reason(ConfidenceReasons::POOL_ID_FROM_IX);
+
+            if in_amount == *amount_in as u128 && out_amount >= *minimum_amount_out as u128 {
+                builder.add_confidence_reason(ConfidenceReasons::AMOUNTS_CONFIRMED);
+            }
+            if in_delta.owner.as_deref() == Some(trader)
+                && out_delta.owner.as_deref() == Some(trader)
+            {
+                builder.add_confidence_reason(ConfidenceReasons::TRADER_FROM_OWNER);
+            } else if facts.is_signer(trader) {
+                builder.add_confidence_reason(ConfidenceReasons::TRADER_IS_SIGNER);
+            }
+            if !is_multi_hop {
+                builder.add_confidence_reason(ConfidenceReasons::SINGLE_HOP);
+            }
+            if facts.is_success {
+                builder.add_confidence_reason(ConfidenceReasons::TX_SUCCESS);
+            }
+
+            let swap = builder.build();
+            swap.validate_with(opts.allow_same_mint_arb).is_ok().then_some(swap)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::{fixture_facts, fixture_json};
+
+    #[test]
+    fn test_lifinity_swap_from_trader_deltas() {
+        let facts = fixture_facts("lifinity_swap", "LifinitySwapSig");
+        let swaps = parse_lifinity_swaps(
+            &facts,
+            "solana-mainnet",
+            0,
+            true,
+            &DetectorOptions::default(),
+        );
+
+        assert_eq!(swaps.len(), 1);
+        let swap = &swaps[0];
+        assert_eq!(swap.venue, "lifinity");
+        assert_eq!(
+            swap.pool_id.as_deref(),
+            Some("LifinityAmmPool11111111111111111111111111111")
+        );
+        assert_eq!(swap.trader, "LifinityTrader111111111111111111111111111111");
+        assert_eq!(swap.in_mint, "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+        assert_eq!(swap.in_amount, "100000000");
+        assert_eq!(swap.out_mint, schema::WSOL_MINT);
+        assert_eq!(swap.out_amount, "951200000");
+        let reasons = ConfidenceReasons(swap.confidence_reasons);
+        assert!(reasons.has(ConfidenceReasons::TRADER_FROM_OWNER));
+        assert!(reasons.has(ConfidenceReasons::AMOUNTS_CONFIRMED));
+        assert!(!reasons.has(ConfidenceReasons::VAULT_MATCH));
+
+        // Another Lifinity instruction with the same accounts is not a swap
+        let mut not_swap = fixture_json("lifinity_swap");
+        not_swap["transaction"]["message"]["instructions"][0]["data"] =
+            bs58::encode([1u8; 24]).into_string().into();
+        let not_swap = TxFacts::from_json(&not_swap, "LifinitySwapSig", 250000500);
+        let opts = DetectorOptions::default();
+        assert!(parse_lifinity_swaps(&not_swap, "solana-mainnet", 0, false, &opts).is_empty());
+
+        // Gate follows the configured program ID
+        let other = DetectorOptions {
+            programs: schema::ChainPrograms::mainnet().with_lifinity_v2("OtherLifinity111"),
+            ..Default::default()
+        };
+        assert!(parse_lifinity_swaps(&facts, "solana-mainnet", 0, false, &other).is_empty());
+    }
+}
@@ -0,0 +1,278 @@
+//! Meteora DLMM (dynamic liquidity market maker) swaps.
+//!
+//! Every swap variant (`swap`, `swap_exact_out`, `swap_with_price_impact` and
+//! their `*2` Token-2022 forms) names the lb_pair, its two reserves and the
+//! user's token accounts at the same positions. The lb_pair is the pool id and
+//! the trader is the owner of the user token accounts. Meteora is often reached
+//! as a CPI of a Jupiter route, where the user accounts may be the aggregator's
+//! intermediates; such hops take their legs from the lb_pair's reserves.
+
+use schema::{
+    ConfidenceReasons, DexSwapV1, DexSwapV1Builder, InOut, PairingStrategy, ParsedInstruction,
+    TxFacts, pair_in_out,
+};
+
+use super::DetectorOptions;
+use super::raydium_v4_gold::vault_flow_between;
+
+/// Anchor discriminators of the swap instructions (sha256("global:<name>")[..8])
+const SWAP_DISCRIMINATORS: [[u8; 8]; 6] = [
+    // swap
+    [248, 198, 158, 145, 225, 117, 135, 200],
+    // swap_exact_out
+    [250, 73, 101, 33, 38, 207, 75, 184],
+    // swap_with_price_impact
+    [56, 173, 230, 208, 173, 228, 156, 205],
+    // swap2
+    [65, 75, 63, 76, 235, 91, 91, 136],
+    // swap_exact_out2
+    [43, 215, 247, 132, 137, 60, 243, 81],
+    // swap_with_price_impact2
+    [74, 98, 192, 214, 177, 51, 75, 51],
+];
+
+mod dlmm_accounts {
+    /// lb_pair (pool) account (index 0 in swap instruction)
+    pub const LB_PAIR: usize = 0;
+    /// Reserve of token X (index 2)
+    pub const RESERVE_X: usize = 2;
+    /// Reserve of token Y (index 3)
+    pub const RESERVE_Y: usize = 3;
+    /// User token account paid from (index 4)
+    pub const USER_TOKEN_IN: usize = 4;
+    /// User token account paid into (index 5)
+    pub const USER_TOKEN_OUT: usize = 5;
+    /// User (signer) account (index 10)
+    pub const USER: usize = 10;
+}
+
+/// Whether `ix` is one of the DLMM swap instructions with its full account list
+fn is_swap_ix(ix: &ParsedInstruction) -> bool {
+    let Some(data) = ix
+        .data
+        .as_deref()
+        .and_then(|d| bs58::decode(d).into_vec().ok())
+    else {
+        return false;
+    };
+    data.get(..8)
+        .is_some_and(|d| SWAP_DISCRIMINATORS.iter().any(|s| s == d))
+        && ix.accounts.len() > dlmm_accounts::USER
+}
+
+/// Single owner of every balance entry of the listed token accounts, if any
+fn token_account_owner<'a>(facts: &'a TxFacts, accounts: &[usize]) -> Option<&'a str> {
+    let mut owners = facts
+        .pre_token_balances
+        .iter()
+        .chain(&facts.post_token_balances)
+        .filter(|b| accounts.contains(&(b.account_index as usize)))
+        .map(|b| b.owner.as_deref());
+    let first = owners.next()??;
+    owners.all(|owner| owner == Some(first)).then_some(first)
+}
+
+/// Parse Meteora DLMM swaps from TxFacts.
+///
+/// This is a pure function - no RPC calls, no side effects.
+///
+/// Returns one DexSwapV1 per swap instruction, outer or CPI.
+pub fn parse_meteora_dlmm_swaps(
+    facts: &TxFacts,
+    chain: &str,
+    index_in_block: u32,
+    explain_enabled: bool,
+    opts: &DetectorOptions,
+) -> Vec<DexSwapV1> {
+    if !facts.has_program(&opts.programs.meteora_dlmm) {
+        return vec![];
+    }
+
+    let swap_ixs: Vec<&ParsedInstruction> = facts
+        .instructions_for_program(&opts.programs.meteora_dlmm)
+        .into_iter()
+        .filter(|ix| is_swap_ix(ix))
+        .collect();
+    if swap_ixs.is_empty() {
+        return vec![];
+    }
+
+    let is_multi_hop = swap_ixs.len() > 1;
+    let route_id = is_multi_hop.then(|| {
+        let first_ix = swap_ixs[0].outer_ix_index.unwrap_or(0);
+        format!(
+            "{}:{}",
+            &facts.signature[..16.min(facts.signature.len())],
+            first_ix
+        )
+    });
+    let jito_bundle = facts.tip_lamports(&opts.jito_tip_accounts) > 0;
+
+    swap_ixs
+        .iter()
+        .enumerate()
+        .filter_map(|(hop_idx, ix)| {
+            let account = |i: usize| facts.account_at(ix.accounts[i]);
+            let pool = account(dlmm_accounts::LB_PAIR)?;
+            let user_accounts = [
+                ix.accounts[dlmm_accounts::USER_TOKEN_IN],
+                ix.accounts[dlmm_accounts::USER_TOKEN_OUT],
+            ];
+            let owner = token_account_owner(facts, &user_accounts)
+                .filter(|o| !opts.trader_exclude.contains(*o));
+            let trader = match owner {
+                Some(owner) => owner,
+                None => account(dlmm_accounts::USER)?,
+            };
+            let reserves = vault_flow_between(
+                facts,
+                ix.accounts[dlmm_accounts::RESERVE_X],
+                ix.accounts[dlmm_accounts::RESERVE_Y],
+            );
+
+            // A CPI'd hop may pay through an aggregator's intermediate accounts,
+            // which net to zero across the route: take its legs from the reserves
+            let (in_mint, in_amount, out_mint, out_amount, vault_match) =
+                if is_multi_hop || ix.stack_depth > 0 {
+                    let (reserve_in, reserve_out) = reserves?;
+                    (
+                        reserve_in.mint.clone(),
+                        reserve_in.delta.unsigned_abs(),
+                        reserve_out.mint.clone(),
+                        reserve_out.delta.unsigned_abs(),
+                        true,
+                    )
+                } else {
+                    let deltas: Vec<_> = facts
+                        .token_balance_deltas
+                        .iter()
+                        .filter(|d| user_accounts.contains(&(d.account_index as usize)))
+                        .collect();
+                    let InOut {
+                        in_delta,
+                        out_delta,
+                    } = pair_in_out(&deltas, PairingStrategy::FirstMatch, &[])?;
+                    let vault_match = reserves.is_some_and(|(reserve_in, reserve_out)| {
+                        reserve_in.mint == in_delta.mint && reserve_out.mint == out_delta.mint
+                    });
+                    (
+                        in_delta.mint.clone(),
+                        in_delta.delta.unsigned_abs(),
+                        out_delta.mint.clone(),
+                        out_delta.delta.unsigned_abs(),
+                        vault_match,
+                    )
+                };
+
+            let mut builder = DexSwapV1Builder::new()
+                .chain(chain)
+                .slot(facts.slot)
+                .block_time(facts.block_time)
+                .signature(&facts.signature)
+                .index_in_block(index_in_block)
+                .index_in_tx(opts.index_in_tx_mode.index_in_tx(ix))
+                .hop_index(hop_idx as u8)
+                .venue("meteora")
+                .pool_id(Some(pool.to_string()))
+                .trader(trader)
+                .in_token(&in_mint, in_amount)
+                .out_token(&out_mint, out_amount)
+                .route_id(route_id.clone())
+                .sol_cost_lamports(Some(facts.sol_cost(trader, &opts.jito_tip_accounts)))
+                .jito_bundle(jito_bundle)
+                .explain_enabled(explain_enabled)
+                .with_confidence_reason(ConfidenceReasons::PROGRAM_GATE)
+                .with_confidence_reason(ConfidenceReasons::POOL_ID_FROM_IX);
+
+            if vault_match {
+                builder.add_confidence_reason(ConfidenceReasons::VAULT_MATCH);
+            }
+            if owner.is_some() {
+                builder.add_confidence_reason(ConfidenceReasons::TRADER_FROM_OWNER);
+            } else if facts.is_signer(trader) {
+                builder.add_confidence_reason(ConfidenceReasons::TRADER_IS_SIGNER);
+            }
+            if !is_multi_hop {
+                builder.add_confidence_reason(ConfidenceReasons::SINGLE_HOP);
+            }
+            if facts.is_success {
+                builder.add_confidence_reason(ConfidenceReasons::TX_SUCCESS);
+            }
+
+            let swap = builder.build();
+            swap.validate_with(opts.allow_same_mint_arb)
+                .is_ok()
+                .then_some(swap)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::fixture_facts;
+    use crate::detectors::registry::DetectorRegistry;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_dlmm_swap_emits_meteora_swap() {
+        let facts = fixture_facts("meteora_dlmm_swap", "MeteoraSwapSig");
+        let swaps = parse_meteora_dlmm_swaps(
+            &facts,
+            "solana-mainnet",
+            0,
+            true,
+            &DetectorOptions::default(),
+        );
+
+        assert_eq!(swaps.len(), 1);
+        let swap = &swaps[0];
+        assert_eq!(swap.venue, "meteora");
+        assert_eq!(
+            swap.pool_id.as_deref(),
+            Some("MeteoraLbPair1111111111111111111111111111111")
+        );
+        assert_eq!(swap.trader, "MeteoraTrader1111111111111111111111111111111");
+        assert_eq!(swap.in_mint, schema::WSOL_MINT);
+        assert_eq!(swap.in_amount, "1000000000");
+        assert_eq!(
+            swap.out_mint,
+            "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+        );
+        assert_eq!(swap.out_amount, "150000000");
+        let reasons = ConfidenceReasons(swap.confidence_reasons);
+        assert!(reasons.has(ConfidenceReasons::VAULT_MATCH));
+        assert!(reasons.has(ConfidenceReasons::TRADER_FROM_OWNER));
+
+        // Gate follows the configured program ID
+        let other = DetectorOptions {
+            programs: schema::ChainPrograms::mainnet().with_meteora_dlmm("OtherDlmm111"),
+            ..Default::default()
+        };
+        assert!(parse_meteora_dlmm_swaps(&facts, "solana-mainnet", 0, false, &other).is_empty());
+    }
+
+    #[test]
+    fn test_dlmm_cpi_from_jupiter_joins_the_route() {
+        let facts = fixture_facts("meteora_dlmm_jupiter_cpi", "MeteoraJupiterSig");
+        let opts = DetectorOptions::default();
+
+        // The inner swap ix is parsed, legs from the lb_pair's reserves
+        let swaps = parse_meteora_dlmm_swaps(&facts, "solana-mainnet", 0, false, &opts);
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].in_amount, "1000000000");
+        assert_eq!(swaps[0].out_amount, "150000000");
+
+        let registry = DetectorRegistry::new(Arc::new(opts));
+        let swaps = registry.detect(&facts, "solana-mainnet", 0, false).unwrap();
+        assert_eq!(swaps.len(), 1);
+        let swap = &swaps[0];
+        assert_eq!(swap.venue, "meteora");
+        assert_eq!(
+            swap.pool_id.as_deref(),
+            Some("MeteoraLbPair1111111111111111111111111111111")
+        );
+        assert_eq!(swap.trader, "RouteTrader111111111111111111111111111111111");
+        assert!(swap.route_id.is_some());
+    }
+}
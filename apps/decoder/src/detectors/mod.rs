@@ -1,2 +1,483 @@
+#[cfg(test)]
+pub(crate) mod diff;
+pub mod jupiter;
+pub mod lifinity;
+pub mod meteora_dlmm;
+pub mod orca_whirlpool;
+pub mod phoenix;
+pub mod raydium_liquidity;
 pub mod raydium_v4;
 pub mod raydium_v4_gold;
+pub mod registry;
+pub mod stable_pool;
+
+use schema::{ChainPrograms, DexSwapV1, ParsedInstruction, TxFacts};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::metrics::{ParseFailReason, SwapMetrics};
+
+/// Transaction JSON of the shared fixture `crates/schema/tests/fixtures/<name>.json`
+#[cfg(test)]
+pub(crate) fn fixture_json(name: &str) -> serde_json::Value {
+    let path = format!(
+        "{}/../../crates/schema/tests/fixtures/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// Facts of fixture `name` under signature `sig`, at the fixture's own slot
+#[cfg(test)]
+pub(crate) fn fixture_facts(name: &str, sig: &str) -> TxFacts {
+    let tx = fixture_json(name);
+    TxFacts::from_json(&tx, sig, tx["slot"].as_u64().unwrap())
+}
+
+/// How `index_in_tx` is derived from a swap instruction's position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexInTxMode {
+    /// Outer instruction index only: inner swaps under one outer ix share it
+    #[default]
+    Outer,
+    /// `outer * 1000 + inner + 1` for inner (CPI) swaps, `outer * 1000` for outer
+    /// ones, so every swap in a tx gets its own index
+    Composite,
+}
+
+impl IndexInTxMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "outer" => Some(Self::Outer),
+            "composite" => Some(Self::Composite),
+            _ => None,
+        }
+    }
+
+    /// `index_in_tx` for a swap at `outer` / `inner`. A composite index past
+    /// u16::MAX (outer ix 66 and up) falls back to the outer index instead of
+    /// saturating, so swaps under different outer ixs never share u16::MAX
+    pub fn encode(self, outer: usize, inner: Option<usize>) -> u16 {
+        let composite = match (self, inner) {
+            (Self::Outer, _) => None,
+            (Self::Composite, None) => outer.checked_mul(1000),
+            (Self::Composite, Some(inner)) => {
+                outer.checked_mul(1000).and_then(|o| o.checked_add(inner + 1))
+            }
+        };
+        composite
+            .and_then(|index| u16::try_from(index).ok())
+            .unwrap_or_else(|| u16::try_from(outer).unwrap_or(u16::MAX))
+    }
+
+    /// `index_in_tx` for a swap executed by `ix`
+    pub fn index_in_tx(self, ix: &ParsedInstruction) -> u16 {
+        self.encode(ix.outer_ix_index.unwrap_or(0), ix.inner_ix_index)
+    }
+}
+
+/// What to do with a multi-hop route when some of its legs cannot be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MultihopPolicy {
+    /// Reject the whole tx (`IncompleteRoute`), so no inconsistent route is emitted
+    AllOrNothing,
+    /// Emit the parseable legs, tagged `partial`
+    #[default]
+    BestEffort,
+}
+
+impl MultihopPolicy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "all_or_nothing" => Some(Self::AllOrNothing),
+            "best_effort" => Some(Self::BestEffort),
+            _ => None,
+        }
+    }
+}
+
+/// A multi-hop route with unparseable legs, rejected under `AllOrNothing`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncompleteRoute {
+    pub venue: &'static str,
+    /// Legs (swap instructions) in the route
+    pub legs: usize,
+    /// Legs that could not be parsed
+    pub failed_legs: usize,
+}
+
+impl fmt::Display for IncompleteRoute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} route: {} of {} legs unparseable",
+            self.venue, self.failed_legs, self.legs
+        )
+    }
+}
+
+impl std::error::Error for IncompleteRoute {}
+
+/// Where a swap's amounts come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AmountSource {
+    /// Token balance deltas
+    #[default]
+    Delta,
+    /// The instruction's exact amount where it has one (exact-in: input,
+    /// exact-out: output) and the observed flow confirms it; the other leg
+    /// still comes from deltas
+    Instruction,
+    /// By discriminator: exact-in uses the instruction's input amount,
+    /// exact-out keeps deltas
+    Auto,
+}
+
+impl AmountSource {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "delta" => Some(Self::Delta),
+            "instruction" => Some(Self::Instruction),
+            "auto" => Some(Self::Auto),
+            _ => None,
+        }
+    }
+}
+
+/// Per-venue `AmountSource` (`AMOUNT_SOURCE`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AmountSources {
+    default: AmountSource,
+    venues: HashMap<String, AmountSource>,
+}
+
+impl AmountSources {
+    /// `auto` applies to every venue; `raydium:instruction,phoenix:delta` sets
+    /// venues one by one (a bare mode in the list sets the default)
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut sources = Self::default();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once(':') {
+                Some((venue, mode)) => {
+                    sources
+                        .venues
+                        .insert(venue.trim().to_ascii_lowercase(), AmountSource::parse(mode)?);
+                }
+                None => sources.default = AmountSource::parse(entry)?,
+            }
+        }
+        Some(sources)
+    }
+
+    pub fn for_venue(&self, venue: &str) -> AmountSource {
+        self.venues.get(venue).copied().unwrap_or(self.default)
+    }
+
+    /// Same source for every venue
+    pub fn all(source: AmountSource) -> Self {
+        Self {
+            default: source,
+            venues: HashMap::new(),
+        }
+    }
+}
+
+/// Tunables shared by the gold (TxFacts-based) detectors
+#[derive(Debug, Clone, Default)]
+pub struct DetectorOptions {
+    /// Known router/aggregator-owned addresses that must never be reported as the trader
+    pub trader_exclude: HashSet<String>,
+    /// Venue program IDs for the configured chain (mainnet by default)
+    pub programs: ChainPrograms,
+    /// Venue `Program log:` lines appended to the explain of low-confidence swaps (0 = off)
+    pub explain_log_lines: usize,
+    /// Keep explain only on swaps at or below this confidence (`None` = every swap)
+    pub explain_max_confidence: Option<u8>,
+    /// Jito tip accounts: tips are excluded from the trader's SOL cost and flag `jito_bundle`
+    pub jito_tip_accounts: HashSet<String>,
+    /// Keep liquidity events with a zero leg (`LiquidityEventV1::validate`); swaps always reject zero
+    pub liquidity_allow_zero_amount: bool,
+    /// `index_in_tx` encoding; composite keeps `(signature, index_in_tx)` unique per swap
+    pub index_in_tx_mode: IndexInTxMode,
+    /// Handling of multi-hop routes with unparseable legs
+    pub multihop_policy: MultihopPolicy,
+    /// Instruction-encoded vs balance-delta amounts, per venue
+    pub amount_sources: AmountSources,
+    /// Time each venue's parse into `metrics()` (`DETECTOR_TIMINGS`)
+    pub record_timings: bool,
+    /// `(venue, program_id)` of multi-asset pools (`STABLE_POOL_PROGRAMS`); empty = off
+    pub stable_pool_programs: Vec<(String, String)>,
+    /// Report WSOL legs wrapped from / unwrapped into the trader's SOL as native SOL
+    pub normalize_wsol: bool,
+    /// Keep route legs with `in_mint == out_mint` (cyclic arbitrage), see `validate_with`
+    pub allow_same_mint_arb: bool,
+    /// Raydium AMM v4 `vault -> pool` (`RAYDIUM_POOLS_FILE`), for pool ids of swaps whose
+    /// pool account is not where expected; empty = off
+    pub raydium_pool_vaults: Arc<HashMap<String, String>>,
+}
+
+/// Run one venue's parser, recording its duration when timings are enabled
+fn timed<T>(opts: &DetectorOptions, venue: &str, parse: impl FnOnce() -> T) -> T {
+    if !opts.record_timings {
+        return parse();
+    }
+    let start = Instant::now();
+    let out = parse();
+    crate::metrics::metrics().record_parse_duration(venue, start.elapsed());
+    out
+}
+
+/// Drop explain from swaps above `max_confidence`; only the ambiguous ones get debugged
+fn strip_confident_explains(swaps: &mut [DexSwapV1], max_confidence: Option<u8>) {
+    let Some(max) = max_confidence else {
+        return;
+    };
+    for swap in swaps.iter_mut().filter(|s| s.confidence > max) {
+        swap.explain = None;
+    }
+}
+
+/// Venue name and program of each built-in gold detector, in `DetectorRegistry::new` order
+pub fn venue_programs(programs: &ChainPrograms) -> [(&'static str, &str); 5] {
+    [
+        ("raydium", &programs.raydium_amm_v4),
+        ("phoenix", &programs.phoenix),
+        ("orca", &programs.orca_whirlpool),
+        ("lifinity", &programs.lifinity_v2),
+        ("meteora", &programs.meteora_dlmm),
+    ]
+}
+
+/// Why a venue present in `facts` yielded no swap
+fn parse_fail_reason(facts: &TxFacts, program: &str) -> ParseFailReason {
+    if facts.token_balance_deltas.is_empty() {
+        return ParseFailReason::NoTokenDeltas;
+    }
+
+    // A swap has some owner paying one token and receiving another on the
+    // venue instructions' accounts; liquidity and admin ixs do not
+    let accounts: HashSet<usize> = facts
+        .instructions_for_program(program)
+        .iter()
+        .flat_map(|ix| ix.accounts.iter().copied())
+        .collect();
+    let mut owners: HashMap<&str, (bool, bool)> = HashMap::new();
+    for delta in facts
+        .token_balance_deltas
+        .iter()
+        .filter(|d| d.delta != 0 && accounts.contains(&(d.account_index as usize)))
+    {
+        let (paid, received) = owners.entry(delta.owner.as_deref().unwrap_or("")).or_default();
+        *paid |= delta.delta < 0;
+        *received |= delta.delta > 0;
+    }
+    if owners.values().any(|&(paid, received)| paid && received) {
+        ParseFailReason::Unknown
+    } else {
+        ParseFailReason::NoInOutPair
+    }
+}
+
+/// Count one tx's per-venue detection failures into `metrics`.
+///
+/// A venue whose program is in the tx's `program_ids` (as the streamer saw it)
+/// but invokes no instruction in `facts` fails the gate (e.g. unresolved
+/// lookup tables); one invoked (outer or CPI) in a successful tx without any
+/// detected swap fails parsing. Venues not invoked, and failed txs, which move
+/// no balances, count as neither. `venues` are the detectors that ran
+/// (`DetectorRegistry::venue_programs`). The parse failures are returned
+/// (`EMIT_PARSE_DIAGNOSTICS`).
+pub fn record_venue_outcomes(
+    metrics: &SwapMetrics,
+    facts: &TxFacts,
+    program_ids: &[String],
+    swaps: &[DexSwapV1],
+    venues: &[(&'static str, &str)],
+) -> Vec<(&'static str, ParseFailReason)> {
+    let mut parse_fails = Vec::new();
+    for &(venue, program) in venues {
+        if !facts.has_program(program) {
+            if program_ids.iter().any(|p| p == program) {
+                metrics.record_gate_fail(venue);
+            }
+        } else if facts.is_success && !swaps.iter().any(|s| s.venue == venue) {
+            let reason = parse_fail_reason(facts, program);
+            metrics.record_parse_fail(venue, reason);
+            parse_fails.push((venue, reason));
+        }
+    }
+    parse_fails
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use registry::DetectorRegistry;
+    use schema::{ConfidenceReasons, DexSwapV1Builder};
+
+    #[test]
+    fn test_composite_index_past_u16_falls_back_to_outer() {
+        let composite = IndexInTxMode::Composite;
+        assert_eq!(composite.encode(2, None), 2000);
+        assert_eq!(composite.encode(2, Some(0)), 2001);
+        assert_eq!(composite.encode(65, Some(534)), u16::MAX);
+        // Outer ixs 65 and 66 no longer both saturate to u16::MAX
+        assert_eq!(composite.encode(65, Some(535)), 65);
+        assert_eq!(composite.encode(66, Some(0)), 66);
+        assert_eq!(IndexInTxMode::Outer.encode(66, Some(3)), 66);
+    }
+
+    #[test]
+    fn test_explain_only_at_or_below_max_confidence() {
+        let swap = |reasons: &[u32]| {
+            let mut builder = DexSwapV1Builder::new()
+                .signature("sig_explain_threshold")
+                .in_token("SOL", 1_000_000_000u128)
+                .out_token("USDC", 50_000_000u128)
+                .explain_enabled(true);
+            for &reason in reasons {
+                builder.add_confidence_reason(reason);
+            }
+            builder.build()
+        };
+        let mut swaps = vec![
+            swap(&[
+                ConfidenceReasons::PROGRAM_GATE,
+                ConfidenceReasons::POOL_ID_FROM_IX,
+                ConfidenceReasons::TRADER_FROM_OWNER,
+                ConfidenceReasons::AMOUNTS_CONFIRMED,
+                ConfidenceReasons::VAULT_MATCH,
+                ConfidenceReasons::SINGLE_HOP,
+                ConfidenceReasons::TX_SUCCESS,
+            ]),
+            swap(&[
+                ConfidenceReasons::PROGRAM_GATE,
+                ConfidenceReasons::POOL_ID_FROM_IX,
+                ConfidenceReasons::AMOUNTS_CONFIRMED,
+            ]),
+        ];
+        assert_eq!(
+            swaps.iter().map(|s| s.confidence).collect::<Vec<_>>(),
+            vec![100, 60]
+        );
+
+        // Unset keeps every explain
+        strip_confident_explains(&mut swaps, None);
+        assert!(swaps.iter().all(|s| s.explain.is_some()));
+
+        strip_confident_explains(&mut swaps, Some(60));
+        assert!(swaps[0].explain.is_none());
+        assert!(swaps[1].explain.is_some());
+    }
+
+    #[test]
+    fn test_record_venue_outcomes_gate_and_parse_failures() {
+        let programs = ChainPrograms::mainnet();
+        let facts = TxFacts::from_json(
+            &serde_json::json!({
+                "meta": {"err": null, "fee": 5000},
+                "transaction": {
+                    "message": {
+                        "accountKeys": ["Trader111", programs.phoenix.clone()],
+                        "instructions": [{"programIdIndex": 1, "accounts": [0], "data": ""}]
+                    },
+                    "signatures": ["sig_outcomes"]
+                }
+            }),
+            "sig_outcomes",
+            1,
+        );
+
+        // Streamer saw Raydium and Phoenix; only Phoenix survived into the facts
+        // and it produced no swap. Orca was never involved
+        let metrics = SwapMetrics::new();
+        let program_ids = vec![programs.raydium_amm_v4.clone(), programs.phoenix.clone()];
+        record_venue_outcomes(&metrics, &facts, &program_ids, &[], &venue_programs(&programs));
+
+        let summary = metrics.summary();
+        assert!(summary.contains("gate_fail{venue=raydium}=1"));
+        assert!(summary.contains("parse_fail{venue=phoenix,reason=no_token_deltas}=1"));
+        assert!(!summary.contains("venue=orca"));
+
+        // Orca only referenced as an account, and Phoenix in a failed tx: no counts
+        let facts = TxFacts::from_json(
+            &serde_json::json!({
+                "meta": {"err": {"InstructionError": [0, "Custom"]}, "fee": 5000},
+                "transaction": {
+                    "message": {
+                        "accountKeys": [
+                            "Trader111", programs.phoenix.clone(), programs.orca_whirlpool.clone()
+                        ],
+                        "instructions": [{"programIdIndex": 1, "accounts": [0, 2], "data": ""}]
+                    },
+                    "signatures": ["sig_failed"]
+                }
+            }),
+            "sig_failed",
+            1,
+        );
+        let metrics = SwapMetrics::new();
+        let fails = record_venue_outcomes(&metrics, &facts, &[], &[], &venue_programs(&programs));
+        assert!(fails.is_empty());
+        assert!(!metrics.summary().contains("parse_fail"));
+    }
+
+    #[test]
+    fn test_non_swap_raydium_ix_reports_no_in_out_pair() {
+        let programs = ChainPrograms::mainnet();
+        let balance = |index: u32, owner: &str, mint: &str, amount: &str| {
+            serde_json::json!({
+                "accountIndex": index,
+                "mint": mint,
+                "owner": owner,
+                "uiTokenAmount": {"amount": amount, "decimals": 6}
+            })
+        };
+        let usdc = "USDCMint111";
+        let authority = programs.raydium_amm_v4_authority.clone();
+        // Single-sided deposit: the LP pays USDC into the pool, nothing comes back
+        let tx = serde_json::json!({
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "preBalances": [1000000, 2039280, 2039280, 1],
+                "postBalances": [995000, 2039280, 2039280, 1],
+                "preTokenBalances": [
+                    balance(1, "Lp111", usdc, "100"),
+                    balance(2, &authority, usdc, "1000"),
+                ],
+                "postTokenBalances": [
+                    balance(1, "Lp111", usdc, "50"),
+                    balance(2, &authority, usdc, "1050"),
+                ]
+            },
+            "transaction": {
+                "message": {
+                    "header": {"numRequiredSignatures": 1},
+                    "accountKeys": [
+                        "Lp111", "LpUsdc111", "VaultUsdc111", programs.raydium_amm_v4.clone()
+                    ],
+                    "instructions": [
+                        {"programIdIndex": 3, "accounts": [0, 1, 2], "data": "Deposit"}
+                    ]
+                },
+                "signatures": ["sig_deposit"]
+            }
+        });
+        let facts = TxFacts::from_json(&tx, "sig_deposit", 1);
+        let opts = DetectorOptions::default();
+        let registry = DetectorRegistry::new(Arc::new(opts));
+        let swaps = registry.detect(&facts, "solana-mainnet", 0, false).unwrap();
+        assert!(swaps.is_empty());
+
+        let metrics = SwapMetrics::new();
+        let program_ids = vec![programs.raydium_amm_v4.clone()];
+        let venues = venue_programs(&programs);
+        let fails = record_venue_outcomes(&metrics, &facts, &program_ids, &swaps, &venues);
+        assert_eq!(fails, vec![("raydium", ParseFailReason::NoInOutPair)]);
+        assert!(metrics.summary().contains("parse_fail{venue=raydium,reason=no_in_out_pair}=1"));
+    }
+}
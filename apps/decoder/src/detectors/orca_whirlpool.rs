@@ -0,0 +1,323 @@
+//! Orca Whirlpool (concentrated liquidity) swaps.
+//!
+//! `swap` and `swapV2` name the trader (token authority), the whirlpool and
+//! both of its vaults in their accounts, so the pool id is read from the
+//! instruction and each swap is checked against its own vaults. A tx that splits
+//! an order across several whirlpools yields one hop per swap instruction.
+
+use schema::{
+    ConfidenceReasons, DexSwapV1, DexSwapV1Builder, InOut, PairingStrategy, ParsedInstruction,
+    TxFacts, pair_in_out,
+};
+
+use super::DetectorOptions;
+use super::raydium_v4_gold::vault_flow_between;
+
+/// Anchor discriminator of `swap` (sha256("global:swap")[..8])
+const SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+/// Anchor discriminator of `swap_v2`
+const SWAP_V2_DISCRIMINATOR: [u8; 8] = [43, 4, 237, 11, 26, 201, 30, 98];
+
+/// Account positions in one swap instruction version
+#[derive(Debug, Clone, Copy)]
+struct SwapLayout {
+    token_authority: usize,
+    whirlpool: usize,
+    owner_account_a: usize,
+    vault_a: usize,
+    owner_account_b: usize,
+    vault_b: usize,
+}
+
+impl SwapLayout {
+    /// `swap`: token program, token authority, whirlpool, then owner/vault A and B
+    const SWAP: Self = Self {
+        token_authority: 1,
+        whirlpool: 2,
+        owner_account_a: 3,
+        vault_a: 4,
+        owner_account_b: 5,
+        vault_b: 6,
+    };
+    /// `swapV2`: both token programs, memo program, authority, whirlpool, both
+    /// mints, then owner/vault A and B
+    const SWAP_V2: Self = Self {
+        token_authority: 3,
+        whirlpool: 4,
+        owner_account_a: 7,
+        vault_a: 8,
+        owner_account_b: 9,
+        vault_b: 10,
+    };
+
+    /// Layout and args of a swap instruction; `None` for any other Whirlpool
+    /// instruction
+    fn for_ix(ix: &ParsedInstruction) -> Option<(Self, SwapArgs)> {
+        let data = bs58::decode(ix.data.as_deref()?).into_vec().ok()?;
+        let layout = match data.get(..8)? {
+            d if d == SWAP_DISCRIMINATOR => Self::SWAP,
+            d if d == SWAP_V2_DISCRIMINATOR => Self::SWAP_V2,
+            _ => return None,
+        };
+        if ix.accounts.len() <= layout.vault_b {
+            return None;
+        }
+        Some((layout, SwapArgs::parse(&data)?))
+    }
+}
+
+/// Amount args shared by `swap` and `swapV2` (after the discriminator):
+/// amount, other_amount_threshold, sqrt_price_limit (u128),
+/// amount_specified_is_input, a_to_b
+#[derive(Debug, Clone, Copy)]
+struct SwapArgs {
+    amount: u64,
+    other_amount_threshold: u64,
+    amount_specified_is_input: bool,
+}
+
+impl SwapArgs {
+    fn parse(data: &[u8]) -> Option<Self> {
+        let u64_at = |at: usize| Some(u64::from_le_bytes(data.get(at..at + 8)?.try_into().ok()?));
+        Some(Self {
+            amount: u64_at(8)?,
+            other_amount_threshold: u64_at(16)?,
+            amount_specified_is_input: *data.get(40)? != 0,
+        })
+    }
+
+    /// Whether the legs match the specified amount exactly and respect the
+    /// slippage threshold on the other side
+    fn agrees_with(&self, in_amount: u128, out_amount: u128) -> bool {
+        let (amount, threshold) = (self.amount as u128, self.other_amount_threshold as u128);
+        if self.amount_specified_is_input {
+            in_amount == amount && out_amount >= threshold
+        } else {
+            out_amount == amount && in_amount <= threshold
+        }
+    }
+}
+
+/// Parse Orca Whirlpool swaps from TxFacts.
+///
+/// This is a pure function - no RPC calls, no side effects.
+///
+/// Returns one DexSwapV1 per swap instruction.
+pub fn parse_orca_whirlpool_swaps(
+    facts: &TxFacts,
+    chain: &str,
+    index_in_block: u32,
+    explain_enabled: bool,
+    opts: &DetectorOptions,
+) -> Vec<DexSwapV1> {
+    if !facts.has_program(&opts.programs.orca_whirlpool) {
+        return vec![];
+    }
+
+    let swap_ixs: Vec<(&ParsedInstruction, (SwapLayout, SwapArgs))> = facts
+        .instructions_for_program(&opts.programs.orca_whirlpool)
+        .into_iter()
+        .filter_map(|ix| Some((ix, SwapLayout::for_ix(ix)?)))
+        .collect();
+    if swap_ixs.is_empty() {
+        return vec![];
+    }
+
+    let is_multi_hop = swap_ixs.len() > 1;
+    let route_id = is_multi_hop.then(|| {
+        let first_ix = swap_ixs[0].0.outer_ix_index.unwrap_or(0);
+        format!(
+            "{}:{}",
+            &facts.signature[..16.min(facts.signature.len())],
+            first_ix
+        )
+    });
+    let jito_bundle = facts.tip_lamports(&opts.jito_tip_accounts) > 0;
+
+    swap_ixs
+        .iter()
+        .enumerate()
+        .filter_map(|(hop_idx, (ix, (layout, args)))| {
+            let account = |i: usize| facts.account_at(ix.accounts[i]);
+            let pool = account(layout.whirlpool)?;
+            let trader = account(layout.token_authority)?;
+            let trader_accounts = [
+                ix.accounts[layout.owner_account_a],
+                ix.accounts[layout.owner_account_b],
+            ];
+            let vaults =
+                vault_flow_between(facts, ix.accounts[layout.vault_a], ix.accounts[layout.vault_b]);
+
+            let (in_mint, in_amount, out_mint, out_amount, vault_match) = if is_multi_hop {
+                // The trader's accounts net every split; each hop's legs come
+                // from its own whirlpool's vaults
+                let (vault_in, vault_out) = vaults?;
+                (
+                    vault_in.mint.clone(),
+                    vault_in.delta.unsigned_abs(),
+                    vault_out.mint.clone(),
+                    vault_out.delta.unsigned_abs(),
+                    true,
+                )
+            } else {
+                let deltas: Vec<_> = facts
+                    .token_balance_deltas
+                    .iter()
+                    .filter(|d| trader_accounts.contains(&(d.account_index as usize)))
+                    .collect();
+                let InOut {
+                    in_delta,
+                    out_delta,
+                } = pair_in_out(&deltas, PairingStrategy::FirstMatch, &[])?;
+                let vault_match = vaults.is_some_and(|(vault_in, vault_out)| {
+                    vault_in.mint == in_delta.mint && vault_out.mint == out_delta.mint
+                });
+                (
+                    in_delta.mint.clone(),
+                    in_delta.delta.unsigned_abs(),
+                    out_delta.mint.clone(),
+                    out_delta.delta.unsigned_abs(),
+                    vault_match,
+                )
+            };
+
+            let mut builder = DexSwapV1Builder::new()
+                .chain(chain)
+                .slot(facts.slot)
+                .block_time(facts.block_time)
+                .signature(&facts.signature)
+                .index_in_block(index_in_block)
+                .index_in_tx(opts.index_in_tx_mode.index_in_tx(ix))
+                .hop_index(hop_idx as u8)
+                .venue("orca")
+                .pool_id(Some(pool.to_string()))
+                .trader(trader)
+                .in_token(&in_mint, in_amount)
+                .out_token(&out_mint, out_amount)
+                .route_id(route_id.clone())
+                .sol_cost_lamports(Some(facts.sol_cost(trader, &opts.jito_tip_accounts)))
+                .jito_bundle(jito_bundle)
+                .explain_enabled(explain_enabled)
+                .with_confidence_reason(ConfidenceReasons::PROGRAM_GATE)
+                .with_confidence_reason(ConfidenceReasons::POOL_ID_FROM_IX);
+
+            if args.agrees_with(in_amount, out_amount) {
+                builder.add_confidence_reason(ConfidenceReasons::AMOUNTS_CONFIRMED);
+            }
+            if vault_match {
+                builder.add_confidence_reason(ConfidenceReasons::VAULT_MATCH);
+            }
+            if owns_token_accounts(facts, trader, &trader_accounts) {
+                builder.add_confidence_reason(ConfidenceReasons::TRADER_FROM_OWNER);
+            } else if facts.is_signer(trader) {
+                builder.add_confidence_reason(ConfidenceReasons::TRADER_IS_SIGNER);
+            }
+            if !is_multi_hop {
+                builder.add_confidence_reason(ConfidenceReasons::SINGLE_HOP);
+            }
+            if facts.is_success {
+                builder.add_confidence_reason(ConfidenceReasons::TX_SUCCESS);
+            }
+
+            let swap = builder.build();
+            swap.validate_with(opts.allow_same_mint_arb).is_ok().then_some(swap)
+        })
+        .collect()
+}
+
+/// Whether every listed token account with a balance entry is owned by `trader`
+fn owns_token_accounts(facts: &TxFacts, trader: &str, accounts: &[usize]) -> bool {
+    let mut owners = facts
+        .pre_token_balances
+        .iter()
+        .chain(&facts.post_token_balances)
+        .filter(|b| accounts.contains(&(b.account_index as usize)))
+        .map(|b| b.owner.as_deref())
+        .peekable();
+    owners.peek().is_some() && owners.all(|owner| owner == Some(trader))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::fixture_facts;
+
+    #[test]
+    fn test_whirlpool_swap_emits_orca_swap() {
+        let facts = fixture_facts("orca_whirlpool_swap", "OrcaSwapSig");
+        let swaps = parse_orca_whirlpool_swaps(
+            &facts,
+            "solana-mainnet",
+            0,
+            true,
+            &DetectorOptions::default(),
+        );
+
+        assert_eq!(swaps.len(), 1);
+        let swap = &swaps[0];
+        assert_eq!(swap.venue, "orca");
+        assert_eq!(
+            swap.pool_id.as_deref(),
+            Some("HJPjoWUrhoZzkNfRpHuieeFk9WcZWjwy6PBjZ81ngndJ")
+        );
+        assert_eq!(swap.trader, "OrcaTrader111111111111111111111111111111111");
+        assert_eq!(swap.in_mint, "So11111111111111111111111111111111111111112");
+        assert_eq!(swap.in_amount, "2000000000");
+        assert_eq!(swap.out_mint, "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+        assert_eq!(swap.out_amount, "203456789");
+        assert!(swap.route_id.is_none());
+        let reasons = ConfidenceReasons(swap.confidence_reasons);
+        assert!(reasons.has(ConfidenceReasons::PROGRAM_GATE));
+        assert!(reasons.has(ConfidenceReasons::VAULT_MATCH));
+        assert!(reasons.has(ConfidenceReasons::TRADER_FROM_OWNER));
+        assert!(reasons.has(ConfidenceReasons::AMOUNTS_CONFIRMED));
+
+        // Exact-out args pin the out leg and cap the in leg
+        let exact_out = SwapArgs {
+            amount: 203456789,
+            other_amount_threshold: 2_100_000_000,
+            amount_specified_is_input: false,
+        };
+        assert!(exact_out.agrees_with(2_000_000_000, 203456789));
+        assert!(!exact_out.agrees_with(2_000_000_000, 203456788));
+        assert!(!exact_out.agrees_with(2_200_000_000, 203456789));
+
+        // Gate follows the configured program ID
+        let other = DetectorOptions {
+            programs: schema::ChainPrograms::mainnet().with_orca_whirlpool("OtherWhirl111"),
+            ..Default::default()
+        };
+        assert!(parse_orca_whirlpool_swaps(&facts, "solana-mainnet", 0, false, &other).is_empty());
+    }
+
+    #[test]
+    fn test_split_across_whirlpools_yields_one_hop_each() {
+        let facts = fixture_facts("orca_whirlpool_split", "OrcaSplitSig");
+        let swaps = parse_orca_whirlpool_swaps(
+            &facts,
+            "solana-mainnet",
+            0,
+            false,
+            &DetectorOptions::default(),
+        );
+
+        // Two swap ixs (the non-swap Whirlpool ix is ignored), legs per pool
+        assert_eq!(swaps.len(), 2);
+        assert_ne!(swaps[0].pool_id, swaps[1].pool_id);
+        assert_eq!(
+            swaps.iter().map(|s| s.hop_index).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert_eq!(swaps[0].in_amount, "600000000");
+        assert_eq!(swaps[0].out_amount, "61000000");
+        assert_eq!(swaps[1].in_amount, "400000000");
+        assert_eq!(swaps[1].out_amount, "40500000");
+        assert!(swaps[0].route_id.is_some());
+        assert_eq!(swaps[0].route_id, swaps[1].route_id);
+        assert!(swaps.iter().all(|s| {
+            let reasons = ConfidenceReasons(s.confidence_reasons);
+            reasons.has(ConfidenceReasons::VAULT_MATCH)
+                && !reasons.has(ConfidenceReasons::SINGLE_HOP)
+        }));
+    }
+}
@@ -0,0 +1,225 @@
+//! Phoenix order-book fills.
+//!
+//! Phoenix is a CLOB: a taker `Swap` fills against resting orders and settles
+//! through the market's vaults. The market account is the pool id, and the
+//! trader and its base/quote token accounts are read from the instruction, so
+//! no owner heuristics are needed. The order packet is in lots, so amounts are
+//! checked against the market vaults (which mirror the taker's legs) instead
+//! of the instruction data.
+
+use schema::{
+    ConfidenceReasons, DexSwapV1, DexSwapV1Builder, InOut, PairingStrategy, ParsedInstruction,
+    TxFacts, pair_in_out,
+};
+
+use super::DetectorOptions;
+use super::raydium_v4_gold::vault_flow_between;
+
+/// Instruction tag of `Swap` (first data byte); other instructions, including
+/// the self-CPI event log, are not fills
+const SWAP_TAG: u8 = 0;
+
+mod phoenix_accounts {
+    /// Market account (index 2 in swap instruction)
+    pub const MARKET: usize = 2;
+    /// Taker wallet (index 3)
+    pub const TRADER: usize = 3;
+    /// Taker base token account (index 4)
+    pub const BASE_ACCOUNT: usize = 4;
+    /// Taker quote token account (index 5)
+    pub const QUOTE_ACCOUNT: usize = 5;
+    /// Market base vault (index 6)
+    pub const BASE_VAULT: usize = 6;
+    /// Market quote vault (index 7)
+    pub const QUOTE_VAULT: usize = 7;
+}
+
+/// Whether `ix` is a taker `Swap` with its full account list
+fn is_swap_ix(ix: &ParsedInstruction) -> bool {
+    let Some(data) = ix
+        .data
+        .as_deref()
+        .and_then(|d| bs58::decode(d).into_vec().ok())
+    else {
+        return false;
+    };
+    data.first() == Some(&SWAP_TAG) && ix.accounts.len() > phoenix_accounts::QUOTE_VAULT
+}
+
+/// Parse Phoenix fills from TxFacts.
+///
+/// This is a pure function - no RPC calls, no side effects.
+///
+/// Returns one DexSwapV1 per fill instruction.
+pub fn parse_phoenix_fills(
+    facts: &TxFacts,
+    chain: &str,
+    index_in_block: u32,
+    explain_enabled: bool,
+    opts: &DetectorOptions,
+) -> Vec<DexSwapV1> {
+    if !facts.has_program(&opts.programs.phoenix) {
+        return vec![];
+    }
+
+    let fill_ixs: Vec<&ParsedInstruction> = facts
+        .instructions_for_program(&opts.programs.phoenix)
+        .into_iter()
+        .filter(|ix| is_swap_ix(ix))
+        .collect();
+    if fill_ixs.is_empty() {
+        return vec![];
+    }
+
+    let is_multi_fill = fill_ixs.len() > 1;
+    let route_id = is_multi_fill.then(|| {
+        let first_ix = fill_ixs[0].outer_ix_index.unwrap_or(0);
+        format!(
+            "{}:{}",
+            &facts.signature[..16.min(facts.signature.len())],
+            first_ix
+        )
+    });
+    let jito_bundle = facts.tip_lamports(&opts.jito_tip_accounts) > 0;
+
+    fill_ixs
+        .iter()
+        .enumerate()
+        .filter_map(|(hop_idx, ix)| {
+            let account = |i: usize| facts.account_at(ix.accounts[i]);
+            let market = account(phoenix_accounts::MARKET)?;
+            let trader = account(phoenix_accounts::TRADER)?;
+
+            // Taker's base/quote token accounts from this instruction
+            let taker_accounts = [
+                ix.accounts[phoenix_accounts::BASE_ACCOUNT],
+                ix.accounts[phoenix_accounts::QUOTE_ACCOUNT],
+            ];
+            let deltas: Vec<_> = facts
+                .token_balance_deltas
+                .iter()
+                .filter(|d| taker_accounts.contains(&(d.account_index as usize)))
+                .collect();
+            let InOut {
+                in_delta,
+                out_delta,
+            } = pair_in_out(&deltas, PairingStrategy::FirstMatch, &[])?;
+
+            // The vaults take exactly what the taker pays and pay what it receives
+            let vault_match = vault_flow_between(
+                facts,
+                ix.accounts[phoenix_accounts::BASE_VAULT],
+                ix.accounts[phoenix_accounts::QUOTE_VAULT],
+            )
+            .is_some_and(|(vault_in, vault_out)| {
+                vault_in.mint == in_delta.mint
+                    && vault_in.delta == -in_delta.delta
+                    && vault_out.mint == out_delta.mint
+                    && vault_out.delta == -out_delta.delta
+            });
+
+            let mut builder = DexSwapV1Builder::new()
+                .chain(chain)
+                .slot(facts.slot)
+                .block_time(facts.block_time)
+                .signature(&facts.signature)
+                .index_in_block(index_in_block)
+                .index_in_tx(opts.index_in_tx_mode.index_in_tx(ix))
+                .hop_index(hop_idx as u8)
+                .venue("phoenix")
+                .pool_id(Some(market.to_string()))
+                .trader(trader)
+                .in_token(&in_delta.mint, (-in_delta.delta) as u128)
+                .out_token(&out_delta.mint, out_delta.delta as u128)
+                .route_id(route_id.clone())
+                .sol_cost_lamports(Some(facts.sol_cost(trader, &opts.jito_tip_accounts)))
+                .jito_bundle(jito_bundle)
+                .explain_enabled(explain_enabled)
+                .with_confidence_reason(ConfidenceReasons::PROGRAM_GATE)
+                .with_confidence_reason(ConfidenceReasons::POOL_ID_FROM_IX);
+
+            if vault_match {
+                builder.add_confidence_reason(ConfidenceReasons::VAULT_MATCH);
+            }
+            if in_delta.owner.as_deref() == Some(trader)
+                && out_delta.owner.as_deref() == Some(trader)
+            {
+                builder.add_confidence_reason(ConfidenceReasons::TRADER_FROM_OWNER);
+            } else if facts.is_signer(trader) {
+                builder.add_confidence_reason(ConfidenceReasons::TRADER_IS_SIGNER);
+            }
+            if !is_multi_fill {
+                builder.add_confidence_reason(ConfidenceReasons::SINGLE_HOP);
+            }
+            if facts.is_success {
+                builder.add_confidence_reason(ConfidenceReasons::TX_SUCCESS);
+            }
+
+            let swap = builder.build();
+            swap.validate_with(opts.allow_same_mint_arb).is_ok().then_some(swap)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::{fixture_facts, fixture_json};
+
+    #[test]
+    fn test_phoenix_fill_emits_swap() {
+        let facts = fixture_facts("phoenix_fill", "PhoenixFillSig");
+        let swaps = parse_phoenix_fills(
+            &facts,
+            "solana-mainnet",
+            0,
+            true,
+            &DetectorOptions::default(),
+        );
+
+        assert_eq!(swaps.len(), 1);
+        let swap = &swaps[0];
+        assert_eq!(swap.venue, "phoenix");
+        assert_eq!(
+            swap.pool_id.as_deref(),
+            Some("4DoNfFBfF7UokCC2FQzriy7yHK6DY6NVdYpuekQ5pRgg")
+        );
+        assert_eq!(swap.trader, "PhoenixTrader11111111111111111111111111111");
+        assert_eq!(swap.in_mint, "So11111111111111111111111111111111111111112");
+        assert_eq!(swap.in_amount, "1000000000");
+        assert_eq!(
+            swap.out_mint,
+            "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+        );
+        assert_eq!(swap.out_amount, "101234567");
+        // Vaults mirror the taker's legs; the order packet (lots) is not checked
+        let reasons = ConfidenceReasons(swap.confidence_reasons);
+        assert!(reasons.has(ConfidenceReasons::VAULT_MATCH));
+        assert!(!reasons.has(ConfidenceReasons::AMOUNTS_CONFIRMED));
+        assert!(reasons.has(ConfidenceReasons::TRADER_FROM_OWNER));
+        assert_eq!(swap.confidence, 85);
+
+        // Another Phoenix instruction with the same accounts is not a fill
+        let mut tx = fixture_json("phoenix_fill");
+        tx["transaction"]["message"]["instructions"][0]["data"] =
+            bs58::encode([2u8, 0, 0, 0]).into_string().into();
+        let not_swap = TxFacts::from_json(&tx, "PhoenixFillSig", 250000300);
+        assert!(
+            parse_phoenix_fills(
+                &not_swap,
+                "solana-mainnet",
+                0,
+                false,
+                &DetectorOptions::default()
+            )
+            .is_empty()
+        );
+
+        // Gate follows the configured program ID
+        let other = DetectorOptions {
+            programs: schema::ChainPrograms::mainnet().with_phoenix("OtherPhoenix111"),
+            ..Default::default()
+        };
+        assert!(parse_phoenix_fills(&facts, "solana-mainnet", 0, false, &other).is_empty());
+    }
+}
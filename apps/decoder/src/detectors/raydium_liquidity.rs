@@ -0,0 +1,264 @@
+//! Raydium AMM v4 `deposit` / `withdraw` as `LiquidityEventV1`.
+//!
+//! Both instructions touch the same pool vaults as a swap, but move them the
+//! same way: a deposit fills both, a withdrawal drains both. The token legs
+//! are the vault deltas and the LP leg is the provider's LP token account. An
+//! unchanged vault is a zero leg (single-sided), kept only with
+//! `LIQUIDITY_ALLOW_ZERO_AMOUNT`.
+
+use schema::{LiquidityEventV1, LiquidityKind, ParsedInstruction, TxFacts};
+
+use super::DetectorOptions;
+
+/// Instruction discriminators (first data byte)
+const DEPOSIT: u8 = 3;
+const WITHDRAW: u8 = 4;
+
+/// Pool/AMM account (index 1 in both instructions)
+const POOL_ID: usize = 1;
+
+/// Account positions that differ between the two instructions
+struct LiquidityAccounts {
+    lp_mint: usize,
+    vault_a: usize,
+    vault_b: usize,
+    user_lp: usize,
+    user_owner: usize,
+}
+
+const DEPOSIT_ACCOUNTS: LiquidityAccounts = LiquidityAccounts {
+    lp_mint: 5,
+    vault_a: 6,
+    vault_b: 7,
+    user_lp: 11,
+    user_owner: 12,
+};
+
+const WITHDRAW_ACCOUNTS: LiquidityAccounts = LiquidityAccounts {
+    lp_mint: 5,
+    vault_a: 6,
+    vault_b: 7,
+    user_lp: 15,
+    user_owner: 18,
+};
+
+/// Add for `deposit`, Remove for `withdraw`, None for any other instruction
+fn liquidity_kind(ix: &ParsedInstruction) -> Option<LiquidityKind> {
+    let data = bs58::decode(ix.data.as_deref()?).into_vec().ok()?;
+    match *data.first()? {
+        DEPOSIT => Some(LiquidityKind::Add),
+        WITHDRAW => Some(LiquidityKind::Remove),
+        _ => None,
+    }
+}
+
+/// Parse Raydium AMM v4 liquidity events from TxFacts.
+///
+/// This is a pure function - no RPC calls, no side effects.
+///
+/// Returns one LiquidityEventV1 per deposit/withdraw instruction whose vaults
+/// moved in the instruction's direction.
+pub fn parse_raydium_v4_liquidity(
+    facts: &TxFacts,
+    chain: &str,
+    opts: &DetectorOptions,
+) -> Vec<LiquidityEventV1> {
+    if !facts.has_program(&opts.programs.raydium_amm_v4) {
+        return vec![];
+    }
+
+    facts
+        .instructions_for_program(&opts.programs.raydium_amm_v4)
+        .into_iter()
+        .filter_map(|ix| {
+            let kind = liquidity_kind(ix)?;
+            let layout = match kind {
+                LiquidityKind::Add => &DEPOSIT_ACCOUNTS,
+                LiquidityKind::Remove => &WITHDRAW_ACCOUNTS,
+            };
+            liquidity_event(facts, chain, ix, kind, layout, opts)
+        })
+        .filter(|event| event.validate(opts.liquidity_allow_zero_amount).is_ok())
+        .collect()
+}
+
+fn liquidity_event(
+    facts: &TxFacts,
+    chain: &str,
+    ix: &ParsedInstruction,
+    kind: LiquidityKind,
+    layout: &LiquidityAccounts,
+    opts: &DetectorOptions,
+) -> Option<LiquidityEventV1> {
+    let account = |pos: usize| ix.accounts.get(pos).copied();
+
+    // A vault that moved against the instruction means this is not its flow
+    let leg = |pos: usize| -> Option<(String, u128)> {
+        let index = account(pos)?;
+        match facts.token_delta_at(index) {
+            Some(d) => {
+                let expected = match kind {
+                    LiquidityKind::Add => d.delta > 0,
+                    LiquidityKind::Remove => d.delta < 0,
+                };
+                expected.then(|| (d.mint.clone(), d.delta.unsigned_abs()))
+            }
+            None => facts
+                .post_token_balances
+                .iter()
+                .find(|b| b.account_index as usize == index)
+                .map(|b| (b.mint.clone(), 0)),
+        }
+    };
+    let (token_a_mint, token_a_amount) = leg(layout.vault_a)?;
+    let (token_b_mint, token_b_amount) = leg(layout.vault_b)?;
+
+    let provider = facts.account_at(account(layout.user_owner)?)?.to_string();
+    let lp_mint = account(layout.lp_mint)
+        .and_then(|i| facts.account_at(i))
+        .map(|s| s.to_string());
+    let lp_amount = account(layout.user_lp)
+        .and_then(|i| facts.token_delta_at(i))
+        .filter(|d| lp_mint.as_deref() == Some(d.mint.as_str()))
+        .map(|d| d.delta.unsigned_abs().to_string());
+
+    Some(LiquidityEventV1 {
+        schema_version: LiquidityEventV1::SCHEMA_VERSION,
+        chain: chain.to_string(),
+        slot: facts.slot,
+        block_time: facts.block_time,
+        signature: facts.signature.clone(),
+        index_in_tx: opts.index_in_tx_mode.index_in_tx(ix),
+        venue: "raydium".to_string(),
+        pool_id: account(POOL_ID)
+            .and_then(|i| facts.account_at(i))
+            .map(|s| s.to_string()),
+        provider,
+        kind,
+        token_a_mint,
+        token_a_amount: token_a_amount.to_string(),
+        token_b_mint,
+        token_b_amount: token_b_amount.to_string(),
+        lp_mint,
+        lp_amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::{RAYDIUM_AMM_V4_AUTHORITY, RAYDIUM_AMM_V4_PROGRAM_ID};
+    use serde_json::json;
+
+    /// Deposit of 1000 MintA + 2000 MintB for 700 LP, or (`withdraw`) the
+    /// reverse; `pc_moved: false` leaves the MintB vault untouched
+    fn liquidity_tx(withdraw: bool, pc_moved: bool) -> serde_json::Value {
+        let balance = |index: u32, mint: &str, owner: &str, amount: u64| {
+            json!({"accountIndex": index, "mint": mint, "owner": owner,
+                   "uiTokenAmount": {"amount": amount.to_string(), "decimals": 6}})
+        };
+        let authority = RAYDIUM_AMM_V4_AUTHORITY;
+        let pc = if pc_moved { 2000 } else { 0 };
+        let before = [
+            balance(6, "MintA111", authority, 10000),
+            balance(7, "MintB111", authority, 20000),
+            balance(9, "MintA111", "Provider111", 5000),
+            balance(10, "MintB111", "Provider111", 5000),
+            balance(11, "LpMint111", "Provider111", 0),
+        ];
+        let after = [
+            balance(6, "MintA111", authority, 11000),
+            balance(7, "MintB111", authority, 20000 + pc),
+            balance(9, "MintA111", "Provider111", 4000),
+            balance(10, "MintB111", "Provider111", 5000 - pc),
+            balance(11, "LpMint111", "Provider111", 700),
+        ];
+        let (pre, post, data, accounts) = if withdraw {
+            (
+                after,
+                before,
+                "4f6E88RnFwPm",
+                vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 8, 8, 8, 8, 8, 8, 11, 9, 10, 0],
+            )
+        } else {
+            (
+                before,
+                after,
+                "2aAaKGtz7BBT74uNVsWx4zQ1JadmQmAf5y",
+                vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 0, 8],
+            )
+        };
+        json!({
+            "blockTime": 1703001234,
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "preTokenBalances": pre,
+                "postTokenBalances": post,
+                "innerInstructions": []
+            },
+            "slot": 250000000,
+            "transaction": {
+                "message": {
+                    "header": {"numRequiredSignatures": 1},
+                    "accountKeys": [
+                        "Provider111", "Pool111", authority, "OpenOrders111", "TargetOrders111",
+                        "LpMint111", "VaultA111", "VaultB111", "Market111",
+                        "UserA111", "UserB111", "UserLp111", RAYDIUM_AMM_V4_PROGRAM_ID
+                    ],
+                    "instructions": [
+                        {"programIdIndex": 12, "accounts": accounts, "data": data}
+                    ]
+                },
+                "signatures": ["sig_liquidity"]
+            }
+        })
+    }
+
+    fn parse(tx: serde_json::Value, opts: &DetectorOptions) -> Vec<LiquidityEventV1> {
+        let facts = TxFacts::from_json(&tx, "sig_liquidity", 250000000);
+        parse_raydium_v4_liquidity(&facts, "solana-mainnet", opts)
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw() {
+        let opts = DetectorOptions::default();
+
+        let events = parse(liquidity_tx(false, true), &opts);
+        assert_eq!(events.len(), 1);
+        let add = &events[0];
+        assert_eq!(add.kind, LiquidityKind::Add);
+        assert_eq!(add.venue, "raydium");
+        assert_eq!(add.pool_id.as_deref(), Some("Pool111"));
+        assert_eq!(add.provider, "Provider111");
+        assert_eq!((add.token_a_mint.as_str(), add.token_a_amount.as_str()), ("MintA111", "1000"));
+        assert_eq!((add.token_b_mint.as_str(), add.token_b_amount.as_str()), ("MintB111", "2000"));
+        assert_eq!(add.lp_mint.as_deref(), Some("LpMint111"));
+        assert_eq!(add.lp_amount.as_deref(), Some("700"));
+
+        let events = parse(liquidity_tx(true, true), &opts);
+        assert_eq!(events.len(), 1);
+        let remove = &events[0];
+        assert_eq!(remove.kind, LiquidityKind::Remove);
+        assert_eq!(remove.provider, "Provider111");
+        assert_eq!(remove.token_a_amount, "1000");
+        assert_eq!(remove.token_b_amount, "2000");
+        assert_eq!(remove.lp_amount.as_deref(), Some("700"));
+    }
+
+    #[test]
+    fn test_single_sided_deposit_needs_allow_zero_amount() {
+        assert!(parse(liquidity_tx(false, false), &DetectorOptions::default()).is_empty());
+
+        let opts = DetectorOptions {
+            liquidity_allow_zero_amount: true,
+            ..Default::default()
+        };
+        let events = parse(liquidity_tx(false, false), &opts);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].token_b_mint, "MintB111");
+        assert_eq!(events[0].token_b_amount, "0");
+    }
+}
@@ -1,7 +1,21 @@
-use schema::SwapEvent;
+//! Legacy Raydium AMM v4 detector (`SWAPS_DUAL_WRITE=false`).
+//!
+//! Nets the fee payer's token deltas into one `SwapEvent`; it never reads
+//! instruction accounts. Superseded by `raydium_v4_gold::parse_raydium_v4_swaps`
+//! (pool id, vault checks, multi-hop, scored confidence) and kept only for
+//! consumers of the legacy topic; see "Migrating off the legacy swap detector"
+//! in the RUNBOOK.
+
+use schema::{SwapEvent, extract_program_ids_from_transaction, resolve_full_account_keys};
 use serde_json::Value;
 use std::collections::HashMap;
 
+/// Legacy `SwapEvent` of a Raydium AMM v4 tx, or `None`.
+///
+/// `raw_program_ids` is only a cheap pre-gate: the program must also be
+/// invoked per `tx` itself, with v0 lookup-table addresses resolved, and the
+/// trader is read from the resolved keys, so a stale or unresolved id list
+/// cannot pass a tx the detector would misread.
 #[allow(clippy::too_many_arguments)]
 pub fn detect_raydium_v4_swap(
     chain: &str,
@@ -20,11 +34,16 @@ pub fn detect_raydium_v4_swap(
     {
         return None;
     }
+    if !extract_program_ids_from_transaction(tx)
+        .iter()
+        .any(|p| p == raydium_amm_v4_program_id)
+    {
+        return None;
+    }
 
-    // Determine trader: fee payer (accountKeys[0].pubkey) for jsonParsed.
-    let trader = tx_pointer_str(tx, "/transaction/message/accountKeys/0/pubkey")
-        .or_else(|| tx_pointer_str(tx, "/transaction/message/accountKeys/0"))
-        .map(|s| s.to_string())?;
+    // Determine trader: fee payer, the first of the ALT-resolved account keys
+    // (jsonParsed `{pubkey}` objects and plain strings alike)
+    let trader = resolve_full_account_keys(tx).into_iter().next()?;
 
     // Extract token balances: pre + post
     let pre = tx
@@ -152,6 +171,43 @@ fn parse_i128(s: &str) -> Option<i128> {
     s.parse::<i128>().ok()
 }
 
-fn tx_pointer_str<'a>(tx: &'a Value, ptr: &str) -> Option<&'a str> {
-    tx.pointer(ptr).and_then(|v| v.as_str())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::RAYDIUM_AMM_V4_PROGRAM_ID;
+
+    fn fixture(name: &str) -> Value {
+        let path = format!(
+            "{}/../../crates/schema/tests/fixtures/{}.json",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn detect(tx: &Value, raw_program_ids: &[String]) -> Option<SwapEvent> {
+        detect_raydium_v4_swap(
+            "solana-mainnet",
+            0,
+            None,
+            "LegacySig",
+            raw_program_ids,
+            RAYDIUM_AMM_V4_PROGRAM_ID,
+            tx,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_gate_uses_alt_resolved_programs() {
+        let raydium = vec![RAYDIUM_AMM_V4_PROGRAM_ID.to_string()];
+
+        // v0 tx: Raydium is only reachable through its lookup table
+        let v0 = fixture("v0_raydium_swap");
+        let swap = detect(&v0, &raydium).unwrap();
+        assert_eq!(swap.trader, "TraderWallet1111111111111111111111111111");
+
+        // An id list naming Raydium does not pass a tx that never invokes it
+        assert!(detect(&fixture("lifinity_swap"), &raydium).is_none());
+    }
 }
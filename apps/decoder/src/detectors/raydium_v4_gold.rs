@@ -1,9 +1,10 @@
 use schema::{
-    ConfidenceReasons, DexSwapV1, DexSwapV1Builder, TxFacts,
-    RAYDIUM_AMM_V4_PROGRAM_ID,
+    pair_in_out, ConfidenceReasons, DexSwapV1, DexSwapV1Builder, InOut, PairingStrategy, TxFacts,
+    NATIVE_SOL_MINT, WSOL_MINT,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use super::{AmountSource, DetectorOptions, IncompleteRoute, MultihopPolicy};
 
 mod raydium_accounts {
     /// Pool/AMM account (index 1 in swap instruction)
@@ -18,11 +19,15 @@ mod raydium_accounts {
     pub const VAULT_B: usize = 5;
 }
 
+/// AMM v4 trade fee on the input amount: 25 bps, rounded up like the program
+const TRADE_FEE_NUMERATOR: u128 = 25;
+const TRADE_FEE_DENOMINATOR: u128 = 10_000;
+
 #[derive(Debug, Clone)]
 pub struct RaydiumSwapHop {
     /// Outer instruction index
     pub outer_ix_index: usize,
-    /// Inner instruction index (if CPI)
+    /// Position within the outer ix's inner instructions (if CPI)
     pub inner_ix_index: Option<usize>,
     /// Pool ID (AMM account)
     pub pool_id: Option<String>,
@@ -38,6 +43,101 @@ pub struct RaydiumSwapHop {
     pub out_amount: u128,
     /// Confidence reasons
     pub confidence_reasons: ConfidenceReasons,
+    /// Amounts encoded in the swap instruction, if its data decodes
+    pub swap_ix: Option<RaydiumSwapIx>,
+    /// Exact-in or exact-out, from the instruction discriminator (None if the
+    /// data doesn't decode)
+    pub variant: Option<SwapVariant>,
+    /// Pool fee on the input leg, when the pool's vault flow was matched
+    pub fee_amount: Option<u128>,
+}
+
+/// Which side of a swap the trader fixed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapVariant {
+    /// `swapBaseIn`: input amount exact, output at least a minimum
+    ExactIn,
+    /// `swapBaseOut`: output amount exact, input at most a maximum
+    ExactOut,
+}
+
+/// Raydium v4 swap instruction: discriminator byte, then two little-endian u64
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaydiumSwapIx {
+    /// `swapBaseIn` (9): exact input amount
+    BaseIn { amount_in: u64, minimum_amount_out: u64 },
+    /// `swapBaseOut` (11): exact output amount
+    BaseOut { max_amount_in: u64, amount_out: u64 },
+}
+
+impl RaydiumSwapIx {
+    pub const SWAP_BASE_IN: u8 = 9;
+    pub const SWAP_BASE_OUT: u8 = 11;
+
+    /// Decode raw instruction data
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let (&discriminator, rest) = data.split_first()?;
+        let word = |i: usize| -> Option<u64> {
+            Some(u64::from_le_bytes(rest.get(i * 8..i * 8 + 8)?.try_into().ok()?))
+        };
+        match discriminator {
+            Self::SWAP_BASE_IN => Some(Self::BaseIn {
+                amount_in: word(0)?,
+                minimum_amount_out: word(1)?,
+            }),
+            Self::SWAP_BASE_OUT => Some(Self::BaseOut {
+                max_amount_in: word(0)?,
+                amount_out: word(1)?,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn variant(&self) -> SwapVariant {
+        match self {
+            Self::BaseIn { .. } => SwapVariant::ExactIn,
+            Self::BaseOut { .. } => SwapVariant::ExactOut,
+        }
+    }
+
+    /// Decode an instruction's base58 `data`
+    fn from_ix(ix: &schema::ParsedInstruction) -> Option<Self> {
+        decode_raydium_swap_ix_data(ix.data.as_deref()?)
+    }
+
+    /// Whether observed amounts are what this instruction asked for: the exact
+    /// side matches and the other side is within the slippage bound
+    pub fn agrees_with(&self, in_amount: u128, out_amount: u128) -> bool {
+        match *self {
+            Self::BaseIn {
+                amount_in,
+                minimum_amount_out,
+            } => in_amount == amount_in as u128 && out_amount >= minimum_amount_out as u128,
+            Self::BaseOut {
+                max_amount_in,
+                amount_out,
+            } => out_amount == amount_out as u128 && in_amount <= max_amount_in as u128,
+        }
+    }
+}
+
+/// Decode a swap instruction's base58 `ParsedInstruction::data`.
+///
+/// None for other Raydium instructions (deposit, withdraw, ...) and malformed data.
+pub fn decode_raydium_swap_ix_data(data: &str) -> Option<RaydiumSwapIx> {
+    let data = bs58::decode(data).into_vec().ok()?;
+    RaydiumSwapIx::decode(&data)
+}
+
+/// How the trader for a transaction was resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TraderSource {
+    /// Owner with both negative and positive token deltas
+    Owner,
+    /// First non-excluded signer (no usable owner candidate)
+    Signer,
+    /// Every candidate was excluded; trader is a best-effort guess
+    Unresolved,
 }
 
 /// Parse Raydium AMM v4 swaps from TxFacts.
@@ -45,64 +145,111 @@ pub struct RaydiumSwapHop {
 /// This is a pure function - no RPC calls, no side effects.
 ///
 /// Returns a vector of DexSwapV1 (one per hop for multi-hop, or one for single swap).
+/// An incomplete route rejected under `MultihopPolicy::AllOrNothing` yields none.
 pub fn parse_raydium_v4_swaps(
     facts: &TxFacts,
     chain: &str,
     index_in_block: u32,
     explain_enabled: bool,
+    opts: &DetectorOptions,
 ) -> Vec<DexSwapV1> {
+    parse_raydium_v4_route(facts, chain, index_in_block, explain_enabled, opts).unwrap_or_default()
+}
+
+/// Same as `parse_raydium_v4_swaps`, but reports a multi-hop route with
+/// unparseable legs under `MultihopPolicy::AllOrNothing` instead of dropping it.
+///
+/// Under `BestEffort` the parseable legs of such a route are tagged `partial`.
+pub fn parse_raydium_v4_route(
+    facts: &TxFacts,
+    chain: &str,
+    index_in_block: u32,
+    explain_enabled: bool,
+    opts: &DetectorOptions,
+) -> Result<Vec<DexSwapV1>, IncompleteRoute> {
     // Gate: check if Raydium program is invoked
-    if !facts.has_program(RAYDIUM_AMM_V4_PROGRAM_ID) {
-        return vec![];
+    if !facts.has_program(&opts.programs.raydium_amm_v4) {
+        return Ok(vec![]);
     }
 
     // Find all Raydium instructions
-    let raydium_ixs = facts.instructions_for_program(RAYDIUM_AMM_V4_PROGRAM_ID);
+    let raydium_ixs = facts.instructions_for_program(&opts.programs.raydium_amm_v4);
     if raydium_ixs.is_empty() {
-        return vec![];
+        return Ok(vec![]);
     }
 
     // Detect swap hops
-    let hops = detect_swap_hops(facts, &raydium_ixs);
+    let hops = detect_swap_hops(
+        facts,
+        &raydium_ixs,
+        &opts.trader_exclude,
+        &opts.programs.raydium_amm_v4_authority,
+        &opts.jito_tip_accounts,
+        &opts.raydium_pool_vaults,
+    );
     if hops.is_empty() {
-        return vec![];
+        return Ok(vec![]);
     }
 
-    // Determine if this is a multi-hop route
-    let is_multi_hop = hops.len() > 1;
+    // Determine if this is a multi-hop route (failed legs included)
+    let legs = hops.len();
+    let is_multi_hop = legs > 1;
 
     // Generate route_id for multi-hop
     let route_id = if is_multi_hop {
         // Hash of signature + first outer_ix_index
-        let first_ix = hops.first().map(|h| h.outer_ix_index).unwrap_or(0);
+        let first_ix = raydium_ixs[0].outer_ix_index.unwrap_or(0);
         Some(format!("{}:{}", &facts.signature[..16.min(facts.signature.len())], first_ix))
     } else {
         None
     };
 
-    // Build DexSwapV1 for each hop
-    hops.iter()
+    // Bundle tips are not part of the trader's swap cost
+    let jito_bundle = facts.tip_lamports(&opts.jito_tip_accounts) > 0;
+    let amount_source = opts.amount_sources.for_venue("raydium");
+
+    // Build DexSwapV1 for each hop (None = unparseable leg)
+    let built: Vec<Option<DexSwapV1>> = hops
+        .iter()
         .enumerate()
-        .filter_map(|(hop_idx, hop)| {
+        .map(|(hop_idx, hop)| {
+            let hop = hop.as_ref()?;
+            let (in_amount, out_amount) = select_amounts(hop, amount_source);
+
             // Validate amounts
-            if hop.in_amount == 0 || hop.out_amount == 0 {
+            if in_amount == 0 || out_amount == 0 {
                 return None;
             }
 
+            // WSOL paid from / paid out as the trader's SOL: report native SOL
+            let (in_netted, out_netted) = if opts.normalize_wsol {
+                let lamports = trader_swap_lamports(facts, &hop.trader, &opts.jito_tip_accounts);
+                (
+                    hop.in_mint == WSOL_MINT && -lamports >= in_amount as i128,
+                    hop.out_mint == WSOL_MINT && lamports >= out_amount as i128,
+                )
+            } else {
+                (false, false)
+            };
+            let in_mint = if in_netted { NATIVE_SOL_MINT } else { &hop.in_mint };
+            let out_mint = if out_netted { NATIVE_SOL_MINT } else { &hop.out_mint };
+
             let mut builder = DexSwapV1Builder::new()
                 .chain(chain)
                 .slot(facts.slot)
                 .block_time(facts.block_time)
                 .signature(&facts.signature)
                 .index_in_block(index_in_block)
-                .index_in_tx(hop.outer_ix_index as u16)
+                .index_in_tx(opts.index_in_tx_mode.encode(hop.outer_ix_index, hop.inner_ix_index))
                 .hop_index(hop_idx as u8)
                 .venue("raydium")
                 .pool_id(hop.pool_id.clone())
                 .trader(&hop.trader)
-                .in_token(&hop.in_mint, hop.in_amount.to_string())
-                .out_token(&hop.out_mint, hop.out_amount.to_string())
+                .in_token(in_mint, in_amount)
+                .out_token(out_mint, out_amount)
                 .route_id(route_id.clone())
+                .sol_cost_lamports(Some(facts.sol_cost(&hop.trader, &opts.jito_tip_accounts)))
+                .jito_bundle(jito_bundle)
                 .explain_enabled(explain_enabled);
 
             // Copy confidence reasons
@@ -116,12 +263,26 @@ pub fn parse_raydium_v4_swaps(
                 ConfidenceReasons::VAULT_MATCH,
                 ConfidenceReasons::SINGLE_HOP,
                 ConfidenceReasons::TX_SUCCESS,
+                ConfidenceReasons::UNVERIFIED,
+                ConfidenceReasons::SOL_INFERRED,
+                ConfidenceReasons::BALANCES_TRUNCATED,
+                ConfidenceReasons::MULTI_TRADER,
             ] {
                 if hop.confidence_reasons.has(flag) {
                     builder.add_confidence_reason(flag);
                 }
             }
 
+            if in_netted || out_netted {
+                builder.add_confidence_reason(ConfidenceReasons::WSOL_NETTED);
+            }
+
+            // Fee is taken on the input leg, in its (reported) mint
+            if let Some(fee) = hop.fee_amount {
+                builder = builder.fee(Some(in_mint.to_string()), Some(fee.to_string()));
+                builder.add_confidence_reason(ConfidenceReasons::FEE_COMPUTED);
+            }
+
             // Single hop bonus
             if !is_multi_hop {
                 builder.add_confidence_reason(ConfidenceReasons::SINGLE_HOP);
@@ -132,23 +293,63 @@ pub fn parse_raydium_v4_swaps(
                 builder.add_confidence_reason(ConfidenceReasons::TX_SUCCESS);
             }
 
-            let swap = builder.build();
+            let mut swap = builder.build();
+
+            // Low-confidence swaps: attach the venue's program logs for diagnosis
+            if explain_enabled && opts.explain_log_lines > 0 && !swap.is_high_confidence() {
+                let logs =
+                    facts.logs_for_program(&opts.programs.raydium_amm_v4, opts.explain_log_lines);
+                if let Some(explain) = swap.explain.as_mut()
+                    && !logs.is_empty()
+                {
+                    explain.push_str(" | logs: ");
+                    explain.push_str(&logs.join(" | "));
+                }
+            }
 
             // Validate before returning
-            if swap.validate().is_ok() {
+            if swap.validate_with(opts.allow_same_mint_arb).is_ok() {
                 Some(swap)
             } else {
                 None
             }
         })
-        .collect()
+        .collect();
+
+    let failed_legs = built.iter().filter(|swap| swap.is_none()).count();
+    let mut swaps: Vec<DexSwapV1> = built.into_iter().flatten().collect();
+    if is_multi_hop && failed_legs > 0 {
+        match opts.multihop_policy {
+            MultihopPolicy::AllOrNothing => {
+                return Err(IncompleteRoute {
+                    venue: "raydium",
+                    legs,
+                    failed_legs,
+                });
+            }
+            MultihopPolicy::BestEffort => {
+                for swap in &mut swaps {
+                    swap.partial = true;
+                }
+            }
+        }
+    }
+
+    Ok(swaps)
 }
 
-/// Detect individual swap hops from Raydium instructions
+/// Detect individual swap hops from Raydium instructions.
+///
+/// In a route (several Raydium ixs), a leg whose pool vaults show no flow
+/// cannot be attributed and is returned as `None`.
 fn detect_swap_hops(
     facts: &TxFacts,
     raydium_ixs: &[&schema::ParsedInstruction],
-) -> Vec<RaydiumSwapHop> {
+    trader_exclude: &HashSet<String>,
+    authority: &str,
+    tip_accounts: &HashSet<String>,
+    pool_vaults: &HashMap<String, String>,
+) -> Vec<Option<RaydiumSwapHop>> {
     let mut hops = Vec::new();
 
     // Build owner -> account index map for trader detection
@@ -162,23 +363,74 @@ fn detect_swap_hops(
         map
     };
 
-    // Find the most likely trader (owner with both negative and positive deltas)
-    let trader = find_trader(facts, &owner_to_deltas);
+    // Candidate traders (owners with both negative and positive deltas); each
+    // hop goes to the one holding its accounts. Hops attributed to different
+    // owners mean several wallets traded, e.g. an arbitrage through
+    // intermediate owners. Candidates no hop is attributed to don't count:
+    // another venue's vault owner (an Orca pool in the same route) also shows
+    // a swap pattern
+    let (traders, trader_source) =
+        find_traders(facts, &owner_to_deltas, trader_exclude, authority);
+    let hop_traders: Vec<&str> = raydium_ixs
+        .iter()
+        .map(|ix| hop_trader(facts, ix, &traders))
+        .collect();
+    let multi_trader = hop_traders.iter().any(|t| *t != hop_traders[0]);
 
-    for ix in raydium_ixs {
+    for (ix, trader) in raydium_ixs.iter().zip(&hop_traders) {
         let mut reasons = ConfidenceReasons::new();
         reasons.set(ConfidenceReasons::PROGRAM_GATE);
+        if multi_trader {
+            reasons.set(ConfidenceReasons::MULTI_TRADER);
+        }
+        let trader = trader.to_string();
 
-        // Extract pool_id from instruction accounts
-        let pool_id = if ix.accounts.len() > raydium_accounts::POOL_ID {
-            let pool_idx = ix.accounts[raydium_accounts::POOL_ID];
-            facts.account_at(pool_idx).map(|s| s.to_string())
+        // Extract pool_id from instruction accounts, else from the known vaults
+        let pool_id = if let Some(pool_id) = pool_id_from_ix(facts, ix) {
+            reasons.set(ConfidenceReasons::POOL_ID_FROM_IX);
+            Some(pool_id)
+        } else if let Some(pool_id) = pool_id_from_vaults(facts, ix, pool_vaults) {
+            reasons.set(ConfidenceReasons::POOL_ID_FROM_VAULT);
+            Some(pool_id)
         } else {
             None
         };
 
-        if pool_id.is_some() {
-            reasons.set(ConfidenceReasons::POOL_ID_FROM_IX);
+        // Routes: the trader's deltas are net of every hop, so each hop's legs
+        // come from its own pool's vault flow
+        if raydium_ixs.len() > 1 {
+            let Some((vault_in, vault_out)) = vault_flow(facts, ix) else {
+                hops.push(None);
+                continue;
+            };
+
+            match trader_source {
+                TraderSource::Owner => reasons.set(ConfidenceReasons::TRADER_FROM_OWNER),
+                TraderSource::Signer => reasons.set(ConfidenceReasons::TRADER_IS_SIGNER),
+                TraderSource::Unresolved => {}
+            }
+            let swap_ix = RaydiumSwapIx::from_ix(ix);
+            let (in_amount, out_amount) = (vault_in.delta as u128, vault_out.delta.unsigned_abs());
+            if amounts_confirmed(facts, ix, swap_ix, in_amount, out_amount) {
+                reasons.set(ConfidenceReasons::AMOUNTS_CONFIRMED);
+            }
+            reasons.set(ConfidenceReasons::VAULT_MATCH);
+
+            hops.push(Some(RaydiumSwapHop {
+                outer_ix_index: ix.outer_ix_index.unwrap_or(0),
+                inner_ix_index: ix.inner_ix_index,
+                pool_id,
+                trader: trader.clone(),
+                in_mint: vault_in.mint.clone(),
+                in_amount,
+                out_mint: vault_out.mint.clone(),
+                out_amount,
+                confidence_reasons: reasons,
+                swap_ix,
+                variant: swap_ix.map(|ix| ix.variant()),
+                fee_amount: Some(trade_fee(in_amount, in_amount)),
+            }));
+            continue;
         }
 
         // Get trader's token deltas
@@ -186,115 +438,312 @@ fn detect_swap_hops(
 
         if trader_deltas.is_empty() {
             // Fallback: use all token deltas
-            if let Some(hop) = create_hop_from_all_deltas(facts, ix, pool_id, &trader, reasons) {
-                hops.push(hop);
+            if let Some(hop) = create_hop_from_all_deltas(
+                facts,
+                ix,
+                pool_id,
+                &trader,
+                trader_source,
+                authority,
+                reasons,
+            ) {
+                hops.push(Some(hop));
             }
             continue;
         }
 
         // Identify in/out from trader deltas
-        let (in_delta, out_delta) = identify_in_out_deltas(&trader_deltas);
+        let Some(InOut { in_delta, out_delta }) =
+            pair_in_out(&trader_deltas, PairingStrategy::FirstMatch, &[])
+        else {
+            // One-sided token movement: the other leg may be native SOL
+            if let Some(hop) = create_hop_from_sol_leg(
+                facts,
+                ix,
+                pool_id.clone(),
+                &trader,
+                trader_source,
+                &trader_deltas,
+                authority,
+                tip_accounts,
+                reasons,
+            ) {
+                hops.push(Some(hop));
+                continue;
+            }
 
-        if in_delta.is_none() || out_delta.is_none() {
             // Fallback to all deltas
-            if let Some(hop) = create_hop_from_all_deltas(facts, ix, pool_id, &trader, reasons) {
-                hops.push(hop);
+            if let Some(hop) = create_hop_from_all_deltas(
+                facts,
+                ix,
+                pool_id,
+                &trader,
+                trader_source,
+                authority,
+                reasons,
+            ) {
+                hops.push(Some(hop));
             }
             continue;
-        }
-
-        let in_delta = in_delta.unwrap();
-        let out_delta = out_delta.unwrap();
+        };
 
-        reasons.set(ConfidenceReasons::TRADER_FROM_OWNER);
-        reasons.set(ConfidenceReasons::AMOUNTS_CONFIRMED);
+        if trader_source != TraderSource::Unresolved {
+            reasons.set(ConfidenceReasons::TRADER_FROM_OWNER);
+        }
+        let swap_ix = RaydiumSwapIx::from_ix(ix);
+        let (in_amount, out_amount) = ((-in_delta.delta) as u128, out_delta.delta as u128);
+        if amounts_confirmed(facts, ix, swap_ix, in_amount, out_amount) {
+            reasons.set(ConfidenceReasons::AMOUNTS_CONFIRMED);
+        }
 
         // Verify vault match if possible
-        if verify_vault_match(facts, ix, in_delta, out_delta) {
+        let vault_in_gain = verify_vault_match(facts, ix, in_delta, out_delta);
+        if vault_in_gain.is_some() {
             reasons.set(ConfidenceReasons::VAULT_MATCH);
+        } else if looks_fake(facts, authority, in_amount, out_amount) {
+            reasons.set(ConfidenceReasons::UNVERIFIED);
         }
 
-        let outer_ix_index = ix.outer_ix_index.unwrap_or(0);
-
-        hops.push(RaydiumSwapHop {
-            outer_ix_index,
-            inner_ix_index: if ix.stack_depth > 0 {
-                Some(outer_ix_index)
-            } else {
-                None
-            },
+        hops.push(Some(RaydiumSwapHop {
+            outer_ix_index: ix.outer_ix_index.unwrap_or(0),
+            inner_ix_index: ix.inner_ix_index,
             pool_id,
             trader: trader.clone(),
             in_mint: in_delta.mint.clone(),
-            in_amount: (-in_delta.delta) as u128,
+            in_amount,
             out_mint: out_delta.mint.clone(),
-            out_amount: out_delta.delta as u128,
+            out_amount,
             confidence_reasons: reasons,
-        });
+            swap_ix,
+            variant: swap_ix.map(|ix| ix.variant()),
+            fee_amount: vault_in_gain.map(|gain| trade_fee(in_amount, gain)),
+        }));
     }
 
-    // Deduplicate hops (same outer_ix_index)
-    let mut seen_ix: HashMap<usize, usize> = HashMap::new();
-    let mut deduped = Vec::new();
-    for hop in hops {
-        if let std::collections::hash_map::Entry::Vacant(e) = seen_ix.entry(hop.outer_ix_index) {
-            e.insert(deduped.len());
-            deduped.push(hop);
+    // One hop per instruction: a direct swap and a CPI swap under the same
+    // outer ix are separate instructions, so both are kept even when their
+    // legs match
+    hops
+}
+
+/// Hop amounts under the venue's `AmountSource` (deltas unless the
+/// instruction's exact amount is preferred).
+///
+/// The exact amount is only used when the observed flow confirmed it: an
+/// instruction that asked for something else did not transfer it, whichever
+/// variant it is.
+fn select_amounts(hop: &RaydiumSwapHop, source: AmountSource) -> (u128, u128) {
+    let swap_ix = hop
+        .swap_ix
+        .filter(|_| hop.confidence_reasons.has(ConfidenceReasons::AMOUNTS_CONFIRMED));
+    match (source, swap_ix) {
+        (
+            AmountSource::Instruction | AmountSource::Auto,
+            Some(RaydiumSwapIx::BaseIn { amount_in, .. }),
+        ) => (amount_in as u128, hop.out_amount),
+        (AmountSource::Instruction, Some(RaydiumSwapIx::BaseOut { amount_out, .. })) => {
+            (hop.in_amount, amount_out as u128)
         }
+        _ => (hop.in_amount, hop.out_amount),
+    }
+}
+
+/// Whether the swap instruction's own amounts back the observed ones: either
+/// the hop's balance deltas or, when the wallet's deltas are net of several
+/// swaps or fees, the pool's vault flow for this instruction
+fn amounts_confirmed(
+    facts: &TxFacts,
+    ix: &schema::ParsedInstruction,
+    swap_ix: Option<RaydiumSwapIx>,
+    in_amount: u128,
+    out_amount: u128,
+) -> bool {
+    let Some(swap_ix) = swap_ix else {
+        return false;
+    };
+    swap_ix.agrees_with(in_amount, out_amount)
+        || vault_flow(facts, ix).is_some_and(|(vault_in, vault_out)| {
+            swap_ix.agrees_with(vault_in.delta as u128, vault_out.delta.unsigned_abs())
+        })
+}
+
+/// Pool side of one swap instruction: the vault that received tokens (user's
+/// input) and the vault that paid out (user's output)
+fn vault_flow<'a>(
+    facts: &'a TxFacts,
+    ix: &schema::ParsedInstruction,
+) -> Option<(
+    &'a schema::tx_facts::TokenBalanceDelta,
+    &'a schema::tx_facts::TokenBalanceDelta,
+)> {
+    if ix.accounts.len() <= raydium_accounts::VAULT_B {
+        return None;
     }
+    vault_flow_between(
+        facts,
+        ix.accounts[raydium_accounts::VAULT_A],
+        ix.accounts[raydium_accounts::VAULT_B],
+    )
+}
 
-    deduped
+/// The AMM account at `POOL_ID`, unless the slot is missing or holds a token
+/// account (a pool never does), i.e. the account layout is not the expected one
+fn pool_id_from_ix(facts: &TxFacts, ix: &schema::ParsedInstruction) -> Option<String> {
+    let index = *ix.accounts.get(raydium_accounts::POOL_ID)?;
+    let holds_tokens = facts
+        .pre_token_balances
+        .iter()
+        .chain(&facts.post_token_balances)
+        .any(|b| b.account_index as usize == index);
+    if holds_tokens {
+        return None;
+    }
+    facts.account_at(index).map(|s| s.to_string())
 }
 
-/// Find the most likely trader from token balance deltas
-fn find_trader(
+/// The pool owning the vaults among the instruction's accounts, wherever they
+/// sit, from the `vault -> pool` map (`RAYDIUM_POOLS_FILE`). None unless every
+/// known vault passed belongs to the same pool
+fn pool_id_from_vaults(
     facts: &TxFacts,
-    owner_to_deltas: &HashMap<String, Vec<&schema::tx_facts::TokenBalanceDelta>>,
-) -> String {
-    // Look for an owner with both negative and positive token deltas (swap pattern)
-    for (owner, deltas) in owner_to_deltas {
-        let has_negative = deltas.iter().any(|d| d.delta < 0);
-        let has_positive = deltas.iter().any(|d| d.delta > 0);
-        if has_negative && has_positive {
-            return owner.clone();
+    ix: &schema::ParsedInstruction,
+    pool_vaults: &HashMap<String, String>,
+) -> Option<String> {
+    let mut pools = ix
+        .accounts
+        .iter()
+        .filter_map(|&i| facts.account_at(i))
+        .filter_map(|account| pool_vaults.get(account));
+    let pool = pools.next()?;
+    pools.all(|p| p == pool).then(|| pool.clone())
+}
+
+/// `vault -> pool` map from Raydium's liquidity pool list (`{"official": [..],
+/// "unOfficial": [..]}`, or a bare array), whose entries carry `id`,
+/// `baseVault` and `quoteVault`
+pub fn parse_raydium_pool_vaults(list: &serde_json::Value) -> HashMap<String, String> {
+    let entries: Vec<&serde_json::Value> = match list {
+        serde_json::Value::Array(pools) => pools.iter().collect(),
+        _ => ["official", "unOfficial"]
+            .iter()
+            .filter_map(|key| list.get(key).and_then(|v| v.as_array()))
+            .flatten()
+            .collect(),
+    };
+
+    let mut vaults = HashMap::new();
+    for pool in entries {
+        let Some(id) = pool.get("id").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        for key in ["baseVault", "quoteVault"] {
+            if let Some(vault) = pool.get(key).and_then(|v| v.as_str()) {
+                vaults.insert(vault.to_string(), id.to_string());
+            }
         }
     }
+    vaults
+}
 
-    // Fallback: fee payer
-    facts.fee_payer().unwrap_or("unknown").to_string()
+/// `vault_flow` for a pool whose vaults sit at the given account indices
+pub(super) fn vault_flow_between(
+    facts: &TxFacts,
+    vault_a: usize,
+    vault_b: usize,
+) -> Option<(
+    &schema::tx_facts::TokenBalanceDelta,
+    &schema::tx_facts::TokenBalanceDelta,
+)> {
+    let va = facts.token_delta_at(vault_a)?;
+    let vb = facts.token_delta_at(vault_b)?;
+
+    match (va.delta.signum(), vb.delta.signum()) {
+        (1, -1) => Some((va, vb)),
+        (-1, 1) => Some((vb, va)),
+        _ => None,
+    }
 }
 
-/// Identify input (negative delta) and output (positive delta) from trader's deltas
-fn identify_in_out_deltas<'a>(
-    deltas: &[&'a schema::tx_facts::TokenBalanceDelta],
-) -> (
-    Option<&'a schema::tx_facts::TokenBalanceDelta>,
-    Option<&'a schema::tx_facts::TokenBalanceDelta>,
-) {
-    let mut in_delta: Option<&schema::tx_facts::TokenBalanceDelta> = None;
-    let mut out_delta: Option<&schema::tx_facts::TokenBalanceDelta> = None;
-
-    for delta in deltas {
-        if delta.delta < 0 && in_delta.is_none() {
-            in_delta = Some(delta);
-        } else if delta.delta > 0 && out_delta.is_none() {
-            out_delta = Some(delta);
-        }
+/// Find the candidate traders from token balance deltas, most likely first.
+///
+/// Every owner with both a negative and a positive delta is a candidate.
+/// Excluded addresses (routers/aggregators) and the pool authority, which owns
+/// the vaults and so also shows a swap pattern, are skipped; if no owner
+/// candidate remains, the first non-excluded signer is the only one.
+fn find_traders(
+    facts: &TxFacts,
+    owner_to_deltas: &HashMap<String, Vec<&schema::tx_facts::TokenBalanceDelta>>,
+    trader_exclude: &HashSet<String>,
+    authority: &str,
+) -> (Vec<String>, TraderSource) {
+    // Look for owners with both negative and positive token deltas (swap pattern).
+    // Several can match (an arbitrage's intermediate owners, an unrecognized vault
+    // owner): signers first, then by pubkey, so the order never depends on map
+    // iteration order
+    let mut candidates: Vec<String> = owner_to_deltas
+        .iter()
+        .filter(|(owner, deltas)| {
+            !trader_exclude.contains(*owner)
+                && *owner != authority
+                && deltas.iter().any(|d| d.delta < 0)
+                && deltas.iter().any(|d| d.delta > 0)
+        })
+        .map(|(owner, _)| owner.clone())
+        .collect();
+    candidates.sort_by(|a, b| {
+        (!facts.is_signer(a), a.as_str()).cmp(&(!facts.is_signer(b), b.as_str()))
+    });
+    if !candidates.is_empty() {
+        return (candidates, TraderSource::Owner);
     }
 
-    (in_delta, out_delta)
+    // Next candidate: first non-excluded signer (fee payer comes first)
+    if let Some(signer) = facts.signers.iter().find(|s| !trader_exclude.contains(*s)) {
+        return (vec![signer.clone()], TraderSource::Signer);
+    }
+
+    // Nothing usable: keep the fee payer but don't vouch for it
+    (
+        vec![facts.fee_payer().unwrap_or("unknown").to_string()],
+        TraderSource::Unresolved,
+    )
+}
+
+/// The candidate trader of one swap instruction: the owner of its user source
+/// or destination token account, else the most likely candidate
+fn hop_trader<'a>(
+    facts: &TxFacts,
+    ix: &schema::ParsedInstruction,
+    traders: &'a [String],
+) -> &'a str {
+    [raydium_accounts::USER_SOURCE, raydium_accounts::USER_DEST]
+        .iter()
+        .filter_map(|&pos| ix.accounts.get(pos))
+        .filter_map(|&index| {
+            facts
+                .token_balance_deltas
+                .iter()
+                .find(|d| d.account_index as usize == index)?
+                .owner
+                .as_deref()
+        })
+        .find_map(|owner| traders.iter().find(|t| *t == owner))
+        .unwrap_or(&traders[0])
 }
 
-/// Verify that vault balance changes match user balance changes
+/// Verify that vault balance changes match user balance changes.
+///
+/// Returns how much the input vault gained (the basis for the fee), None when
+/// the vaults don't match.
 fn verify_vault_match(
     facts: &TxFacts,
     ix: &schema::ParsedInstruction,
     in_delta: &schema::tx_facts::TokenBalanceDelta,
     out_delta: &schema::tx_facts::TokenBalanceDelta,
-) -> bool {
+) -> Option<u128> {
     // Get vault account indices from instruction
     if ix.accounts.len() <= raydium_accounts::VAULT_B {
-        return false;
+        return None;
     }
 
     let vault_a_idx = ix.accounts[raydium_accounts::VAULT_A];
@@ -309,17 +758,155 @@ fn verify_vault_match(
     });
 
     // Verify: user's in should match vault's in (positive), user's out should match vault's out (negative)
-    match (vault_a_delta, vault_b_delta) {
-        (Some(va), Some(vb)) => {
-            // Vault A received what user sent OR Vault B received what user sent
-            let vault_received_in = (va.mint == in_delta.mint && va.delta > 0)
-                || (vb.mint == in_delta.mint && vb.delta > 0);
-            let vault_sent_out = (va.mint == out_delta.mint && va.delta < 0)
-                || (vb.mint == out_delta.mint && vb.delta < 0);
-            vault_received_in && vault_sent_out
-        }
-        _ => false,
+    let (va, vb) = (vault_a_delta?, vault_b_delta?);
+    // Vault A received what user sent OR Vault B received what user sent
+    let vault_in = [va, vb]
+        .into_iter()
+        .find(|v| v.mint == in_delta.mint && v.delta > 0)?;
+    let vault_sent_out = (va.mint == out_delta.mint && va.delta < 0)
+        || (vb.mint == out_delta.mint && vb.delta < 0);
+    vault_sent_out.then_some(vault_in.delta as u128)
+}
+
+/// Pool fee on a swap of `in_amount` whose input vault gained `vault_in_gain`.
+///
+/// A vault that kept less than the trader sent means the difference left as a
+/// fee; otherwise the fee stayed in the pool and only the fee tier tells it.
+fn trade_fee(in_amount: u128, vault_in_gain: u128) -> u128 {
+    if vault_in_gain < in_amount {
+        return in_amount - vault_in_gain;
+    }
+    (in_amount * TRADE_FEE_NUMERATOR).div_ceil(TRADE_FEE_DENOMINATOR)
+}
+
+/// Heuristic for fake swaps once vault correspondence has failed: both amounts
+/// suspiciously round, or no token account owned by the Raydium AMM authority
+/// moved (i.e. the "pool" is not a real v4 pool).
+fn looks_fake(facts: &TxFacts, authority: &str, in_amount: u128, out_amount: u128) -> bool {
+    const ROUND_UNIT: u128 = 1_000_000;
+    let both_round = in_amount.is_multiple_of(ROUND_UNIT) && out_amount.is_multiple_of(ROUND_UNIT);
+
+    let pool_vault_moved = facts
+        .token_balance_deltas
+        .iter()
+        .any(|d| d.owner.as_deref() == Some(authority));
+
+    both_round || !pool_vault_moved
+}
+
+/// Lamports the trader received (positive) or spent (negative) in this tx,
+/// excluding the fee and bundle tips when the trader paid them, and the rent
+/// of its token accounts
+fn trader_swap_lamports(facts: &TxFacts, trader: &str, tip_accounts: &HashSet<String>) -> i128 {
+    let mut lamports: i128 = facts
+        .sol_balance_deltas
+        .iter()
+        .filter(|d| d.account == trader)
+        .map(|d| d.delta as i128)
+        .sum();
+    if facts.fee_payer() == Some(trader) {
+        lamports += facts.fee as i128 + facts.tip_lamports(tip_accounts) as i128;
+    }
+    lamports + trader_token_account_rent(facts, trader)
+}
+
+/// Rent-exempt deposits of the trader's (non-WSOL) token accounts opened in
+/// this tx, minus those refunded by accounts it closed. A swap into a new
+/// token account funds it from the trader's SOL, which is not part of the leg.
+/// WSOL accounts are left out: their lamports are the wrapped amount itself.
+fn trader_token_account_rent(facts: &TxFacts, trader: &str) -> i128 {
+    let trader_accounts: HashSet<u32> = facts
+        .pre_token_balances
+        .iter()
+        .chain(&facts.post_token_balances)
+        .filter(|b| b.owner.as_deref() == Some(trader) && b.mint != WSOL_MINT)
+        .map(|b| b.account_index)
+        .collect();
+    facts
+        .sol_balance_deltas
+        .iter()
+        .filter(|d| trader_accounts.contains(&(d.account_index as u32)))
+        .map(|d| match (d.pre_balance, d.post_balance) {
+            (0, opened) => opened as i128,
+            (closed, 0) => -(closed as i128),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Create a hop whose WSOL leg is inferred from the trader's native SOL change.
+///
+/// Swapping through a temporary WSOL account (created and closed in the same
+/// tx) leaves no token balances for that leg, only lamports on the trader.
+/// When the SOL deltas were truncated, the trader's change may be missing or
+/// incomplete, so such hops are flagged and penalized.
+#[allow(clippy::too_many_arguments)]
+fn create_hop_from_sol_leg(
+    facts: &TxFacts,
+    ix: &schema::ParsedInstruction,
+    pool_id: Option<String>,
+    trader: &str,
+    trader_source: TraderSource,
+    trader_deltas: &[&schema::tx_facts::TokenBalanceDelta],
+    authority: &str,
+    tip_accounts: &HashSet<String>,
+    mut reasons: ConfidenceReasons,
+) -> Option<RaydiumSwapHop> {
+    let [token_delta] = trader_deltas else {
+        return None;
+    };
+    if token_delta.mint == WSOL_MINT {
+        return None;
+    }
+
+    // SOL must move opposite to the token
+    let lamports = trader_swap_lamports(facts, trader, tip_accounts);
+    let (in_mint, in_amount, out_mint, out_amount) = if token_delta.delta > 0 && lamports < 0 {
+        (
+            WSOL_MINT.to_string(),
+            lamports.unsigned_abs(),
+            token_delta.mint.clone(),
+            token_delta.delta as u128,
+        )
+    } else if token_delta.delta < 0 && lamports > 0 {
+        (
+            token_delta.mint.clone(),
+            token_delta.delta.unsigned_abs(),
+            WSOL_MINT.to_string(),
+            lamports as u128,
+        )
+    } else {
+        return None;
+    };
+
+    match trader_source {
+        TraderSource::Owner => reasons.set(ConfidenceReasons::TRADER_FROM_OWNER),
+        TraderSource::Signer => reasons.set(ConfidenceReasons::TRADER_IS_SIGNER),
+        TraderSource::Unresolved => {}
+    }
+    reasons.set(ConfidenceReasons::SOL_INFERRED);
+    if facts.sol_deltas_truncated {
+        reasons.set(ConfidenceReasons::BALANCES_TRUNCATED);
     }
+    if looks_fake(facts, authority, in_amount, out_amount) {
+        reasons.set(ConfidenceReasons::UNVERIFIED);
+    }
+
+    let swap_ix = RaydiumSwapIx::from_ix(ix);
+    Some(RaydiumSwapHop {
+        outer_ix_index: ix.outer_ix_index.unwrap_or(0),
+        inner_ix_index: ix.inner_ix_index,
+        pool_id,
+        trader: trader.to_string(),
+        in_mint,
+        in_amount,
+        out_mint,
+        out_amount,
+        confidence_reasons: reasons,
+        swap_ix,
+        variant: swap_ix.map(|ix| ix.variant()),
+        fee_amount: None,
+    })
 }
 
 /// Fallback: create hop from all token deltas (not trader-specific)
@@ -328,24 +915,32 @@ fn create_hop_from_all_deltas(
     ix: &schema::ParsedInstruction,
     pool_id: Option<String>,
     trader: &str,
+    trader_source: TraderSource,
+    authority: &str,
     mut reasons: ConfidenceReasons,
 ) -> Option<RaydiumSwapHop> {
-    // Find any negative and positive delta
+    // Find any negative and positive delta of another mint: the input mint
+    // moving into its vault is the same leg, and on its own a deposit
     let in_delta = facts.token_balance_deltas.iter().find(|d| d.delta < 0)?;
-    let out_delta = facts.token_balance_deltas.iter().find(|d| d.delta > 0)?;
+    let out_delta = facts
+        .token_balance_deltas
+        .iter()
+        .find(|d| d.delta > 0 && d.mint != in_delta.mint)?;
 
     // Lower confidence since we couldn't confirm trader
-    reasons.set(ConfidenceReasons::TRADER_IS_SIGNER);
+    if trader_source != TraderSource::Unresolved {
+        reasons.set(ConfidenceReasons::TRADER_IS_SIGNER);
+    }
 
-    let outer_ix_index = ix.outer_ix_index.unwrap_or(0);
+    // No vault check on this path, so only the fake-swap heuristics apply
+    if looks_fake(facts, authority, (-in_delta.delta) as u128, out_delta.delta as u128) {
+        reasons.set(ConfidenceReasons::UNVERIFIED);
+    }
 
+    let swap_ix = RaydiumSwapIx::from_ix(ix);
     Some(RaydiumSwapHop {
-        outer_ix_index,
-        inner_ix_index: if ix.stack_depth > 0 {
-            Some(outer_ix_index)
-        } else {
-            None
-        },
+        outer_ix_index: ix.outer_ix_index.unwrap_or(0),
+        inner_ix_index: ix.inner_ix_index,
         pool_id,
         trader: trader.to_string(),
         in_mint: in_delta.mint.clone(),
@@ -353,13 +948,20 @@ fn create_hop_from_all_deltas(
         out_mint: out_delta.mint.clone(),
         out_amount: out_delta.delta as u128,
         confidence_reasons: reasons,
+        swap_ix,
+        variant: swap_ix.map(|ix| ix.variant()),
+        fee_amount: None,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::detectors::registry::DetectorRegistry;
+    use crate::detectors::{AmountSources, IndexInTxMode};
+    use schema::{ChainPrograms, RAYDIUM_AMM_V4_PROGRAM_ID};
     use serde_json::json;
+    use std::sync::Arc;
 
     fn make_tx_facts(tx: serde_json::Value, sig: &str) -> TxFacts {
         TxFacts::from_json(&tx, sig, 250000000)
@@ -435,7 +1037,8 @@ mod tests {
             .push(json!(RAYDIUM_AMM_V4_PROGRAM_ID));
 
         let facts = make_tx_facts(tx, "sig123");
-        let swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true);
+        let swaps =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &DetectorOptions::default());
 
         assert_eq!(swaps.len(), 1);
         let swap = &swaps[0];
@@ -462,11 +1065,1001 @@ mod tests {
         });
 
         let facts = make_tx_facts(tx, "sig_no_raydium");
-        let swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false);
+        let swaps =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &DetectorOptions::default());
 
         assert!(swaps.is_empty());
     }
 
+    /// Router PDA holds both legs (the classic both-sided owner); the user
+    /// only signs and pays the input, the output lands with a recipient.
+    fn routed_swap_tx() -> serde_json::Value {
+        json!({
+            "blockTime": 1703001234,
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "preBalances": [1000000000],
+                "postBalances": [999995000],
+                "preTokenBalances": [
+                    {"accountIndex": 2, "mint": "MintIn111", "owner": "UserWallet111", "uiTokenAmount": {"amount": "1000", "decimals": 6}},
+                    {"accountIndex": 3, "mint": "MintOut111", "owner": "Recipient111", "uiTokenAmount": {"amount": "0", "decimals": 6}},
+                    {"accountIndex": 4, "mint": "MintIn111", "owner": "RouterPda111", "uiTokenAmount": {"amount": "0", "decimals": 6}},
+                    {"accountIndex": 5, "mint": "MintOut111", "owner": "RouterPda111", "uiTokenAmount": {"amount": "900", "decimals": 6}}
+                ],
+                "postTokenBalances": [
+                    {"accountIndex": 2, "mint": "MintIn111", "owner": "UserWallet111", "uiTokenAmount": {"amount": "0", "decimals": 6}},
+                    {"accountIndex": 3, "mint": "MintOut111", "owner": "Recipient111", "uiTokenAmount": {"amount": "900", "decimals": 6}},
+                    {"accountIndex": 4, "mint": "MintIn111", "owner": "RouterPda111", "uiTokenAmount": {"amount": "1000", "decimals": 6}},
+                    {"accountIndex": 5, "mint": "MintOut111", "owner": "RouterPda111", "uiTokenAmount": {"amount": "0", "decimals": 6}}
+                ],
+                "innerInstructions": []
+            },
+            "slot": 250000000,
+            "transaction": {
+                "message": {
+                    "header": {"numRequiredSignatures": 1},
+                    "accountKeys": [
+                        "UserWallet111",
+                        "PoolAccount123",
+                        "UserTokenIn",
+                        "RecipientTokenOut",
+                        "RouterTokenIn",
+                        "RouterTokenOut",
+                        "RouterPda111",
+                        RAYDIUM_AMM_V4_PROGRAM_ID
+                    ],
+                    "instructions": [
+                        {"programIdIndex": 7, "accounts": [0, 1, 2, 3, 4, 5, 6], "data": "SwapData"}
+                    ]
+                },
+                "signatures": ["sig_routed"]
+            }
+        })
+    }
+
+    #[test]
+    fn test_excluded_router_reresolves_to_signer() {
+        let facts = make_tx_facts(routed_swap_tx(), "sig_routed");
+
+        let baseline =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &DetectorOptions::default());
+        assert_eq!(baseline.len(), 1);
+        assert_eq!(baseline[0].trader, "RouterPda111");
+
+        let opts = DetectorOptions {
+            trader_exclude: HashSet::from(["RouterPda111".to_string()]),
+            ..Default::default()
+        };
+        let swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &opts);
+        assert_eq!(swaps.len(), 1);
+        let swap = &swaps[0];
+        assert_eq!(swap.trader, "UserWallet111");
+        assert_eq!(swap.in_mint, "MintIn111");
+        assert_eq!(swap.out_mint, "MintOut111");
+
+        let reasons = ConfidenceReasons(swap.confidence_reasons);
+        assert!(reasons.has(ConfidenceReasons::TRADER_IS_SIGNER));
+        assert!(!reasons.has(ConfidenceReasons::TRADER_FROM_OWNER));
+        assert!(swap.confidence < baseline[0].confidence);
+    }
+
+    #[test]
+    fn test_all_candidates_excluded_lowers_confidence() {
+        let facts = make_tx_facts(routed_swap_tx(), "sig_routed");
+
+        let router_only = DetectorOptions {
+            trader_exclude: HashSet::from(["RouterPda111".to_string()]),
+            ..Default::default()
+        };
+        let everyone = DetectorOptions {
+            trader_exclude: HashSet::from([
+                "RouterPda111".to_string(),
+                "UserWallet111".to_string(),
+            ]),
+            ..Default::default()
+        };
+
+        let resolved =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &router_only);
+        let unresolved =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &everyone);
+        assert_eq!(unresolved.len(), 1);
+
+        let reasons = ConfidenceReasons(unresolved[0].confidence_reasons);
+        assert!(!reasons.has(ConfidenceReasons::TRADER_IS_SIGNER));
+        assert!(!reasons.has(ConfidenceReasons::TRADER_FROM_OWNER));
+        assert!(unresolved[0].confidence < resolved[0].confidence);
+    }
+
+    #[test]
+    fn test_all_deltas_fallback_skips_same_mint_out_leg() {
+        let balance = |index: u32, mint: &str, amount: &str| {
+            json!({
+                "accountIndex": index,
+                "mint": mint,
+                "uiTokenAmount": {"amount": amount, "decimals": 6}
+            })
+        };
+        let (mint_x, mint_y) = ("MintX111", "MintY111");
+        // No owners on the balances, so the hop comes from all deltas; the
+        // input's own vault gains before the output account does
+        let tx = json!({
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "preBalances": [1000000000],
+                "postBalances": [999995000],
+                "preTokenBalances": [
+                    balance(2, mint_x, "1000"),
+                    balance(3, mint_x, "5000"),
+                    balance(4, mint_y, "5000"),
+                    balance(5, mint_y, "0"),
+                ],
+                "postTokenBalances": [
+                    balance(2, mint_x, "900"),
+                    balance(3, mint_x, "5100"),
+                    balance(4, mint_y, "4950"),
+                    balance(5, mint_y, "50"),
+                ],
+                "innerInstructions": []
+            },
+            "transaction": {
+                "message": {
+                    "header": {"numRequiredSignatures": 1},
+                    "accountKeys": [
+                        "Trader111", "Pool111", "UserX111", "VaultX111", "VaultY111", "UserY111",
+                        RAYDIUM_AMM_V4_PROGRAM_ID
+                    ],
+                    "instructions": [
+                        {"programIdIndex": 6, "accounts": [0, 1, 2, 3, 4, 5], "data": "SwapData"}
+                    ]
+                },
+                "signatures": ["sig_same_mint_out"]
+            }
+        });
+        let facts = make_tx_facts(tx, "sig_same_mint_out");
+
+        let swaps =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &DetectorOptions::default());
+        assert_eq!(swaps.len(), 1);
+        assert_eq!((swaps[0].in_mint.as_str(), swaps[0].in_amount.get()), (mint_x, 100));
+        assert_eq!((swaps[0].out_mint.as_str(), swaps[0].out_amount.get()), (mint_y, 50));
+    }
+
+    #[test]
+    fn test_fake_swap_without_vault_flow_is_unverified() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/fake_swap_no_vault.json"
+        );
+        let tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let facts = make_tx_facts(tx, "FakeSwapSig");
+
+        let swaps =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &DetectorOptions::default());
+        assert_eq!(swaps.len(), 1);
+
+        let swap = &swaps[0];
+        let reasons = ConfidenceReasons(swap.confidence_reasons);
+        // Its instruction data is not a swap, so the deltas are not confirmed
+        assert!(!reasons.has(ConfidenceReasons::AMOUNTS_CONFIRMED));
+        assert!(!reasons.has(ConfidenceReasons::VAULT_MATCH));
+        assert!(reasons.has(ConfidenceReasons::UNVERIFIED));
+        assert!(swap.explain.as_deref().unwrap().contains("!unverified"));
+        assert!(!swap.is_high_confidence());
+        assert!(!swap.explain.as_deref().unwrap().contains("logs:"));
+
+        // With log lines enabled, the venue's program logs are appended
+        let opts = DetectorOptions {
+            explain_log_lines: 5,
+            ..Default::default()
+        };
+        let swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &opts);
+        let explain = swaps[0].explain.as_deref().unwrap();
+        assert!(explain.ends_with(" | logs: Program log: ray_log: swap"));
+    }
+
+    #[test]
+    fn test_devnet_programs_gate_on_devnet_amm() {
+        let devnet = ChainPrograms::devnet();
+        let mut tx = routed_swap_tx();
+        tx["transaction"]["message"]["accountKeys"][7] = json!(devnet.raydium_amm_v4);
+        let facts = make_tx_facts(tx, "sig_routed");
+
+        // Mainnet defaults do not recognise the devnet AMM
+        let mainnet =
+            parse_raydium_v4_swaps(&facts, "solana-devnet", 0, false, &DetectorOptions::default());
+        assert!(mainnet.is_empty());
+
+        let opts = DetectorOptions {
+            programs: devnet,
+            ..Default::default()
+        };
+        let swaps = parse_raydium_v4_swaps(&facts, "solana-devnet", 0, false, &opts);
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].chain, "solana-devnet");
+    }
+
+    #[test]
+    fn test_jito_tip_excluded_from_sol_cost() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/jito_tip_swap.json"
+        );
+        let tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let facts = make_tx_facts(tx, "JitoBundleSig");
+
+        let opts = DetectorOptions {
+            jito_tip_accounts: schema::JITO_TIP_ACCOUNTS
+                .iter()
+                .map(|a| a.to_string())
+                .collect(),
+            ..Default::default()
+        };
+        let swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &opts);
+        assert_eq!(swaps.len(), 1);
+
+        let swap = &swaps[0];
+        assert_eq!(swap.trader, "BundleTrader1111111111111111111111111111111");
+        assert!(swap.jito_bundle);
+        // Trader paid 5000 fee + 100000 tip; only the fee counts as cost
+        assert_eq!(swap.sol_cost_lamports, Some(5000));
+
+        // Without known tip accounts the tip is counted as trader cost
+        let swaps =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &DetectorOptions::default());
+        assert!(!swaps[0].jito_bundle);
+        assert_eq!(swaps[0].sol_cost_lamports, Some(105000));
+    }
+
+    #[test]
+    fn test_sol_inferred_leg_downgraded_when_balances_truncated() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/sol_inferred_truncated.json"
+        );
+        let tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+        // accountKeys has one more entry than the balance arrays
+        let facts = make_tx_facts(tx.clone(), "SolInferredSig");
+        assert!(facts.sol_deltas_truncated);
+        let swaps =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &DetectorOptions::default());
+        assert_eq!(swaps.len(), 1);
+        let truncated = &swaps[0];
+        assert_eq!(truncated.in_mint, WSOL_MINT);
+        assert_eq!(truncated.in_amount, "1000000000");
+        assert_eq!(truncated.out_amount, "101234567");
+        let reasons = ConfidenceReasons(truncated.confidence_reasons);
+        assert!(reasons.has(ConfidenceReasons::SOL_INFERRED));
+        assert!(reasons.has(ConfidenceReasons::BALANCES_TRUNCATED));
+        assert!(truncated.explain.as_deref().unwrap().contains("!balances_truncated"));
+
+        // Same swap with aligned balances: inferred, but not penalized
+        let mut tx = tx;
+        for key in ["preBalances", "postBalances"] {
+            tx["meta"][key].as_array_mut().unwrap().push(json!(1));
+        }
+        let facts = make_tx_facts(tx, "SolInferredSig");
+        assert!(!facts.sol_deltas_truncated);
+        let swaps =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &DetectorOptions::default());
+        let reasons = ConfidenceReasons(swaps[0].confidence_reasons);
+        assert!(reasons.has(ConfidenceReasons::SOL_INFERRED));
+        assert!(!reasons.has(ConfidenceReasons::BALANCES_TRUNCATED));
+        assert_eq!(swaps[0].confidence, truncated.confidence + 20);
+    }
+
+    #[test]
+    fn test_sol_leg_excludes_token_account_rent() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/sol_inferred_truncated.json"
+        );
+        let mut tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        for key in ["preBalances", "postBalances"] {
+            tx["meta"][key].as_array_mut().unwrap().push(json!(1));
+        }
+
+        // The swap opens the trader's USDC account: the trader also pays its rent
+        let rent = 2039280u64;
+        tx["meta"]["preBalances"][0] = json!(3_000_000_000u64 + rent);
+        tx["meta"]["preBalances"][2] = json!(0);
+        tx["meta"]["preTokenBalances"]
+            .as_array_mut()
+            .unwrap()
+            .retain(|b| b["accountIndex"] != 2);
+        let facts = make_tx_facts(tx.clone(), "SolRentSig");
+        let swaps =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &DetectorOptions::default());
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].in_mint, WSOL_MINT);
+        assert_eq!(swaps[0].in_amount, "1000000000");
+        // Still a cost to the trader, just not part of the leg
+        assert_eq!(swaps[0].sol_cost_lamports, Some(1_000_000_000 + 5000 + rent));
+
+        // Closing it again refunds the rent to the trader, also not a leg
+        tx["meta"]["preBalances"][0] = json!(3_000_000_000u64);
+        tx["meta"]["postBalances"][0] = json!(1_999_995_000u64 + rent);
+        tx["meta"]["preBalances"][2] = json!(rent);
+        tx["meta"]["postBalances"][2] = json!(0);
+        let facts = make_tx_facts(tx, "SolRentSig");
+        let trader = "NativeSolTrader111111111111111111111111111";
+        assert_eq!(trader_token_account_rent(&facts, trader), -(rent as i128));
+    }
+
+    #[test]
+    fn test_two_inner_swaps_under_one_outer_ix_get_distinct_index_in_tx() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/jupiter_two_hop_inner.json"
+        );
+        let tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let facts = make_tx_facts(tx, "JupiterTwoHopSig");
+
+        // SOL -> mSOL -> USDC, both hops CPI'd from outer ix 0
+        let composite = DetectorOptions {
+            index_in_tx_mode: IndexInTxMode::Composite,
+            ..Default::default()
+        };
+        let swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &composite);
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(
+            swaps.iter().map(|s| s.index_in_tx).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        // Each hop's legs come from its own pool's vaults
+        assert_eq!(swaps[0].in_mint, WSOL_MINT);
+        assert_eq!(swaps[0].in_amount, "5000000000");
+        assert_eq!(swaps[0].out_mint, "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So");
+        assert_eq!(swaps[0].out_amount, "4400000000");
+        assert_eq!(swaps[1].in_amount, "4400000000");
+        assert_eq!(swaps[1].out_mint, "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+        assert_eq!(swaps[1].out_amount, "485000000");
+
+        // Outer-only mode (the default): both hops collapse onto the outer ix index
+        let outer = DetectorOptions::default();
+        assert_eq!(outer.index_in_tx_mode, IndexInTxMode::Outer);
+        let swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &outer);
+        assert!(swaps.iter().all(|s| s.index_in_tx == 0));
+    }
+
+    #[test]
+    fn test_direct_and_cpi_swap_under_same_outer_ix_both_emitted() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/raydium_direct_and_cpi_same_outer.json"
+        );
+        let tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let facts = make_tx_facts(tx, "DirectAndCpiSig");
+
+        // Outer ix 0 swaps in pool A and CPIs an identical-sized swap in pool B
+        let opts = DetectorOptions::default();
+        let hops = detect_swap_hops(
+            &facts,
+            &facts.instructions_for_program(RAYDIUM_AMM_V4_PROGRAM_ID),
+            &opts.trader_exclude,
+            &opts.programs.raydium_amm_v4_authority,
+            &opts.jito_tip_accounts,
+            &opts.raydium_pool_vaults,
+        );
+        assert_eq!(hops.len(), 2);
+        let hops: Vec<_> = hops.into_iter().map(Option::unwrap).collect();
+        assert_eq!(
+            hops.iter().map(|h| (h.outer_ix_index, h.inner_ix_index)).collect::<Vec<_>>(),
+            vec![(0, None), (0, Some(0))]
+        );
+        assert_eq!(hops[0].in_amount, hops[1].in_amount);
+        assert_ne!(hops[0].pool_id, hops[1].pool_id);
+
+        let composite = DetectorOptions {
+            index_in_tx_mode: IndexInTxMode::Composite,
+            ..Default::default()
+        };
+        let swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &composite);
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(
+            swaps.iter().map(|s| s.index_in_tx).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn test_route_with_failed_middle_leg_under_each_policy() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/jupiter_three_hop_broken_leg.json"
+        );
+        let tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let facts = make_tx_facts(tx, "JupiterBrokenLegSig");
+
+        // SOL -> mSOL -> USDT -> USDC; the middle pool's vaults have no balances
+        let best_effort = DetectorOptions::default();
+        let swaps =
+            parse_raydium_v4_route(&facts, "solana-mainnet", 0, false, &best_effort).unwrap();
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(
+            swaps.iter().map(|s| s.hop_index).collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+        assert_eq!(swaps[0].in_mint, WSOL_MINT);
+        assert_eq!(swaps[1].out_mint, "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+        assert!(swaps.iter().all(|s| s.partial));
+        assert!(
+            swaps
+                .iter()
+                .all(|s| !ConfidenceReasons(s.confidence_reasons).has(ConfidenceReasons::SINGLE_HOP))
+        );
+
+        // All-or-nothing: the whole tx is rejected
+        let strict = DetectorOptions {
+            multihop_policy: MultihopPolicy::AllOrNothing,
+            ..Default::default()
+        };
+        let err = parse_raydium_v4_route(&facts, "solana-mainnet", 0, false, &strict).unwrap_err();
+        assert_eq!(
+            err,
+            IncompleteRoute {
+                venue: "raydium",
+                legs: 3,
+                failed_legs: 1,
+            }
+        );
+        let registry = DetectorRegistry::new(Arc::new(strict.clone()));
+        assert!(registry.detect(&facts, "solana-mainnet", 0, false).is_err());
+        assert!(parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &strict).is_empty());
+
+        // A complete route is never tagged
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/jupiter_two_hop_inner.json"
+        );
+        let tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let facts = make_tx_facts(tx, "JupiterTwoHopSig");
+        let swaps = parse_raydium_v4_route(&facts, "solana-mainnet", 0, false, &strict).unwrap();
+        assert_eq!(swaps.len(), 2);
+        assert!(swaps.iter().all(|s| !s.partial));
+    }
+
+    #[test]
+    fn test_amount_source_modes() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/raydium_swap_instruction_amounts.json"
+        );
+        let mut tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let amounts = |tx: &serde_json::Value, source: AmountSource| {
+            let facts = make_tx_facts(tx.clone(), "AmountSourceSig");
+            let opts = DetectorOptions {
+                amount_sources: AmountSources::all(source),
+                ..Default::default()
+            };
+            let swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &opts);
+            assert_eq!(swaps.len(), 1);
+            (swaps[0].in_amount.get(), swaps[0].out_amount.get())
+        };
+
+        // swapBaseIn(amount_in=5_000_000); the trader also paid a 250 referrer fee
+        let facts = make_tx_facts(tx.clone(), "AmountSourceSig");
+        let ix = facts.instructions_for_program(RAYDIUM_AMM_V4_PROGRAM_ID)[0];
+        assert_eq!(
+            RaydiumSwapIx::from_ix(ix),
+            Some(RaydiumSwapIx::BaseIn {
+                amount_in: 5_000_000,
+                minimum_amount_out: 48_000_000,
+            })
+        );
+        let delta = (5_000_250, 48_123_457);
+        let exact_in = (5_000_000, 48_123_457);
+        assert_eq!(amounts(&tx, AmountSource::Delta), delta);
+        assert_eq!(amounts(&tx, AmountSource::Instruction), exact_in);
+        assert_eq!(amounts(&tx, AmountSource::Auto), exact_in);
+
+        // swapBaseOut(max_amount_in, amount_out): auto keeps deltas
+        let mut data = vec![RaydiumSwapIx::SWAP_BASE_OUT];
+        data.extend_from_slice(&5_100_000u64.to_le_bytes());
+        data.extend_from_slice(&48_123_457u64.to_le_bytes());
+        tx["transaction"]["message"]["instructions"][0]["data"] =
+            json!(bs58::encode(data).into_string());
+        assert_eq!(amounts(&tx, AmountSource::Delta), delta);
+        assert_eq!(amounts(&tx, AmountSource::Instruction), delta);
+        assert_eq!(amounts(&tx, AmountSource::Auto), delta);
+
+        // An amount_out that never moved is not reported, even when preferred
+        let mut data = vec![RaydiumSwapIx::SWAP_BASE_OUT];
+        data.extend_from_slice(&5_100_000u64.to_le_bytes());
+        data.extend_from_slice(&48_000_000u64.to_le_bytes());
+        tx["transaction"]["message"]["instructions"][0]["data"] =
+            json!(bs58::encode(data).into_string());
+        assert_eq!(amounts(&tx, AmountSource::Instruction), delta);
+
+        // Per-venue list: other venues fall back to the bare default
+        let sources = AmountSources::parse("raydium:instruction, delta").unwrap();
+        assert_eq!(sources.for_venue("raydium"), AmountSource::Instruction);
+        assert_eq!(sources.for_venue("phoenix"), AmountSource::Delta);
+        assert!(AmountSources::parse("raydium:exact").is_none());
+    }
+
+    #[test]
+    fn test_exact_in_and_exact_out_report_transferred_amounts() {
+        let cases = [
+            // amount_in=5_000_000 exact, output above minimum_amount_out=48_000_000
+            ("raydium_swap_base_in.json", SwapVariant::ExactIn, "5000000", "48123457"),
+            // amount_out=48_000_000 exact, input below max_amount_in=5_100_000
+            ("raydium_swap_base_out.json", SwapVariant::ExactOut, "5050000", "48000000"),
+        ];
+        for (fixture, variant, in_amount, out_amount) in cases {
+            let path = format!(
+                "{}/../../crates/schema/tests/fixtures/{fixture}",
+                env!("CARGO_MANIFEST_DIR")
+            );
+            let tx: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+            let facts = make_tx_facts(tx, fixture);
+
+            let opts = DetectorOptions::default();
+            let ixs = facts.instructions_for_program(RAYDIUM_AMM_V4_PROGRAM_ID);
+            let hops = detect_swap_hops(
+                &facts,
+                &ixs,
+                &HashSet::new(),
+                &opts.programs.raydium_amm_v4_authority,
+                &HashSet::new(),
+                &HashMap::new(),
+            );
+            assert_eq!(hops[0].as_ref().unwrap().variant, Some(variant), "{fixture}");
+
+            // Never the bounds (minimum_amount_out / max_amount_in), in any mode
+            for source in [AmountSource::Delta, AmountSource::Instruction, AmountSource::Auto] {
+                let opts = DetectorOptions {
+                    amount_sources: AmountSources::all(source),
+                    ..Default::default()
+                };
+                let swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &opts);
+                assert_eq!(swaps.len(), 1, "{fixture} {source:?}");
+                assert_eq!(swaps[0].in_amount, in_amount, "{fixture} {source:?}");
+                assert_eq!(swaps[0].out_amount, out_amount, "{fixture} {source:?}");
+                let reasons = ConfidenceReasons(swaps[0].confidence_reasons);
+                assert!(reasons.has(ConfidenceReasons::AMOUNTS_CONFIRMED), "{fixture}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_amounts_confirmed_by_instruction_data() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/raydium_swap_instruction_amounts.json"
+        );
+        let mut tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let confirmed = |tx: &serde_json::Value| {
+            let facts = make_tx_facts(tx.clone(), "AmountsConfirmedSig");
+            let swaps =
+                parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &Default::default());
+            ConfidenceReasons(swaps[0].confidence_reasons).has(ConfidenceReasons::AMOUNTS_CONFIRMED)
+        };
+        let set_data = |tx: &mut serde_json::Value, data: &[u8]| {
+            tx["transaction"]["message"]["instructions"][0]["data"] =
+                json!(bs58::encode(data).into_string());
+        };
+        let swap_data = |discriminator: u8, a: u64, b: u64| {
+            let mut data = vec![discriminator];
+            data.extend_from_slice(&a.to_le_bytes());
+            data.extend_from_slice(&b.to_le_bytes());
+            data
+        };
+
+        let data = tx["transaction"]["message"]["instructions"][0]["data"].as_str().unwrap();
+        assert_eq!(
+            decode_raydium_swap_ix_data(data),
+            Some(RaydiumSwapIx::BaseIn {
+                amount_in: 5_000_000,
+                minimum_amount_out: 48_000_000,
+            })
+        );
+        assert_eq!(decode_raydium_swap_ix_data("not base58!"), None);
+
+        // The wallet's delta includes a 250 referrer fee; the vault got exactly amount_in
+        assert!(confirmed(&tx));
+
+        // Instruction asked for a different amount than anything that moved
+        set_data(&mut tx, &swap_data(RaydiumSwapIx::SWAP_BASE_IN, 4_000_000, 48_000_000));
+        assert!(!confirmed(&tx));
+
+        // Output below the slippage bound
+        set_data(&mut tx, &swap_data(RaydiumSwapIx::SWAP_BASE_IN, 5_000_000, 49_000_000));
+        assert!(!confirmed(&tx));
+
+        // swapBaseOut: exact output, input within max_amount_in
+        let data = swap_data(RaydiumSwapIx::SWAP_BASE_OUT, 5_100_000, 48_123_457);
+        set_data(&mut tx, &data);
+        assert!(confirmed(&tx));
+
+        // Not a swap instruction (deposit)
+        set_data(&mut tx, &[3]);
+        assert!(!confirmed(&tx));
+    }
+
+    #[test]
+    fn test_trader_pick_is_stable_with_unrecognized_vault_owner() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/v0_raydium_swap_full.json"
+        );
+        let tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let facts = make_tx_facts(tx, "V0FullSig");
+
+        // The vault owner also has +/- deltas; the signing wallet must win every
+        // time, whatever order the (freshly seeded) owner map iterates in
+        for _ in 0..32 {
+            let swaps = parse_raydium_v4_swaps(
+                &facts,
+                "solana-mainnet",
+                0,
+                false,
+                &DetectorOptions::default(),
+            );
+            assert_eq!(swaps[0].trader, "TraderWallet2222222222222222222222222222");
+            assert_eq!(swaps[0].in_mint, WSOL_MINT);
+        }
+    }
+
+    #[test]
+    fn test_token_2022_mint_swap() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/raydium_token_2022_swap.json"
+        );
+        let tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let facts = make_tx_facts(tx.clone(), "Token2022Sig");
+
+        // WSOL (classic token) -> PYUSD (Token-2022): vault and owner matching
+        // don't depend on which token program holds the balance
+        let swaps =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &DetectorOptions::default());
+        assert_eq!(swaps.len(), 1);
+        let swap = &swaps[0];
+        assert_eq!(swap.trader, "Token22Trader111111111111111111111111111111");
+        assert_eq!(swap.in_mint, WSOL_MINT);
+        assert_eq!(swap.in_amount, "1000000000");
+        assert_eq!(swap.out_mint, "2b1kV6DkPAnxd5ixfnxCpjxmKwqjjaYmCZfHsFu24GXo");
+        assert_eq!(swap.out_amount, "101500000");
+        let reasons = ConfidenceReasons(swap.confidence_reasons);
+        assert!(reasons.has(ConfidenceReasons::VAULT_MATCH));
+        assert!(reasons.has(ConfidenceReasons::TRADER_FROM_OWNER));
+
+        // Each delta keeps the token program of its balance
+        let programs: HashSet<_> = facts
+            .token_balance_deltas
+            .iter()
+            .map(|d| (d.mint.as_str(), d.token_program.as_deref()))
+            .collect();
+        assert!(programs.contains(&(WSOL_MINT, Some(schema::TOKEN_PROGRAM_ID))));
+        assert!(programs.contains(&(swap.out_mint.as_str(), Some(schema::TOKEN_2022_PROGRAM_ID))));
+
+        // Vault kept the full input: fee from the 25 bps tier, on the input mint
+        assert!(reasons.has(ConfidenceReasons::FEE_COMPUTED));
+        assert_eq!(swap.fee_mint.as_deref(), Some(WSOL_MINT));
+        assert_eq!(swap.fee_amount.as_deref(), Some("2500000"));
+        assert_eq!(trade_fee(1_000_000_000, 999_000_000), 1_000_000);
+        assert_eq!(trade_fee(1_001, 1_001), 3);
+
+        // The Token-2022 CPI is never picked as the tx's main program
+        let program_ids = schema::extract_program_ids_from_transaction(&tx);
+        assert!(program_ids.contains(&schema::TOKEN_2022_PROGRAM_ID.to_string()));
+        assert_eq!(
+            ChainPrograms::mainnet().pick_main_program(&program_ids).as_deref(),
+            Some(RAYDIUM_AMM_V4_PROGRAM_ID)
+        );
+    }
+
+    #[test]
+    fn test_wsol_wrapped_from_trader_sol_normalized_to_native() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/raydium_token_2022_swap.json"
+        );
+        let mut tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let opts = DetectorOptions {
+            normalize_wsol: true,
+            ..Default::default()
+        };
+
+        // Pre-existing WSOL balance: the trader's SOL only paid the fee
+        let facts = make_tx_facts(tx.clone(), "Token2022Sig");
+        let swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &opts);
+        assert_eq!(swaps[0].in_mint, WSOL_MINT);
+        let reasons = ConfidenceReasons(swaps[0].confidence_reasons);
+        assert!(!reasons.has(ConfidenceReasons::WSOL_NETTED));
+
+        // The 1 SOL swapped was wrapped from the trader's lamports in this tx
+        tx["meta"]["preBalances"][0] = json!(2_000_000_000u64);
+        let facts = make_tx_facts(tx, "Token2022Sig");
+        let swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &opts);
+        let swap = &swaps[0];
+        assert_eq!(swap.in_mint, NATIVE_SOL_MINT);
+        assert_eq!(swap.in_amount, "1000000000");
+        assert_eq!(swap.out_mint, "2b1kV6DkPAnxd5ixfnxCpjxmKwqjjaYmCZfHsFu24GXo");
+        let reasons = ConfidenceReasons(swap.confidence_reasons);
+        assert!(reasons.has(ConfidenceReasons::WSOL_NETTED));
+        assert!(swap.explain.as_deref().unwrap().contains("wsol_netted"));
+
+        // Off by default
+        let swaps =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &DetectorOptions::default());
+        assert_eq!(swaps[0].in_mint, WSOL_MINT);
+    }
+
+    #[test]
+    fn test_token_program_owner_is_not_the_trader() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/raydium_token_program_owner.json"
+        );
+        let tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let facts = make_tx_facts(tx, "PreAtaOwnerSig");
+
+        // Both trader balances name the token program as owner. The WSOL account
+        // resolves to the signer that authorized its transfer; the USDC account
+        // only received, so its owner stays unknown
+        let owners: Vec<_> =
+            facts.token_balance_deltas.iter().map(|d| d.owner.as_deref()).collect();
+        assert_eq!(
+            owners[..2],
+            [Some("PreAtaTrader11111111111111111111111111111111"), None]
+        );
+
+        let swaps =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &DetectorOptions::default());
+        assert_eq!(swaps.len(), 1);
+        let swap = &swaps[0];
+        assert_eq!(swap.trader, "PreAtaTrader11111111111111111111111111111111");
+        assert_eq!(swap.in_mint, WSOL_MINT);
+        assert_eq!(swap.in_amount, "1000000000");
+        assert_eq!(swap.out_amount, "101500000");
+        let reasons = ConfidenceReasons(swap.confidence_reasons);
+        assert!(!reasons.has(ConfidenceReasons::TRADER_FROM_OWNER));
+        assert!(reasons.has(ConfidenceReasons::TRADER_IS_SIGNER));
+    }
+
+    #[test]
+    fn test_arbitrage_hops_keep_their_own_trader() {
+        // Two bots chained through two pools in one tx: BotA sells X for Y on
+        // Pool1, BotB sells Y back for X on Pool2
+        let balance = |index: u32, mint: &str, owner: &str, amount: &str| {
+            json!({"accountIndex": index, "mint": mint, "owner": owner,
+                   "uiTokenAmount": {"amount": amount, "decimals": 6}})
+        };
+        let authority = schema::RAYDIUM_AMM_V4_AUTHORITY;
+        let ix_accounts = |pool: u32, vault_a: u32, vault_b: u32, src: u32, dst: u32| {
+            let mut accounts = vec![0u32; 17];
+            accounts[1] = pool;
+            accounts[4] = vault_a;
+            accounts[5] = vault_b;
+            accounts[15] = src;
+            accounts[16] = dst;
+            accounts
+        };
+        let tx = json!({
+            "blockTime": 1703001234,
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "preBalances": [1000000000, 1000000000],
+                "postBalances": [999995000, 1000000000],
+                "preTokenBalances": [
+                    balance(2, "MintX111", "BotA111", "1000"),
+                    balance(3, "MintY111", "BotA111", "0"),
+                    balance(4, "MintY111", "BotB111", "500"),
+                    balance(5, "MintX111", "BotB111", "0"),
+                    balance(6, "MintX111", authority, "5000"),
+                    balance(7, "MintY111", authority, "5000"),
+                    balance(8, "MintX111", authority, "5000"),
+                    balance(9, "MintY111", authority, "5000")
+                ],
+                "postTokenBalances": [
+                    balance(2, "MintX111", "BotA111", "900"),
+                    balance(3, "MintY111", "BotA111", "200"),
+                    balance(4, "MintY111", "BotB111", "300"),
+                    balance(5, "MintX111", "BotB111", "110"),
+                    balance(6, "MintX111", authority, "5100"),
+                    balance(7, "MintY111", authority, "4800"),
+                    balance(8, "MintX111", authority, "4890"),
+                    balance(9, "MintY111", authority, "5200")
+                ],
+                "innerInstructions": []
+            },
+            "slot": 250000000,
+            "transaction": {
+                "message": {
+                    "header": {"numRequiredSignatures": 2},
+                    "accountKeys": [
+                        "BotA111", "BotB111",
+                        "BotAX", "BotAY", "BotBY", "BotBX",
+                        "Pool1VaultX", "Pool1VaultY", "Pool2VaultX", "Pool2VaultY",
+                        "Pool1", "Pool2",
+                        RAYDIUM_AMM_V4_PROGRAM_ID
+                    ],
+                    "instructions": [
+                        {"programIdIndex": 12, "accounts": ix_accounts(10, 6, 7, 2, 3),
+                         "data": "SwapData"},
+                        {"programIdIndex": 12, "accounts": ix_accounts(11, 8, 9, 4, 5),
+                         "data": "SwapData"}
+                    ]
+                },
+                "signatures": ["sig_arb"]
+            }
+        });
+        let facts = make_tx_facts(tx, "sig_arb");
+
+        let swaps =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &DetectorOptions::default());
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(swaps[0].trader, "BotA111");
+        assert_eq!(swaps[0].pool_id.as_deref(), Some("Pool1"));
+        assert_eq!((swaps[0].in_mint.as_str(), swaps[0].in_amount.get()), ("MintX111", 100));
+        assert_eq!((swaps[0].out_mint.as_str(), swaps[0].out_amount.get()), ("MintY111", 200));
+        assert_eq!(swaps[1].trader, "BotB111");
+        assert_eq!(swaps[1].pool_id.as_deref(), Some("Pool2"));
+        assert_eq!((swaps[1].in_mint.as_str(), swaps[1].in_amount.get()), ("MintY111", 200));
+        assert_eq!((swaps[1].out_mint.as_str(), swaps[1].out_amount.get()), ("MintX111", 110));
+
+        for swap in &swaps {
+            let reasons = ConfidenceReasons(swap.confidence_reasons);
+            assert!(reasons.has(ConfidenceReasons::MULTI_TRADER));
+            let single = ConfidenceReasons(reasons.0 & !ConfidenceReasons::MULTI_TRADER);
+            assert!(swap.confidence < single.to_confidence_u8());
+        }
+    }
+
+    #[test]
+    fn test_route_through_another_venue_is_not_multi_trader() {
+        // Raydium X -> Y, then Orca Y -> Z: the whirlpool's vault owner also
+        // spends one token and receives another, but no Raydium hop is its
+        let authority = schema::RAYDIUM_AMM_V4_AUTHORITY;
+        let balance = |index: u32, mint: &str, owner: &str, amount: &str| {
+            json!({"accountIndex": index, "mint": mint, "owner": owner,
+                   "uiTokenAmount": {"amount": amount, "decimals": 6}})
+        };
+        let mut raydium_accounts = vec![0u32; 17];
+        raydium_accounts[1] = 8;
+        raydium_accounts[4] = 4;
+        raydium_accounts[5] = 5;
+        raydium_accounts[15] = 1;
+        raydium_accounts[16] = 2;
+        let tx = json!({
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "preBalances": [1000000000],
+                "postBalances": [999995000],
+                "preTokenBalances": [
+                    balance(1, "MintX111", "Trader111", "1000"),
+                    balance(2, "MintY111", "Trader111", "0"),
+                    balance(3, "MintZ111", "Trader111", "0"),
+                    balance(4, "MintX111", authority, "5000"),
+                    balance(5, "MintY111", authority, "5000"),
+                    balance(6, "MintY111", "WhirlpoolPda111", "5000"),
+                    balance(7, "MintZ111", "WhirlpoolPda111", "5000")
+                ],
+                "postTokenBalances": [
+                    balance(1, "MintX111", "Trader111", "900"),
+                    balance(2, "MintY111", "Trader111", "0"),
+                    balance(3, "MintZ111", "Trader111", "50"),
+                    balance(4, "MintX111", authority, "5100"),
+                    balance(5, "MintY111", authority, "4800"),
+                    balance(6, "MintY111", "WhirlpoolPda111", "5200"),
+                    balance(7, "MintZ111", "WhirlpoolPda111", "4950")
+                ],
+                "innerInstructions": []
+            },
+            "slot": 250000000,
+            "transaction": {
+                "message": {
+                    "header": {"numRequiredSignatures": 1},
+                    "accountKeys": [
+                        "Trader111", "TraderX", "TraderY", "TraderZ",
+                        "RayVaultX", "RayVaultY", "OrcaVaultY", "OrcaVaultZ",
+                        "RayPool", "Whirlpool111",
+                        RAYDIUM_AMM_V4_PROGRAM_ID, schema::ORCA_WHIRLPOOL_PROGRAM_ID
+                    ],
+                    "instructions": [
+                        {"programIdIndex": 10, "accounts": raydium_accounts, "data": "SwapData"},
+                        {"programIdIndex": 11, "accounts": [0, 9, 2, 6, 3, 7], "data": "Swap"}
+                    ]
+                },
+                "signatures": ["sig_ray_orca"]
+            }
+        });
+        let facts = make_tx_facts(tx, "sig_ray_orca");
+
+        let swaps =
+            parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, false, &DetectorOptions::default());
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].trader, "Trader111");
+        let reasons = ConfidenceReasons(swaps[0].confidence_reasons);
+        assert!(!reasons.has(ConfidenceReasons::MULTI_TRADER));
+    }
+
+    #[test]
+    fn test_pool_id_from_vaults_when_pool_slot_holds_a_token_account() {
+        let authority = schema::RAYDIUM_AMM_V4_AUTHORITY;
+        let balance = |index: u32, mint: &str, owner: &str, amount: &str| {
+            json!({"accountIndex": index, "mint": mint, "owner": owner,
+                   "uiTokenAmount": {"amount": amount, "decimals": 6}})
+        };
+        // Account list shifted by one: slot 1 is the trader's USDC account
+        let tx = json!({
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "preBalances": [1000000000],
+                "postBalances": [999995000],
+                "preTokenBalances": [
+                    balance(1, "MintA111", "Trader111", "1000"),
+                    balance(2, "MintB111", "Trader111", "0"),
+                    balance(3, "MintA111", authority, "50000"),
+                    balance(4, "MintB111", authority, "90000")
+                ],
+                "postTokenBalances": [
+                    balance(1, "MintA111", "Trader111", "0"),
+                    balance(2, "MintB111", "Trader111", "1800"),
+                    balance(3, "MintA111", authority, "51000"),
+                    balance(4, "MintB111", authority, "88200")
+                ],
+                "innerInstructions": []
+            },
+            "transaction": {
+                "message": {
+                    "accountKeys": [
+                        "Trader111", "TraderA111", "TraderB111", "VaultA111", "VaultB111",
+                        RAYDIUM_AMM_V4_PROGRAM_ID
+                    ],
+                    "instructions": [
+                        {"programIdIndex": 5, "accounts": [0, 2, 3, 4, 1], "data": ""}
+                    ]
+                },
+                "signatures": ["sig_shifted"]
+            }
+        });
+        let facts = make_tx_facts(tx, "sig_shifted");
+
+        // Without the pool list nothing identifies the pool
+        let swaps = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &Default::default());
+        assert_eq!(swaps[0].pool_id, None);
+        let reasons = ConfidenceReasons(swaps[0].confidence_reasons);
+        assert!(!reasons.has(ConfidenceReasons::POOL_ID_FROM_IX));
+
+        let pools = json!({"official": [
+            {"id": "PoolAB111", "baseVault": "VaultA111", "quoteVault": "VaultB111"},
+            {"id": "PoolCD111", "baseVault": "VaultC111", "quoteVault": "VaultD111"}
+        ]});
+        let opts = DetectorOptions {
+            raydium_pool_vaults: Arc::new(parse_raydium_pool_vaults(&pools)),
+            ..Default::default()
+        };
+        let with_vaults = parse_raydium_v4_swaps(&facts, "solana-mainnet", 0, true, &opts);
+        assert_eq!(with_vaults[0].pool_id.as_deref(), Some("PoolAB111"));
+        let reasons = ConfidenceReasons(with_vaults[0].confidence_reasons);
+        assert!(reasons.has(ConfidenceReasons::POOL_ID_FROM_VAULT));
+        assert!(with_vaults[0].confidence > swaps[0].confidence);
+    }
+
     #[test]
     fn test_confidence_scoring() {
         let mut reasons = ConfidenceReasons::new();
@@ -0,0 +1,320 @@
+//! Venue registry of the gold detectors.
+//!
+//! Each venue is a `SwapDetector`; `DetectorRegistry` runs the registered ones
+//! over a transaction in registration order, then groups Jupiter-routed hops
+//! and strips explains. A new venue is one more `register` call, and
+//! `SWAP_VENUES` selects which registered venues run.
+//!
+//! The registry is built once (per decoder, backfill run or warmup) and reused
+//! for every transaction.
+
+use schema::{ChainPrograms, DexSwapV1, TxFacts};
+use std::sync::Arc;
+
+use super::{
+    DetectorOptions, IncompleteRoute, jupiter, lifinity, meteora_dlmm, orca_whirlpool, phoenix,
+    raydium_v4_gold, strip_confident_explains, timed,
+};
+
+/// One venue's swap parser
+pub trait SwapDetector: Send + Sync {
+    /// Venue name reported in `DexSwapV1::venue`, metrics and `SWAP_VENUES`
+    fn venue(&self) -> &'static str;
+
+    /// Program whose instructions this detector parses
+    fn program_id(&self) -> &str;
+
+    /// Swaps of this venue in `facts`
+    fn parse(
+        &self,
+        facts: &TxFacts,
+        chain: &str,
+        index_in_block: u32,
+        explain_enabled: bool,
+    ) -> Vec<DexSwapV1>;
+
+    /// Like `parse`, but may reject the whole tx (`MultihopPolicy::AllOrNothing`)
+    fn parse_route(
+        &self,
+        facts: &TxFacts,
+        chain: &str,
+        index_in_block: u32,
+        explain_enabled: bool,
+    ) -> Result<Vec<DexSwapV1>, IncompleteRoute> {
+        Ok(self.parse(facts, chain, index_in_block, explain_enabled))
+    }
+}
+
+/// Where a venue's program sits in `ChainPrograms`
+pub type ProgramOf = fn(&ChainPrograms) -> &str;
+
+/// Parser of a built-in venue (`parse_<venue>_swaps`)
+pub type VenueParser = fn(&TxFacts, &str, u32, bool, &DetectorOptions) -> Vec<DexSwapV1>;
+
+/// All-or-nothing parser of a venue that honours `MultihopPolicy`
+pub type RouteParser =
+    fn(&TxFacts, &str, u32, bool, &DetectorOptions) -> Result<Vec<DexSwapV1>, IncompleteRoute>;
+
+/// A built-in venue as a `SwapDetector`: its name, where its program sits in
+/// `ChainPrograms`, and its parser functions
+pub struct VenueDetector {
+    venue: &'static str,
+    program: ProgramOf,
+    parse: VenueParser,
+    parse_route: Option<RouteParser>,
+    opts: Arc<DetectorOptions>,
+}
+
+impl VenueDetector {
+    pub fn new(
+        venue: &'static str,
+        program: ProgramOf,
+        parse: VenueParser,
+        opts: Arc<DetectorOptions>,
+    ) -> Self {
+        Self {
+            venue,
+            program,
+            parse,
+            parse_route: None,
+            opts,
+        }
+    }
+
+    /// Parse routes with `parse_route` (may reject the tx) instead of `parse`
+    pub fn with_route(mut self, parse_route: RouteParser) -> Self {
+        self.parse_route = Some(parse_route);
+        self
+    }
+}
+
+impl SwapDetector for VenueDetector {
+    fn venue(&self) -> &'static str {
+        self.venue
+    }
+
+    fn program_id(&self) -> &str {
+        (self.program)(&self.opts.programs)
+    }
+
+    fn parse(
+        &self,
+        facts: &TxFacts,
+        chain: &str,
+        index_in_block: u32,
+        explain_enabled: bool,
+    ) -> Vec<DexSwapV1> {
+        (self.parse)(facts, chain, index_in_block, explain_enabled, &self.opts)
+    }
+
+    fn parse_route(
+        &self,
+        facts: &TxFacts,
+        chain: &str,
+        index_in_block: u32,
+        explain_enabled: bool,
+    ) -> Result<Vec<DexSwapV1>, IncompleteRoute> {
+        match self.parse_route {
+            Some(parse_route) => {
+                parse_route(facts, chain, index_in_block, explain_enabled, &self.opts)
+            }
+            None => Ok(self.parse(facts, chain, index_in_block, explain_enabled)),
+        }
+    }
+}
+
+/// Ordered set of venue detectors sharing one `DetectorOptions`
+pub struct DetectorRegistry {
+    opts: Arc<DetectorOptions>,
+    detectors: Vec<Box<dyn SwapDetector>>,
+}
+
+impl DetectorRegistry {
+    /// Registry without any venue
+    pub fn empty(opts: Arc<DetectorOptions>) -> Self {
+        Self {
+            opts,
+            detectors: Vec::new(),
+        }
+    }
+
+    /// The built-in venues: Raydium, Phoenix, Orca, Lifinity, Meteora (in that order)
+    pub fn new(opts: Arc<DetectorOptions>) -> Self {
+        let mut registry = Self::empty(opts.clone());
+        // Raydium is the venue that honours MULTIHOP_POLICY=all_or_nothing
+        let raydium = VenueDetector::new(
+            "raydium",
+            |p| &p.raydium_amm_v4,
+            raydium_v4_gold::parse_raydium_v4_swaps,
+            opts.clone(),
+        );
+        let raydium = raydium.with_route(raydium_v4_gold::parse_raydium_v4_route);
+        registry.register(Box::new(raydium));
+        let venues: [(&'static str, ProgramOf, VenueParser); 4] = [
+            ("phoenix", |p| &p.phoenix, phoenix::parse_phoenix_fills),
+            (
+                "orca",
+                |p| &p.orca_whirlpool,
+                orca_whirlpool::parse_orca_whirlpool_swaps,
+            ),
+            (
+                "lifinity",
+                |p| &p.lifinity_v2,
+                lifinity::parse_lifinity_swaps,
+            ),
+            (
+                "meteora",
+                |p| &p.meteora_dlmm,
+                meteora_dlmm::parse_meteora_dlmm_swaps,
+            ),
+        ];
+        for (venue, program, parse) in venues {
+            let detector = VenueDetector::new(venue, program, parse, opts.clone());
+            registry.register(Box::new(detector));
+        }
+        registry
+    }
+
+    /// Append a venue; it runs after the ones already registered
+    pub fn register(&mut self, detector: Box<dyn SwapDetector>) {
+        self.detectors.push(detector);
+    }
+
+    /// Keep only `venues` (`SWAP_VENUES`), in registration order.
+    ///
+    /// Fails with the first name that matches no registered venue.
+    pub fn retain_venues(&mut self, venues: &[String]) -> Result<(), String> {
+        if let Some(unknown) = venues
+            .iter()
+            .find(|v| !self.detectors.iter().any(|d| d.venue() == v.as_str()))
+        {
+            return Err(unknown.clone());
+        }
+        self.detectors
+            .retain(|d| venues.iter().any(|v| v == d.venue()));
+        Ok(())
+    }
+
+    /// Drop `venues` (those whose program ID variable is unset); names that match
+    /// no registered venue are ignored
+    pub fn remove_venues(&mut self, venues: &[String]) {
+        self.detectors
+            .retain(|d| !venues.iter().any(|v| v == d.venue()));
+    }
+
+    pub fn options(&self) -> &DetectorOptions {
+        &self.opts
+    }
+
+    /// Venue name and program of each registered detector, in detection order
+    pub fn venue_programs(&self) -> Vec<(&'static str, &str)> {
+        self.detectors
+            .iter()
+            .map(|d| (d.venue(), d.program_id()))
+            .collect()
+    }
+
+    /// Run every registered detector over a transaction and collect the swaps found.
+    ///
+    /// Fails only under `MultihopPolicy::AllOrNothing`, when a route has unparseable legs.
+    pub fn detect(
+        &self,
+        facts: &TxFacts,
+        chain: &str,
+        index_in_block: u32,
+        explain_enabled: bool,
+    ) -> Result<Vec<DexSwapV1>, IncompleteRoute> {
+        let opts = &*self.opts;
+        let mut swaps = Vec::new();
+        for detector in &self.detectors {
+            swaps.extend(timed(opts, detector.venue(), || {
+                detector.parse_route(facts, chain, index_in_block, explain_enabled)
+            })?);
+        }
+        // Hops CPI'd by a Jupiter route are re-emitted as one route across venues
+        for route in jupiter::assemble_jupiter_routes(facts, &mut swaps, opts) {
+            swaps.extend(route.hops);
+        }
+        strip_confident_explains(&mut swaps, opts.explain_max_confidence);
+        Ok(swaps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::DexSwapV1Builder;
+
+    /// A venue a fork would add: one fixed swap whenever its program is invoked
+    struct FixedVenue;
+
+    impl SwapDetector for FixedVenue {
+        fn venue(&self) -> &'static str {
+            "fixed"
+        }
+
+        fn program_id(&self) -> &str {
+            "FixedVenue111"
+        }
+
+        fn parse(&self, facts: &TxFacts, chain: &str, _: u32, _: bool) -> Vec<DexSwapV1> {
+            if !facts.has_program(self.program_id()) {
+                return vec![];
+            }
+            vec![
+                DexSwapV1Builder::new()
+                    .chain(chain)
+                    .signature(&facts.signature)
+                    .venue(self.venue())
+                    .in_token("SOL", 1000u128)
+                    .out_token("USDC", 50u128)
+                    .build(),
+            ]
+        }
+    }
+
+    #[test]
+    fn test_registered_venue_runs_and_venues_can_be_selected() {
+        let facts = TxFacts::from_json(
+            &serde_json::json!({
+                "meta": {"err": null, "fee": 5000},
+                "transaction": {
+                    "message": {
+                        "accountKeys": ["Trader111", "FixedVenue111"],
+                        "instructions": [{"programIdIndex": 1, "accounts": [0], "data": ""}]
+                    },
+                    "signatures": ["sig_registry"]
+                }
+            }),
+            "sig_registry",
+            1,
+        );
+        let opts = Arc::new(DetectorOptions::default());
+
+        let mut registry = DetectorRegistry::new(opts);
+        registry.register(Box::new(FixedVenue));
+        let venues: Vec<_> = registry.venue_programs().iter().map(|(v, _)| *v).collect();
+        assert_eq!(
+            venues,
+            vec!["raydium", "phoenix", "orca", "lifinity", "meteora", "fixed"]
+        );
+
+        let swaps = registry.detect(&facts, "solana-mainnet", 0, false).unwrap();
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].venue, "fixed");
+
+        // Deselected venues do not run; unknown names are rejected
+        assert_eq!(
+            registry.retain_venues(&["raydium".to_string(), "jupiter".to_string()]),
+            Err("jupiter".to_string())
+        );
+        registry.retain_venues(&["raydium".to_string()]).unwrap();
+        assert_eq!(registry.venue_programs().len(), 1);
+        assert!(
+            registry
+                .detect(&facts, "solana-mainnet", 0, false)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
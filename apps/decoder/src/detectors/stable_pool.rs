@@ -0,0 +1,174 @@
+//! Multi-asset (stable/curve) pool interactions.
+//!
+//! Pools run by the programs in `STABLE_POOL_PROGRAMS` can hold three or more
+//! tokens, and one instruction may move several of them (imbalanced deposits
+//! and withdrawals, one-in/many-out). The pool side of an instruction is the
+//! token accounts it references that the trader does not own, i.e. the vaults;
+//! when those moved three or more distinct mints the interaction becomes a
+//! `StableSwapV1`. Fewer mints is an ordinary swap and is left to `DexSwapV1`.
+
+use schema::{MintDelta, StableSwapV1, TxFacts};
+
+use super::DetectorOptions;
+
+/// Vault mints an interaction must move to be reported here
+const MIN_POOL_MINTS: usize = 3;
+
+/// `venue:program_id` pairs, comma-separated (`STABLE_POOL_PROGRAMS`)
+pub fn parse_stable_pool_programs(s: &str) -> Option<Vec<(String, String)>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|entry| {
+            let (venue, program) = entry.split_once(':')?;
+            let (venue, program) = (venue.trim(), program.trim());
+            (!venue.is_empty() && !program.is_empty())
+                .then(|| (venue.to_ascii_lowercase(), program.to_string()))
+        })
+        .collect()
+}
+
+/// Parse multi-asset pool interactions from TxFacts.
+///
+/// This is a pure function - no RPC calls, no side effects.
+///
+/// Returns one StableSwapV1 per pool instruction that moved 3+ vault mints.
+pub fn parse_stable_pool_swaps(
+    facts: &TxFacts,
+    chain: &str,
+    index_in_block: u32,
+    opts: &DetectorOptions,
+) -> Vec<StableSwapV1> {
+    // Pool programs take the trader as a signer; routers sign for nobody here
+    let Some(trader) = facts
+        .signers
+        .iter()
+        .find(|s| !opts.trader_exclude.contains(*s))
+    else {
+        return vec![];
+    };
+
+    let mut events = Vec::new();
+    for (venue, program) in &opts.stable_pool_programs {
+        if !facts.has_program(program) {
+            continue;
+        }
+
+        for ix in facts.instructions_for_program(program) {
+            // Vault flow per mint, in first-seen order
+            let mut pool: Vec<(&str, i128)> = Vec::new();
+            let mut owners: Vec<Option<&str>> = Vec::new();
+            for delta in facts.token_balance_deltas.iter().filter(|d| {
+                d.delta != 0
+                    && d.owner.as_deref() != Some(trader.as_str())
+                    && ix.accounts.contains(&(d.account_index as usize))
+            }) {
+                match pool.iter_mut().find(|(mint, _)| *mint == delta.mint) {
+                    Some((_, sum)) => *sum += delta.delta,
+                    None => pool.push((&delta.mint, delta.delta)),
+                }
+                owners.push(delta.owner.as_deref());
+            }
+            pool.retain(|(_, sum)| *sum != 0);
+            if pool.len() < MIN_POOL_MINTS {
+                continue;
+            }
+
+            let pool_authority = match owners.split_first() {
+                Some((first, rest)) if rest.iter().all(|o| o == first) => {
+                    first.map(str::to_string)
+                }
+                _ => None,
+            };
+
+            // What the pool gained the trader paid, and vice versa
+            let event = StableSwapV1 {
+                schema_version: StableSwapV1::SCHEMA_VERSION,
+                chain: chain.to_string(),
+                slot: facts.slot,
+                block_time: facts.block_time,
+                signature: facts.signature.clone(),
+                index_in_block,
+                index_in_tx: opts.index_in_tx_mode.index_in_tx(ix),
+                venue: venue.clone(),
+                pool_authority,
+                trader: trader.clone(),
+                deltas: pool
+                    .iter()
+                    .map(|(mint, sum)| MintDelta {
+                        mint: mint.to_string(),
+                        delta: (-sum).to_string(),
+                    })
+                    .collect(),
+            };
+            if event.validate().is_ok() {
+                events.push(event);
+            }
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MERCURIAL: &str = "MERLuDFBMmsHnsBPZw2sDQZHvXFMwp8EdjudcU2HKky";
+
+    #[test]
+    fn test_three_asset_pool_interaction() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/stable_pool_three_asset.json"
+        );
+        let mut tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let opts = DetectorOptions {
+            stable_pool_programs: parse_stable_pool_programs(&format!("Mercurial:{MERCURIAL}"))
+                .unwrap(),
+            ..Default::default()
+        };
+        let parse = |tx: &serde_json::Value| {
+            let facts = TxFacts::from_json(tx, "StableThreeAssetSig", 250000400);
+            parse_stable_pool_swaps(&facts, "solana-mainnet", 7, &opts)
+        };
+
+        let events = parse(&tx);
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.venue, "mercurial");
+        assert_eq!(event.trader, "StableTrader1111111111111111111111111111111");
+        assert_eq!(
+            event.pool_authority.as_deref(),
+            Some("MercurialPoolAuthority11111111111111111111")
+        );
+        assert_eq!((event.index_in_block, event.index_in_tx), (7, 0));
+        let deltas: Vec<(&str, &str)> = event
+            .deltas
+            .iter()
+            .map(|d| (d.mint.as_str(), d.delta.as_str()))
+            .collect();
+        assert_eq!(
+            deltas,
+            vec![
+                ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "-1000000000"),
+                ("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB", "600000000"),
+                ("Ea5SjE2Y6yvCeW5dYTn7PYMuW5ikXkvbGdcmSnXeaLjS", "399000000"),
+            ]
+        );
+
+        // Only two vault mints moved: an ordinary swap, not reported here
+        for path in ["/meta/preTokenBalances/5", "/meta/postTokenBalances/5"] {
+            tx.pointer_mut(path).unwrap()["uiTokenAmount"]["amount"] = "4999601000000".into();
+        }
+        assert!(parse(&tx).is_empty());
+
+        // Unconfigured program
+        let facts = TxFacts::from_json(&tx, "StableThreeAssetSig", 250000400);
+        let none = DetectorOptions::default();
+        assert!(parse_stable_pool_swaps(&facts, "solana-mainnet", 7, &none).is_empty());
+
+        assert!(parse_stable_pool_programs("mercurial").is_none());
+        assert_eq!(parse_stable_pool_programs("").unwrap(), vec![]);
+    }
+}
@@ -6,6 +6,22 @@
 use serde::{Deserialize, Serialize};
 use std::time::SystemTime;
 
+/// Appended to fields cut down to `DLQ_MAX_FIELD_BYTES`
+pub const TRUNCATED_SUFFIX: &str = "...[truncated]";
+
+/// Cap `s` at `max_bytes` (suffix included), cutting on a char boundary.
+/// `0` disables the cap.
+pub fn truncate_field(s: &str, max_bytes: usize) -> String {
+    if max_bytes == 0 || s.len() <= max_bytes {
+        return s.to_string();
+    }
+    let mut end = max_bytes.saturating_sub(TRUNCATED_SUFFIX.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", &s[..end], TRUNCATED_SUFFIX)
+}
+
 /// DLQ entry for a failed transaction
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DlqEntry {
@@ -95,6 +111,13 @@ impl DlqEntry {
         self
     }
 
+    /// Cap `error` and `context` (e.g. a whole RPC body) at `max_bytes` each
+    pub fn with_max_field_bytes(mut self, max_bytes: usize) -> Self {
+        self.error = truncate_field(&self.error, max_bytes);
+        self.context = self.context.map(|c| truncate_field(&c, max_bytes));
+        self
+    }
+
     /// Convert to JSON for Kafka publishing
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string(self)
@@ -109,6 +132,9 @@ pub mod reasons {
     pub const NO_TOKEN_DELTAS: &str = "no_token_deltas";
     pub const INVALID_AMOUNTS: &str = "invalid_amounts";
     pub const MULTI_HOP_FAILED: &str = "multi_hop_failed";
+    pub const MESSAGE_TOO_LARGE: &str = "message_too_large";
+    pub const BALANCE_LENGTH_MISMATCH: &str = "balance_length_mismatch";
+    pub const SCHEMA_VERSION_MISMATCH: &str = "schema_version_mismatch";
 }
 
 #[cfg(test)]
@@ -141,4 +167,22 @@ mod tests {
         assert!(json.contains("sig123"));
         assert!(json.contains("rpc_fetch_failed"));
     }
+
+    #[test]
+    fn test_oversized_error_truncated() {
+        let body = "é".repeat(10_000);
+        let entry = DlqEntry::new("sig123", 250000000, reasons::RPC_FETCH_FAILED, &body)
+            .with_context(json!({"body": body}))
+            .with_max_field_bytes(1024);
+
+        assert!(entry.error.len() <= 1024);
+        assert!(entry.error.ends_with(TRUNCATED_SUFFIX));
+        assert!(entry.error.starts_with("éé"));
+        let context = entry.context.unwrap();
+        assert!(context.len() <= 1024 && context.ends_with(TRUNCATED_SUFFIX));
+
+        // Short fields and a disabled cap are left alone
+        assert_eq!(truncate_field("timeout", 1024), "timeout");
+        assert_eq!(truncate_field(&body, 0), body);
+    }
 }
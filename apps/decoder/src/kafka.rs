@@ -1,20 +1,180 @@
 use anyhow::{Result, anyhow};
+use decoder::block_buffer::{MessagePosition, SwapOrderBuffer};
+use log::{info, warn};
+use rdkafka::Offset;
+use rdkafka::bindings::{rd_kafka_commit, rd_kafka_resp_err_t};
+use rdkafka::client::{ClientContext, NativeClient};
 use rdkafka::config::ClientConfig;
-use rdkafka::consumer::StreamConsumer;
-use rdkafka::message::Message;
-use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::consumer::{
+    CommitMode, Consumer, ConsumerContext, DefaultConsumerContext, StreamConsumer,
+};
+use rdkafka::error::RDKafkaErrorCode;
+use rdkafka::message::{BorrowedMessage, Message};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use rdkafka::topic_partition_list::TopicPartitionList;
+use rdkafka::types::RDKafkaRespErr;
+use std::collections::BTreeMap;
+use std::ptr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::Duration;
+use tokio::time::MissedTickBehavior;
 
-pub fn create_consumer(broker: &str, group: &str) -> Result<StreamConsumer> {
-    let c: StreamConsumer = ClientConfig::new()
+/// Consumer context that synchronously commits stored offsets when partitions
+/// are revoked, so work finished before a rebalance is not reprocessed by the
+/// partition's next owner.
+#[derive(Default)]
+pub struct CommitOnRevokeContext {
+    revoke_commits: AtomicU64,
+    revoked: AtomicBool,
+}
+
+impl CommitOnRevokeContext {
+    /// Number of successful commits triggered by a revoke
+    pub fn revoke_commits(&self) -> u64 {
+        self.revoke_commits.load(Ordering::Relaxed)
+    }
+
+    /// Whether partitions were revoked since the last call. Messages held by
+    /// a `SwapOrderBuffer` are not covered by the revoke commit, so the
+    /// buffered slot is then emitted and committed without waiting.
+    pub fn take_revoked(&self) -> bool {
+        self.revoked.swap(false, Ordering::Relaxed)
+    }
+}
+
+impl ClientContext for CommitOnRevokeContext {}
+
+impl ConsumerContext for CommitOnRevokeContext {
+    fn rebalance(
+        &self,
+        native_client: &NativeClient,
+        err: RDKafkaRespErr,
+        tpl: &mut TopicPartitionList,
+    ) {
+        if err == RDKafkaRespErr::RD_KAFKA_RESP_ERR__REVOKE_PARTITIONS {
+            self.revoked.store(true, Ordering::Relaxed);
+            match commit_stored(native_client) {
+                rd_kafka_resp_err_t::RD_KAFKA_RESP_ERR_NO_ERROR => {
+                    self.revoke_commits.fetch_add(1, Ordering::Relaxed);
+                    info!(
+                        "rebalance: committed stored offsets for {} revoked partitions",
+                        tpl.count()
+                    );
+                }
+                // Nothing stored since the last commit
+                rd_kafka_resp_err_t::RD_KAFKA_RESP_ERR__NO_OFFSET => {}
+                e => warn!(
+                    "rebalance: commit on revoke failed: {}",
+                    RDKafkaErrorCode::from(e)
+                ),
+            }
+        }
+
+        // Default assign/unassign handling
+        DefaultConsumerContext.rebalance(native_client, err, tpl);
+    }
+}
+
+pub type DecoderConsumer = StreamConsumer<CommitOnRevokeContext>;
+
+/// Synchronously commit the stored offsets of the current assignment
+fn commit_stored(native_client: &NativeClient) -> rd_kafka_resp_err_t {
+    // SAFETY: the client handle is live while borrowed; a null offset list
+    // commits the stored offsets of the current assignment.
+    unsafe { rd_kafka_commit(native_client.ptr(), ptr::null(), 0) }
+}
+
+/// Consumer fetch/prefetch overrides; unset values keep librdkafka defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FetchTuning {
+    /// `fetch.min.bytes`
+    pub fetch_min_bytes: Option<u32>,
+    /// `fetch.max.bytes`
+    pub fetch_max_bytes: Option<u32>,
+    /// `queued.max.messages.kbytes` (local prefetch queue per partition)
+    pub queued_max_messages_kbytes: Option<u32>,
+}
+
+impl FetchTuning {
+    /// Set the configured overrides on a client config
+    pub fn apply(&self, config: &mut ClientConfig) {
+        for (key, value) in [
+            ("fetch.min.bytes", self.fetch_min_bytes),
+            ("fetch.max.bytes", self.fetch_max_bytes),
+            (
+                "queued.max.messages.kbytes",
+                self.queued_max_messages_kbytes,
+            ),
+        ] {
+            if let Some(v) = value {
+                config.set(key, v.to_string());
+            }
+        }
+    }
+}
+
+pub fn create_consumer(broker: &str, group: &str, fetch: &FetchTuning) -> Result<DecoderConsumer> {
+    let mut config = ClientConfig::new();
+    config
         .set("bootstrap.servers", broker)
         .set("group.id", group)
         .set("enable.auto.commit", "false") // we commit only after we successfully publish outputs
-        .set("auto.offset.reset", "earliest")
-        .create()?;
+        .set("enable.auto.offset.store", "false") // offsets are stored by `commit`, flushed on revoke
+        .set("auto.offset.reset", "earliest");
+    fetch.apply(&mut config);
+    let c: DecoderConsumer = config.create_with_context(CommitOnRevokeContext::default())?;
     Ok(c)
 }
 
+/// Mark a message as done: store its offset (for the revoke commit) and commit async
+pub fn commit(consumer: &DecoderConsumer, msg: &BorrowedMessage<'_>) {
+    let _ = consumer.store_offset_from_message(msg);
+    let _ = consumer.commit_message(msg, CommitMode::Async);
+}
+
+/// Mark a message as done, unless swaps of a buffered slot are pending: then
+/// it is held in the buffer and committed with the slot (`commit_positions`)
+pub fn commit_or_hold(
+    consumer: &DecoderConsumer,
+    buffer: Option<&mut SwapOrderBuffer>,
+    msg: &BorrowedMessage<'_>,
+) {
+    let held = buffer.is_some_and(|b| b.hold((msg.partition(), msg.offset())));
+    if !held {
+        commit(consumer, msg);
+    }
+}
+
+/// Store and async-commit the offsets of messages held for an emitted slot
+pub fn commit_positions(consumer: &DecoderConsumer, topic: &str, positions: &[MessagePosition]) {
+    let mut next: BTreeMap<i32, i64> = BTreeMap::new();
+    for &(partition, offset) in positions {
+        let _ = consumer.store_offset(topic, partition, offset);
+        let e = next.entry(partition).or_insert(offset + 1);
+        *e = (*e).max(offset + 1);
+    }
+    if next.is_empty() {
+        return;
+    }
+    let mut tpl = TopicPartitionList::new();
+    for (partition, offset) in next {
+        let _ = tpl.add_partition_offset(topic, partition, Offset::Offset(offset));
+    }
+    let _ = consumer.commit(&tpl, CommitMode::Async);
+}
+
+/// Synchronously commit every offset stored by `commit` (shutdown): the async
+/// commits may still be in flight. Returns false if nothing was stored since
+/// the last commit.
+pub fn commit_stored_sync(consumer: &DecoderConsumer) -> Result<bool> {
+    match commit_stored(consumer.client().native_client()) {
+        rd_kafka_resp_err_t::RD_KAFKA_RESP_ERR_NO_ERROR => Ok(true),
+        rd_kafka_resp_err_t::RD_KAFKA_RESP_ERR__NO_OFFSET => Ok(false),
+        e => Err(anyhow!("commit failed: {}", RDKafkaErrorCode::from(e))),
+    }
+}
+
 pub fn create_producer(broker: &str) -> Result<FutureProducer> {
     let p: FutureProducer = ClientConfig::new()
         .set("bootstrap.servers", broker)
@@ -40,9 +200,220 @@ pub async fn send_json(
     }
 }
 
+/// Flush the producer every `interval` in a background task, bounding how long
+/// buffered messages wait on a quiet stream. Returns the completed-flush count.
+pub fn spawn_periodic_flush(producer: FutureProducer, interval: Duration) -> Arc<AtomicU64> {
+    let flushes = Arc::new(AtomicU64::new(0));
+    let counter = flushes.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick completes immediately
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            // `flush` blocks until the queue drains or the timeout expires
+            let p = producer.clone();
+            match tokio::task::spawn_blocking(move || p.flush(interval)).await {
+                Ok(Ok(())) => {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(Err(e)) => warn!("producer flush failed: {e:?}"),
+                Err(e) => warn!("producer flush task failed: {e:?}"),
+            }
+        }
+    });
+    flushes
+}
+
+/// Payload size in bytes if it exceeds `max_bytes` (0 disables the check).
+///
+/// Checked before `msg_to_str` so oversized payloads are never parsed.
+pub fn oversized_payload<M: Message>(msg: &M, max_bytes: usize) -> Option<usize> {
+    let len = msg.payload().map(|p| p.len()).unwrap_or(0);
+    (max_bytes > 0 && len > max_bytes).then_some(len)
+}
+
 pub fn msg_to_str<M: Message>(msg: &M) -> Result<&str> {
     msg.payload_view::<str>()
         .transpose()
         .map_err(|e| anyhow!("invalid utf8 payload: {e:?}"))?
         .ok_or_else(|| anyhow!("empty payload"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rdkafka::Timestamp;
+    use rdkafka::message::OwnedMessage;
+
+    fn msg(payload: Vec<u8>) -> OwnedMessage {
+        OwnedMessage::new(
+            Some(payload),
+            Some(b"sig123".to_vec()),
+            "sol_raw_txs".to_string(),
+            Timestamp::NotAvailable,
+            0,
+            42,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_oversized_payload_guard() {
+        let over = msg(vec![b'x'; 2048]);
+        assert_eq!(oversized_payload(&over, 1024), Some(2048));
+
+        let at_limit = msg(vec![b'x'; 1024]);
+        assert_eq!(oversized_payload(&at_limit, 1024), None);
+
+        // 0 disables the guard
+        assert_eq!(oversized_payload(&over, 0), None);
+    }
+
+    #[test]
+    fn test_fetch_tuning_maps_to_rdkafka_keys() {
+        let mut config = ClientConfig::new();
+        FetchTuning::default().apply(&mut config);
+        assert_eq!(config.get("fetch.min.bytes"), None);
+        assert_eq!(config.get("fetch.max.bytes"), None);
+        assert_eq!(config.get("queued.max.messages.kbytes"), None);
+
+        let tuning = FetchTuning {
+            fetch_min_bytes: Some(65536),
+            fetch_max_bytes: Some(104857600),
+            queued_max_messages_kbytes: None,
+        };
+        tuning.apply(&mut config);
+        assert_eq!(config.get("fetch.min.bytes"), Some("65536"));
+        assert_eq!(config.get("fetch.max.bytes"), Some("104857600"));
+        assert_eq!(config.get("queued.max.messages.kbytes"), None);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_commits_stored_offsets() {
+        use rdkafka::Offset;
+        use rdkafka::mocking::MockCluster;
+
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic("sol_raw_txs", 1, 1).unwrap();
+        let broker = cluster.bootstrap_servers();
+
+        let producer = create_producer(&broker).unwrap();
+        for _ in 0..3 {
+            send_json(&producer, "sol_raw_txs", "sig123", "{}")
+                .await
+                .unwrap();
+        }
+
+        let consumer = create_consumer(&broker, "decoder_test", &FetchTuning::default()).unwrap();
+        consumer.subscribe(&["sol_raw_txs"]).unwrap();
+
+        // Process two messages: offsets stored, no explicit commit
+        for _ in 0..2 {
+            let m = consumer.recv().await.unwrap();
+            consumer.store_offset_from_message(&m).unwrap();
+        }
+
+        // Unsubscribing revokes the assignment; the callback runs on the next poll
+        consumer.unsubscribe();
+        let _ = tokio::time::timeout(Duration::from_secs(2), consumer.recv()).await;
+        assert_eq!(consumer.context().revoke_commits(), 1);
+
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition("sol_raw_txs", 0);
+        let committed = consumer
+            .committed_offsets(tpl, Duration::from_secs(5))
+            .unwrap();
+        let offset = committed.find_partition("sol_raw_txs", 0).unwrap().offset();
+        assert_eq!(offset, Offset::Offset(2));
+    }
+
+    #[tokio::test]
+    async fn test_no_commit_for_messages_held_by_buffered_slot() {
+        use decoder::block_buffer::SwapOrderBuffer;
+        use rdkafka::mocking::MockCluster;
+        use schema::DexSwapV1Builder;
+
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic("sol_raw_txs", 1, 1).unwrap();
+        let broker = cluster.bootstrap_servers();
+
+        let producer = create_producer(&broker).unwrap();
+        for _ in 0..4 {
+            send_json(&producer, "sol_raw_txs", "sig123", "{}")
+                .await
+                .unwrap();
+        }
+
+        let consumer = create_consumer(&broker, "decoder_test", &FetchTuning::default()).unwrap();
+        consumer.subscribe(&["sol_raw_txs"]).unwrap();
+        let committed = || {
+            commit_stored_sync(&consumer).unwrap();
+            let mut tpl = TopicPartitionList::new();
+            tpl.add_partition("sol_raw_txs", 0);
+            let committed = consumer
+                .committed_offsets(tpl, Duration::from_secs(5))
+                .unwrap();
+            committed.find_partition("sol_raw_txs", 0).unwrap().offset()
+        };
+        let swap = |slot| {
+            DexSwapV1Builder::new()
+                .slot(slot)
+                .signature("sig123")
+                .in_token("SOL", 1_000_000_000u128)
+                .out_token("USDC", 50_000_000u128)
+                .build()
+        };
+
+        // Offset 0 finishes with nothing buffered: committed
+        let mut buffer = SwapOrderBuffer::new();
+        let m = consumer.recv().await.unwrap();
+        commit_or_hold(&consumer, Some(&mut buffer), &m);
+        assert_eq!(committed(), Offset::Offset(1));
+
+        // Offset 1 buffers a swap of slot 100 and offset 2 finishes after it:
+        // neither is committed, as committing 2 would cover 1
+        let m = consumer.recv().await.unwrap();
+        assert!(buffer.push(100, vec![swap(100)]).is_none());
+        commit_or_hold(&consumer, Some(&mut buffer), &m);
+        let m = consumer.recv().await.unwrap();
+        commit_or_hold(&consumer, Some(&mut buffer), &m);
+        assert_eq!(committed(), Offset::Offset(1));
+
+        // Once the slot is emitted, its held messages are committed
+        let released = buffer.take().unwrap();
+        assert_eq!(released.held, vec![(0, 1), (0, 2)]);
+        commit_positions(&consumer, "sol_raw_txs", &released.held);
+        assert_eq!(committed(), Offset::Offset(3));
+
+        // A revoke commits stored offsets only, not a held message
+        let m = consumer.recv().await.unwrap();
+        assert!(buffer.push(101, vec![swap(101)]).is_none());
+        commit_or_hold(&consumer, Some(&mut buffer), &m);
+        consumer.unsubscribe();
+        let _ = tokio::time::timeout(Duration::from_secs(2), consumer.recv()).await;
+        assert!(consumer.context().take_revoked());
+        assert!(!consumer.context().take_revoked());
+        assert_eq!(committed(), Offset::Offset(3));
+    }
+
+    #[tokio::test]
+    async fn test_periodic_flush_runs_on_schedule() {
+        use rdkafka::mocking::MockCluster;
+
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic("sol_swaps", 1, 1).unwrap();
+        let producer = create_producer(&cluster.bootstrap_servers()).unwrap();
+
+        let flushes = spawn_periodic_flush(producer.clone(), Duration::from_millis(20));
+        // No flush at spawn time, only once the first interval has elapsed
+        assert_eq!(flushes.load(Ordering::Relaxed), 0);
+
+        send_json(&producer, "sol_swaps", "sig123", "{}")
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(flushes.load(Ordering::Relaxed) >= 3);
+    }
+}
@@ -0,0 +1,13 @@
+//! Decoder library: swap detectors (shared with the backfill tool), DLQ entries,
+//! notional filtering, the learned pool mint cache, pipeline metrics, the RPC
+//! retry policy and error type, and per-slot buffering.
+
+pub mod block_buffer;
+pub mod detectors;
+pub mod dlq;
+pub mod metrics;
+pub mod mint_registry;
+pub mod notional;
+pub mod pool_mints;
+pub mod retry;
+pub mod rpc_error;
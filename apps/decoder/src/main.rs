@@ -1,38 +1,251 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use log::{debug, info, warn};
 use rdkafka::consumer::Consumer;
-use std::collections::HashMap;
+use rdkafka::message::{BorrowedMessage, Message};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::signal::unix::{SignalKind, signal};
 use tokio::time::sleep;
 
 mod config;
+mod confirm;
 mod decode;
-mod detectors;
 mod kafka;
+mod metrics_server;
+mod retry_budget;
 mod rpc;
+#[cfg(feature = "json-schema")]
+mod schema_export;
+mod schema_sampler;
 mod sinks;
+mod throttle;
 mod types;
+mod warmup;
 
 use config::Config;
-use rpc::RpcClient;
-use types::RawTxEvent;
+use confirm::ConfirmQueue;
+use decoder::block_buffer::{BlockCounter, ReleasedSlot, SwapOrderBuffer};
+use decoder::detectors::{self, registry::DetectorRegistry};
+use decoder::dlq::{self, DlqEntry};
+use decoder::metrics::{DlqReason, ParseFailReason, metrics};
+use decoder::mint_registry::MintRegistry;
+use decoder::notional::NotionalFilter;
+use decoder::pool_mints::{PoolMintCache, PoolMintsCheck};
+use retry_budget::RetryBudget;
+use rpc::{RpcClient, TxFetch};
+use sinks::{Sink, SinkKind};
+use types::{ParseDiagnostic, RawFacts, RawTxEvent, SchemaMismatchMode};
 
 // Retry budget: max attempts before committing and moving on (with optional DLQ)
 const MAX_ATTEMPTS: u32 = 3;
 const MAX_FAILURE_MAP_SIZE: usize = 10000;
 const BASE_BACKOFF_MS: u64 = 200;
 
+/// How long shutdown waits for queued output to be delivered
+const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
 fn setup_logging() {
+    // Always stderr: with SINK_KIND=stdout, stdout carries only NDJSON events
     let _ = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .target(env_logger::Target::Stderr)
         .try_init();
 }
 
+/// Replace a Prometheus textfile atomically (write beside it, then rename) so
+/// the collector never reads a partial file
+fn write_prom_file(path: &Path, contents: &str) -> std::io::Result<()> {
+    let tmp = path.with_extension("prom.tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Fetch the transactions for a drained message batch in one RPC batch, keyed by
+/// signature. Messages the loop will skip anyway (oversized, unparsable, failed
+/// txs) are left out; the loop fetches anything missing with a single call.
+async fn prefetch_transactions(
+    rpc: &RpcClient,
+    batch: &[BorrowedMessage<'_>],
+    cfg: &Config,
+) -> HashMap<String, TxFetch> {
+    let mut seen = HashSet::new();
+    let signatures: Vec<String> = batch
+        .iter()
+        .filter(|msg| kafka::oversized_payload(*msg, cfg.max_message_bytes).is_none())
+        .filter_map(|msg| kafka::msg_to_str(msg).ok())
+        .filter_map(|payload| serde_json::from_str::<RawTxEvent>(payload).ok())
+        .filter(|evt| cfg.include_failed || evt.is_success)
+        .map(|evt| evt.signature)
+        .filter(|sig| seen.insert(sig.clone()))
+        .collect();
+
+    let results = rpc.get_transactions_json_parsed(&signatures).await;
+    signatures.into_iter().zip(results).collect()
+}
+
+/// Re-check emitted swaps whose confirmation delay has passed and send a
+/// retraction for each one whose tx was rolled back. Returns retractions sent.
+async fn retract_rolled_back(
+    rpc: &RpcClient,
+    sink: &Sink,
+    topic: &str,
+    queue: &mut ConfirmQueue,
+) -> Result<u64> {
+    let now = Instant::now();
+    let due = queue.take_due(now);
+    check_and_retract(rpc, sink, topic, queue, due, now).await
+}
+
+/// Status-check `due` and retract the rolled back ones; failed checks and txs
+/// still at `processed` go back into `queue`
+async fn check_and_retract(
+    rpc: &RpcClient,
+    sink: &Sink,
+    topic: &str,
+    queue: &mut ConfirmQueue,
+    due: Vec<confirm::PendingTx>,
+    now: Instant,
+) -> Result<u64> {
+    if due.is_empty() {
+        return Ok(0);
+    }
+
+    let signatures: Vec<String> = due.iter().map(|p| p.signature.clone()).collect();
+    let statuses = match rpc.get_signature_statuses(&signatures).await {
+        Ok(statuses) => statuses,
+        Err(e) => {
+            warn!("confirmation check failed for {} txs: {e:?}", due.len());
+            queue.requeue(due, now);
+            return Ok(0);
+        }
+    };
+
+    let mut sent = 0;
+    for retraction in queue.resolve(due, &statuses, now) {
+        warn!(
+            "tx rolled back, retracting swap sig={} index_in_tx={} hop={}",
+            retraction.signature, retraction.index_in_tx, retraction.hop_index
+        );
+        let json = serde_json::to_string(&retraction)?;
+        match sink.send_json(topic, &retraction.signature, &json).await {
+            Ok(_) => sent += 1,
+            Err(e) => warn!(
+                "retraction publish failed sig={} err={:?}",
+                retraction.signature, e
+            ),
+        }
+    }
+    Ok(sent)
+}
+
+/// Sleep until `deadline`; never wakes without one
+async fn sleep_until_some(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Shutdown: check every tracked tx once, due or not, and retract the rolled
+/// back ones. Txs still unconfirmed are dropped. Returns retractions sent.
+async fn retract_all(
+    rpc: &RpcClient,
+    sink: &Sink,
+    topic: &str,
+    queue: &mut ConfirmQueue,
+) -> Result<u64> {
+    let now = Instant::now();
+    let mut pending = queue.take_all();
+    let mut sent = 0;
+    while !pending.is_empty() {
+        let rest = pending.split_off(confirm::MAX_STATUS_BATCH.min(pending.len()));
+        sent += check_and_retract(rpc, sink, topic, queue, pending, now).await?;
+        pending = rest;
+    }
+    Ok(sent)
+}
+
+/// Dual-write gold swaps that passed the filters, throttled, tracking each for
+/// the confirmation pass
+async fn emit_gold_swaps(
+    swaps: Vec<schema::DexSwapV1>,
+    sink: &Sink,
+    cfg: &Config,
+    throttle: &mut throttle::Throttle,
+    throttled: &AtomicU64,
+    confirm_queue: &mut ConfirmQueue,
+) {
+    for swap in swaps {
+        if !throttle.admit().await {
+            throttled.fetch_add(1, Ordering::Relaxed);
+            continue;
+        }
+
+        match sinks::dual::send_dual(sink, &cfg.out_swaps_topic, &cfg.out_swaps_v2_topic, &swap)
+            .await
+        {
+            Ok(_) => {
+                metrics().record_swap_emitted(&swap.venue, swap.confidence);
+                if cfg.confirm_retractions {
+                    confirm_queue.track((&swap).into(), Instant::now());
+                }
+                debug!(
+                    "swap dual-emitted: sig={} hop={} trader={} confidence={}",
+                    swap.signature, swap.hop_index, swap.trader, swap.confidence
+                );
+            }
+            Err(e) => {
+                metrics().record_publish_error();
+                warn!("swap publish failed sig={} err={:?}", swap.signature, e);
+            }
+        }
+    }
+}
+
+/// SWAPS_ORDER_BY_BLOCK: emit a released slot's swaps, then commit the input
+/// messages held back for it (the sends above are acked by now)
+#[allow(clippy::too_many_arguments)]
+async fn emit_released_slot(
+    released: ReleasedSlot,
+    consumer: &kafka::DecoderConsumer,
+    sink: &Sink,
+    cfg: &Config,
+    throttle: &mut throttle::Throttle,
+    throttled: &AtomicU64,
+    confirm_queue: &mut ConfirmQueue,
+) {
+    emit_gold_swaps(
+        released.swaps,
+        sink,
+        cfg,
+        throttle,
+        throttled,
+        confirm_queue,
+    )
+    .await;
+    kafka::commit_positions(consumer, &cfg.in_topic, &released.held);
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
     setup_logging();
 
+    #[cfg(feature = "json-schema")]
+    {
+        let args: Vec<String> = std::env::args().collect();
+        if let [_, flag, dir] = &args[..]
+            && flag == schema_export::FLAG
+        {
+            for path in schema_export::write_all(Path::new(dir))? {
+                info!("wrote {}", path);
+            }
+            return Ok(());
+        }
+    }
+
     let cfg: Config = config::load()?;
 
     // Log comprehensive config on startup
@@ -41,12 +254,48 @@ async fn main() -> Result<()> {
     info!("  in_topic={}", cfg.in_topic);
     info!("  out_sol_deltas={}", cfg.out_sol_deltas_topic);
     info!("  out_token_deltas={}", cfg.out_token_deltas_topic);
+    if cfg.emit_combined_facts {
+        info!("  out_facts={}", cfg.out_facts_topic);
+    }
+    info!("  emit_tx_summary={}", cfg.emit_tx_summary);
+    if cfg.emit_tx_summary {
+        info!("  out_tx_summary={}", cfg.out_tx_summary_topic);
+    }
+    info!("  confirm_retractions={}", cfg.confirm_retractions);
+    if cfg.confirm_retractions {
+        info!(
+            "  confirm_delay_ms={} out_retractions={}",
+            cfg.confirm_delay_ms, cfg.out_retractions_topic
+        );
+    }
     info!("  include_failed={}", cfg.include_failed);
+    info!("  prefer_event_metadata={}", cfg.prefer_event_metadata);
+    info!("  strict_balance_lengths={}", cfg.strict_balance_lengths);
+    info!(
+        "  raw_schema_versions={} raw_schema_mismatch={:?}",
+        cfg.raw_schema_versions, cfg.raw_schema_mismatch
+    );
+    info!("  sink_kind={:?}", cfg.sink_kind);
+    if cfg.sink_kind == SinkKind::Log {
+        warn!(
+            "  DRY RUN: events are logged, not produced; offsets of group {} still advance",
+            cfg.consumer_group
+        );
+    }
+    if cfg.producer_flush_ms > 0 {
+        info!("  producer_flush_ms={}", cfg.producer_flush_ms);
+    }
 
     if let Some(ref dlq) = cfg.dlq_topic {
         info!("  dlq_topic={}", dlq);
     }
     info!("  consumer_group={}", cfg.consumer_group);
+    if cfg.fetch_tuning != kafka::FetchTuning::default() {
+        info!("  fetch_tuning={:?}", cfg.fetch_tuning);
+    }
+    info!("  max_message_bytes={}", cfg.max_message_bytes);
+    info!("  dlq_max_field_bytes={}", cfg.dlq_max_field_bytes);
+    info!("  retry_max_block_ms={}", cfg.retry_max_block_ms);
     info!("  rpc_primary={}", cfg.rpc_primary_url);
     info!("  rpc_fallback_count={}", cfg.rpc_fallback_urls.len());
     if !cfg.rpc_fallback_urls.is_empty() {
@@ -55,280 +304,881 @@ async fn main() -> Result<()> {
     info!("  rpc_concurrency={}", cfg.rpc_concurrency);
     info!("  rpc_min_delay_ms={}", cfg.rpc_min_delay_ms);
     info!("  rpc_max_tx_version={}", cfg.rpc_max_tx_version);
+    info!("  rpc_get_tx_method={}", cfg.rpc_get_tx_method.as_str());
+    info!(
+        "  rpc_commitment={}",
+        cfg.rpc_commitment.map_or("node default", |c| c.as_str())
+    );
+    info!("  rpc_max_backoff_ms={}", cfg.rpc_max_backoff_ms);
+    info!("  rpc_max_retry_after_ms={}", cfg.rpc_max_retry_after_ms);
+    info!("  rpc_timeout_ms={}", cfg.rpc_timeout_ms);
+    if cfg.rpc_batch_size > 1 {
+        info!("  rpc_batch_size={}", cfg.rpc_batch_size);
+    }
+    info!("  resolve_alt_via_rpc={}", cfg.resolve_alt_via_rpc);
+    info!("  startup_warmup={}", cfg.startup_warmup);
+    if let Some(port) = cfg.metrics_port {
+        info!("  metrics_port={}", port);
+    }
+
+    let detector_opts = Arc::new(detectors::DetectorOptions {
+        trader_exclude: cfg.trader_exclude.iter().cloned().collect(),
+        programs: cfg.chain_programs.clone(),
+        explain_log_lines: cfg.swaps_explain_log_lines,
+        explain_max_confidence: cfg.swaps_explain_max_confidence,
+        jito_tip_accounts: cfg.jito_tip_accounts.iter().cloned().collect(),
+        liquidity_allow_zero_amount: cfg.liquidity_allow_zero_amount,
+        index_in_tx_mode: cfg.index_in_tx_mode,
+        multihop_policy: cfg.multihop_policy,
+        amount_sources: cfg.amount_sources.clone(),
+        record_timings: cfg.detector_timings,
+        stable_pool_programs: cfg.stable_pool_programs.clone(),
+        normalize_wsol: cfg.normalize_wsol,
+        allow_same_mint_arb: cfg.swaps_allow_same_mint_arb,
+        raydium_pool_vaults: cfg.raydium_pool_vaults.clone(),
+    });
+    // Venues run per tx on the gold path; forks register theirs here
+    let mut detector_registry = DetectorRegistry::new(detector_opts.clone());
+    if let Some(ref venues) = cfg.swap_venues {
+        detector_registry
+            .retain_venues(venues)
+            .map_err(|venue| anyhow!("SWAP_VENUES: unknown venue {}", venue))?;
+    }
+    detector_registry.remove_venues(&cfg.disabled_venues);
+    let venue_programs = detector_registry.venue_programs();
+
+    // The legacy Raydium detector is gated on RAYDIUM_AMM_V4_PROGRAM_ID; the
+    // gold registry (SWAPS_DUAL_WRITE or a venue program ID) runs whenever one of
+    // its venues is enabled
+    let legacy_swaps = !cfg.gold_swaps && !cfg.raydium_amm_v4_program_id.is_empty();
+    let gold_swaps = cfg.gold_swaps && !venue_programs.is_empty();
 
     // Log swap detection config
-    if !cfg.raydium_amm_v4_program_id.is_empty() {
+    if legacy_swaps || gold_swaps {
         info!("  swap_detection=ENABLED");
+        if gold_swaps {
+            let venues: Vec<_> = venue_programs.iter().map(|(venue, _)| *venue).collect();
+            info!("  swap_venues_enabled={:?}", venues);
+        } else {
+            info!("  swap_venues_enabled=[\"raydium\"] (legacy detector)");
+        }
         info!(
             "  raydium_amm_v4_program_id={}",
-            cfg.raydium_amm_v4_program_id
+            cfg.chain_programs.raydium_amm_v4
         );
+        info!("  chain={}", cfg.chain_programs.chain);
+        info!("  phoenix_program_id={}", cfg.chain_programs.phoenix);
+        info!("  orca_whirlpool_program_id={}", cfg.chain_programs.orca_whirlpool);
+        info!("  lifinity_program_id={}", cfg.chain_programs.lifinity_v2);
+        info!("  meteora_dlmm_program_id={}", cfg.chain_programs.meteora_dlmm);
+        info!("  jupiter_program_id={}", cfg.chain_programs.jupiter_v6);
         info!("  out_swaps_topic={}", cfg.out_swaps_topic);
+        info!("  swaps_dual_write={}", cfg.swaps_dual_write);
+        if gold_swaps {
+            info!("  out_swaps_v2_topic={}", cfg.out_swaps_v2_topic);
+            info!("  swaps_order_by_block={}", cfg.swaps_order_by_block);
+        }
         info!("  swaps_explain={}", cfg.swaps_explain);
         info!("  swaps_explain_limit={}", cfg.swaps_explain_limit);
+        info!("  swaps_explain_log_lines={}", cfg.swaps_explain_log_lines);
+        if let Some(max) = cfg.swaps_explain_max_confidence {
+            info!("  swaps_explain_max_confidence={}", max);
+        }
+        info!("  swaps_allow_same_mint_arb={}", cfg.swaps_allow_same_mint_arb);
+        info!("  swaps_min_confidence={}", cfg.swaps_min_confidence);
+        info!("  trader_exclude={} addresses", cfg.trader_exclude.len());
+        info!("  jito_tip_accounts={} addresses", cfg.jito_tip_accounts.len());
+        info!("  liquidity_allow_zero_amount={}", cfg.liquidity_allow_zero_amount);
+        if let Some(ref topic) = cfg.out_liquidity_topic {
+            info!("  out_liquidity={}", topic);
+        }
+        info!("  normalize_wsol={}", cfg.normalize_wsol);
+        info!("  pool_mints_cache_size={}", cfg.pool_mints_cache_size);
+        info!("  index_in_tx_mode={:?}", cfg.index_in_tx_mode);
+        info!("  multihop_policy={:?}", cfg.multihop_policy);
+        info!("  amount_sources={:?}", cfg.amount_sources);
+        if !cfg.stable_pool_programs.is_empty() {
+            info!(
+                "  stable_pool_programs={:?} out_stable_swaps={}",
+                cfg.stable_pool_programs, cfg.out_stable_swaps_topic
+            );
+        }
+        if !cfg.raydium_pool_vaults.is_empty() {
+            info!("  raydium_pool_vaults={}", cfg.raydium_pool_vaults.len());
+        }
+        info!("  emit_parse_diagnostics={}", cfg.emit_parse_diagnostics);
+        if cfg.emit_parse_diagnostics {
+            info!("  out_diagnostics={}", cfg.out_diagnostics_topic);
+        }
+        info!("  block_swap_counts={}", cfg.block_swap_counts);
+        if let Some(ref topic) = cfg.out_block_counts_topic
+            && cfg.block_swap_counts
+        {
+            info!("  out_block_counts={}", topic);
+        }
+        info!("  detector_timings={}", cfg.detector_timings);
+        if let Some(ref path) = cfg.metrics_prom_file {
+            info!("  metrics_prom_file={}", path.display());
+        }
+        if cfg.swaps_min_notional_usd > 0.0 {
+            info!(
+                "  swaps_min_notional_usd={} drop_unknown={} priced_mints={}",
+                cfg.swaps_min_notional_usd,
+                cfg.swaps_drop_unknown_notional,
+                cfg.swap_prices.len()
+            );
+        }
+        if cfg.swaps_max_eps > 0 {
+            info!("  swaps_max_eps={} throttle_mode={:?}", cfg.swaps_max_eps, cfg.throttle_mode);
+        }
+        if !cfg.mint_metadata.is_empty() || cfg.mint_metadata_fetch {
+            info!(
+                "  mint_metadata={} mint_metadata_fetch={} mint_cache_size={} mint_cache_ttl={:?}",
+                cfg.mint_metadata.len(),
+                cfg.mint_metadata_fetch,
+                cfg.mint_cache_size,
+                cfg.mint_cache_ttl
+            );
+        }
+    } else if cfg.gold_swaps {
+        info!("  swap_detection=DISABLED (SWAP_VENUES selects no venue with its program ID set)");
     } else {
         info!("  swap_detection=DISABLED (RAYDIUM_AMM_V4_PROGRAM_ID not set)");
     }
 
-    let consumer = kafka::create_consumer(&cfg.kafka_broker, &cfg.consumer_group)?;
+    if let Some(port) = cfg.metrics_port {
+        metrics_server::serve(port).await?;
+    }
+
+    let consumer =
+        kafka::create_consumer(&cfg.kafka_broker, &cfg.consumer_group, &cfg.fetch_tuning)?;
     info!("consumer created (group={}, in_topic={})", cfg.consumer_group, cfg.in_topic);
     consumer.subscribe(&[&cfg.in_topic])?;
 
-    let producer = kafka::create_producer(&cfg.kafka_broker)?;
+    let sink = Sink::new(cfg.sink_kind, &cfg.kafka_broker)?;
+    let producer_flushes = (cfg.producer_flush_ms > 0)
+        .then(|| sink.spawn_periodic_flush(Duration::from_millis(cfg.producer_flush_ms)))
+        .flatten();
     let rpc = RpcClient::new(
         cfg.rpc_primary_url.clone(),
         cfg.rpc_fallback_urls.clone(),
         cfg.rpc_concurrency,
         cfg.rpc_min_delay_ms,
         cfg.rpc_max_tx_version,
-    );
+        cfg.rpc_get_tx_method,
+        cfg.rpc_max_backoff_ms,
+    )
+    .with_max_retry_after_ms(cfg.rpc_max_retry_after_ms)
+    .with_timeout_ms(cfg.rpc_timeout_ms)
+    .with_commitment(cfg.rpc_commitment);
+
+    // Fail fast on a broken RPC (URL, auth) or parser before consuming anything
+    if cfg.startup_warmup {
+        warmup::run(&rpc, &cfg.chain_programs.chain, &detector_registry).await?;
+    }
 
-    let processed = AtomicU64::new(0);
     let sol_deltas_produced = AtomicU64::new(0);
     let token_deltas_produced = AtomicU64::new(0);
+    let facts_emitted = AtomicU64::new(0);
     let errors = AtomicU64::new(0);
     let skipped_failed = AtomicU64::new(0);
-    let dlq_sent = AtomicU64::new(0);
-    let swaps_detected = AtomicU64::new(0);
-    let swaps_emitted = AtomicU64::new(0);
-    let swaps_publish_errors = AtomicU64::new(0);
+    let messages_too_large = AtomicU64::new(0);
+    let balance_mismatches = AtomicU64::new(0);
+    let token_balances_out_of_range = AtomicU64::new(0);
+    let schema_version_mismatches = AtomicU64::new(0);
+    let routes_incomplete = AtomicU64::new(0);
+    let swaps_throttled = AtomicU64::new(0);
+    let swaps_below_notional = AtomicU64::new(0);
+    let pool_mints_matched = AtomicU64::new(0);
+    let swaps_retracted = AtomicU64::new(0);
+    let tx_summaries_emitted = AtomicU64::new(0);
+    let stable_swaps_emitted = AtomicU64::new(0);
+    let liquidity_events_emitted = AtomicU64::new(0);
+    let parse_diagnostics_emitted = AtomicU64::new(0);
+
+    // Overload protection for swap emission (no-op unless SWAPS_MAX_EPS > 0)
+    let mut swap_throttle = throttle::Throttle::new(cfg.swaps_max_eps, cfg.throttle_mode);
+    let mut swap_order_buffer = cfg.swaps_order_by_block.then(SwapOrderBuffer::new);
+    let mut block_counter = cfg.block_swap_counts.then(BlockCounter::new);
+    let notional_filter = NotionalFilter::new(
+        cfg.swap_prices.clone(),
+        cfg.swaps_min_notional_usd,
+        cfg.swaps_drop_unknown_notional,
+    );
+    let mut pool_mints = PoolMintCache::new(cfg.pool_mints_cache_size);
+    let mint_registry = (!cfg.mint_metadata.is_empty() || cfg.mint_metadata_fetch).then(|| {
+        Arc::new(MintRegistry::new(
+            cfg.mint_metadata.clone(),
+            cfg.mint_metadata_fetch,
+            cfg.mint_cache_size,
+            cfg.mint_cache_ttl,
+        ))
+    });
+    let mut confirm_queue = ConfirmQueue::new(Duration::from_millis(cfg.confirm_delay_ms));
+
+    // Schema validation: log (and optionally dump) the first N messages of each type
+    let mut sampler =
+        schema_sampler::SchemaSampler::new(cfg.schema_dump_count, cfg.schema_dump_dir.clone());
+
+    // Retry budget: track failures per signature to prevent poison-pill stalls,
+    // bounded in attempts and in how long one signature may block the loop
+    let mut failure_counts = RetryBudget::new(
+        MAX_ATTEMPTS,
+        Duration::from_millis(BASE_BACKOFF_MS),
+        Duration::from_millis(cfg.retry_max_block_ms),
+        MAX_FAILURE_MAP_SIZE,
+    );
+
+    // Ctrl+C / SIGTERM stop the loop between batches, never mid-transaction
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut shutdown = std::pin::pin!(async move {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+            _ = sigterm.recv() => "SIGTERM",
+        }
+    });
 
-    // Schema validation: log first message of each type (rate-limited)
-    let mut logged_raw_tx_schema = false;
-    let mut logged_sol_delta_schema = false;
-    let mut logged_token_delta_schema = false;
-    let mut logged_swap_schema = false;
+    // SWAPS_EXPLAIN_LIMIT: swaps given an explain so far
+    let mut swaps_explained: u64 = 0;
 
-    // Retry budget: track failure count per signature to prevent poison-pill stalls
-    let mut failure_counts: HashMap<String, u32> = HashMap::new();
+    // SWAPS_ORDER_BY_BLOCK: the buffered slot goes out once the input is idle
+    let mut last_message_at = Instant::now();
 
+    info!("starting main loop (Ctrl+C or SIGTERM to stop)");
     loop {
-        match consumer.recv().await {
-            Err(e) => {
-                warn!("consumer error: {e:?}");
-                sleep(Duration::from_millis(200)).await;
+        let idle_flush_at = swap_order_buffer
+            .as_ref()
+            .and_then(SwapOrderBuffer::slot)
+            .map(|_| last_message_at + cfg.swaps_order_idle_flush);
+        let mut batch = tokio::select! {
+            sig = &mut shutdown => {
+                warn!("shutdown signal received ({sig}), stopping after the current batch");
+                break;
+            }
+            _ = sleep_until_some(idle_flush_at) => {
+                let taken = swap_order_buffer.as_mut().and_then(SwapOrderBuffer::take);
+                if let Some(released) = taken {
+                    debug!(
+                        "input idle, emitting buffered slot={} swaps={}",
+                        released.slot,
+                        released.swaps.len()
+                    );
+                    emit_released_slot(
+                        released,
+                        &consumer,
+                        &sink,
+                        &cfg,
+                        &mut swap_throttle,
+                        &swaps_throttled,
+                        &mut confirm_queue,
+                    )
+                    .await;
+                }
                 continue;
             }
-            Ok(msg) => {
-                let payload = match kafka::msg_to_str(&msg) {
-                    Ok(p) => p,
-                    Err(e) => {
-                        warn!("bad payload: {e:?}");
-                        errors.fetch_add(1, Ordering::Relaxed);
-                        // commit to avoid poison-pill loops
-                        let _ = consumer.commit_message(&msg, rdkafka::consumer::CommitMode::Async);
-                        continue;
+            res = consumer.recv() => match res {
+                Err(e) => {
+                    warn!("consumer error: {e:?}");
+                    sleep(Duration::from_millis(200)).await;
+                    continue;
+                }
+                Ok(msg) => vec![msg],
+            },
+        };
+        last_message_at = Instant::now();
+
+        // Batched RPC: take messages already buffered locally (never waits) and
+        // fetch their transactions in one request
+        while batch.len() < cfg.rpc_batch_size {
+            match tokio::time::timeout(Duration::ZERO, consumer.recv()).await {
+                Ok(Ok(msg)) => batch.push(msg),
+                _ => break,
+            }
+        }
+
+        // A revoke commits only stored offsets, never the messages held for the
+        // buffered slot: emit it now so a later commit cannot skip its swaps
+        if consumer.context().take_revoked()
+            && let Some(released) = swap_order_buffer.as_mut().and_then(SwapOrderBuffer::take)
+        {
+            info!(
+                "partitions revoked, emitting buffered slot={} swaps={}",
+                released.slot,
+                released.swaps.len()
+            );
+            emit_released_slot(
+                released,
+                &consumer,
+                &sink,
+                &cfg,
+                &mut swap_throttle,
+                &swaps_throttled,
+                &mut confirm_queue,
+            )
+            .await;
+        }
+        let mut prefetched = if batch.len() > 1 {
+            prefetch_transactions(&rpc, &batch, &cfg).await
+        } else {
+            HashMap::new()
+        };
+
+        // Confirmation pass: retract emitted swaps whose tx was rolled back
+        if cfg.confirm_retractions {
+            let sent = retract_rolled_back(
+                &rpc,
+                &sink,
+                &cfg.out_retractions_topic,
+                &mut confirm_queue,
+            )
+            .await?;
+            swaps_retracted.fetch_add(sent, Ordering::Relaxed);
+        }
+
+        for msg in batch {
+            // Size guard: never parse oversized payloads
+            if let Some(size) = kafka::oversized_payload(&msg, cfg.max_message_bytes) {
+                messages_too_large.fetch_add(1, Ordering::Relaxed);
+                let key = msg
+                    .key()
+                    .and_then(|k| std::str::from_utf8(k).ok())
+                    .unwrap_or("")
+                    .to_string();
+                warn!(
+                    "oversized message key={} size={} max={} partition={} offset={}",
+                    key,
+                    size,
+                    cfg.max_message_bytes,
+                    msg.partition(),
+                    msg.offset()
+                );
+
+                if let Some(ref dlq_topic) = cfg.dlq_topic {
+                    let entry = DlqEntry::new(
+                        &key,
+                        0,
+                        dlq::reasons::MESSAGE_TOO_LARGE,
+                        &format!(
+                            "payload {} bytes exceeds MAX_MESSAGE_BYTES={}",
+                            size, cfg.max_message_bytes
+                        ),
+                    )
+                    .with_context(serde_json::json!({
+                        "topic": msg.topic(),
+                        "partition": msg.partition(),
+                        "offset": msg.offset(),
+                        "size_bytes": size,
+                    }))
+                    .with_max_field_bytes(cfg.dlq_max_field_bytes);
+                    let dlq_json = entry.to_json()?;
+                    match sink.send_json(dlq_topic, &key, &dlq_json).await {
+                        Ok(_) => {
+                            metrics().record_dlq_sent(DlqReason::MessageTooLarge);
+                        }
+                        Err(dlq_err) => {
+                            warn!("failed to send oversized message to DLQ: {dlq_err:?}");
+                        }
                     }
-                };
+                }
 
-                let evt: RawTxEvent = match serde_json::from_str(payload) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        warn!("json parse fail: {e:?}");
-                        errors.fetch_add(1, Ordering::Relaxed);
-                        let _ = consumer.commit_message(&msg, rdkafka::consumer::CommitMode::Async);
-                        continue;
+                kafka::commit_or_hold(&consumer, swap_order_buffer.as_mut(), &msg);
+                continue;
+            }
+
+            let payload = match kafka::msg_to_str(&msg) {
+                Ok(p) => p,
+                Err(e) => {
+                    warn!("bad payload: {e:?}");
+                    errors.fetch_add(1, Ordering::Relaxed);
+                    // commit to avoid poison-pill loops
+                    kafka::commit_or_hold(&consumer, swap_order_buffer.as_mut(), &msg);
+                    continue;
+                }
+            };
+
+            let evt: RawTxEvent = match serde_json::from_str(payload) {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!("json parse fail: {e:?}");
+                    errors.fetch_add(1, Ordering::Relaxed);
+                    kafka::commit_or_hold(&consumer, swap_order_buffer.as_mut(), &msg);
+                    continue;
+                }
+            };
+
+            // Sample consumed RawTxEvent schema
+            sampler.sample("RawTxEvent", &evt);
+
+            metrics().record_tx_processed();
+
+            // Streamer/decoder contract: only decode RawTxEvent versions this build knows
+            if !cfg.raw_schema_versions.contains(evt.schema_version) {
+                // Log once; the stats line carries the running count
+                if schema_version_mismatches.fetch_add(1, Ordering::Relaxed) == 0 {
+                    warn!(
+                        "RawTxEvent schema_version={} outside supported {} sig={} (mode={:?})",
+                        evt.schema_version,
+                        cfg.raw_schema_versions,
+                        evt.signature,
+                        cfg.raw_schema_mismatch
+                    );
+                }
+
+                if cfg.raw_schema_mismatch == SchemaMismatchMode::Dlq {
+                    if let Some(ref dlq_topic) = cfg.dlq_topic {
+                        let entry = DlqEntry::new(
+                            &evt.signature,
+                            evt.slot,
+                            dlq::reasons::SCHEMA_VERSION_MISMATCH,
+                            &format!(
+                                "schema_version={} supported={}",
+                                evt.schema_version, cfg.raw_schema_versions
+                            ),
+                        )
+                        .with_block_time(evt.block_time)
+                        .with_chain(&evt.chain)
+                        .with_max_field_bytes(cfg.dlq_max_field_bytes);
+                        let dlq_json = entry.to_json()?;
+                        match sink.send_json(dlq_topic, &evt.signature, &dlq_json).await {
+                            Ok(_) => {
+                                metrics().record_dlq_sent(DlqReason::SchemaVersionMismatch);
+                            }
+                            Err(dlq_err) => {
+                                warn!("failed to send schema mismatch to DLQ: {dlq_err:?}");
+                            }
+                        }
                     }
-                };
 
-                // Log first consumed RawTxEvent schema
-                if !logged_raw_tx_schema {
-                    let schema_sample = serde_json::to_string_pretty(&serde_json::json!({
-                        "schema_version": evt.schema_version,
-                        "chain": &evt.chain,
-                        "slot": evt.slot,
-                        "block_time": evt.block_time,
-                        "signature": &evt.signature,
-                        "index_in_block": evt.index_in_block,
-                        "tx_version": evt.tx_version,
-                        "is_success": evt.is_success,
-                        "fee_lamports": evt.fee_lamports,
-                        "compute_units_consumed": evt.compute_units_consumed,
-                        "main_program": &evt.main_program,
-                        "program_ids_count": evt.program_ids.len(),
-                    }))
-                    .unwrap_or_default();
-                    info!("🔍 First RawTxEvent schema sample:\n{}", schema_sample);
-                    logged_raw_tx_schema = true;
+                    kafka::commit_or_hold(&consumer, swap_order_buffer.as_mut(), &msg);
+                    continue;
                 }
+            }
 
-                processed.fetch_add(1, Ordering::Relaxed);
+            // Skip failed txs unless explicitly enabled
+            if !cfg.include_failed && !evt.is_success {
+                skipped_failed.fetch_add(1, Ordering::Relaxed);
 
-                // Skip failed txs unless explicitly enabled
-                if !cfg.include_failed && !evt.is_success {
-                    skipped_failed.fetch_add(1, Ordering::Relaxed);
+                if metrics().get_txs_processed().is_multiple_of(200) {
+                    debug!(
+                        "skipping failed txs (include_failed=false); last_skipped_sig={}",
+                        evt.signature
+                    );
+                }
 
-                    let proc_count = processed.load(Ordering::Relaxed);
-                    if proc_count.is_multiple_of(200) {
-                        debug!(
-                            "skipping failed txs (include_failed=false); last_skipped_sig={}",
-                            evt.signature
+                kafka::commit_or_hold(&consumer, swap_order_buffer.as_mut(), &msg);
+                continue;
+            }
+
+            // Fetch full tx from RPC (or take the batch-prefetched result)
+            let fetched = match prefetched.remove(&evt.signature) {
+                Some(result) => result,
+                None => rpc.get_transaction_json_parsed(&evt.signature).await,
+            };
+            // Transient failures are retried in place (backoff slept inline), so
+            // the message is never passed over while later offsets commit
+            let fetched = retry_budget::retry_fetch(
+                &mut failure_counts,
+                &evt.signature,
+                fetched,
+                || rpc.get_transaction_json_parsed(&evt.signature),
+                |attempt, delay, e| {
+                    errors.fetch_add(1, Ordering::Relaxed);
+                    if let Some(delay) = delay {
+                        warn!(
+                            "rpc getTransaction failed sig={} attempt={}/{} err={e:?} (retrying after {}ms)",
+                            evt.signature, attempt, MAX_ATTEMPTS, delay.as_millis()
                         );
                     }
+                },
+            )
+            .await;
+            let mut tx = match fetched {
+                Ok(v) => v,
+                Err((attempts_now, e)) => {
+                    // Permanent failure (out of attempts or RETRY_MAX_BLOCK_MS): send to DLQ
+                    // if configured, then commit to unblock
+                    warn!(
+                        "rpc getTransaction failed sig={} after {} attempts, moving to DLQ/commit: {e:?}",
+                        evt.signature, attempts_now
+                    );
 
-                    let _ = consumer.commit_message(&msg, rdkafka::consumer::CommitMode::Async);
+                    // Send to DLQ if configured
+                    if let Some(ref dlq_topic) = cfg.dlq_topic {
+                        let dlq_payload = serde_json::json!({
+                            "reason": "rpc_getTransaction_failed",
+                            "attempts": attempts_now,
+                            "error": dlq::truncate_field(
+                                &format!("{e:?}"),
+                                cfg.dlq_max_field_bytes
+                            ),
+                            "signature": evt.signature,
+                            "slot": evt.slot,
+                            "block_time": evt.block_time,
+                            "chain": evt.chain,
+                        });
+                        let dlq_json = serde_json::to_string(&dlq_payload)?;
+                        match sink.send_json(dlq_topic, &evt.signature, &dlq_json).await {
+                            Ok(_) => {
+                                metrics().record_dlq_sent(DlqReason::RpcFetchFailed);
+                                debug!(
+                                    "sent poison-pill sig={} to DLQ after {} attempts",
+                                    evt.signature, attempts_now
+                                );
+                            }
+                            Err(dlq_err) => {
+                                warn!(
+                                    "failed to send to DLQ sig={}: {dlq_err:?}",
+                                    evt.signature
+                                );
+                            }
+                        }
+                    }
+
+                    // CRITICAL: commit offset to unblock consumer (at-least-once preserved for transient errors)
+                    kafka::commit_or_hold(&consumer, swap_order_buffer.as_mut(), &msg);
                     continue;
                 }
+            };
 
-                // Fetch full tx from RPC
-                let tx = match rpc.get_transaction_json_parsed(&evt.signature).await {
-                    Ok(v) => {
-                        // Success: clear any failure tracking for this signature
-                        failure_counts.remove(&evt.signature);
-                        v
-                    }
-                    Err(e) => {
-                        errors.fetch_add(1, Ordering::Relaxed);
-
-                        // Track failure attempts to prevent poison-pill stalls
-                        // Compute attempts_now in a scope to avoid borrow checker issues
-                        let attempts_now = {
-                            let attempts = failure_counts.entry(evt.signature.clone()).or_insert(0);
-                            *attempts += 1;
-                            *attempts
-                        };
-
-                        // Guard against unbounded map growth
-                        if failure_counts.len() > MAX_FAILURE_MAP_SIZE {
-                            warn!(
-                                "failure_counts map exceeded {}, clearing old entries",
-                                MAX_FAILURE_MAP_SIZE
-                            );
-                            failure_counts.clear();
+            // Provider omitted meta.loadedAddresses: resolve ALTs from the table accounts
+            if cfg.resolve_alt_via_rpc
+                && let Err(e) = rpc.resolve_lookup_tables(&mut tx).await
+            {
+                warn!("ALT resolution failed sig={}: {e:?}", evt.signature);
+            }
+
+            // Malformed response: balance arrays must align with the full account keys
+            let balance_lengths = schema::BalanceLengths::from_json(&tx);
+            if !balance_lengths.is_consistent() {
+                balance_mismatches.fetch_add(1, Ordering::Relaxed);
+                warn!(
+                    "balance array length mismatch sig={} {} (strict={})",
+                    evt.signature, balance_lengths, cfg.strict_balance_lengths
+                );
+
+                if cfg.strict_balance_lengths {
+                    if let Some(ref dlq_topic) = cfg.dlq_topic {
+                        let entry = DlqEntry::new(
+                            &evt.signature,
+                            evt.slot,
+                            dlq::reasons::BALANCE_LENGTH_MISMATCH,
+                            &balance_lengths.to_string(),
+                        )
+                        .with_block_time(evt.block_time)
+                        .with_chain(&evt.chain)
+                        .with_v0_alt(tx.pointer("/meta/loadedAddresses").is_some())
+                        .with_max_field_bytes(cfg.dlq_max_field_bytes);
+                        let dlq_json = entry.to_json()?;
+                        match sink.send_json(dlq_topic, &evt.signature, &dlq_json).await {
+                            Ok(_) => {
+                                metrics().record_dlq_sent(DlqReason::BalanceLengthMismatch);
+                            }
+                            Err(dlq_err) => {
+                                warn!("failed to send balance mismatch to DLQ: {dlq_err:?}");
+                            }
                         }
+                    }
+
+                    kafka::commit_or_hold(&consumer, swap_order_buffer.as_mut(), &msg);
+                    continue;
+                }
+            }
+
+            // Decode facts
+            let sol_deltas =
+                decode::decode_sol_deltas(evt.slot, evt.block_time, &evt.signature, &tx);
+            let tok_deltas =
+                decode::decode_token_deltas(evt.slot, evt.block_time, &evt.signature, &tx);
+
+            // Debug log: if token deltas are empty but token balances exist
+            if tok_deltas.is_empty() {
+                let (pre_len, post_len, _) = decode::inspect_token_balances(&tx);
+                if pre_len > 0 || post_len > 0 {
+                    debug!(
+                        "tx {} has token balances (pre={}, post={}) but produced 0 deltas",
+                        evt.signature, pre_len, post_len
+                    );
+                }
+            }
+
+            // One combined message per tx, in addition to the per-delta topics
+            if cfg.emit_combined_facts {
+                let facts = RawFacts {
+                    signature: &evt.signature,
+                    slot: evt.slot,
+                    block_time: evt.block_time,
+                    sol_deltas: &sol_deltas,
+                    token_deltas: &tok_deltas,
+                };
+                sampler.sample("RawFacts", &facts);
+                let json = serde_json::to_string(&facts)?;
+                sink.send_json(&cfg.out_facts_topic, &evt.signature, &json)
+                    .await?;
+                facts_emitted.fetch_add(1, Ordering::Relaxed);
+            }
+
+            // Publish facts
+            let sol_count = sol_deltas.len();
+            for d in sol_deltas {
+                let json = serde_json::to_string(&d)?;
+
+                sampler.sample("SolBalanceDelta", &d);
+
+                sink.send_json(&cfg.out_sol_deltas_topic, &evt.signature, &json)
+                    .await?;
+            }
+            sol_deltas_produced.fetch_add(sol_count as u64, Ordering::Relaxed);
+
+            let tok_count = tok_deltas.len();
+            for d in tok_deltas {
+                let json = serde_json::to_string(&d)?;
+
+                sampler.sample("TokenBalanceDelta", &d);
+
+                sink.send_json(&cfg.out_token_deltas_topic, &evt.signature, &json)
+                    .await?;
+            }
+            token_deltas_produced.fetch_add(tok_count as u64, Ordering::Relaxed);
+
+            // Venues of the swaps detected in this tx (for the tx summary)
+            let mut tx_swap_venues: Vec<String> = Vec::new();
+
+            // Swap detection (best-effort, errors logged but not fatal)
+            if legacy_swaps || gold_swaps {
+                // Recompute program_ids from fetched tx for validation (handles v0+ALT)
+                let recomputed_program_ids = schema::extract_program_ids_from_transaction(&tx);
+
+                // Check if tx is v0 with loadedAddresses for observability
+                let has_loaded_addresses = tx.pointer("/meta/loadedAddresses").is_some();
+                let tx_version = tx.pointer("/version").and_then(|v| v.as_u64());
+                if has_loaded_addresses && tx_version == Some(0) {
+                    metrics().record_v0_alt_tx();
+                }
+
+                // Determine if we should attach explain (respect limit)
+                let should_explain =
+                    cfg.swaps_explain && swaps_explained < cfg.swaps_explain_limit as u64;
 
-                        if attempts_now < MAX_ATTEMPTS {
-                            // Transient failure: apply backoff and retry later (do NOT commit)
-                            let backoff_ms = BASE_BACKOFF_MS * (attempts_now as u64);
+                if gold_swaps {
+                    // Migration mode: gold parser drives detection, legacy event derived from it
+                    let facts = evt.tx_facts(&tx, cfg.prefer_event_metadata);
+                    if facts.out_of_range_token_balances > 0 {
+                        // Logged once; the stats line keeps the running count
+                        if token_balances_out_of_range
+                            .fetch_add(facts.out_of_range_token_balances as u64, Ordering::Relaxed)
+                            == 0
+                        {
                             warn!(
-                                "rpc getTransaction failed sig={} attempt={}/{} err={e:?} (retrying after {}ms)",
-                                evt.signature, attempts_now, MAX_ATTEMPTS, backoff_ms
+                                "token balances with accountIndex beyond account keys sig={} count={} (excluded from detection, further occurrences only counted)",
+                                evt.signature, facts.out_of_range_token_balances
                             );
-                            sleep(Duration::from_millis(backoff_ms)).await;
-                            continue;
-                        } else {
-                            // Permanent failure: send to DLQ if configured, then commit to unblock
-                            warn!(
-                                "rpc getTransaction failed sig={} after {} attempts, moving to DLQ/commit: {e:?}",
-                                evt.signature, attempts_now
+                        }
+                    }
+                    let swaps = match detector_registry.detect(
+                        &facts,
+                        &evt.chain,
+                        evt.index_in_block,
+                        should_explain,
+                    ) {
+                        Ok(swaps) => {
+                            let parse_fails = detectors::record_venue_outcomes(
+                                metrics(),
+                                &facts,
+                                &evt.program_ids,
+                                &swaps,
+                                &venue_programs,
                             );
-
-                            // Send to DLQ if configured
+                            if cfg.emit_parse_diagnostics {
+                                for (venue, reason) in parse_fails {
+                                    let diagnostic = ParseDiagnostic {
+                                        signature: &evt.signature,
+                                        slot: evt.slot,
+                                        venue,
+                                        reason: reason.as_str(),
+                                    };
+                                    let json = serde_json::to_string(&diagnostic)?;
+                                    match sink
+                                        .send_json(
+                                            &cfg.out_diagnostics_topic,
+                                            &evt.signature,
+                                            &json,
+                                        )
+                                        .await
+                                    {
+                                        Ok(_) => {
+                                            parse_diagnostics_emitted
+                                                .fetch_add(1, Ordering::Relaxed);
+                                        }
+                                        Err(e) => {
+                                            metrics().record_publish_error();
+                                            warn!(
+                                                "parse diagnostic publish failed sig={} err={:?}",
+                                                evt.signature, e
+                                            );
+                                        }
+                                    }
+                                }
+                            }
+                            swaps
+                        }
+                        Err(route) => {
+                            // MULTIHOP_POLICY=all_or_nothing: no legs emitted, tx to DLQ
+                            routes_incomplete.fetch_add(1, Ordering::Relaxed);
+                            metrics()
+                                .record_parse_fail(route.venue, ParseFailReason::MultiHopFailed);
+                            warn!("incomplete multi-hop route sig={} {}", evt.signature, route);
                             if let Some(ref dlq_topic) = cfg.dlq_topic {
-                                let dlq_payload = serde_json::json!({
-                                    "reason": "rpc_getTransaction_failed",
-                                    "attempts": attempts_now,
-                                    "error": format!("{e:?}"),
-                                    "signature": evt.signature,
-                                    "slot": evt.slot,
-                                    "block_time": evt.block_time,
-                                    "chain": evt.chain,
-                                });
-                                let dlq_json = serde_json::to_string(&dlq_payload)?;
-                                match kafka::send_json(
-                                    &producer,
-                                    dlq_topic,
+                                let entry = DlqEntry::new(
                                     &evt.signature,
-                                    &dlq_json,
+                                    evt.slot,
+                                    dlq::reasons::MULTI_HOP_FAILED,
+                                    &route.to_string(),
                                 )
-                                .await
-                                {
+                                .with_block_time(evt.block_time)
+                                .with_chain(&evt.chain)
+                                .with_venue(route.venue)
+                                .with_v0_alt(has_loaded_addresses)
+                                .with_max_field_bytes(cfg.dlq_max_field_bytes);
+                                let dlq_json = entry.to_json()?;
+                                match sink.send_json(dlq_topic, &evt.signature, &dlq_json).await {
                                     Ok(_) => {
-                                        dlq_sent.fetch_add(1, Ordering::Relaxed);
-                                        debug!(
-                                            "sent poison-pill sig={} to DLQ after {} attempts",
-                                            evt.signature, attempts_now
-                                        );
+                                        metrics().record_dlq_sent(DlqReason::MultiHopFailed);
                                     }
                                     Err(dlq_err) => {
                                         warn!(
-                                            "failed to send to DLQ sig={}: {dlq_err:?}",
-                                            evt.signature
+                                            "failed to send incomplete route to DLQ: {dlq_err:?}"
                                         );
                                     }
                                 }
                             }
+                            Vec::new()
+                        }
+                    };
 
-                            // CRITICAL: commit offset to unblock consumer (at-least-once preserved for transient errors)
-                            let _ =
-                                consumer.commit_message(&msg, rdkafka::consumer::CommitMode::Async);
-                            failure_counts.remove(&evt.signature);
-                            continue;
+                    // Multi-asset pool interactions go to their own topic, unthrottled
+                    for event in detectors::stable_pool::parse_stable_pool_swaps(
+                        &facts,
+                        &evt.chain,
+                        evt.index_in_block,
+                        &detector_opts,
+                    ) {
+                        sampler.sample("StableSwapV1", &event);
+                        let json = serde_json::to_string(&event)?;
+                        match sink
+                            .send_json(&cfg.out_stable_swaps_topic, &evt.signature, &json)
+                            .await
+                        {
+                            Ok(_) => {
+                                stable_swaps_emitted.fetch_add(1, Ordering::Relaxed);
+                            }
+                            Err(e) => {
+                                metrics().record_publish_error();
+                                warn!(
+                                    "stable swap publish failed sig={} err={:?}",
+                                    evt.signature, e
+                                );
+                            }
                         }
                     }
-                };
 
-                // Decode facts
-                let sol_deltas =
-                    decode::decode_sol_deltas(evt.slot, evt.block_time, &evt.signature, &tx);
-                let tok_deltas =
-                    decode::decode_token_deltas(evt.slot, evt.block_time, &evt.signature, &tx);
-
-                // Debug log: if token deltas are empty but token balances exist
-                if tok_deltas.is_empty() {
-                    let (pre_len, post_len, _) = decode::inspect_token_balances(&tx);
-                    if pre_len > 0 || post_len > 0 {
-                        debug!(
-                            "tx {} has token balances (pre={}, post={}) but produced 0 deltas",
-                            evt.signature, pre_len, post_len
-                        );
+                    // Raydium deposits/withdrawals, unthrottled like the stable pools
+                    if let Some(ref topic) = cfg.out_liquidity_topic {
+                        for event in detectors::raydium_liquidity::parse_raydium_v4_liquidity(
+                            &facts,
+                            &evt.chain,
+                            &detector_opts,
+                        ) {
+                            sampler.sample("LiquidityEventV1", &event);
+                            let json = serde_json::to_string(&event)?;
+                            match sink.send_json(topic, &evt.signature, &json).await {
+                                Ok(_) => {
+                                    liquidity_events_emitted.fetch_add(1, Ordering::Relaxed);
+                                }
+                                Err(e) => {
+                                    metrics().record_publish_error();
+                                    warn!(
+                                        "liquidity event publish failed sig={} err={:?}",
+                                        evt.signature, e
+                                    );
+                                }
+                            }
+                        }
                     }
-                }
 
-                // Publish facts
-                let sol_count = sol_deltas.len();
-                for d in sol_deltas {
-                    let json = serde_json::to_string(&d)?;
+                    swaps_explained += swaps.iter().filter(|s| s.explain.is_some()).count() as u64;
 
-                    // Log first SOL delta schema
-                    if !logged_sol_delta_schema {
-                        let schema_sample = serde_json::to_string_pretty(&d).unwrap_or_default();
-                        info!("🔍 First SolBalanceDelta schema sample:\n{}", schema_sample);
-                        logged_sol_delta_schema = true;
-                    }
+                    let mut ready = Vec::new();
+                    for mut swap in swaps {
+                        metrics().record_swap_detected();
+                        tx_swap_venues.push(swap.venue.clone());
 
-                    kafka::send_json(&producer, &cfg.out_sol_deltas_topic, &evt.signature, &json)
-                        .await?;
-                }
-                sol_deltas_produced.fetch_add(sol_count as u64, Ordering::Relaxed);
-
-                let tok_count = tok_deltas.len();
-                for d in tok_deltas {
-                    let json = serde_json::to_string(&d)?;
-
-                    // Log first token delta schema
-                    if !logged_token_delta_schema {
-                        let schema_sample = serde_json::to_string_pretty(&d).unwrap_or_default();
-                        info!(
-                            "🔍 First TokenBalanceDelta schema sample:\n{}",
-                            schema_sample
-                        );
-                        logged_token_delta_schema = true;
-                    }
+                        if pool_mints.apply(&mut swap) == PoolMintsCheck::Matched {
+                            pool_mints_matched.fetch_add(1, Ordering::Relaxed);
+                        }
 
-                    kafka::send_json(
-                        &producer,
-                        &cfg.out_token_deltas_topic,
-                        &evt.signature,
-                        &json,
-                    )
-                    .await?;
-                }
-                token_deltas_produced.fetch_add(tok_count as u64, Ordering::Relaxed);
-
-                // Swap detection (best-effort, errors logged but not fatal)
-                if !cfg.raydium_amm_v4_program_id.is_empty() {
-                    // Recompute program_ids from fetched tx for validation (handles v0+ALT)
-                    let recomputed_program_ids = schema::extract_program_ids_from_transaction(&tx);
-                    
-                    // Check if tx is v0 with loadedAddresses for observability
-                    let has_loaded_addresses = tx.pointer("/meta/loadedAddresses").is_some();
-                    let tx_version = tx.pointer("/version").and_then(|v| v.as_u64());
-                    
-                    // Determine if we should attach explain (respect limit)
-                    let should_explain = cfg.swaps_explain
-                        && swaps_emitted.load(Ordering::Relaxed) < cfg.swaps_explain_limit as u64;
+                        sampler.sample("DexSwapV1", &swap);
+
+                        if !swap.meets_confidence(cfg.swaps_min_confidence) {
+                            metrics().record_swap_below_confidence();
+                            continue;
+                        }
 
+                        if !notional_filter.admit(&swap) {
+                            swaps_below_notional.fetch_add(1, Ordering::Relaxed);
+                            continue;
+                        }
+
+                        // Symbols / ui amounts from known mints only; unknown
+                        // ones are fetched in the background for later swaps
+                        if let Some(registry) = &mint_registry {
+                            for mint in registry.enrich(&mut swap, Instant::now()) {
+                                let (registry, rpc) = (registry.clone(), rpc.clone());
+                                tokio::spawn(async move {
+                                    let info = match rpc.get_mint_info(&mint).await {
+                                        Ok(info) => info,
+                                        Err(e) => {
+                                            debug!("mint fetch failed mint={} err={:?}", mint, e);
+                                            None
+                                        }
+                                    };
+                                    registry.finish_fetch(&mint, info, Instant::now());
+                                });
+                            }
+                        }
+
+                        ready.push(swap);
+                    }
+
+                    // SWAPS_ORDER_BY_BLOCK: hold the slot's swaps until the next slot
+                    // starts, then emit them in on-chain order
+                    match swap_order_buffer.as_mut() {
+                        Some(buffer) => {
+                            if let Some(released) = buffer.push(evt.slot, ready) {
+                                emit_released_slot(
+                                    released,
+                                    &consumer,
+                                    &sink,
+                                    &cfg,
+                                    &mut swap_throttle,
+                                    &swaps_throttled,
+                                    &mut confirm_queue,
+                                )
+                                .await;
+                            }
+                        }
+                        None => {
+                            emit_gold_swaps(
+                                ready,
+                                &sink,
+                                &cfg,
+                                &mut swap_throttle,
+                                &swaps_throttled,
+                                &mut confirm_queue,
+                            )
+                            .await;
+                        }
+                    }
+                } else {
                     match detectors::raydium_v4::detect_raydium_v4_swap(
                         &evt.chain,
                         evt.slot,
@@ -339,41 +1189,69 @@ async fn main() -> Result<()> {
                         &tx,
                         should_explain,
                     ) {
-                        Some(swap) => {
-                            swaps_detected.fetch_add(1, Ordering::Relaxed);
-
-                            // Log first swap schema
-                            if !logged_swap_schema {
-                                let schema_sample =
-                                    serde_json::to_string_pretty(&swap).unwrap_or_default();
-                                info!("🔍 First SwapEvent schema sample:\n{}", schema_sample);
-                                logged_swap_schema = true;
+                        Some(mut swap) => {
+                            metrics().record_swap_detected();
+                            tx_swap_venues.push(swap.venue.clone());
+                            if cfg
+                                .swaps_explain_max_confidence
+                                .is_some_and(|max| swap.confidence > max)
+                            {
+                                swap.explain = None;
+                            }
+                            if swap.explain.is_some() {
+                                swaps_explained += 1;
                             }
 
-                            match sinks::swap::send_swap(&producer, &cfg.out_swaps_topic, &swap)
-                                .await
-                            {
-                                Ok(_) => {
-                                    swaps_emitted.fetch_add(1, Ordering::Relaxed);
-                                    debug!(
-                                        "swap emitted: sig={} trader={} in_mint={} out_mint={} confidence={}",
-                                        swap.signature,
-                                        swap.trader,
-                                        swap.in_mint,
-                                        swap.out_mint,
-                                        swap.confidence
-                                    );
-                                }
-                                Err(e) => {
-                                    swaps_publish_errors.fetch_add(1, Ordering::Relaxed);
-                                    warn!("swap publish failed sig={} err={:?}", evt.signature, e);
+                            sampler.sample("SwapEvent", &swap);
+
+                            if swap.confidence < cfg.swaps_min_confidence {
+                                metrics().record_swap_below_confidence();
+                            } else if !notional_filter.admit_legacy(&swap) {
+                                swaps_below_notional.fetch_add(1, Ordering::Relaxed);
+                            } else if !swap_throttle.admit().await {
+                                swaps_throttled.fetch_add(1, Ordering::Relaxed);
+                            } else {
+                                match sinks::swap::send_swap(&sink, &cfg.out_swaps_topic, &swap)
+                                    .await
+                                {
+                                    Ok(_) => {
+                                        metrics().record_swap_emitted(&swap.venue, swap.confidence);
+                                        if cfg.confirm_retractions {
+                                            confirm_queue.track((&swap).into(), Instant::now());
+                                        }
+                                        debug!(
+                                            "swap emitted: sig={} trader={} in_mint={} out_mint={} confidence={}",
+                                            swap.signature,
+                                            swap.trader,
+                                            swap.in_mint,
+                                            swap.out_mint,
+                                            swap.confidence
+                                        );
+                                    }
+                                    Err(e) => {
+                                        metrics().record_publish_error();
+                                        warn!(
+                                            "swap publish failed sig={} err={:?}",
+                                            evt.signature, e
+                                        );
+                                    }
                                 }
                             }
                         }
                         None => {
+                            let raydium = &cfg.raydium_amm_v4_program_id;
+                            if !recomputed_program_ids.contains(raydium) {
+                                if evt.program_ids.contains(raydium) {
+                                    metrics().record_gate_fail("raydium");
+                                }
+                            } else {
+                                metrics().record_parse_fail("raydium", ParseFailReason::Unknown);
+                            }
+
                             // Observability: log when program gate fails for v0+ALT tx
                             if has_loaded_addresses && tx_version == Some(0) {
-                                if !recomputed_program_ids.contains(&cfg.raydium_amm_v4_program_id) {
+                                if !recomputed_program_ids.contains(&cfg.raydium_amm_v4_program_id)
+                                {
                                     debug!(
                                         "v0+ALT tx sig={} missing Raydium in recomputed program_ids (possible ALT extraction issue)",
                                         evt.signature
@@ -388,37 +1266,167 @@ async fn main() -> Result<()> {
                         }
                     }
                 }
+            }
 
-                // Commit offset only after successful publish
-                let _ = consumer.commit_message(&msg, rdkafka::consumer::CommitMode::Async);
-
-                // periodic log with detailed breakdown
-                let proc_count = processed.load(Ordering::Relaxed);
-                if proc_count.is_multiple_of(200) {
-                    let sol_prod = sol_deltas_produced.load(Ordering::Relaxed);
-                    let tok_prod = token_deltas_produced.load(Ordering::Relaxed);
-                    let total_prod = sol_prod + tok_prod;
-                    let err_count = errors.load(Ordering::Relaxed);
-                    let dlq_count = dlq_sent.load(Ordering::Relaxed);
-                    let pending_retries = failure_counts.len();
-                    let swaps_det = swaps_detected.load(Ordering::Relaxed);
-                    let swaps_emit = swaps_emitted.load(Ordering::Relaxed);
-                    let swaps_err = swaps_publish_errors.load(Ordering::Relaxed);
-                    info!(
-                        "stats: processed={} sol_deltas={} token_deltas={} total_produced={} errors={} dlq_sent={} pending_retries={} swaps_detected={} swaps_emitted={} swap_errors={}",
-                        proc_count,
-                        sol_prod,
-                        tok_prod,
-                        total_prod,
-                        err_count,
-                        dlq_count,
-                        pending_retries,
-                        swaps_det,
-                        swaps_emit,
-                        swaps_err
-                    );
+            // BLOCK_SWAP_COUNTS: every decoded tx and its swaps (either path), released
+            // when the next slot starts
+            if let Some(counts) = block_counter
+                .as_mut()
+                .and_then(|counter| counter.record(evt.slot, tx_swap_venues.len()))
+            {
+                metrics().record_block_counts(&counts);
+                debug!(
+                    "block slot={} swaps={} txs={}",
+                    counts.slot, counts.swap_count, counts.tx_count
+                );
+                if let Some(ref topic) = cfg.out_block_counts_topic {
+                    let json = serde_json::to_string(&counts)?;
+                    let key = counts.slot.to_string();
+                    if let Err(e) = sink.send_json(topic, &key, &json).await {
+                        metrics().record_publish_error();
+                        warn!("block counts publish failed slot={} err={:?}", key, e);
+                    }
+                }
+            }
+
+            // Per-tx heartbeat for monitoring (best-effort)
+            if cfg.emit_tx_summary {
+                let facts = evt.tx_facts(&tx, cfg.prefer_event_metadata);
+                let summary = schema::TxSummaryV1::from_facts(
+                    &facts,
+                    tx_swap_venues.iter().map(String::as_str),
+                );
+                sampler.sample("TxSummaryV1", &summary);
+                let json = serde_json::to_string(&summary)?;
+                match sink
+                    .send_json(&cfg.out_tx_summary_topic, &evt.signature, &json)
+                    .await
+                {
+                    Ok(_) => {
+                        tx_summaries_emitted.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(e) => {
+                        warn!(
+                            "tx summary publish failed sig={} err={:?}",
+                            evt.signature, e
+                        );
+                    }
+                }
+            }
+
+            // Commit offset only after successful publish
+            kafka::commit_or_hold(&consumer, swap_order_buffer.as_mut(), &msg);
+
+            // periodic log with detailed breakdown
+            let proc_count = metrics().get_txs_processed();
+            if proc_count.is_multiple_of(200) {
+                let sol_prod = sol_deltas_produced.load(Ordering::Relaxed);
+                let tok_prod = token_deltas_produced.load(Ordering::Relaxed);
+                let total_prod = sol_prod + tok_prod;
+                let err_count = errors.load(Ordering::Relaxed);
+                let dlq_count = metrics().get_dlq_sent();
+                let too_large = messages_too_large.load(Ordering::Relaxed);
+                let pending_retries = failure_counts.len();
+                let swaps_det = metrics().get_swaps_detected();
+                let swaps_emit = metrics().get_swaps_emitted();
+                let swaps_err = metrics().get_publish_errors();
+                let swaps_thr = swaps_throttled.load(Ordering::Relaxed);
+                let rpc_stats = rpc.retry_stats();
+                info!(
+                    "stats: processed={} sol_deltas={} token_deltas={} combined_facts={} total_produced={} errors={} dlq_sent={} too_large={} balance_mismatches={} token_balances_out_of_range={} schema_version_mismatches={} pending_retries={} swaps_detected={} routes_incomplete={} swaps_emitted={} swap_errors={} swaps_throttled={} swaps_below_confidence={} swaps_below_notional={} pool_mints_matched={} swaps_retracted={} confirm_pending={} tx_summaries={} stable_swaps={} liquidity_events={} parse_diagnostics={} rpc_retries={} rpc_retries_429={} rpc_backoff_ms={} revoke_commits={} producer_flushes={}",
+                    proc_count,
+                    sol_prod,
+                    tok_prod,
+                    facts_emitted.load(Ordering::Relaxed),
+                    total_prod,
+                    err_count,
+                    dlq_count,
+                    too_large,
+                    balance_mismatches.load(Ordering::Relaxed),
+                    token_balances_out_of_range.load(Ordering::Relaxed),
+                    schema_version_mismatches.load(Ordering::Relaxed),
+                    pending_retries,
+                    swaps_det,
+                    routes_incomplete.load(Ordering::Relaxed),
+                    swaps_emit,
+                    swaps_err,
+                    swaps_thr,
+                    metrics().get_swaps_below_confidence(),
+                    swaps_below_notional.load(Ordering::Relaxed),
+                    pool_mints_matched.load(Ordering::Relaxed),
+                    swaps_retracted.load(Ordering::Relaxed),
+                    confirm_queue.len(),
+                    tx_summaries_emitted.load(Ordering::Relaxed),
+                    stable_swaps_emitted.load(Ordering::Relaxed),
+                    liquidity_events_emitted.load(Ordering::Relaxed),
+                    parse_diagnostics_emitted.load(Ordering::Relaxed),
+                    rpc_stats.retries.load(Ordering::Relaxed),
+                    rpc_stats.retries_429.load(Ordering::Relaxed),
+                    rpc_stats.backoff_ms_total.load(Ordering::Relaxed),
+                    consumer.context().revoke_commits(),
+                    producer_flushes
+                        .as_ref()
+                        .map_or(0, |f| f.load(Ordering::Relaxed))
+                );
+                // Per-venue breakdown (emitted by confidence, gate/parse failures,
+                // DLQ reasons, parse timings with DETECTOR_TIMINGS)
+                info!("swap metrics: {}", metrics().summary());
+                if cfg.detector_timings
+                    && let Some(ref path) = cfg.metrics_prom_file
+                    && let Err(e) = write_prom_file(path, &metrics().parse_timings_prometheus())
+                {
+                    warn!("failed to write {}: {e:?}", path.display());
                 }
             }
         }
     }
+
+    // Output still held in memory goes to the sink before the flush: the
+    // buffered slot's swaps (their held offsets are stored once emitted), then
+    // a last confirmation pass over the swaps awaiting one
+    if let Some(released) = swap_order_buffer.as_mut().and_then(SwapOrderBuffer::take) {
+        info!(
+            "shutdown: emitting buffered slot={} swaps={}",
+            released.slot,
+            released.swaps.len()
+        );
+        emit_released_slot(
+            released,
+            &consumer,
+            &sink,
+            &cfg,
+            &mut swap_throttle,
+            &swaps_throttled,
+            &mut confirm_queue,
+        )
+        .await;
+    }
+    if cfg.confirm_retractions && confirm_queue.len() > 0 {
+        match retract_all(&rpc, &sink, &cfg.out_retractions_topic, &mut confirm_queue).await {
+            Ok(sent) => info!(
+                "shutdown: confirmation pass retracted={} unconfirmed_dropped={}",
+                sent,
+                confirm_queue.len()
+            ),
+            Err(e) => warn!("shutdown: confirmation pass failed: {e:?}"),
+        }
+    }
+
+    // Flush before committing: a committed offset must never point past
+    // output still queued in the producer
+    match sink.flush(SHUTDOWN_FLUSH_TIMEOUT) {
+        Ok(()) => match kafka::commit_stored_sync(&consumer) {
+            Ok(true) => info!("shutdown: producer flushed, offsets committed"),
+            Ok(false) => info!("shutdown: producer flushed, no offsets to commit"),
+            Err(e) => warn!("shutdown: offset commit failed: {e:?}"),
+        },
+        // Uncommitted messages are redelivered on restart
+        Err(e) => warn!("shutdown: producer flush failed, offsets left uncommitted: {e:?}"),
+    }
+    info!(
+        "shutdown: processed={} swap metrics: {}",
+        metrics().get_txs_processed(),
+        metrics().summary()
+    );
+    Ok(())
 }
@@ -5,11 +5,18 @@
 //! - parse_fail_total{venue, reason}
 //! - gate_fail_total{venue}
 //! - v0_alt_tx_seen_total
+//! - swaps_below_confidence_total (`SWAPS_MIN_CONFIDENCE`)
 //! - dlq_sent_total{reason}
+//! - parse_duration{venue} (min/avg/max, with `DETECTOR_TIMINGS`)
+//! - last_block_{slot,swaps,txs} gauges (with `BLOCK_SWAP_COUNTS`)
 
 use std::collections::HashMap;
+use std::fmt::Write;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::block_buffer::BlockSwapCounts;
 
 /// Global metrics instance
 static METRICS: once_cell::sync::Lazy<SwapMetrics> =
@@ -61,6 +68,9 @@ pub enum ParseFailReason {
     NoTokenDeltas,
     /// Could not identify in/out amounts
     InvalidAmounts,
+    /// No account on the venue instructions both paid and received a token
+    /// (deposits, withdrawals, cranks)
+    NoInOutPair,
     /// Multi-hop segmentation failed
     MultiHopFailed,
     /// Instruction data decode failed
@@ -74,6 +84,7 @@ impl ParseFailReason {
         match self {
             ParseFailReason::NoTokenDeltas => "no_token_deltas",
             ParseFailReason::InvalidAmounts => "invalid_amounts",
+            ParseFailReason::NoInOutPair => "no_in_out_pair",
             ParseFailReason::MultiHopFailed => "multi_hop_failed",
             ParseFailReason::InstructionDecodeFailed => "instruction_decode_failed",
             ParseFailReason::Unknown => "unknown",
@@ -90,6 +101,14 @@ pub enum DlqReason {
     ParseFailed,
     /// Schema validation failed
     ValidationFailed,
+    /// Kafka payload over MAX_MESSAGE_BYTES
+    MessageTooLarge,
+    /// Balance arrays don't align with the account keys (STRICT_BALANCE_LENGTHS)
+    BalanceLengthMismatch,
+    /// Multi-hop route with unparseable legs (MULTIHOP_POLICY=all_or_nothing)
+    MultiHopFailed,
+    /// RawTxEvent schema_version outside RAW_SCHEMA_VERSIONS
+    SchemaVersionMismatch,
 }
 
 impl DlqReason {
@@ -98,10 +117,64 @@ impl DlqReason {
             DlqReason::RpcFetchFailed => "rpc_fetch_failed",
             DlqReason::ParseFailed => "parse_failed",
             DlqReason::ValidationFailed => "validation_failed",
+            DlqReason::MessageTooLarge => "message_too_large",
+            DlqReason::BalanceLengthMismatch => "balance_length_mismatch",
+            DlqReason::MultiHopFailed => "multi_hop_failed",
+            DlqReason::SchemaVersionMismatch => "schema_version_mismatch",
         }
     }
 }
 
+/// Running min/avg/max of one venue's parse time
+#[derive(Debug)]
+pub struct ParseTiming {
+    count: AtomicU64,
+    total_ns: AtomicU64,
+    min_ns: AtomicU64,
+    max_ns: AtomicU64,
+}
+
+/// Point-in-time copy of a `ParseTiming`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseTimingSnapshot {
+    pub count: u64,
+    pub min: Duration,
+    pub avg: Duration,
+    pub max: Duration,
+}
+
+impl ParseTiming {
+    fn new() -> Self {
+        Self {
+            count: AtomicU64::new(0),
+            total_ns: AtomicU64::new(0),
+            min_ns: AtomicU64::new(u64::MAX),
+            max_ns: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, elapsed: Duration) {
+        let ns = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_ns.fetch_add(ns, Ordering::Relaxed);
+        self.min_ns.fetch_min(ns, Ordering::Relaxed);
+        self.max_ns.fetch_max(ns, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> Option<ParseTimingSnapshot> {
+        let count = self.count.load(Ordering::Relaxed);
+        if count == 0 {
+            return None;
+        }
+        Some(ParseTimingSnapshot {
+            count,
+            min: Duration::from_nanos(self.min_ns.load(Ordering::Relaxed)),
+            avg: Duration::from_nanos(self.total_ns.load(Ordering::Relaxed) / count),
+            max: Duration::from_nanos(self.max_ns.load(Ordering::Relaxed)),
+        })
+    }
+}
+
 /// Swap pipeline metrics
 pub struct SwapMetrics {
     /// Total swaps emitted by venue and confidence bucket
@@ -125,8 +198,19 @@ pub struct SwapMetrics {
     /// Total swaps detected (before filtering)
     swaps_detected: AtomicU64,
 
+    /// Swaps dropped below `SWAPS_MIN_CONFIDENCE`
+    swaps_below_confidence: AtomicU64,
+
     /// Total publish errors
     publish_errors: AtomicU64,
+
+    /// Detector parse time by venue
+    parse_timings: RwLock<HashMap<String, ParseTiming>>,
+
+    /// Last completed block (`BLOCK_SWAP_COUNTS`): slot, swaps, txs
+    last_block_slot: AtomicU64,
+    last_block_swaps: AtomicU64,
+    last_block_txs: AtomicU64,
 }
 
 impl SwapMetrics {
@@ -139,7 +223,12 @@ impl SwapMetrics {
             dlq_sent: RwLock::new(HashMap::new()),
             txs_processed: AtomicU64::new(0),
             swaps_detected: AtomicU64::new(0),
+            swaps_below_confidence: AtomicU64::new(0),
             publish_errors: AtomicU64::new(0),
+            parse_timings: RwLock::new(HashMap::new()),
+            last_block_slot: AtomicU64::new(0),
+            last_block_swaps: AtomicU64::new(0),
+            last_block_txs: AtomicU64::new(0),
         }
     }
 
@@ -230,16 +319,248 @@ impl SwapMetrics {
         self.swaps_detected.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Record a swap dropped below the minimum confidence
+    pub fn record_swap_below_confidence(&self) {
+        self.swaps_below_confidence.fetch_add(1, Ordering::Relaxed);
+    }
+
     /// Record a publish error
     pub fn record_publish_error(&self) {
         self.publish_errors.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Record the swap and tx counts of a completed block
+    pub fn record_block_counts(&self, counts: &BlockSwapCounts) {
+        self.last_block_slot.store(counts.slot, Ordering::Relaxed);
+        self.last_block_swaps.store(counts.swap_count as u64, Ordering::Relaxed);
+        self.last_block_txs.store(counts.tx_count as u64, Ordering::Relaxed);
+    }
+
+    /// Record how long one venue's `parse_*` call took
+    pub fn record_parse_duration(&self, venue: &str, elapsed: Duration) {
+        {
+            let map = self.parse_timings.read().unwrap();
+            if let Some(timing) = map.get(venue) {
+                timing.record(elapsed);
+                return;
+            }
+        }
+
+        let mut map = self.parse_timings.write().unwrap();
+        map.entry(venue.to_string())
+            .or_insert_with(ParseTiming::new)
+            .record(elapsed);
+    }
+
+    /// Parse time so far for a venue (`None` until first recorded)
+    pub fn parse_timing(&self, venue: &str) -> Option<ParseTimingSnapshot> {
+        let map = self.parse_timings.read().unwrap();
+        map.get(venue).and_then(ParseTiming::snapshot)
+    }
+
+    /// Parse timings by venue, sorted by venue
+    fn parse_timings(&self) -> Vec<(String, ParseTimingSnapshot)> {
+        let map = self.parse_timings.read().unwrap();
+        let mut timings: Vec<_> = map
+            .iter()
+            .filter_map(|(venue, timing)| Some((venue.clone(), timing.snapshot()?)))
+            .collect();
+        timings.sort_by(|a, b| a.0.cmp(&b.0));
+        timings
+    }
+
+    /// Per-venue parse times for logging, in microseconds (`min/avg/max`)
+    pub fn parse_timings_summary(&self) -> String {
+        self.parse_timings()
+            .iter()
+            .map(|(venue, t)| {
+                format!(
+                    "parse_us{{venue={}}}={}/{}/{} n={}",
+                    venue,
+                    t.min.as_micros(),
+                    t.avg.as_micros(),
+                    t.max.as_micros(),
+                    t.count
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Per-venue parse times in the Prometheus text exposition format
+    pub fn parse_timings_prometheus(&self) -> String {
+        let timings = self.parse_timings();
+        let mut out = String::new();
+        let mut gauge = |stat: &str, help: &str, value: fn(&ParseTimingSnapshot) -> Duration| {
+            let name = format!("decoder_parse_duration_seconds_{stat}");
+            let _ = writeln!(out, "# HELP {name} {help} detector parse time per venue");
+            let _ = writeln!(out, "# TYPE {name} gauge");
+            for (venue, t) in &timings {
+                let secs = value(t).as_secs_f64();
+                let _ = writeln!(out, "{name}{{venue=\"{venue}\"}} {secs}");
+            }
+        };
+        gauge("min", "Fastest", |t| t.min);
+        gauge("avg", "Average", |t| t.avg);
+        gauge("max", "Slowest", |t| t.max);
+        let _ = writeln!(out, "# HELP decoder_parse_total Detector parse calls per venue");
+        let _ = writeln!(out, "# TYPE decoder_parse_total counter");
+        for (venue, t) in &timings {
+            let _ = writeln!(out, "decoder_parse_total{{venue=\"{venue}\"}} {}", t.count);
+        }
+        out
+    }
+
+    /// All counters, plus parse timings, in the Prometheus text exposition
+    /// format; series are sorted by label so scrapes are stable
+    pub fn prometheus(&self) -> String {
+        fn header(out: &mut String, name: &str, help: &str) {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} counter");
+        }
+
+        let mut out = String::new();
+
+        for (name, help, value) in [
+            (
+                "decoder_txs_processed_total",
+                "Transactions processed",
+                self.get_txs_processed(),
+            ),
+            (
+                "decoder_swaps_detected_total",
+                "Swaps detected, before filtering",
+                self.get_swaps_detected(),
+            ),
+            (
+                "decoder_swaps_below_confidence_total",
+                "Swaps dropped below SWAPS_MIN_CONFIDENCE",
+                self.get_swaps_below_confidence(),
+            ),
+            (
+                "decoder_v0_alt_tx_seen_total",
+                "v0 transactions with address lookup tables",
+                self.get_v0_alt_tx_seen(),
+            ),
+            (
+                "decoder_publish_errors_total",
+                "Sink publish errors",
+                self.get_publish_errors(),
+            ),
+        ] {
+            header(&mut out, name, help);
+            let _ = writeln!(out, "{name} {value}");
+        }
+
+        header(
+            &mut out,
+            "decoder_swaps_emitted_total",
+            "Swaps emitted by venue and confidence bucket",
+        );
+        let mut emitted: Vec<_> = {
+            let map = self.swaps_emitted.read().unwrap();
+            map.iter()
+                .map(|((venue, bucket), c)| {
+                    (venue.clone(), bucket.as_str(), c.load(Ordering::Relaxed))
+                })
+                .collect()
+        };
+        emitted.sort();
+        for (venue, confidence, count) in emitted {
+            let _ = writeln!(
+                out,
+                "decoder_swaps_emitted_total{{venue=\"{venue}\",confidence=\"{confidence}\"}} {count}"
+            );
+        }
+
+        header(
+            &mut out,
+            "decoder_parse_fail_total",
+            "Detector parse failures by venue and reason",
+        );
+        let mut parse_fails: Vec<_> = {
+            let map = self.parse_fails.read().unwrap();
+            map.iter()
+                .map(|((venue, reason), c)| {
+                    (venue.clone(), reason.as_str(), c.load(Ordering::Relaxed))
+                })
+                .collect()
+        };
+        parse_fails.sort();
+        for (venue, reason, count) in parse_fails {
+            let _ = writeln!(
+                out,
+                "decoder_parse_fail_total{{venue=\"{venue}\",reason=\"{reason}\"}} {count}"
+            );
+        }
+
+        header(&mut out, "decoder_gate_fail_total", "Program gate misses by venue");
+        let mut gate_fails: Vec<_> = {
+            let map = self.gate_fails.read().unwrap();
+            map.iter()
+                .map(|(venue, c)| (venue.clone(), c.load(Ordering::Relaxed)))
+                .collect()
+        };
+        gate_fails.sort();
+        for (venue, count) in gate_fails {
+            let _ = writeln!(out, "decoder_gate_fail_total{{venue=\"{venue}\"}} {count}");
+        }
+
+        header(&mut out, "decoder_dlq_sent_total", "DLQ entries sent by reason");
+        let mut dlq_sent: Vec<_> = {
+            let map = self.dlq_sent.read().unwrap();
+            map.iter()
+                .map(|(reason, c)| (reason.as_str(), c.load(Ordering::Relaxed)))
+                .collect()
+        };
+        dlq_sent.sort();
+        for (reason, count) in dlq_sent {
+            let _ = writeln!(out, "decoder_dlq_sent_total{{reason=\"{reason}\"}} {count}");
+        }
+
+        // Gauges of the last completed block; absent until one was counted
+        let last_block_slot = self.last_block_slot.load(Ordering::Relaxed);
+        if last_block_slot > 0 {
+            for (name, help, value) in [
+                ("decoder_last_block_slot", "Last completed slot", last_block_slot),
+                (
+                    "decoder_last_block_swaps",
+                    "Swaps detected in the last completed slot",
+                    self.last_block_swaps.load(Ordering::Relaxed),
+                ),
+                (
+                    "decoder_last_block_txs",
+                    "Transactions decoded for the last completed slot",
+                    self.last_block_txs.load(Ordering::Relaxed),
+                ),
+            ] {
+                let _ = writeln!(out, "# HELP {name} {help}");
+                let _ = writeln!(out, "# TYPE {name} gauge");
+                let _ = writeln!(out, "{name} {value}");
+            }
+        }
+
+        out.push_str(&self.parse_timings_prometheus());
+        out
+    }
+
     /// Get total v0+ALT transactions seen
     pub fn get_v0_alt_tx_seen(&self) -> u64 {
         self.v0_alt_tx_seen.load(Ordering::Relaxed)
     }
 
+    /// Get total swaps emitted, all venues and buckets
+    pub fn get_swaps_emitted(&self) -> u64 {
+        let map = self.swaps_emitted.read().unwrap();
+        map.values().map(|c| c.load(Ordering::Relaxed)).sum()
+    }
+
+    /// Get total DLQ sends, all reasons
+    pub fn get_dlq_sent(&self) -> u64 {
+        let map = self.dlq_sent.read().unwrap();
+        map.values().map(|c| c.load(Ordering::Relaxed)).sum()
+    }
+
     /// Get total transactions processed
     pub fn get_txs_processed(&self) -> u64 {
         self.txs_processed.load(Ordering::Relaxed)
@@ -250,6 +571,11 @@ impl SwapMetrics {
         self.swaps_detected.load(Ordering::Relaxed)
     }
 
+    /// Get total swaps dropped below the minimum confidence
+    pub fn get_swaps_below_confidence(&self) -> u64 {
+        self.swaps_below_confidence.load(Ordering::Relaxed)
+    }
+
     /// Get total publish errors
     pub fn get_publish_errors(&self) -> u64 {
         self.publish_errors.load(Ordering::Relaxed)
@@ -321,6 +647,11 @@ impl SwapMetrics {
             }
         }
 
+        let timings = self.parse_timings_summary();
+        if !timings.is_empty() {
+            lines.push(timings);
+        }
+
         lines.join(" ")
     }
 }
@@ -382,9 +713,65 @@ mod tests {
 
         assert_eq!(metrics.get_v0_alt_tx_seen(), 1);
         assert_eq!(metrics.get_txs_processed(), 1);
+        assert_eq!(metrics.get_swaps_emitted(), 3);
+        assert_eq!(metrics.get_dlq_sent(), 1);
 
         let summary = metrics.summary();
         assert!(summary.contains("txs_processed=1"));
         assert!(summary.contains("v0_alt_seen=1"));
     }
+
+    #[test]
+    fn test_parse_timing_aggregation() {
+        let metrics = SwapMetrics::new();
+        assert_eq!(metrics.parse_timing("raydium"), None);
+
+        for us in [300, 100, 200] {
+            metrics.record_parse_duration("raydium", Duration::from_micros(us));
+        }
+        metrics.record_parse_duration("phoenix", Duration::from_micros(50));
+
+        assert_eq!(
+            metrics.parse_timing("raydium"),
+            Some(ParseTimingSnapshot {
+                count: 3,
+                min: Duration::from_micros(100),
+                avg: Duration::from_micros(200),
+                max: Duration::from_micros(300),
+            })
+        );
+        assert!(metrics.summary().contains("parse_us{venue=raydium}=100/200/300 n=3"));
+
+        let prom = metrics.parse_timings_prometheus();
+        assert!(prom.contains("decoder_parse_duration_seconds_max{venue=\"raydium\"} 0.0003"));
+        assert!(prom.contains("decoder_parse_total{venue=\"phoenix\"} 1"));
+    }
+
+    #[test]
+    fn test_prometheus_renders_all_series() {
+        let metrics = SwapMetrics::new();
+        metrics.record_swap_emitted("raydium", 85);
+        metrics.record_swap_emitted("raydium", 90);
+        metrics.record_swap_emitted("orca", 40);
+        metrics.record_parse_fail("phoenix", ParseFailReason::InvalidAmounts);
+        metrics.record_gate_fail("orca");
+        metrics.record_dlq_sent(DlqReason::RpcFetchFailed);
+        metrics.record_tx_processed();
+        metrics.record_swap_below_confidence();
+
+        let prom = metrics.prometheus();
+        assert!(prom.contains("# TYPE decoder_swaps_emitted_total counter\n"));
+        assert!(prom.contains("decoder_txs_processed_total 1\n"));
+        assert!(prom.contains("decoder_publish_errors_total 0\n"));
+        assert!(prom.contains("decoder_swaps_below_confidence_total 1\n"));
+        assert!(prom.contains(
+            "decoder_swaps_emitted_total{venue=\"orca\",confidence=\"low\"} 1\n\
+             decoder_swaps_emitted_total{venue=\"raydium\",confidence=\"high\"} 2\n"
+        ));
+        assert!(prom.contains(
+            "decoder_parse_fail_total{venue=\"phoenix\",reason=\"invalid_amounts\"} 1\n"
+        ));
+        assert!(prom.contains("decoder_gate_fail_total{venue=\"orca\"} 1\n"));
+        assert!(prom.contains("decoder_dlq_sent_total{reason=\"rpc_fetch_failed\"} 1\n"));
+    }
 }
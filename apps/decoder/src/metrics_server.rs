@@ -0,0 +1,63 @@
+//! Prometheus scrape endpoint (`METRICS_PORT`): `GET /metrics` renders the
+//! global `SwapMetrics`; any other path is a 404.
+
+use anyhow::Result;
+use decoder::metrics::metrics;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use log::{info, warn};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+
+/// Content type of the Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+fn respond(req: &Request<Incoming>) -> Response<Full<Bytes>> {
+    let (status, body) = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => (StatusCode::OK, metrics().prometheus()),
+        _ => (StatusCode::NOT_FOUND, "not found\n".to_string()),
+    };
+    Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, CONTENT_TYPE)
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response parts")
+}
+
+/// Bind `0.0.0.0:port` and serve scrapes in the background until the process exits.
+///
+/// Binding errors are returned so a taken port fails startup; per-connection
+/// errors are only logged.
+pub async fn serve(port: u16) -> Result<()> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let listener = TcpListener::bind(addr).await?;
+    info!("metrics endpoint listening on http://{addr}/metrics");
+
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("metrics endpoint accept failed: {e}");
+                    continue;
+                }
+            };
+            tokio::spawn(async move {
+                let service =
+                    service_fn(|req| async move { Ok::<_, Infallible>(respond(&req)) });
+                if let Err(e) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    warn!("metrics endpoint connection error: {e}");
+                }
+            });
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,305 @@
+//! Mint metadata (decimals, symbol) for swap enrichment.
+//!
+//! `MINT_METADATA` seeds a static `mint:decimals:symbol` table; with
+//! `MINT_METADATA_FETCH` the other mints are looked up once via
+//! `getAccountInfo` in the background and kept in a bounded LRU cache with a
+//! TTL. Enrichment only reads what is already known: a mint still being fetched
+//! (or whose fetch failed) leaves the swap's symbol/ui fields `None`, so
+//! emission never waits on the RPC.
+
+use anyhow::{Result, anyhow};
+use schema::{DexSwapV1, NATIVE_SOL_MINT, WSOL_MINT};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Background mint fetches allowed at once, their whole RPC budget (lookups skip
+/// the tx fetches' concurrency and retries); further misses wait for a later swap
+const MAX_IN_FLIGHT: usize = 16;
+
+/// Metadata of one mint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MintInfo {
+    pub decimals: u8,
+    pub symbol: Option<String>,
+}
+
+/// Parse `mint:decimals:symbol` entries separated by commas (symbol may be empty)
+pub fn parse_mint_metadata(spec: &str) -> Result<HashMap<String, MintInfo>> {
+    let mut mints = HashMap::new();
+    for entry in spec.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        let parts: Vec<&str> = entry.split(':').collect();
+        let [mint, decimals, symbol] = parts[..] else {
+            return Err(anyhow!(
+                "mint entry must be mint:decimals:symbol, got {}",
+                entry
+            ));
+        };
+        let decimals = decimals
+            .parse()
+            .map_err(|_| anyhow!("invalid decimals in mint entry {}", entry))?;
+        let symbol = (!symbol.is_empty()).then(|| symbol.to_string());
+        mints.insert(mint.to_string(), MintInfo { decimals, symbol });
+    }
+    Ok(mints)
+}
+
+/// Mint metadata from a jsonParsed `getAccountInfo` result: decimals, and the
+/// symbol of a Token-2022 `tokenMetadata` extension (Metaplex metadata lives in
+/// a separate account and is not read). `None` if the account is not a mint.
+pub fn parse_mint_account(result: &Value) -> Option<MintInfo> {
+    let parsed = result.pointer("/value/data/parsed")?;
+    if parsed.get("type").and_then(|t| t.as_str()) != Some("mint") {
+        return None;
+    }
+    let info = parsed.get("info")?;
+    let decimals = u8::try_from(info.get("decimals")?.as_u64()?).ok()?;
+    let symbol = info
+        .get("extensions")
+        .and_then(|e| e.as_array())
+        .into_iter()
+        .flatten()
+        .find(|e| e.get("extension").and_then(|x| x.as_str()) == Some("tokenMetadata"))
+        .and_then(|e| e.pointer("/state/symbol"))
+        .and_then(|s| s.as_str())
+        .filter(|s| !s.is_empty())
+        .map(str::to_string);
+    Some(MintInfo { decimals, symbol })
+}
+
+struct CacheEntry {
+    /// `None`: the fetch failed or the account is not a mint (retried after the TTL)
+    info: Option<MintInfo>,
+    fetched_at: Instant,
+    last_used: u64,
+}
+
+/// Fetched mints, least recently used evicted first, entries expire after `ttl`
+struct MintCache {
+    capacity: usize,
+    ttl: Duration,
+    entries: HashMap<String, CacheEntry>,
+    /// `last_used` tick -> mint, oldest first
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+    in_flight: HashSet<String>,
+}
+
+impl MintCache {
+    /// Cached entry for `mint` (`Some(None)`: known to have no metadata)
+    fn get(&mut self, mint: &str, now: Instant) -> Option<Option<MintInfo>> {
+        let entry = self.entries.get_mut(mint)?;
+        if now.duration_since(entry.fetched_at) >= self.ttl {
+            self.recency.remove(&entry.last_used);
+            self.entries.remove(mint);
+            return None;
+        }
+        self.tick += 1;
+        self.recency.remove(&entry.last_used);
+        self.recency.insert(self.tick, mint.to_string());
+        entry.last_used = self.tick;
+        Some(entry.info.clone())
+    }
+
+    fn insert(&mut self, mint: &str, info: Option<MintInfo>, now: Instant) {
+        if let Some(old) = self.entries.remove(mint) {
+            self.recency.remove(&old.last_used);
+        }
+        while self.entries.len() >= self.capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.tick += 1;
+        self.recency.insert(self.tick, mint.to_string());
+        self.entries.insert(
+            mint.to_string(),
+            CacheEntry {
+                info,
+                fetched_at: now,
+                last_used: self.tick,
+            },
+        );
+    }
+}
+
+/// Static mint table plus, when fetching is on, the cache of fetched mints
+pub struct MintRegistry {
+    static_mints: HashMap<String, MintInfo>,
+    fetch: bool,
+    cache: Mutex<MintCache>,
+}
+
+impl MintRegistry {
+    /// `capacity` fetched mints at most (0 disables fetching), each kept for `ttl`.
+    /// Native SOL and WSOL are always known.
+    pub fn new(
+        mut static_mints: HashMap<String, MintInfo>,
+        fetch: bool,
+        capacity: usize,
+        ttl: Duration,
+    ) -> Self {
+        for mint in [NATIVE_SOL_MINT, WSOL_MINT] {
+            static_mints.entry(mint.to_string()).or_insert(MintInfo {
+                decimals: 9,
+                symbol: Some("SOL".to_string()),
+            });
+        }
+        Self {
+            static_mints,
+            fetch: fetch && capacity > 0,
+            cache: Mutex::new(MintCache {
+                capacity,
+                ttl,
+                entries: HashMap::new(),
+                recency: BTreeMap::new(),
+                tick: 0,
+                in_flight: HashSet::new(),
+            }),
+        }
+    }
+
+    /// Metadata known for `mint` right now, without fetching
+    pub fn lookup(&self, mint: &str, now: Instant) -> Option<MintInfo> {
+        if let Some(info) = self.static_mints.get(mint) {
+            return Some(info.clone());
+        }
+        if !self.fetch {
+            return None;
+        }
+        self.cache
+            .lock()
+            .expect("mint cache lock")
+            .get(mint, now)
+            .flatten()
+    }
+
+    /// Fill the swap's symbol and ui amount fields from known metadata.
+    ///
+    /// Returns the mints the caller should fetch (then report with
+    /// `finish_fetch`): unknown, not already in flight, within `MAX_IN_FLIGHT`.
+    pub fn enrich(&self, swap: &mut DexSwapV1, now: Instant) -> Vec<String> {
+        let mut to_fetch = Vec::new();
+        let mut resolve = |mint: &str| {
+            let info = self.lookup(mint, now);
+            if info.is_none() && self.claim_fetch(mint, now) {
+                to_fetch.push(mint.to_string());
+            }
+            info
+        };
+        if let Some(info) = resolve(&swap.in_mint) {
+            swap.in_ui_amount = swap.in_amount.to_ui_string(info.decimals);
+            swap.in_symbol = info.symbol;
+        }
+        if let Some(info) = resolve(&swap.out_mint) {
+            swap.out_ui_amount = swap.out_amount.to_ui_string(info.decimals);
+            swap.out_symbol = info.symbol;
+        }
+        to_fetch
+    }
+
+    /// Mark `mint` as being fetched; false if fetching is off, it is cached
+    /// (even as a failure), already in flight, or too many fetches are
+    fn claim_fetch(&self, mint: &str, now: Instant) -> bool {
+        if !self.fetch || self.static_mints.contains_key(mint) {
+            return false;
+        }
+        let mut cache = self.cache.lock().expect("mint cache lock");
+        if cache.get(mint, now).is_some()
+            || cache.in_flight.contains(mint)
+            || cache.in_flight.len() >= MAX_IN_FLIGHT
+        {
+            return false;
+        }
+        cache.in_flight.insert(mint.to_string())
+    }
+
+    /// Record the outcome of a fetch claimed by `enrich` (`None` on failure)
+    pub fn finish_fetch(&self, mint: &str, info: Option<MintInfo>, now: Instant) {
+        let mut cache = self.cache.lock().expect("mint cache lock");
+        cache.in_flight.remove(mint);
+        cache.insert(mint, info, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::DexSwapV1Builder;
+    use serde_json::json;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const BONK: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+
+    fn swap() -> DexSwapV1 {
+        DexSwapV1Builder::new()
+            .signature("sig_mints")
+            .trader("trader")
+            .in_token(WSOL_MINT, 1_500_000_000u128)
+            .out_token(USDC, 210_250_000u128)
+            .build()
+    }
+
+    #[test]
+    fn test_enrich_from_static_table_and_fetches() {
+        let statics = parse_mint_metadata(&format!("{USDC}:6:USDC")).unwrap();
+        let registry = MintRegistry::new(statics, true, 1, Duration::from_secs(60));
+        let now = Instant::now();
+
+        let mut s = swap();
+        assert!(registry.enrich(&mut s, now).is_empty());
+        assert_eq!(s.in_symbol.as_deref(), Some("SOL"));
+        assert_eq!(s.in_ui_amount.as_deref(), Some("1.5"));
+        assert_eq!(s.out_symbol.as_deref(), Some("USDC"));
+        assert_eq!(s.out_ui_amount.as_deref(), Some("210.25"));
+
+        // An unknown mint is fetched once, left empty meanwhile
+        let mut s = swap();
+        s.out_mint = BONK.to_string();
+        assert_eq!(registry.enrich(&mut s, now), vec![BONK.to_string()]);
+        assert!(s.out_symbol.is_none() && s.out_ui_amount.is_none());
+        assert!(registry.enrich(&mut s.clone(), now).is_empty());
+
+        let bonk = MintInfo {
+            decimals: 5,
+            symbol: None,
+        };
+        registry.finish_fetch(BONK, Some(bonk.clone()), now);
+        registry.enrich(&mut s, now);
+        assert_eq!(s.out_ui_amount.as_deref(), Some("2102.5"));
+
+        // Entries expire after the TTL; the capacity bounds the cache
+        let later = now + Duration::from_secs(61);
+        assert_eq!(registry.lookup(BONK, later), None);
+        registry.finish_fetch(BONK, Some(bonk.clone()), now);
+        registry.finish_fetch("OtherMint111", None, now);
+        assert_eq!(registry.lookup(BONK, now), None);
+    }
+
+    #[test]
+    fn test_parse_mint_account() {
+        let token_2022 = json!({"value": {"data": {"parsed": {"type": "mint", "info": {
+            "decimals": 6,
+            "extensions": [
+                {"extension": "metadataPointer", "state": {}},
+                {"extension": "tokenMetadata", "state": {"symbol": "PYUSD", "name": "PayPal USD"}}
+            ]
+        }}}}});
+        assert_eq!(
+            parse_mint_account(&token_2022),
+            Some(MintInfo {
+                decimals: 6,
+                symbol: Some("PYUSD".to_string())
+            })
+        );
+
+        let spl = json!({"value": {"data": {"parsed": {"type": "mint", "info": {"decimals": 9}}}}});
+        assert_eq!(parse_mint_account(&spl).unwrap().symbol, None);
+
+        let token_account = json!({"value": {"data": {"parsed": {"type": "account", "info": {}}}}});
+        assert_eq!(parse_mint_account(&token_account), None);
+        assert_eq!(parse_mint_account(&json!({"value": null})), None);
+    }
+}
@@ -0,0 +1,176 @@
+//! USD notional enrichment from a static price table, and the minimum-notional
+//! swap filter built on it.
+//!
+//! Prices come from `SWAPS_PRICES` as `mint:decimals:usd` entries. A swap's
+//! notional is valued on its input side when that mint is priced, otherwise on
+//! its output side.
+
+use anyhow::{Result, anyhow};
+use schema::{DexSwapV1, NATIVE_SOL_MINT, SwapEvent, WSOL_MINT};
+use std::collections::HashMap;
+
+/// USD price of one whole token
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenPrice {
+    pub decimals: u8,
+    pub usd: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PriceTable {
+    prices: HashMap<String, TokenPrice>,
+}
+
+impl PriceTable {
+    /// Parse `mint:decimals:usd` entries separated by commas
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut prices = HashMap::new();
+        for entry in spec.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let parts: Vec<&str> = entry.split(':').collect();
+            let [mint, decimals, usd] = parts[..] else {
+                return Err(anyhow!(
+                    "price entry must be mint:decimals:usd, got {}",
+                    entry
+                ));
+            };
+            let decimals = decimals
+                .parse()
+                .map_err(|_| anyhow!("invalid decimals in price entry {}", entry))?;
+            let usd = usd
+                .parse()
+                .map_err(|_| anyhow!("invalid usd price in price entry {}", entry))?;
+            prices.insert(mint.to_string(), TokenPrice { decimals, usd });
+        }
+        Ok(Self { prices })
+    }
+
+    pub fn len(&self) -> usize {
+        self.prices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prices.is_empty()
+    }
+
+    /// USD value of `amount` base units of `mint`, if priced
+    pub fn value_usd(&self, mint: &str, amount: u128) -> Option<f64> {
+        // Native SOL legs are priced as WSOL
+        let mint = if mint == NATIVE_SOL_MINT { WSOL_MINT } else { mint };
+        let p = self.prices.get(mint)?;
+        Some(amount as f64 / 10f64.powi(p.decimals as i32) * p.usd)
+    }
+
+    /// Swap notional, valued on the input side first
+    pub fn notional_usd(
+        &self,
+        in_mint: &str,
+        in_amount: Option<u128>,
+        out_mint: &str,
+        out_amount: Option<u128>,
+    ) -> Option<f64> {
+        in_amount
+            .and_then(|a| self.value_usd(in_mint, a))
+            .or_else(|| out_amount.and_then(|a| self.value_usd(out_mint, a)))
+    }
+}
+
+/// Drops swaps below `SWAPS_MIN_NOTIONAL_USD`
+#[derive(Debug, Clone)]
+pub struct NotionalFilter {
+    prices: PriceTable,
+    min_usd: f64,
+    drop_unknown: bool,
+}
+
+impl NotionalFilter {
+    pub fn new(prices: PriceTable, min_usd: f64, drop_unknown: bool) -> Self {
+        Self {
+            prices,
+            min_usd,
+            drop_unknown,
+        }
+    }
+
+    /// Filtering only applies with a threshold and a non-empty price table
+    pub fn enabled(&self) -> bool {
+        self.min_usd > 0.0 && !self.prices.is_empty()
+    }
+
+    fn admit_notional(&self, notional: Option<f64>) -> bool {
+        if !self.enabled() {
+            return true;
+        }
+        match notional {
+            Some(usd) => usd >= self.min_usd,
+            None => !self.drop_unknown,
+        }
+    }
+
+    pub fn admit(&self, swap: &DexSwapV1) -> bool {
+        self.admit_notional(self.prices.notional_usd(
+            &swap.in_mint,
+            Some(swap.in_amount.get()),
+            &swap.out_mint,
+            Some(swap.out_amount.get()),
+        ))
+    }
+
+    pub fn admit_legacy(&self, swap: &SwapEvent) -> bool {
+        self.admit_notional(self.prices.notional_usd(
+            &swap.in_mint,
+            swap.in_amount.parse().ok(),
+            &swap.out_mint,
+            swap.out_amount.parse().ok(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::DexSwapV1Builder;
+
+    const SOL: &str = "So11111111111111111111111111111111111111112";
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn swap(in_mint: &str, in_amount: u128, out_mint: &str, out_amount: u128) -> DexSwapV1 {
+        DexSwapV1Builder::new()
+            .chain("solana-mainnet")
+            .slot(250000000)
+            .signature("sig123")
+            .venue("raydium")
+            .trader("TraderWallet111")
+            .in_token(in_mint, in_amount)
+            .out_token(out_mint, out_amount)
+            .build()
+    }
+
+    #[test]
+    fn test_min_notional_threshold_with_known_prices() {
+        let prices = PriceTable::parse(&format!("{SOL}:9:100, {USDC}:6:1")).unwrap();
+        let filter = NotionalFilter::new(prices.clone(), 1.0, false);
+        assert!(filter.enabled());
+
+        // 0.5 SOL @ $100 = $50
+        assert!(filter.admit(&swap(SOL, 500_000_000, USDC, 50_000_000)));
+        // 0.005 USDC in: valued on the input side
+        assert!(!filter.admit(&swap(USDC, 5000, SOL, 50_000)));
+        // Unpriced input falls back to the output side: 0.00001 SOL = $0.001
+        assert!(!filter.admit(&swap("UnknownMint111", 123, SOL, 10_000)));
+
+        // Unknown notional passes through unless drop_unknown is set
+        let unknown = swap("UnknownMint111", 1, "OtherMint111", 1);
+        assert!(filter.admit(&unknown));
+        assert!(!NotionalFilter::new(prices.clone(), 1.0, true).admit(&unknown));
+
+        // Legacy events are valued the same way
+        let legacy = SwapEvent::from(&swap(USDC, 5000, SOL, 50_000));
+        assert!(!filter.admit_legacy(&legacy));
+
+        // Threshold 0 or empty table disables the filter
+        assert!(NotionalFilter::new(prices, 0.0, true).admit(&unknown));
+        assert!(NotionalFilter::new(PriceTable::default(), 1.0, true).admit(&unknown));
+
+        assert!(PriceTable::parse("bad_entry").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! Pool mint pairs learned from observed swaps.
+//!
+//! Confidently parsed swaps reveal each pool's mint pair. Later low-confidence
+//! swaps on the same pool (e.g. from the all-deltas fallback) are checked
+//! against it, and get `POOL_MINTS_MATCH` when their mints belong to the pool.
+
+use schema::{ConfidenceReasons, DexSwapV1, NATIVE_SOL_MINT, WSOL_MINT};
+use std::collections::{HashMap, VecDeque};
+
+/// What the cache did with a swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolMintsCheck {
+    /// High-confidence swap: its pool's mints were recorded
+    Learned,
+    /// Low-confidence swap consistent with the known pool mints (flag set)
+    Matched,
+    /// Low-confidence swap whose mints are not the known pool mints
+    Mismatch,
+    /// Nothing known about the pool (or no pool id / cache disabled)
+    Unknown,
+}
+
+/// Bounded `pool_id -> (mint_a, mint_b)` cache, oldest pool evicted first
+#[derive(Debug, Clone, Default)]
+pub struct PoolMintCache {
+    capacity: usize,
+    pairs: HashMap<String, (String, String)>,
+    order: VecDeque<String>,
+}
+
+impl PoolMintCache {
+    /// `capacity` pools at most; 0 disables the cache
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Learn from a high-confidence swap, or check a low-confidence one
+    pub fn apply(&mut self, swap: &mut DexSwapV1) -> PoolMintsCheck {
+        if self.capacity == 0 {
+            return PoolMintsCheck::Unknown;
+        }
+        let Some(pool_id) = swap.pool_id.as_deref() else {
+            return PoolMintsCheck::Unknown;
+        };
+        let mints = sorted_pair(&swap.in_mint, &swap.out_mint);
+
+        if swap.is_high_confidence() {
+            if !self.pairs.contains_key(pool_id) {
+                if self.order.len() >= self.capacity
+                    && let Some(oldest) = self.order.pop_front()
+                {
+                    self.pairs.remove(&oldest);
+                }
+                self.order.push_back(pool_id.to_string());
+                self.pairs.insert(pool_id.to_string(), mints);
+            }
+            return PoolMintsCheck::Learned;
+        }
+
+        match self.pairs.get(pool_id) {
+            Some(known) if *known == mints => {
+                swap.add_confidence_reason(ConfidenceReasons::POOL_MINTS_MATCH);
+                PoolMintsCheck::Matched
+            }
+            Some(_) => PoolMintsCheck::Mismatch,
+            None => PoolMintsCheck::Unknown,
+        }
+    }
+}
+
+fn sorted_pair(a: &str, b: &str) -> (String, String) {
+    // A pool holds WSOL, whether or not the swap reported it as native SOL
+    let pool_mint = |m| if m == NATIVE_SOL_MINT { WSOL_MINT } else { m };
+    let (a, b) = (pool_mint(a), pool_mint(b));
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::DexSwapV1Builder;
+
+    const SOL: &str = "So11111111111111111111111111111111111111112";
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn swap(pool: &str, in_mint: &str, out_mint: &str, confident: bool) -> DexSwapV1 {
+        let mut builder = DexSwapV1Builder::new()
+            .signature("sig_pool")
+            .venue("raydium")
+            .pool_id(Some(pool.to_string()))
+            .trader("TraderWallet111")
+            .in_token(in_mint, 1000u128)
+            .out_token(out_mint, 2000u128)
+            .explain_enabled(true)
+            .with_confidence_reason(ConfidenceReasons::PROGRAM_GATE)
+            .with_confidence_reason(ConfidenceReasons::POOL_ID_FROM_IX)
+            .with_confidence_reason(ConfidenceReasons::SINGLE_HOP)
+            .with_confidence_reason(ConfidenceReasons::TX_SUCCESS);
+        if confident {
+            builder.add_confidence_reason(ConfidenceReasons::TRADER_FROM_OWNER);
+            builder.add_confidence_reason(ConfidenceReasons::AMOUNTS_CONFIRMED);
+            builder.add_confidence_reason(ConfidenceReasons::VAULT_MATCH);
+        } else {
+            // All-deltas fallback: signer trader, amounts unconfirmed
+            builder.add_confidence_reason(ConfidenceReasons::TRADER_IS_SIGNER);
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_prior_confident_swap_repairs_ambiguous_one() {
+        let mut cache = PoolMintCache::new(2);
+
+        // Unknown pool: ambiguous swap left alone
+        let mut ambiguous = swap("PoolA", USDC, SOL, false);
+        assert!(!ambiguous.is_high_confidence());
+        assert_eq!(cache.apply(&mut ambiguous), PoolMintsCheck::Unknown);
+
+        let mut confident = swap("PoolA", SOL, USDC, true);
+        assert_eq!(cache.apply(&mut confident), PoolMintsCheck::Learned);
+
+        // Same pool, opposite direction: mints match, confidence repaired
+        let before = ambiguous.confidence;
+        assert_eq!(cache.apply(&mut ambiguous), PoolMintsCheck::Matched);
+        assert!(
+            ConfidenceReasons(ambiguous.confidence_reasons)
+                .has(ConfidenceReasons::POOL_MINTS_MATCH)
+        );
+        assert_eq!(ambiguous.confidence, before + 10);
+        assert!(ambiguous.is_high_confidence());
+        assert!(
+            ambiguous
+                .explain
+                .as_deref()
+                .unwrap()
+                .contains("+pool_mints")
+        );
+
+        // A mint outside the pool is not vouched for
+        let mut wrong = swap("PoolA", USDC, "OtherMint111", false);
+        assert_eq!(cache.apply(&mut wrong), PoolMintsCheck::Mismatch);
+        assert!(
+            !ConfidenceReasons(wrong.confidence_reasons).has(ConfidenceReasons::POOL_MINTS_MATCH)
+        );
+
+        // Bounded: the oldest pool is evicted
+        cache.apply(&mut swap("PoolB", SOL, USDC, true));
+        cache.apply(&mut swap("PoolC", SOL, USDC, true));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(
+            cache.apply(&mut swap("PoolA", SOL, USDC, false)),
+            PoolMintsCheck::Unknown
+        );
+
+        // Capacity 0 disables learning
+        let mut disabled = PoolMintCache::new(0);
+        disabled.apply(&mut swap("PoolA", SOL, USDC, true));
+        assert!(disabled.is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+//! Retry with capped exponential backoff, shared by the decoder and backfill
+//! RPC clients.
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::time::Duration;
+
+use tokio::time::sleep;
+
+/// How often and how long to back off between attempts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts, the first one included
+    pub max_attempts: u32,
+    /// Delay after the first failed attempt; doubles after each further one
+    pub base_backoff: Duration,
+    /// Cap for every delay, jitter included
+    pub max_backoff: Duration,
+    /// Random extra delay of up to this many ms, so clients don't retry in lockstep
+    pub jitter_ms: u64,
+}
+
+impl RetryPolicy {
+    /// Delay before retrying after failed attempt `attempt` (1-based)
+    pub fn next_delay(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(31);
+        let backoff = self.base_backoff.saturating_mul(1 << doublings);
+        let jitter = match self.jitter_ms {
+            0 => 0,
+            max => RandomState::new().hash_one(attempt) % (max + 1),
+        };
+        (backoff + Duration::from_millis(jitter)).min(self.max_backoff)
+    }
+}
+
+/// Why one attempt failed (`E`: the caller's error, e.g. `RpcError`)
+#[derive(Debug)]
+pub enum RetryError<E = anyhow::Error> {
+    /// Worth another attempt (429, 5xx, transport errors)
+    Transient(E),
+    /// Worth another attempt after exactly this delay instead of the backoff
+    /// (e.g. a 429's `Retry-After`)
+    RetryAfter(E, Duration),
+    /// Returned as is, without further attempts
+    Permanent(E),
+}
+
+/// Run `op` (passed the 1-based attempt number) until it succeeds, fails
+/// permanently or `policy.max_attempts` is used up; the last error is returned.
+///
+/// `on_retry` sees each transient failure that is retried, with the delay about
+/// to be slept, for logging and retry stats.
+pub async fn retry<T, E, F, Fut>(
+    policy: &RetryPolicy,
+    mut op: F,
+    mut on_retry: impl FnMut(u32, &E, Duration),
+) -> Result<T, E>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T, RetryError<E>>>,
+{
+    let mut attempt = 1;
+    loop {
+        let (e, delay) = match op(attempt).await {
+            Ok(value) => return Ok(value),
+            Err(RetryError::Permanent(e)) => return Err(e),
+            Err(RetryError::Transient(e)) => (e, policy.next_delay(attempt)),
+            Err(RetryError::RetryAfter(e, delay)) => (e, delay),
+        };
+        if attempt >= policy.max_attempts {
+            return Err(e);
+        }
+        on_retry(attempt, &e, delay);
+        sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn policy(max_backoff_ms: u64, jitter_ms: u64) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 6,
+            base_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_millis(max_backoff_ms),
+            jitter_ms,
+        }
+    }
+
+    #[test]
+    fn test_next_delay_doubles_up_to_cap() {
+        let p = policy(1500, 0);
+        let steps: Vec<_> = (1..=6).map(|a| p.next_delay(a).as_millis()).collect();
+        assert_eq!(steps, vec![250, 500, 1000, 1500, 1500, 1500]);
+
+        // A cap below the base backoff clamps immediately
+        assert_eq!(policy(100, 0).next_delay(1), Duration::from_millis(100));
+        // Huge attempt numbers saturate instead of overflowing
+        assert_eq!(p.next_delay(u32::MAX), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_next_delay_jitter_bounds() {
+        let p = policy(60_000, 200);
+        for attempt in 1..=6 {
+            let base = policy(60_000, 0).next_delay(attempt);
+            for _ in 0..50 {
+                let delay = p.next_delay(attempt);
+                assert!(delay >= base && delay <= base + Duration::from_millis(200));
+            }
+        }
+
+        // Jitter never pushes a delay past the cap
+        let capped = policy(300, 200);
+        assert!((0..50).all(|_| capped.next_delay(6) == Duration::from_millis(300)));
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_on_success_permanent_or_exhaustion() {
+        let p = RetryPolicy {
+            base_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            ..policy(0, 0)
+        };
+
+        let mut retried = vec![];
+        let out = retry(
+            &p,
+            |attempt| async move {
+                if attempt < 3 {
+                    Err(RetryError::Transient(anyhow!("busy")))
+                } else {
+                    Ok(attempt)
+                }
+            },
+            |attempt, _, delay| retried.push((attempt, delay.as_millis())),
+        )
+        .await;
+        assert_eq!(out.unwrap(), 3);
+        assert_eq!(retried, vec![(1, 1), (2, 2)]);
+
+        let mut calls = 0;
+        let out: anyhow::Result<()> = retry(
+            &p,
+            |_| {
+                calls += 1;
+                async { Err(RetryError::Permanent(anyhow!("bad request"))) }
+            },
+            |_, _, _| {},
+        )
+        .await;
+        assert_eq!(out.unwrap_err().to_string(), "bad request");
+        assert_eq!(calls, 1);
+
+        let mut calls = 0;
+        let out: anyhow::Result<()> = retry(
+            &p,
+            |_| {
+                calls += 1;
+                async { Err(RetryError::Transient(anyhow!("still busy"))) }
+            },
+            |_, _, _| {},
+        )
+        .await;
+        assert_eq!(out.unwrap_err().to_string(), "still busy");
+        assert_eq!(calls, p.max_attempts);
+    }
+}
@@ -0,0 +1,196 @@
+//! Per-signature retry budget of the consume loop.
+//!
+//! A failed getTransaction is re-fetched in place with a linear backoff, slept
+//! inline ([`retry_fetch`]), so the message is never passed over and while a
+//! signature is in retry nothing else on the partition is decoded.
+//! Besides the attempt limit, the total time one signature may block the loop
+//! is capped (`RETRY_MAX_BLOCK_MS`); once spent, the signature is given up
+//! (DLQ + commit) even if attempts remain.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+/// What to do after a failed fetch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// Sleep this long, then retry
+    Retry { attempt: u32, delay: Duration },
+    /// Out of attempts or blocking time: DLQ and commit
+    GiveUp { attempts: u32 },
+}
+
+#[derive(Debug)]
+pub struct RetryBudget {
+    max_attempts: u32,
+    base_backoff: Duration,
+    max_block: Duration,
+    max_entries: usize,
+    /// Failed attempts and time slept so far, per signature
+    failures: HashMap<String, (u32, Duration)>,
+}
+
+impl RetryBudget {
+    pub fn new(
+        max_attempts: u32,
+        base_backoff: Duration,
+        max_block: Duration,
+        max_entries: usize,
+    ) -> Self {
+        Self {
+            max_attempts,
+            base_backoff,
+            max_block,
+            max_entries,
+            failures: HashMap::new(),
+        }
+    }
+
+    /// Record a failed attempt for `signature` and decide whether to retry
+    pub fn on_failure(&mut self, signature: &str) -> RetryDecision {
+        // Guard against unbounded map growth
+        if self.failures.len() >= self.max_entries && !self.failures.contains_key(signature) {
+            log::warn!(
+                "failure_counts map exceeded {}, clearing old entries",
+                self.max_entries
+            );
+            self.failures.clear();
+        }
+
+        let (attempts, slept) = self.failures.entry(signature.to_string()).or_default();
+        *attempts += 1;
+        let attempt = *attempts;
+        let remaining = self.max_block.saturating_sub(*slept);
+        if attempt >= self.max_attempts || remaining.is_zero() {
+            self.failures.remove(signature);
+            return RetryDecision::GiveUp { attempts: attempt };
+        }
+
+        let delay = (self.base_backoff * attempt).min(remaining);
+        *slept += delay;
+        RetryDecision::Retry { attempt, delay }
+    }
+
+    /// Forget `signature` (fetched, or given up by the caller)
+    pub fn clear(&mut self, signature: &str) {
+        self.failures.remove(signature);
+    }
+
+    /// Signatures currently in retry
+    pub fn len(&self) -> usize {
+        self.failures.len()
+    }
+}
+
+/// Retry one message's fetch in place until it succeeds or `budget` gives up.
+///
+/// `first` is the result already in hand; each failure sleeps the budget's
+/// backoff and calls `refetch` again, so the message is never passed over
+/// while later offsets are committed. `on_failure` sees every failed attempt
+/// with the delay before the next one (`None` when giving up). Returns the
+/// value, or the attempts made and the last error.
+pub async fn retry_fetch<T, E, F, Fut>(
+    budget: &mut RetryBudget,
+    signature: &str,
+    first: Result<T, E>,
+    mut refetch: F,
+    mut on_failure: impl FnMut(u32, Option<Duration>, &E),
+) -> Result<T, (u32, E)>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut result = first;
+    loop {
+        let e = match result {
+            Ok(value) => {
+                budget.clear(signature);
+                return Ok(value);
+            }
+            Err(e) => e,
+        };
+        match budget.on_failure(signature) {
+            RetryDecision::Retry { attempt, delay } => {
+                on_failure(attempt, Some(delay), &e);
+                tokio::time::sleep(delay).await;
+                result = refetch().await;
+            }
+            RetryDecision::GiveUp { attempts } => {
+                on_failure(attempts, None, &e);
+                return Err((attempts, e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_transient_failure_is_refetched_not_skipped() {
+        let mut budget = RetryBudget::new(3, Duration::from_millis(1), Duration::from_secs(1), 10);
+
+        // Fails twice, then the RPC recovers: the same message is decoded
+        let mut responses = vec![Err("timeout"), Ok("tx")].into_iter();
+        let mut failures = Vec::new();
+        let fetched = retry_fetch(
+            &mut budget,
+            "SigFlaky",
+            Err("timeout"),
+            || std::future::ready(responses.next().unwrap()),
+            |attempt, delay, _: &&str| failures.push((attempt, delay.is_some())),
+        )
+        .await;
+        assert_eq!(fetched, Ok("tx"));
+        assert_eq!(failures, vec![(1, true), (2, true)]);
+        assert_eq!(budget.len(), 0);
+
+        // Still failing on the last attempt: given up with the last error
+        let fetched = retry_fetch(
+            &mut budget,
+            "SigDown",
+            Err::<&str, _>("timeout"),
+            || std::future::ready(Err("still down")),
+            |_, _, _| {},
+        )
+        .await;
+        assert_eq!(fetched, Err((3, "still down")));
+    }
+
+    #[test]
+    fn test_repeated_failures_block_at_most_max_block() {
+        // Generous attempts: the blocking cap is what ends the retries
+        let mut budget =
+            RetryBudget::new(100, Duration::from_millis(200), Duration::from_millis(1000), 10);
+        let mut blocked = Duration::ZERO;
+        let attempts = loop {
+            match budget.on_failure("SigDown") {
+                RetryDecision::Retry { delay, .. } => blocked += delay,
+                RetryDecision::GiveUp { attempts } => break attempts,
+            }
+        };
+        // 200 + 400 + 400 (capped), then given up
+        assert_eq!(blocked, Duration::from_millis(1000));
+        assert_eq!(attempts, 4);
+        assert_eq!(budget.len(), 0);
+
+        // Attempt limit still applies below the cap
+        let mut budget =
+            RetryBudget::new(3, Duration::from_millis(200), Duration::from_secs(60), 10);
+        assert_eq!(
+            budget.on_failure("Sig"),
+            RetryDecision::Retry {
+                attempt: 1,
+                delay: Duration::from_millis(200)
+            }
+        );
+        budget.on_failure("Sig");
+        assert_eq!(budget.on_failure("Sig"), RetryDecision::GiveUp { attempts: 3 });
+
+        // Success resets the budget
+        budget.on_failure("Sig");
+        budget.clear("Sig");
+        assert_eq!(budget.len(), 0);
+    }
+}
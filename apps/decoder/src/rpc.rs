@@ -1,21 +1,166 @@
-use anyhow::{Result, anyhow};
+use anyhow::Result;
+use decoder::retry::{RetryError, RetryPolicy, retry};
+use decoder::mint_registry::{MintInfo, parse_mint_account};
+use decoder::rpc_error::RpcError;
 use log::warn;
 use reqwest::Client;
 use serde_json::{Value, json};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tokio::sync::Semaphore;
+use tokio::sync::{OnceCell, Semaphore};
 use tokio::time::{Instant, sleep};
 
+/// Retry counters shared by all clones of an RpcClient
+#[derive(Debug, Default)]
+pub struct RpcRetryStats {
+    /// Number of retried attempts (any cause)
+    pub retries: AtomicU64,
+    /// Retries caused by HTTP 429
+    pub retries_429: AtomicU64,
+    /// Total time spent sleeping in backoff (ms)
+    pub backoff_ms_total: AtomicU64,
+}
+
+/// Method used to fetch transactions (`RPC_GET_TX_METHOD`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GetTxMethod {
+    GetTransaction,
+    /// Deprecated name still required by some older archival providers
+    GetConfirmedTransaction,
+}
+
+impl GetTxMethod {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "gettransaction" => Some(Self::GetTransaction),
+            "getconfirmedtransaction" => Some(Self::GetConfirmedTransaction),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::GetTransaction => "getTransaction",
+            Self::GetConfirmedTransaction => "getConfirmedTransaction",
+        }
+    }
+}
+
+/// Commitment level sent with transaction fetches (`RPC_COMMITMENT`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Commitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "processed" => Some(Self::Processed),
+            "confirmed" => Some(Self::Confirmed),
+            "finalized" => Some(Self::Finalized),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Processed => "processed",
+            Self::Confirmed => "confirmed",
+            Self::Finalized => "finalized",
+        }
+    }
+}
+
+/// Default cap for a 429's `Retry-After` (`RPC_MAX_RETRY_AFTER_MS`)
+pub const DEFAULT_MAX_RETRY_AFTER_MS: u64 = 30_000;
+
+/// Default per-request HTTP timeout (connect included)
+pub const DEFAULT_TIMEOUT_MS: u64 = 25_000;
+
+/// JSON-RPC 2.0 request object
+fn request_body(id: u64, method: &str, params: &Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": method,
+        "params": params
+    })
+}
+
+/// Split a JSON-RPC batch response into per-request results, correlated by `id`
+/// (request `i` is sent with id `i`; servers may answer in any order).
+///
+/// Errors if the response is not an array, i.e. the server does not support batching.
+pub fn parse_batch_response(
+    resp: &Value,
+    len: usize,
+) -> Result<Vec<Result<Value, RpcError>>, RpcError> {
+    let entries = resp
+        .as_array()
+        .ok_or_else(|| RpcError::Decode(format!("batch response is not an array: {resp}")))?;
+
+    let mut by_id: HashMap<u64, &Value> = HashMap::new();
+    for entry in entries {
+        if let Some(id) = entry.get("id").and_then(|v| v.as_u64()) {
+            by_id.insert(id, entry);
+        }
+    }
+
+    Ok((0..len as u64)
+        .map(|id| {
+            let entry = by_id
+                .get(&id)
+                .ok_or_else(|| RpcError::Decode(format!("batch response missing id {id}")))?;
+            if let Some(error) = entry.get("error") {
+                return Err(RpcError::rpc_level(error));
+            }
+            entry
+                .get("result")
+                .cloned()
+                .ok_or_else(|| RpcError::Decode("missing result field".into()))
+        })
+        .collect())
+}
+
+/// Transaction fetch result; the error is shared between single-flight callers
+pub type TxFetch = Result<Value, Arc<RpcError>>;
+
+/// One transaction fetch shared by every caller asking for its signature meanwhile
+type SharedFetch = Arc<OnceCell<TxFetch>>;
+
+fn http_client(timeout: Duration) -> Client {
+    Client::builder().timeout(timeout).build().expect("reqwest")
+}
+
 #[derive(Clone)]
 pub struct RpcClient {
     http: Client,
     primary_url: String,
     fallback_urls: Vec<String>,
+    /// Bounds in-flight HTTP requests (each attempt of a call, each batch)
     semaphore: Arc<Semaphore>,
     min_delay_ms: u64,
     max_tx_version: u8,
+    get_tx_method: GetTxMethod,
+    /// Omitted from the params when unset (node default)
+    commitment: Option<Commitment>,
+    retry_policy: RetryPolicy,
+    /// Cap for a 429's `Retry-After`, so a hostile header can't stall the consumer
+    max_retry_after: Duration,
     last_request: Arc<tokio::sync::Mutex<Instant>>,
+    stats: Arc<RpcRetryStats>,
+    /// Cleared the first time the primary rejects a batch request
+    batch_supported: Arc<AtomicBool>,
+    /// Transaction fetches in flight, by signature. A second fetch of the same
+    /// signature (e.g. a message redelivered while the first attempt sleeps in
+    /// backoff) waits for the first one's result instead of calling again.
+    /// Waiters hold no semaphore permit: only the leader's HTTP attempts take
+    /// one, so a coalesced fetch costs one RPC_CONCURRENCY slot, not one per caller
+    in_flight: Arc<Mutex<HashMap<String, SharedFetch>>>,
 }
 
 impl RpcClient {
@@ -25,148 +170,369 @@ impl RpcClient {
         concurrency: u32,
         min_delay_ms: u64,
         max_tx_version: u8,
+        get_tx_method: GetTxMethod,
+        max_backoff_ms: u64,
     ) -> Self {
-        let http = Client::builder()
-            .timeout(Duration::from_secs(25))
-            .build()
-            .expect("reqwest");
-
         Self {
-            http,
+            http: http_client(Duration::from_millis(DEFAULT_TIMEOUT_MS)),
             primary_url,
             fallback_urls,
             semaphore: Arc::new(Semaphore::new(concurrency as usize)),
             min_delay_ms,
             max_tx_version,
+            get_tx_method,
+            commitment: None,
+            retry_policy: RetryPolicy {
+                max_attempts: 6,
+                base_backoff: Duration::from_millis(250),
+                max_backoff: Duration::from_millis(max_backoff_ms),
+                jitter_ms: 0,
+            },
+            max_retry_after: Duration::from_millis(DEFAULT_MAX_RETRY_AFTER_MS),
             last_request: Arc::new(tokio::sync::Mutex::new(Instant::now())),
+            stats: Arc::new(RpcRetryStats::default()),
+            batch_supported: Arc::new(AtomicBool::new(true)),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
-    pub async fn get_transaction_json_parsed(&self, signature: &str) -> Result<Value> {
-        let params = json!([
-            signature,
-            {"encoding":"jsonParsed", "maxSupportedTransactionVersion": self.max_tx_version}
-        ]);
-        self.call("getTransaction", params).await
+    /// Cap for the delay a 429's `Retry-After` can ask for
+    pub fn with_max_retry_after_ms(mut self, ms: u64) -> Self {
+        self.max_retry_after = Duration::from_millis(ms);
+        self
     }
 
-    async fn call(&self, method: &str, params: Value) -> Result<Value> {
-        // Acquire semaphore permit to limit concurrency
-        let _permit = self.semaphore.acquire().await.expect("semaphore");
+    /// Commitment for transaction fetches, so e.g. only finalized data is decoded
+    pub fn with_commitment(mut self, commitment: Option<Commitment>) -> Self {
+        self.commitment = commitment;
+        self
+    }
+
+    /// Per-request HTTP timeout; a request that runs into it fails over to the
+    /// next URL like a connection error
+    pub fn with_timeout_ms(mut self, ms: u64) -> Self {
+        self.http = http_client(Duration::from_millis(ms));
+        self
+    }
+
+    pub fn retry_stats(&self) -> &RpcRetryStats {
+        &self.stats
+    }
+
+    /// Account for one retry and the backoff slept before it
+    fn record_backoff(&self, slept: Duration) {
+        self.stats.retries.fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .backoff_ms_total
+            .fetch_add(slept.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn get_transaction_params(&self, signature: &str) -> Value {
+        let mut config =
+            json!({"encoding":"jsonParsed", "maxSupportedTransactionVersion": self.max_tx_version});
+        if let Some(commitment) = self.commitment {
+            config["commitment"] = json!(commitment.as_str());
+        }
+        json!([signature, config])
+    }
+
+    /// Transaction fetch request with JSON-RPC id `id`
+    fn get_transaction_request(&self, id: u64, signature: &str) -> Value {
+        request_body(
+            id,
+            self.get_tx_method.as_str(),
+            &self.get_transaction_params(signature),
+        )
+    }
+
+    /// Fetch a transaction; overlapping fetches of one signature share a call
+    pub async fn get_transaction_json_parsed(&self, signature: &str) -> TxFetch {
+        let fetch = self
+            .in_flight
+            .lock()
+            .expect("in_flight lock")
+            .entry(signature.to_string())
+            .or_default()
+            .clone();
+
+        let result = fetch
+            .get_or_init(|| async {
+                self.call(
+                    self.get_tx_method.as_str(),
+                    self.get_transaction_params(signature),
+                )
+                .await
+                .map_err(Arc::new)
+            })
+            .await
+            .clone();
+
+        // Later fetches go to the RPC again (unless a newer one already took over)
+        let mut in_flight = self.in_flight.lock().expect("in_flight lock");
+        if in_flight.get(signature).is_some_and(|f| Arc::ptr_eq(f, &fetch)) {
+            in_flight.remove(signature);
+        }
+        drop(in_flight);
+
+        result
+    }
+
+    /// Statuses for up to 256 signatures (`null` entries: unknown to the cluster)
+    pub async fn get_signature_statuses(
+        &self,
+        signatures: &[String],
+    ) -> Result<Vec<Value>, RpcError> {
+        let params = json!([signatures, {"searchTransactionHistory": true}]);
+        let result = self.call("getSignatureStatuses", params).await?;
+        result
+            .get("value")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .ok_or_else(|| RpcError::Decode("getSignatureStatuses: missing value array".into()))
+    }
+
+    /// Most recent signatures (newest first) of transactions touching `address`
+    pub async fn get_signatures_for_address(
+        &self,
+        address: &str,
+        limit: usize,
+    ) -> Result<Vec<String>, RpcError> {
+        let params = json!([address, {"limit": limit}]);
+        let result = self.call("getSignaturesForAddress", params).await?;
+        let entries = result.as_array().ok_or_else(|| {
+            RpcError::Decode("getSignaturesForAddress: result is not an array".into())
+        })?;
+        Ok(entries
+            .iter()
+            .filter_map(|e| e.get("signature").and_then(|s| s.as_str()))
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Fetch several transactions with one JSON-RPC batch request.
+    ///
+    /// Results are in `signatures` order. Entries that failed inside the batch
+    /// are retried with single calls; if the server does not support batching,
+    /// every signature falls back to single calls and batching is disabled.
+    pub async fn get_transactions_json_parsed(&self, signatures: &[String]) -> Vec<TxFetch> {
+        let mut results = if signatures.len() > 1 && self.batch_supported.load(Ordering::Relaxed) {
+            match self.call_batch(signatures).await {
+                Ok(results) => results
+                    .into_iter()
+                    .map(|r| r.map_err(Arc::new))
+                    .collect(),
+                Err(e) => {
+                    warn!(
+                        "RPC batch {} failed, falling back to single calls: {e:?}",
+                        self.get_tx_method.as_str()
+                    );
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+        results.resize_with(signatures.len(), || {
+            Err(Arc::new(RpcError::Decode("not fetched".into())))
+        });
 
-        // Apply minimum delay between requests to reduce 429s
+        for (signature, result) in signatures.iter().zip(results.iter_mut()) {
+            if result.is_err() {
+                *result = self.get_transaction_json_parsed(signature).await;
+            }
+        }
+        results
+    }
+
+    /// One batch request to the primary URL (no retries; callers fall back to `call`)
+    async fn call_batch(
+        &self,
+        signatures: &[String],
+    ) -> Result<Vec<Result<Value, RpcError>>, RpcError> {
+        let _permit = self.semaphore.acquire().await.expect("semaphore");
         self.apply_rate_limit().await;
 
-        // Build all URLs to try: primary + fallbacks
-        let mut urls_to_try = vec![self.primary_url.clone()];
-        urls_to_try.extend(self.fallback_urls.clone());
+        let body: Vec<Value> = signatures
+            .iter()
+            .enumerate()
+            .map(|(id, signature)| self.get_transaction_request(id as u64, signature))
+            .collect();
+        let v = self.post_once(&json!(body)).await?;
+
+        match parse_batch_response(&v, signatures.len()) {
+            Ok(results) => Ok(results),
+            Err(e) => {
+                // A non-array reply means the endpoint rejects batches outright
+                self.batch_supported.store(false, Ordering::Relaxed);
+                Err(e)
+            }
+        }
+    }
 
-        let mut backoff = Duration::from_millis(250);
-        let max_attempts = 6;
+    /// One POST of `body` to the primary URL: no retries, URL failover, permit
+    /// or minimum delay (callers that want those take them first)
+    async fn post_once(&self, body: &Value) -> Result<Value, RpcError> {
+        let r = self
+            .http
+            .post(&self.primary_url)
+            .json(body)
+            .send()
+            .await
+            .map_err(RpcError::Transport)?;
+        let status = r.status();
+        if status.as_u16() == 429 {
+            return Err(RpcError::rate_limited(r.headers()));
+        }
+        if status.is_server_error() {
+            return Err(RpcError::ServerError(status.as_u16()));
+        }
+        r.json()
+            .await
+            .map_err(|e| RpcError::Decode(format!("{e:?}")))
+    }
 
-        for attempt in 1..=max_attempts {
-            // Rotate through URLs on retries
-            let url_index = (attempt - 1) % urls_to_try.len();
-            let url = &urls_to_try[url_index];
+    /// Addresses stored in an address lookup table account
+    pub async fn get_lookup_table(&self, table: &str) -> Result<Vec<String>, RpcError> {
+        let params = json!([table, {"encoding": "jsonParsed"}]);
+        let res = self.call("getAccountInfo", params).await?;
+        schema::parse_lookup_table_addresses(&res)
+            .ok_or_else(|| RpcError::Decode(format!("account {table} is not a lookup table")))
+    }
 
-            let body = json!({
-                "jsonrpc": "2.0",
-                "id": 1,
-                "method": method,
-                "params": params
-            });
+    /// Decimals and (Token-2022 metadata) symbol of a mint; `None` if the
+    /// account is not a mint.
+    ///
+    /// A single attempt outside the transaction fetches' permits, minimum delay
+    /// and retries, so enrichment never takes RPC budget from decoding; the mint
+    /// registry bounds these lookups and retries a failed mint after its TTL.
+    pub async fn get_mint_info(&self, mint: &str) -> Result<Option<MintInfo>, RpcError> {
+        let params = json!([mint, {"encoding": "jsonParsed"}]);
+        let body = request_body(1, "getAccountInfo", &params);
+        let v = self.post_once(&body).await?;
+        if let Some(error) = v.get("error") {
+            return Err(RpcError::rpc_level(error));
+        }
+        let res = v
+            .get("result")
+            .ok_or_else(|| RpcError::Decode("missing result field".into()))?;
+        Ok(parse_mint_account(res))
+    }
 
-            let resp = self.http.post(url).json(&body).send().await;
+    /// Fill `meta.loadedAddresses` for a v0 tx whose provider omitted it.
+    /// Returns Ok(false) if there was nothing to resolve.
+    pub async fn resolve_lookup_tables(&self, tx: &mut Value) -> Result<bool, RpcError> {
+        let keys = schema::unresolved_lookup_tables(tx);
+        if keys.is_empty() {
+            return Ok(false);
+        }
+
+        let mut tables = HashMap::new();
+        for key in keys {
+            let addresses = self.get_lookup_table(&key).await?;
+            tables.insert(key, addresses);
+        }
+
+        if !schema::apply_lookup_tables(tx, &tables) {
+            return Err(RpcError::Decode("lookup table indexes out of range".into()));
+        }
+        Ok(true)
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        // Build all URLs to try: primary + fallbacks
+        let mut urls_to_try = vec![self.primary_url.clone()];
+        urls_to_try.extend(self.fallback_urls.clone());
 
-            match resp {
-                Ok(r) => {
+        let policy = &self.retry_policy;
+        let body = request_body(1, method, &params);
+        retry(
+            policy,
+            |attempt| {
+                // Rotate through URLs on retries
+                let start = (attempt as usize - 1) % urls_to_try.len();
+                let urls = &urls_to_try;
+                let body = &body;
+                async move {
+                    // One permit per HTTP attempt: backoff sleeps between attempts
+                    // don't hold a slot, so RPC_CONCURRENCY bounds in-flight requests
+                    let _permit = self.semaphore.acquire().await.expect("semaphore");
+
+                    let r = self
+                        .send_to_reachable(urls, start, body)
+                        .await
+                        .map_err(RetryError::Transient)?;
                     let status = r.status();
 
-                    // Handle rate limiting specifically
+                    // Handle rate limiting specifically: wait as long as the
+                    // provider asks (capped), else the exponential backoff
                     if status.as_u16() == 429 {
-                        if attempt < max_attempts {
-                            warn!(
-                                "RPC 429 rate limit, backing off {}ms (attempt {}/{})",
-                                backoff.as_millis(),
-                                attempt,
-                                max_attempts
-                            );
-                            sleep(backoff).await;
-                            backoff = (backoff * 2).min(Duration::from_secs(8));
-                            continue;
+                        if attempt < policy.max_attempts {
+                            self.stats.retries_429.fetch_add(1, Ordering::Relaxed);
                         }
-                        return Err(anyhow!("RPC rate limited after {} attempts", max_attempts));
+                        let e = RpcError::rate_limited(r.headers());
+                        return Err(e.into_retry(self.max_retry_after));
                     }
 
                     // Handle 5xx server errors
                     if status.is_server_error() {
-                        if attempt < max_attempts {
-                            warn!(
-                                "RPC server error {}, retrying (attempt {}/{})",
-                                status, attempt, max_attempts
-                            );
-                            sleep(backoff).await;
-                            backoff = (backoff * 2).min(Duration::from_secs(5));
-                            continue;
-                        }
-                        return Err(anyhow!(
-                            "RPC server error after {} attempts: {}",
-                            max_attempts,
-                            status
-                        ));
+                        return Err(RetryError::Transient(RpcError::ServerError(status.as_u16())));
                     }
 
-                    let v: Value = r
-                        .json()
-                        .await
-                        .map_err(|e| anyhow!("rpc decode error: {e:?}"))?;
+                    let v: Value = r.json().await.map_err(|e| {
+                        RetryError::Permanent(RpcError::Decode(format!("{e:?}")))
+                    })?;
 
                     if let Some(error) = v.get("error") {
-                        if attempt < max_attempts {
-                            warn!(
-                                "RPC error response: {}, retrying (attempt {}/{})",
-                                error, attempt, max_attempts
-                            );
-                            sleep(backoff).await;
-                            backoff = (backoff * 2).min(Duration::from_secs(5));
-                            continue;
-                        }
-                        return Err(anyhow!("RPC error: {}", error));
+                        return Err(RetryError::Transient(RpcError::rpc_level(error)));
                     }
 
                     if !status.is_success() {
-                        if attempt < max_attempts {
-                            sleep(backoff).await;
-                            backoff = (backoff * 2).min(Duration::from_secs(5));
-                            continue;
-                        }
-                        return Err(anyhow!("RPC non-success status: {} body: {}", status, v));
+                        return Err(RetryError::Transient(RpcError::ServerError(status.as_u16())));
                     }
 
-                    return v
-                        .get("result")
-                        .cloned()
-                        .ok_or_else(|| anyhow!("missing result field"));
+                    v.get("result").cloned().ok_or_else(|| {
+                        RetryError::Permanent(RpcError::Decode("missing result field".into()))
+                    })
                 }
+            },
+            |attempt, e, delay| {
+                warn!(
+                    "{e}, backing off {}ms (attempt {}/{})",
+                    delay.as_millis(),
+                    attempt,
+                    policy.max_attempts
+                );
+                self.record_backoff(delay);
+            },
+        )
+        .await
+    }
+
+    /// POST `body` to `urls` from `start` on (wrapping), moving straight on to
+    /// the next URL on a connection error or timeout; only when none of them was
+    /// reachable does the attempt fail and the backoff start
+    async fn send_to_reachable(
+        &self,
+        urls: &[String],
+        start: usize,
+        body: &Value,
+    ) -> Result<reqwest::Response, RpcError> {
+        let mut last_err = None;
+        for (i, url) in urls.iter().cycle().skip(start).take(urls.len()).enumerate() {
+            // Apply minimum delay between requests to reduce 429s
+            self.apply_rate_limit().await;
+
+            match self.http.post(url).json(body).send().await {
+                Ok(r) => return Ok(r),
                 Err(e) => {
-                    if attempt < max_attempts {
-                        warn!(
-                            "RPC request failed: {e:?}, retrying (attempt {}/{})",
-                            attempt, max_attempts
-                        );
-                        sleep(backoff).await;
-                        backoff = (backoff * 2).min(Duration::from_secs(5));
-                        continue;
+                    if i + 1 < urls.len() {
+                        warn!("RPC request failed, trying next URL: {e:?}");
                     }
-                    return Err(anyhow!(
-                        "RPC request failed after {} attempts: {e:?}",
-                        max_attempts
-                    ));
+                    last_err = Some(e);
                 }
             }
         }
-
-        Err(anyhow!("unreachable"))
+        Err(RpcError::Transport(last_err.expect("at least one URL")))
     }
 
     async fn apply_rate_limit(&self) {
@@ -187,3 +553,254 @@ impl RpcClient {
         *last = Instant::now();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decoder::rpc_error::parse_retry_after;
+    use http_body_util::Full;
+    use hyper::body::Bytes;
+    use hyper::server::conn::http1;
+    use hyper::service::service_fn;
+    use hyper_util::rt::TokioIo;
+    use std::convert::Infallible;
+    use std::time::SystemTime;
+    use tokio::net::TcpListener;
+
+    /// In-flight requests seen by a mock RPC server (now, peak, total)
+    #[derive(Default)]
+    struct InFlight {
+        now: AtomicU64,
+        peak: AtomicU64,
+        total: AtomicU64,
+    }
+
+    /// Local JSON-RPC server: each request takes 20ms; the first `failures` are 503s
+    async fn mock_rpc(in_flight: Arc<InFlight>, failures: u64) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let in_flight = in_flight.clone();
+                let service = service_fn(move |_req| {
+                    let in_flight = in_flight.clone();
+                    async move {
+                        let now = in_flight.now.fetch_add(1, Ordering::SeqCst) + 1;
+                        in_flight.peak.fetch_max(now, Ordering::SeqCst);
+                        let n = in_flight.total.fetch_add(1, Ordering::SeqCst);
+                        sleep(Duration::from_millis(20)).await;
+                        in_flight.now.fetch_sub(1, Ordering::SeqCst);
+
+                        let (status, body) = if n < failures {
+                            (503, String::new())
+                        } else {
+                            (200, r#"{"jsonrpc":"2.0","id":1,"result":{"ok":true}}"#.into())
+                        };
+                        let resp = hyper::Response::builder()
+                            .status(status)
+                            .body(Full::new(Bytes::from(body)))
+                            .unwrap();
+                        Ok::<_, Infallible>(resp)
+                    }
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_429_retry_after_replaces_backoff_up_to_cap() {
+        let now = SystemTime::now();
+        assert_eq!(parse_retry_after(" 2 ", now), Some(Duration::from_secs(2)));
+        let date = httpdate::fmt_http_date(now + Duration::from_secs(30));
+        let delay = parse_retry_after(&date, now).unwrap();
+        assert!(delay > Duration::from_secs(28) && delay <= Duration::from_secs(30));
+        let past = httpdate::fmt_http_date(now - Duration::from_secs(30));
+        assert_eq!(parse_retry_after(&past, now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+
+        // First request is a 429 asking for an hour; the cap wins
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let total = Arc::new(AtomicU64::new(0));
+        let served = total.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let served = served.clone();
+                let service = service_fn(move |_req| {
+                    let n = served.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        let resp = if n == 0 {
+                            hyper::Response::builder()
+                                .status(429)
+                                .header("Retry-After", "3600")
+                                .body(Full::new(Bytes::new()))
+                        } else {
+                            hyper::Response::builder().body(Full::new(Bytes::from(
+                                r#"{"jsonrpc":"2.0","id":1,"result":{"ok":true}}"#,
+                            )))
+                        };
+                        Ok::<_, Infallible>(resp.unwrap())
+                    }
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+
+        // 1ms exponential cap would apply without the header
+        let client = RpcClient::new(url, vec![], 1, 0, 0, GetTxMethod::GetTransaction, 1)
+            .with_max_retry_after_ms(40);
+        assert_eq!(client.call("getHealth", json!([])).await.unwrap()["ok"], true);
+        assert_eq!(total.load(Ordering::SeqCst), 2);
+        assert_eq!(client.retry_stats().retries_429.load(Ordering::Relaxed), 1);
+        assert_eq!(client.retry_stats().backoff_ms_total.load(Ordering::Relaxed), 40);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_primary_fails_over_without_backoff() {
+        // Primary accepts connections but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let primary = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut held = vec![];
+            loop {
+                held.push(listener.accept().await.unwrap().0);
+            }
+        });
+        let in_flight = Arc::new(InFlight::default());
+        let fallback = mock_rpc(in_flight.clone(), 0).await;
+
+        let method = GetTxMethod::GetTransaction;
+        let client =
+            RpcClient::new(primary, vec![fallback], 1, 0, 0, method, 5).with_timeout_ms(50);
+        for _ in 0..2 {
+            assert_eq!(client.call("getHealth", json!([])).await.unwrap()["ok"], true);
+        }
+
+        // The timeout moved each call to the fallback within its first attempt
+        assert_eq!(in_flight.total.load(Ordering::SeqCst), 2);
+        assert_eq!(client.retry_stats().retries.load(Ordering::Relaxed), 0);
+        assert_eq!(client.retry_stats().backoff_ms_total.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_fetches_of_one_signature_share_a_call() {
+        let in_flight = Arc::new(InFlight::default());
+        let url = mock_rpc(in_flight.clone(), 0).await;
+        let client = RpcClient::new(url, vec![], 4, 0, 0, GetTxMethod::GetTransaction, 5);
+
+        let fetches: Vec<_> = (0..3)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.get_transaction_json_parsed("Sig1").await })
+            })
+            .collect();
+        for fetch in fetches {
+            assert_eq!(fetch.await.unwrap().unwrap()["ok"], true);
+        }
+        assert_eq!(in_flight.total.load(Ordering::SeqCst), 1);
+
+        // Once done, the signature is fetched again; other signatures never wait
+        client.get_transaction_json_parsed("Sig1").await.unwrap();
+        client.get_transaction_json_parsed("Sig2").await.unwrap();
+        assert_eq!(in_flight.total.load(Ordering::SeqCst), 3);
+        assert!(client.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_batch_response_correlates_by_id() {
+        // Out of order, one error, one not-found (null), one missing entry
+        let resp = json!([
+            {"jsonrpc": "2.0", "id": 2, "result": null},
+            {"jsonrpc": "2.0", "id": 0, "result": {"slot": 250000000}},
+            {"jsonrpc": "2.0", "id": 1, "error": {"code": -32005, "message": "Node is behind"}}
+        ]);
+        let results = parse_batch_response(&resp, 4).unwrap();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap()["slot"], 250000000);
+        assert!(
+            results[1]
+                .as_ref()
+                .unwrap_err()
+                .to_string()
+                .contains("Node is behind")
+        );
+        assert!(matches!(results[1], Err(RpcError::RpcLevel(_))));
+        assert!(results[2].as_ref().unwrap().is_null());
+        assert!(results[3].is_err());
+
+        // Batching unsupported: server answers with a single error object
+        let unsupported = json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32600, "message": "batch requests are disabled"}});
+        assert!(parse_batch_response(&unsupported, 2).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_bounds_in_flight_requests_across_retries() {
+        let in_flight = Arc::new(InFlight::default());
+        let url = mock_rpc(in_flight.clone(), 6).await;
+        let client = RpcClient::new(url, vec![], 2, 0, 0, GetTxMethod::GetTransaction, 5);
+
+        let calls: Vec<_> = (0..6)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.call("getHealth", json!([])).await })
+            })
+            .collect();
+        for call in calls {
+            assert_eq!(call.await.unwrap().unwrap()["ok"], true);
+        }
+
+        // Every call needed a second attempt, yet no more than RPC_CONCURRENCY
+        // requests were ever in flight
+        assert_eq!(in_flight.total.load(Ordering::SeqCst), 12);
+        assert_eq!(in_flight.peak.load(Ordering::SeqCst), 2);
+        assert_eq!(client.retry_stats().retries.load(Ordering::Relaxed), 6);
+    }
+
+    #[tokio::test]
+    async fn test_mint_fetch_is_one_attempt_outside_the_tx_budget() {
+        let in_flight = Arc::new(InFlight::default());
+        let url = mock_rpc(in_flight.clone(), 1).await;
+        let client = RpcClient::new(url, vec![], 1, 0, 0, GetTxMethod::GetTransaction, 5);
+
+        // The only permit is held by a tx fetch; the mint lookup does not wait for it
+        let _permit = client.semaphore.acquire().await.unwrap();
+        let err = client.get_mint_info("Mint1").await.unwrap_err();
+        assert!(matches!(err, RpcError::ServerError(503)));
+        assert_eq!(in_flight.total.load(Ordering::SeqCst), 1);
+
+        // Not a mint account: no metadata
+        assert_eq!(client.get_mint_info("Mint1").await.unwrap(), None);
+        assert_eq!(client.retry_stats().retries.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_get_tx_method_in_request_body() {
+        let client = |method| {
+            RpcClient::new("http://localhost:8899".into(), vec![], 1, 0, 0, method, 1000)
+        };
+
+        let body = client(GetTxMethod::GetTransaction).get_transaction_request(3, "Sig1");
+        assert_eq!(body["method"], "getTransaction");
+        assert_eq!(body["id"], 3);
+        assert_eq!(body["params"][0], "Sig1");
+
+        // Old archival providers: same params under the deprecated name
+        let method = GetTxMethod::parse("getConfirmedTransaction").unwrap();
+        let body = client(method).get_transaction_request(0, "Sig1").to_string();
+        assert!(body.contains(r#""method":"getConfirmedTransaction""#));
+        assert!(GetTxMethod::parse("getBlock").is_none());
+        assert!(!body.contains("commitment"));
+
+        // RPC_COMMITMENT lands in the getTransaction config
+        let commitment = Commitment::parse("Finalized");
+        let client = client(GetTxMethod::GetTransaction).with_commitment(commitment);
+        let body = client.get_transaction_request(0, "Sig1");
+        assert_eq!(body["params"][1]["commitment"], "finalized");
+        assert_eq!(body["params"][1]["encoding"], "jsonParsed");
+        assert!(Commitment::parse("recent").is_none());
+    }
+}
@@ -0,0 +1,137 @@
+//! Typed failure of one JSON-RPC call, shared by the decoder and backfill RPC
+//! clients, so callers match on e.g. `RateLimited` instead of error strings.
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use serde_json::Value;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use crate::retry::RetryError;
+
+#[derive(Debug)]
+pub enum RpcError {
+    /// HTTP 429, with the delay its `Retry-After` header asks for (uncapped)
+    RateLimited { retry_after: Option<Duration> },
+    /// Any other non-2xx HTTP status (5xx, or a 4xx without a JSON-RPC error)
+    ServerError(u16),
+    /// No response: connection error or timeout
+    Transport(reqwest::Error),
+    /// JSON-RPC `error` object (e.g. `{"code":-32005,"message":"Node is behind"}`)
+    RpcLevel(Value),
+    /// Response is not JSON, or has neither `result` nor `error`
+    Decode(String),
+}
+
+impl RpcError {
+    /// 429 answer built from the response headers
+    pub fn rate_limited(headers: &HeaderMap) -> Self {
+        let retry_after = headers
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| parse_retry_after(v, SystemTime::now()));
+        Self::RateLimited { retry_after }
+    }
+
+    /// JSON-RPC `error` object; some providers report rate limits as code 429
+    pub fn rpc_level(error: &Value) -> Self {
+        match error.get("code").and_then(|c| c.as_i64()) {
+            Some(429) => Self::RateLimited { retry_after: None },
+            _ => Self::RpcLevel(error.clone()),
+        }
+    }
+
+    /// Worth another attempt: everything but a response that can't be decoded
+    pub fn is_transient(&self) -> bool {
+        !matches!(self, Self::Decode(_))
+    }
+
+    /// Retry classification: backoff for transient errors, except a 429's
+    /// `Retry-After` (capped at `max_retry_after`) replaces it
+    pub fn into_retry(self, max_retry_after: Duration) -> RetryError<Self> {
+        match self {
+            Self::RateLimited {
+                retry_after: Some(delay),
+            } => RetryError::RetryAfter(self, delay.min(max_retry_after)),
+            e if e.is_transient() => RetryError::Transient(e),
+            e => RetryError::Permanent(e),
+        }
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RateLimited {
+                retry_after: Some(delay),
+            } => write!(
+                f,
+                "RPC rate limited (429), retry after {}ms",
+                delay.as_millis()
+            ),
+            Self::RateLimited { retry_after: None } => write!(f, "RPC rate limited (429)"),
+            Self::ServerError(status) => write!(f, "RPC http error status={status}"),
+            Self::Transport(e) => write!(f, "RPC request failed: {e:?}"),
+            Self::RpcLevel(error) => write!(f, "RPC error: {error}"),
+            Self::Decode(e) => write!(f, "rpc decode error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Delay asked for by a `Retry-After` header: delta-seconds or an HTTP date
+/// (a date in the past means retry now)
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(at.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_retry_classification() {
+        let cap = Duration::from_secs(30);
+        let hour = Duration::from_secs(3600);
+
+        let asked = RpcError::RateLimited {
+            retry_after: Some(hour),
+        };
+        assert!(matches!(asked.into_retry(cap), RetryError::RetryAfter(_, d) if d == cap));
+        let unasked = RpcError::RateLimited { retry_after: None };
+        assert!(matches!(unasked.into_retry(cap), RetryError::Transient(_)));
+        let rpc_level = RpcError::rpc_level(&json!({"code": -32005}));
+        assert!(matches!(
+            rpc_level.into_retry(cap),
+            RetryError::Transient(_)
+        ));
+        assert!(matches!(
+            RpcError::rpc_level(&json!({"code": 429, "message": "Too many requests"})),
+            RpcError::RateLimited { retry_after: None }
+        ));
+        let server = RpcError::ServerError(503);
+        assert!(matches!(server.into_retry(cap), RetryError::Transient(_)));
+        let decode = RpcError::Decode("missing result field".into());
+        assert!(matches!(decode.into_retry(cap), RetryError::Permanent(_)));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "2".parse().unwrap());
+        assert!(matches!(
+            RpcError::rate_limited(&headers),
+            RpcError::RateLimited { retry_after: Some(d) } if d == Duration::from_secs(2)
+        ));
+    }
+}
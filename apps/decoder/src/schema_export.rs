@@ -0,0 +1,70 @@
+//! JSON Schema export of the wire types (`decoder --export-schemas <dir>`,
+//! built with the `json-schema` feature): the RawTxEvent the decoder consumes
+//! and the DexSwapV1 and delta messages it produces, one `<Type>.schema.json`
+//! file each, for consumers that validate or generate code from them.
+
+use anyhow::{Context, Result};
+use schema::DexSwapV1;
+use schemars::{JsonSchema, Schema, schema_for};
+use std::path::Path;
+
+use crate::types::{RawTxEvent, SolBalanceDelta, TokenBalanceDelta};
+
+/// Command line flag that runs the export instead of the decoder
+pub const FLAG: &str = "--export-schemas";
+
+/// Schema of every exported type, by type name
+pub fn schemas() -> Vec<(&'static str, Schema)> {
+    fn entry<T: JsonSchema>(name: &'static str) -> (&'static str, Schema) {
+        (name, schema_for!(T))
+    }
+    vec![
+        entry::<DexSwapV1>("DexSwapV1"),
+        entry::<SolBalanceDelta>("SolBalanceDelta"),
+        entry::<TokenBalanceDelta>("TokenBalanceDelta"),
+        entry::<RawTxEvent>("RawTxEvent"),
+    ]
+}
+
+/// Write `<Type>.schema.json` for every exported type into `dir` (created if
+/// missing). Returns the paths written.
+pub fn write_all(dir: &Path) -> Result<Vec<String>> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("creating schema dir {}", dir.display()))?;
+    let mut written = Vec::new();
+    for (name, schema) in schemas() {
+        let path = dir.join(format!("{name}.schema.json"));
+        let json = serde_json::to_string_pretty(&schema)?;
+        std::fs::write(&path, json + "\n")
+            .with_context(|| format!("writing {}", path.display()))?;
+        written.push(path.display().to_string());
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schemas_follow_the_wire_format() {
+        let schemas = schemas();
+        let swap = &schemas.iter().find(|(n, _)| *n == "DexSwapV1").unwrap().1;
+        let swap = swap.as_value();
+
+        // Amounts are decimal strings, not numbers
+        assert_eq!(swap["properties"]["in_amount"]["$ref"], "#/$defs/Amount");
+        assert_eq!(swap["$defs"]["Amount"]["type"], "string");
+
+        // Fields skipped when None are not required
+        let required = swap["required"].as_array().unwrap();
+        assert!(required.iter().any(|f| f == "signature"));
+        assert!(!required.iter().any(|f| f == "in_symbol"));
+
+        let dir = std::env::temp_dir().join(format!("schema_export_{}", std::process::id()));
+        let written = write_all(&dir).unwrap();
+        assert_eq!(written.len(), 4);
+        assert!(dir.join("RawTxEvent.schema.json").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
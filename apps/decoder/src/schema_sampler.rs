@@ -0,0 +1,76 @@
+//! Schema sampler: log (and optionally dump to files) the first N messages of
+//! each output type, for documenting the wire format.
+
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub struct SchemaSampler {
+    limit: usize,
+    dir: Option<PathBuf>,
+    counts: HashMap<&'static str, usize>,
+}
+
+impl SchemaSampler {
+    pub fn new(limit: usize, dir: Option<PathBuf>) -> Self {
+        if let Some(ref d) = dir
+            && let Err(e) = std::fs::create_dir_all(d)
+        {
+            warn!("schema dump dir {} unusable: {e:?}", d.display());
+        }
+        Self {
+            limit,
+            dir,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Record a sample of `kind` if under the limit. Returns true if taken.
+    pub fn sample<T: Serialize>(&mut self, kind: &'static str, value: &T) -> bool {
+        let n = self.counts.entry(kind).or_insert(0);
+        if *n >= self.limit {
+            return false;
+        }
+        *n += 1;
+        let n = *n;
+
+        let pretty = serde_json::to_string_pretty(value).unwrap_or_default();
+        info!("🔍 {} schema sample #{}:\n{}", kind, n, pretty);
+
+        if let Some(ref dir) = self.dir {
+            let path = dir.join(format!("{}_{}.json", kind, n));
+            if let Err(e) = std::fs::write(&path, &pretty) {
+                warn!("failed to write schema sample {}: {e:?}", path.display());
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sampler_limits_per_kind() {
+        let dir = std::env::temp_dir().join(format!("schema_sampler_{}", std::process::id()));
+        let mut sampler = SchemaSampler::new(2, Some(dir.clone()));
+
+        assert!(sampler.sample("DexSwapV1", &json!({"n": 1})));
+        assert!(sampler.sample("DexSwapV1", &json!({"n": 2})));
+        assert!(!sampler.sample("DexSwapV1", &json!({"n": 3})));
+
+        // Other kinds have their own budget
+        assert!(sampler.sample("SolBalanceDelta", &json!({"n": 1})));
+
+        assert!(dir.join("DexSwapV1_2.json").exists());
+        assert!(!dir.join("DexSwapV1_3.json").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // Limit 0 disables sampling entirely
+        let mut off = SchemaSampler::new(0, None);
+        assert!(!off.sample("RawTxEvent", &json!({})));
+    }
+}
@@ -1,23 +1,16 @@
-//! Sink for DexSwapV1 events to Kafka
+//! Sink for DexSwapV1 events
 
 use anyhow::Result;
-use rdkafka::producer::{FutureProducer, FutureRecord};
 use schema::DexSwapV1;
-use std::time::Duration;
 
-/// Send a DexSwapV1 to Kafka
-pub async fn send_dex_swap_v1(
-    producer: &FutureProducer,
-    topic: &str,
-    swap: &DexSwapV1,
-) -> Result<()> {
-    let payload = serde_json::to_string(swap)?;
-    let key = &swap.signature;
-    let record = FutureRecord::to(topic).key(key).payload(&payload);
+use super::Sink;
 
-    producer
-        .send(record, Duration::from_secs(5))
+/// Send a DexSwapV1 to the configured sink, keyed by `dedup_key` so a swap
+/// republished after a restart overwrites (compaction) or collapses into the
+/// first copy instead of counting twice
+pub async fn send_dex_swap_v1(sink: &Sink, topic: &str, swap: &DexSwapV1) -> Result<()> {
+    let payload = serde_json::to_string(swap)?;
+    sink.send_json(topic, &swap.dedup_key(), &payload)
         .await
-        .map_err(|(err, _)| anyhow::anyhow!("Failed to send DexSwapV1 event: {:?}", err))?;
-    Ok(())
+        .map_err(|err| anyhow::anyhow!("Failed to send DexSwapV1 event: {:?}", err))
 }
@@ -0,0 +1,82 @@
+//! Dual emission of legacy `SwapEvent` and gold `DexSwapV1` during migration.
+//!
+//! Each detected swap is published once to the legacy topic (converted via the
+//! `From<&DexSwapV1>` adapter) and once to the v2 topic, so old and new
+//! consumers can run side by side.
+
+use anyhow::Result;
+use schema::{DexSwapV1, SwapEvent};
+
+use super::Sink;
+use super::dex_swap::send_dex_swap_v1;
+use super::swap::send_swap;
+
+/// Serialize one swap into its (legacy, v2) payloads, as `send_dual` publishes them
+#[cfg(test)]
+pub fn dual_write_payloads(swap: &DexSwapV1) -> Result<(String, String)> {
+    let legacy = serde_json::to_string(&SwapEvent::from(swap))?;
+    let v2 = serde_json::to_string(swap)?;
+    Ok((legacy, v2))
+}
+
+/// Send a swap to both the legacy topic (keyed by signature, as before) and the
+/// v2 topic (keyed by `DexSwapV1::dedup_key`)
+pub async fn send_dual(
+    sink: &Sink,
+    legacy_topic: &str,
+    v2_topic: &str,
+    swap: &DexSwapV1,
+) -> Result<()> {
+    send_swap(sink, legacy_topic, &SwapEvent::from(swap)).await?;
+    send_dex_swap_v1(sink, v2_topic, swap).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::{ConfidenceReasons, DexSwapV1Builder};
+    use serde_json::Value;
+
+    #[test]
+    fn test_dual_write_one_event_per_topic() {
+        let swap = DexSwapV1Builder::new()
+            .chain("solana-mainnet")
+            .slot(250000000)
+            .block_time(Some(1703001234))
+            .signature("sig_dual")
+            .index_in_tx(2)
+            .venue("raydium")
+            .pool_id(Some("PoolAccount123".into()))
+            .trader("TraderWallet111")
+            .in_token("So11111111111111111111111111111111111111112", 500_000_000u128)
+            .out_token("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", 50_000_000u128)
+            .with_confidence_reason(ConfidenceReasons::PROGRAM_GATE)
+            .with_confidence_reason(ConfidenceReasons::TX_SUCCESS)
+            .build();
+
+        let (legacy, v2) = dual_write_payloads(&swap).unwrap();
+        let legacy: Value = serde_json::from_str(&legacy).unwrap();
+        let v2: Value = serde_json::from_str(&v2).unwrap();
+
+        assert_eq!(legacy["schema_version"], 1);
+        assert_eq!(v2["schema_version"], DexSwapV1::SCHEMA_VERSION);
+
+        for field in [
+            "chain",
+            "slot",
+            "block_time",
+            "signature",
+            "index_in_tx",
+            "venue",
+            "trader",
+            "in_mint",
+            "in_amount",
+            "out_mint",
+            "out_amount",
+            "confidence",
+        ] {
+            assert_eq!(legacy[field], v2[field], "field {} differs", field);
+        }
+        assert_eq!(legacy["market_or_pool"], v2["pool_id"]);
+    }
+}
@@ -1,2 +1,84 @@
 pub mod dex_swap;
+pub mod dual;
+pub mod stdout;
 pub mod swap;
+
+use anyhow::Result;
+use log::info;
+use rdkafka::producer::{FutureProducer, Producer};
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::time::Duration;
+
+use crate::kafka;
+use stdout::NdjsonWriter;
+
+/// Where emitted events go (`SINK_KIND`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SinkKind {
+    /// Produce to the configured Kafka topics
+    Kafka,
+    /// One compact JSON line per event on stdout (logs stay on stderr)
+    Stdout,
+    /// Log each event with its topic and key, produce nothing (`DECODER_DRY_RUN`)
+    Log,
+}
+
+impl SinkKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "kafka" => Some(Self::Kafka),
+            "stdout" => Some(Self::Stdout),
+            "log" => Some(Self::Log),
+            _ => None,
+        }
+    }
+}
+
+/// Output for every event the decoder emits (deltas, swaps, DLQ entries)
+pub enum Sink {
+    Kafka(FutureProducer),
+    Stdout(NdjsonWriter<io::Stdout>),
+    Log,
+}
+
+impl Sink {
+    pub fn new(kind: SinkKind, kafka_broker: &str) -> Result<Self> {
+        Ok(match kind {
+            SinkKind::Kafka => Self::Kafka(kafka::create_producer(kafka_broker)?),
+            SinkKind::Stdout => Self::Stdout(NdjsonWriter::new(io::stdout())),
+            SinkKind::Log => Self::Log,
+        })
+    }
+
+    /// Flush the Kafka producer every `interval` (stdout already flushes per line).
+    /// Returns the completed-flush count, None for the other sinks.
+    pub fn spawn_periodic_flush(&self, interval: Duration) -> Option<Arc<AtomicU64>> {
+        match self {
+            Self::Kafka(producer) => Some(kafka::spawn_periodic_flush(producer.clone(), interval)),
+            Self::Stdout(_) | Self::Log => None,
+        }
+    }
+
+    /// Wait up to `timeout` for queued Kafka messages to be delivered (stdout
+    /// already flushes per line)
+    pub fn flush(&self, timeout: Duration) -> Result<()> {
+        match self {
+            Self::Kafka(producer) => Ok(producer.flush(timeout)?),
+            Self::Stdout(_) | Self::Log => Ok(()),
+        }
+    }
+
+    /// Publish one JSON event; the topic and key only apply to Kafka
+    pub async fn send_json(&self, topic: &str, key: &str, json: &str) -> Result<()> {
+        match self {
+            Self::Kafka(producer) => kafka::send_json(producer, topic, key, json).await,
+            Self::Stdout(writer) => writer.write_line(json),
+            Self::Log => {
+                info!("dry-run topic={} key={} {}", topic, key, json);
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Newline-delimited JSON output for ad-hoc pipelines (`decoder | jq ...`).
+//!
+//! Each event is written as one compact JSON line and flushed immediately, so
+//! downstream tools see events as they are decoded. Logging goes to stderr
+//! (see `setup_logging`), which keeps stdout parseable.
+
+use anyhow::{Result, anyhow};
+use std::io::Write;
+use std::sync::Mutex;
+
+pub struct NdjsonWriter<W: Write> {
+    out: Mutex<W>,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out: Mutex::new(out),
+        }
+    }
+
+    /// Write `json` as a single line; rejects payloads that would break framing
+    pub fn write_line(&self, json: &str) -> Result<()> {
+        if json.contains('\n') {
+            return Err(anyhow!("NDJSON payload must be compact (contains newline)"));
+        }
+        let mut out = self
+            .out
+            .lock()
+            .map_err(|_| anyhow!("stdout writer poisoned"))?;
+        out.write_all(json.as_bytes())?;
+        out.write_all(b"\n")?;
+        out.flush()?;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    fn into_inner(self) -> W {
+        self.out.into_inner().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sinks::dual::dual_write_payloads;
+    use schema::DexSwapV1Builder;
+    use serde_json::Value;
+
+    #[test]
+    fn test_stdout_sink_writes_valid_ndjson() {
+        let swap = DexSwapV1Builder::new()
+            .chain("solana-mainnet")
+            .slot(250000000)
+            .signature("sig_stdout")
+            .venue("raydium")
+            .trader("TraderWallet111")
+            .in_token("So11111111111111111111111111111111111111112", 500_000_000u128)
+            .out_token("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", 50_000_000u128)
+            .build();
+        let (legacy, v2) = dual_write_payloads(&swap).unwrap();
+
+        let writer = NdjsonWriter::new(Vec::new());
+        writer.write_line(&legacy).unwrap();
+        writer.write_line(&v2).unwrap();
+        // Pretty-printed JSON would split across lines
+        let pretty = serde_json::to_string_pretty(&swap).unwrap();
+        assert!(writer.write_line(&pretty).is_err());
+
+        let captured = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(captured.ends_with('\n'));
+        let lines: Vec<&str> = captured.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for line in lines {
+            let v: Value = serde_json::from_str(line).unwrap();
+            assert_eq!(v["signature"], "sig_stdout");
+        }
+    }
+}
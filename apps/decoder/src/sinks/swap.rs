@@ -1,16 +1,11 @@
 use anyhow::Result;
-use rdkafka::producer::{FutureProducer, FutureRecord};
 use schema::SwapEvent;
-use std::time::Duration;
 
-pub async fn send_swap(producer: &FutureProducer, topic: &str, swap: &SwapEvent) -> Result<()> {
-    let payload = serde_json::to_string(swap)?;
-    let key = &swap.signature;
-    let record = FutureRecord::to(topic).key(key).payload(&payload);
+use super::Sink;
 
-    producer
-        .send(record, Duration::from_secs(5))
+pub async fn send_swap(sink: &Sink, topic: &str, swap: &SwapEvent) -> Result<()> {
+    let payload = serde_json::to_string(swap)?;
+    sink.send_json(topic, &swap.signature, &payload)
         .await
-        .map_err(|(err, _)| anyhow::anyhow!("Failed to send swap event: {:?}", err))?;
-    Ok(())
+        .map_err(|err| anyhow::anyhow!("Failed to send swap event: {:?}", err))
 }
@@ -0,0 +1,135 @@
+//! Leaky-bucket throttle for swap emission (overload protection).
+//!
+//! The bucket drains at `SWAPS_MAX_EPS` events per second and holds at most one
+//! second worth of events, so short bursts pass while sustained spikes are
+//! either dropped or delayed depending on `THROTTLE_MODE`.
+
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// What to do with an event that exceeds the rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleMode {
+    /// Discard the event (counted by the caller)
+    Drop,
+    /// Wait until the bucket has room
+    Block,
+}
+
+impl ThrottleMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "drop" => Some(Self::Drop),
+            "block" => Some(Self::Block),
+            _ => None,
+        }
+    }
+}
+
+/// Leaky bucket with capacity == rate (one second of burst)
+#[derive(Debug)]
+pub struct LeakyBucket {
+    rate: f64,
+    level: f64,
+    last: Instant,
+}
+
+impl LeakyBucket {
+    pub fn new(max_eps: u32, now: Instant) -> Self {
+        Self {
+            rate: max_eps as f64,
+            level: 0.0,
+            last: now,
+        }
+    }
+
+    /// Try to admit one event at `now`.
+    ///
+    /// Returns `Err(wait)` with the time until the event would fit.
+    pub fn try_admit(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.level = (self.level - elapsed * self.rate).max(0.0);
+        self.last = now;
+
+        if self.level + 1.0 <= self.rate {
+            self.level += 1.0;
+            Ok(())
+        } else {
+            let overflow = self.level + 1.0 - self.rate;
+            Err(Duration::from_secs_f64(overflow / self.rate))
+        }
+    }
+}
+
+/// Emission throttle; a no-op when `SWAPS_MAX_EPS` is unset
+#[derive(Debug)]
+pub struct Throttle {
+    bucket: Option<LeakyBucket>,
+    mode: ThrottleMode,
+}
+
+impl Throttle {
+    pub fn new(max_eps: u32, mode: ThrottleMode) -> Self {
+        let bucket = (max_eps > 0).then(|| LeakyBucket::new(max_eps, Instant::now()));
+        Self { bucket, mode }
+    }
+
+    /// Returns true if the event may be emitted (after waiting in block mode)
+    pub async fn admit(&mut self) -> bool {
+        let Some(bucket) = self.bucket.as_mut() else {
+            return true;
+        };
+
+        loop {
+            match bucket.try_admit(Instant::now()) {
+                Ok(()) => return true,
+                Err(wait) => match self.mode {
+                    ThrottleMode::Drop => return false,
+                    ThrottleMode::Block => sleep(wait).await,
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leaky_bucket_rate_over_window() {
+        let start = Instant::now();
+        let mut bucket = LeakyBucket::new(10, start);
+
+        // Burst of one second worth passes, the next one is rejected
+        for _ in 0..10 {
+            assert!(bucket.try_admit(start).is_ok());
+        }
+        let wait = bucket.try_admit(start).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(100));
+
+        // Offer 100 events/s for 2 seconds: only ~10/s get through
+        let mut admitted = 0;
+        for i in 1..=200 {
+            let now = start + Duration::from_millis(i * 10);
+            if bucket.try_admit(now).is_ok() {
+                admitted += 1;
+            }
+        }
+        assert!((19..=21).contains(&admitted), "admitted {}", admitted);
+
+        // After an idle second the full burst is available again
+        let later = start + Duration::from_secs(4);
+        for _ in 0..10 {
+            assert!(bucket.try_admit(later).is_ok());
+        }
+        assert!(bucket.try_admit(later).is_err());
+    }
+
+    #[test]
+    fn test_throttle_mode_parse() {
+        assert_eq!(ThrottleMode::parse("drop"), Some(ThrottleMode::Drop));
+        assert_eq!(ThrottleMode::parse("BLOCK"), Some(ThrottleMode::Block));
+        assert_eq!(ThrottleMode::parse("queue"), None);
+    }
+}
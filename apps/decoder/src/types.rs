@@ -1,6 +1,10 @@
+use schema::TxFacts;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct RawTxEvent {
     pub schema_version: u8,
     pub chain: String,
@@ -16,7 +20,78 @@ pub struct RawTxEvent {
     pub program_ids: Vec<String>,
 }
 
+impl RawTxEvent {
+    /// Facts of the fetched `tx` for this event. Signature and slot always come
+    /// from the event; with `prefer_event_metadata` so does `block_time` (the
+    /// streamer's block-meta value), falling back to the tx JSON's when unset.
+    pub fn tx_facts(&self, tx: &Value, prefer_event_metadata: bool) -> TxFacts {
+        let mut facts = TxFacts::from_json(tx, &self.signature, self.slot);
+        if prefer_event_metadata && self.block_time.is_some() {
+            facts.block_time = self.block_time;
+        }
+        facts
+    }
+}
+
+/// `RawTxEvent.schema_version`s the decoder accepts (`RAW_SCHEMA_VERSIONS`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaVersionRange {
+    pub min: u8,
+    pub max: u8,
+}
+
+impl SchemaVersionRange {
+    /// Version the streamer emits today
+    pub const CURRENT: Self = Self { min: 1, max: 1 };
+
+    /// A single version (`1`) or an inclusive range (`1-2`)
+    pub fn parse(s: &str) -> Option<Self> {
+        let (min, max) = match s.split_once('-') {
+            Some((min, max)) => (min.trim().parse().ok()?, max.trim().parse().ok()?),
+            None => {
+                let v = s.trim().parse().ok()?;
+                (v, v)
+            }
+        };
+        (min <= max).then_some(Self { min, max })
+    }
+
+    pub fn contains(&self, version: u8) -> bool {
+        (self.min..=self.max).contains(&version)
+    }
+}
+
+impl fmt::Display for SchemaVersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.min == self.max {
+            write!(f, "{}", self.min)
+        } else {
+            write!(f, "{}-{}", self.min, self.max)
+        }
+    }
+}
+
+/// What to do with a RawTxEvent whose `schema_version` is out of range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaMismatchMode {
+    /// Log (first occurrence) and count, then decode it anyway
+    Warn,
+    /// Send it to the DLQ (if configured) and commit without decoding
+    Dlq,
+}
+
+impl SchemaMismatchMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "warn" => Some(Self::Warn),
+            "dlq" => Some(Self::Dlq),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct SolBalanceDelta {
     pub slot: u64,
     pub block_time: Option<i64>,
@@ -28,6 +103,7 @@ pub struct SolBalanceDelta {
 }
 
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct TokenBalanceDelta {
     pub slot: u64,
     pub block_time: Option<i64>,
@@ -35,7 +111,129 @@ pub struct TokenBalanceDelta {
     pub account_index: u32,
     pub mint: String,
     pub decimals: Option<u8>,
-    pub pre_amount: u64,
-    pub post_amount: u64,
-    pub delta: i64,
+    pub pre_amount: u128,
+    pub post_amount: u128,
+    pub delta: i128,
+}
+
+/// Every SOL and token delta of one tx in a single message (`EMIT_COMBINED_FACTS`),
+/// so consumers don't have to join the two delta topics by signature
+#[derive(Debug, Serialize)]
+pub struct RawFacts<'a> {
+    pub signature: &'a str,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub sol_deltas: &'a [SolBalanceDelta],
+    pub token_deltas: &'a [TokenBalanceDelta],
+}
+
+/// A venue present in a tx whose parser produced no swap (`EMIT_PARSE_DIAGNOSTICS`)
+#[derive(Debug, Serialize)]
+pub struct ParseDiagnostic<'a> {
+    pub signature: &'a str,
+    pub slot: u64,
+    pub venue: &'a str,
+    /// `ParseFailReason` label (e.g. `no_token_deltas`, `no_in_out_pair`)
+    pub reason: &'static str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decoder::detectors::DetectorOptions;
+    use decoder::detectors::registry::DetectorRegistry;
+    use std::sync::Arc;
+
+    fn event(schema_version: u8) -> RawTxEvent {
+        serde_json::from_value(serde_json::json!({
+            "schema_version": schema_version,
+            "chain": "solana-mainnet",
+            "slot": 250000000,
+            "block_time": null,
+            "signature": "sig_schema",
+            "index_in_block": 0,
+            "tx_version": null,
+            "is_success": true,
+            "fee_lamports": 5000,
+            "compute_units_consumed": null,
+            "main_program": null,
+            "program_ids": []
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_schema_version_range() {
+        let current = SchemaVersionRange::CURRENT;
+        assert!(current.contains(event(1).schema_version));
+        assert!(!current.contains(event(2).schema_version));
+        assert!(!current.contains(event(0).schema_version));
+
+        // Rolling upgrade: accept the old and the new streamer at once
+        let upgrade = SchemaVersionRange::parse("1-2").unwrap();
+        assert!(upgrade.contains(event(2).schema_version));
+        assert!(!upgrade.contains(event(3).schema_version));
+        assert_eq!(upgrade.to_string(), "1-2");
+
+        assert_eq!(SchemaVersionRange::parse(" 1 "), Some(current));
+        assert_eq!(SchemaVersionRange::parse("2-1"), None);
+        assert_eq!(SchemaVersionRange::parse("v1"), None);
+    }
+
+    #[test]
+    fn test_raw_facts_combines_both_delta_kinds() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/raydium_swap_instruction_amounts.json"
+        );
+        let tx: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let block_time = Some(1703001234);
+        let sol_deltas = crate::decode::decode_sol_deltas(250000000, block_time, "sig_f", &tx);
+        let token_deltas = crate::decode::decode_token_deltas(250000000, block_time, "sig_f", &tx);
+        assert!(!sol_deltas.is_empty() && !token_deltas.is_empty());
+
+        let facts = RawFacts {
+            signature: "sig_f",
+            slot: 250000000,
+            block_time: Some(1703001234),
+            sol_deltas: &sol_deltas,
+            token_deltas: &token_deltas,
+        };
+        let json = serde_json::to_value(&facts).unwrap();
+        assert_eq!(json["signature"], "sig_f");
+        assert_eq!(json["slot"], 250000000);
+        assert_eq!(json["block_time"], 1703001234);
+        assert_eq!(json["sol_deltas"].as_array().unwrap().len(), sol_deltas.len());
+        assert_eq!(json["token_deltas"].as_array().unwrap().len(), token_deltas.len());
+        // Same per-delta shape as the separate topics
+        assert_eq!(json["token_deltas"][0], serde_json::to_value(&token_deltas[0]).unwrap());
+    }
+
+    #[test]
+    fn test_event_block_time_wins_over_tx_json() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/raydium_swap_instruction_amounts.json"
+        );
+        let tx: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let tx_block_time = tx["blockTime"].as_i64();
+        let mut evt = event(1);
+        evt.block_time = tx_block_time.map(|t| t + 1);
+
+        let registry = DetectorRegistry::new(Arc::new(DetectorOptions::default()));
+        let swap_block_time = |facts: &TxFacts| {
+            let swaps = registry.detect(facts, &evt.chain, 0, false);
+            swaps.unwrap()[0].block_time
+        };
+
+        let facts = evt.tx_facts(&tx, true);
+        assert_eq!(facts.slot, evt.slot);
+        assert_eq!(swap_block_time(&facts), evt.block_time);
+
+        // Disabled, or the event has none: the tx JSON's value
+        assert_eq!(swap_block_time(&evt.tx_facts(&tx, false)), tx_block_time);
+        evt.block_time = None;
+        assert_eq!(swap_block_time(&evt.tx_facts(&tx, true)), tx_block_time);
+    }
 }
@@ -0,0 +1,167 @@
+//! Startup warmup (`STARTUP_WARMUP`): before consuming, fetch a couple of recent
+//! transactions of the Raydium AMM v4 program and run them through the full
+//! decode, so a wrong RPC URL, missing auth or broken parser fails at startup
+//! instead of sending every message to retries and the DLQ.
+
+use anyhow::{Result, anyhow};
+use decoder::detectors::registry::DetectorRegistry;
+use decoder::rpc_error::RpcError;
+use log::info;
+use schema::TxFacts;
+
+use crate::decode;
+use crate::rpc::RpcClient;
+
+/// Recent transactions fetched and decoded
+pub const WARMUP_TXS: usize = 2;
+
+/// What the warmup decoded
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct WarmupReport {
+    pub txs: usize,
+    pub token_deltas: usize,
+    pub swaps: usize,
+}
+
+/// Fetch and decode the venue program's latest transactions; any RPC or
+/// decode failure is returned as a startup error.
+pub async fn run(
+    rpc: &RpcClient,
+    chain: &str,
+    registry: &DetectorRegistry,
+) -> Result<WarmupReport> {
+    let program = &registry.options().programs.raydium_amm_v4;
+    let signatures = rpc
+        .get_signatures_for_address(program, WARMUP_TXS)
+        .await
+        .map_err(|e| {
+            let hint = match e {
+                RpcError::RateLimited { .. } => "lower RPC_CONCURRENCY or raise RPC_MIN_DELAY_MS",
+                _ => "check RPC_PRIMARY_URL and its auth",
+            };
+            anyhow!("startup warmup: getSignaturesForAddress({program}) failed, {hint}: {e}")
+        })?;
+    if signatures.is_empty() {
+        return Err(anyhow!(
+            "startup warmup: RPC returned no signatures for {program} (wrong CHAIN or RAYDIUM_AMM_V4_PROGRAM_ID?)"
+        ));
+    }
+
+    let mut report = WarmupReport::default();
+    for signature in &signatures {
+        let tx = rpc.get_transaction_json_parsed(signature).await.map_err(|e| {
+            anyhow!("startup warmup: getTransaction {signature} failed: {e}")
+        })?;
+        if tx.is_null() {
+            return Err(anyhow!(
+                "startup warmup: RPC has no transaction for {signature} (pruned history?)"
+            ));
+        }
+
+        let slot = tx.get("slot").and_then(|v| v.as_u64()).unwrap_or(0);
+        let block_time = tx.get("blockTime").and_then(|v| v.as_i64());
+        let facts = TxFacts::from_json(&tx, signature, slot);
+        if !facts.has_program(program) {
+            return Err(anyhow!(
+                "startup warmup: decoded {signature} does not invoke {program} (account keys or lookup tables not resolved)"
+            ));
+        }
+
+        report.txs += 1;
+        report.token_deltas += decode::decode_token_deltas(slot, block_time, signature, &tx).len();
+        // Recent program txs need not be swaps (deposits, routes), so none is fine
+        report.swaps += registry
+            .detect(&facts, chain, 0, false)
+            .map(|swaps| swaps.len())
+            .unwrap_or(0);
+    }
+
+    info!(
+        "startup warmup ok: txs={} token_deltas={} swaps={}",
+        report.txs, report.token_deltas, report.swaps
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::GetTxMethod;
+    use decoder::detectors::DetectorOptions;
+    use http_body_util::{BodyExt, Full};
+    use hyper::body::Bytes;
+    use hyper::server::conn::http1;
+    use hyper::service::service_fn;
+    use hyper_util::rt::TokioIo;
+    use serde_json::{Value, json};
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    /// Local JSON-RPC server answering by method; `None` = HTTP 401 to everything
+    async fn mock_rpc(signatures: Option<Value>, tx: Value) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (signatures, tx) = (signatures.clone(), tx.clone());
+                let service = service_fn(move |req: hyper::Request<hyper::body::Incoming>| {
+                    let (signatures, tx) = (signatures.clone(), tx.clone());
+                    async move {
+                        let body = req.into_body().collect().await.unwrap().to_bytes();
+                        let body: Value = serde_json::from_slice(&body).unwrap();
+                        let (status, result) = match (&signatures, body["method"].as_str()) {
+                            (None, _) => (401, Value::Null),
+                            (Some(sigs), Some("getSignaturesForAddress")) => (200, sigs.clone()),
+                            (Some(_), _) => (200, tx),
+                        };
+                        let resp = json!({"jsonrpc": "2.0", "id": 1, "result": result});
+                        let body = if status == 200 { resp.to_string() } else { String::new() };
+                        Ok::<_, Infallible>(
+                            hyper::Response::builder()
+                                .status(status)
+                                .body(Full::new(Bytes::from(body)))
+                                .unwrap(),
+                        )
+                    }
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_warmup_decodes_recent_txs_and_fails_fast() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../crates/schema/tests/fixtures/raydium_swap_instruction_amounts.json"
+        );
+        let tx: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let client = |url| RpcClient::new(url, vec![], 1, 0, 0, GetTxMethod::GetTransaction, 5);
+        let registry = DetectorRegistry::new(Arc::new(DetectorOptions::default()));
+
+        let signatures = json!([{"signature": "WarmupSig1"}, {"signature": "WarmupSig2"}]);
+        let url = mock_rpc(Some(signatures), tx.clone()).await;
+        let report = run(&client(url), "solana-mainnet", &registry).await.unwrap();
+        assert_eq!(
+            report,
+            WarmupReport {
+                txs: 2,
+                token_deltas: 10,
+                swaps: 2
+            }
+        );
+
+        // Bad auth: a clear startup error, not a decode loop
+        let url = mock_rpc(None, tx.clone()).await;
+        let err = run(&client(url), "solana-mainnet", &registry).await.unwrap_err();
+        assert!(err.to_string().contains("getSignaturesForAddress"));
+
+        // Program not found in the returned tx: parser/ALT problem
+        let url = mock_rpc(Some(json!([{"signature": "WarmupSig1"}])), json!({"slot": 1})).await;
+        let err = run(&client(url), "solana-mainnet", &registry).await.unwrap_err();
+        assert!(err.to_string().contains("does not invoke"));
+    }
+}
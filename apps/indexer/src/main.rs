@@ -1,63 +1,158 @@
-use anyhow::Result;
-use clickhouse::{Client, Row};
-use serde::Deserialize;
-
-#[derive(Debug, Deserialize, Row)]
-struct RawTxRow {
-    ts: String, // we'll convert DateTime -> String in SQL
-    slot: u64,
-    signature: String,
-    tx_version: Option<u8>,
-    is_success: bool,
-    fee_lamports: u64,
-    main_program: Option<String>,
-    program_ids: Vec<String>,
-}
+mod raw_txs;
+mod swaps;
+
+use std::env;
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, bail};
+use clickhouse::Client;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::Message;
+use schema::DexSwapV1;
+use swaps::{DexSwapRow, InsertFailure, SwapBatch};
+
+/// Pause between attempts while ClickHouse rejects an insert
+const INSERT_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Attempts per batch on transient errors (about a minute) before giving up
+const INSERT_MAX_ATTEMPTS: u32 = 30;
+
+/// Undecodable messages tolerated before the first `DexSwapV1`; past that the
+/// topic is taken to carry something else (e.g. the decoder's legacy `SwapEvent`s)
+const MAX_SKIPPED_BEFORE_FIRST_SWAP: u64 = 100;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
-    println!("Connecting to ClickHouse at http://localhost:8123 ...");
-
-    let client = Client::default()
-        .with_url("http://localhost:8123")
-        .with_database("solana"); // we created this DB earlier
-
-    let query = r#"
-        SELECT
-            toString(ts) AS ts,   -- convert DateTime -> String, alias to `ts`
-            slot,
-            signature,
-            tx_version,
-            is_success,
-            fee_lamports,
-            main_program,
-            program_ids
-        FROM sol_raw_txs
-        ORDER BY ts DESC
-        LIMIT 20
-    "#;
-
-    println!("Running query:\n{query}");
-
-    let mut cursor = client.query(query).fetch::<RawTxRow>()?;
-
-    println!("\nLast 10 raw txs:\n");
-
-    while let Some(row) = cursor.next().await? {
-        println!(
-            "[{}] slot={} sig={} tx_v={:?} success={} fee={} main_prog={:?} programs={:?}",
-            row.ts,
-            row.slot,
-            row.signature,
-            row.tx_version,
-            row.is_success,
-            row.fee_lamports,
-            row.main_program,
-            row.program_ids,
-        );
+
+    let url = env::var("CLICKHOUSE_URL").unwrap_or_else(|_| "http://localhost:8123".to_string());
+    let database = env::var("CLICKHOUSE_DATABASE").unwrap_or_else(|_| "solana".to_string());
+    println!("Connecting to ClickHouse at {url} ...");
+
+    let client = Client::default().with_url(&url).with_database(&database);
+
+    // `indexer raw-txs` keeps the old one-shot preview of the raw table
+    if env::args().nth(1).as_deref() == Some("raw-txs") {
+        return raw_txs::print_latest(&client).await;
     }
 
-    println!("\nDone.");
-    Ok(())
+    run_swaps_sink(&client).await
+}
+
+/// Consume gold `DexSwapV1` messages and batch-insert them into ClickHouse
+async fn run_swaps_sink(client: &Client) -> Result<()> {
+    let broker = env::var("KAFKA_BROKER").unwrap_or_else(|_| "localhost:19092".to_string());
+    // The decoder publishes DexSwapV1 on `sol_swaps_v2` (SWAPS_DUAL_WRITE, backfill
+    // --decode-swaps); `sol_swaps` carries the legacy SwapEvent
+    let topic = env::var("KAFKA_SWAPS_TOPIC").unwrap_or_else(|_| "sol_swaps_v2".to_string());
+    let group = env::var("KAFKA_GROUP").unwrap_or_else(|_| "indexer_dex_swaps".to_string());
+    let table = env::var("CLICKHOUSE_SWAPS_TABLE").unwrap_or_else(|_| "dex_swaps".to_string());
+    let batch_size: usize = env::var("INDEXER_BATCH_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1000);
+    let flush_ms: u64 = env::var("INDEXER_FLUSH_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1000);
+
+    println!(
+        "Sinking {topic} (group={group}) into {table}: batch_size={batch_size} flush_ms={flush_ms}"
+    );
+
+    let consumer: StreamConsumer = ClientConfig::new()
+        .set("bootstrap.servers", &broker)
+        .set("group.id", &group)
+        .set("enable.auto.commit", "false") // we commit only after the insert succeeded
+        .set("auto.offset.reset", "earliest")
+        .create()?;
+    consumer.subscribe(&[topic.as_str()])?;
+
+    let flush_interval = Duration::from_millis(flush_ms);
+    let mut batch = SwapBatch::new(batch_size, flush_interval);
+    let mut tick = tokio::time::interval(flush_interval.min(Duration::from_millis(250)));
+    let mut skipped: u64 = 0;
+    let mut seen_swap = false;
+    let shutdown = tokio::signal::ctrl_c();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            msg = consumer.recv() => {
+                let msg = msg?;
+                match msg.payload().map(serde_json::from_slice::<DexSwapV1>) {
+                    Some(Ok(swap)) => {
+                        seen_swap = true;
+                        batch.push(DexSwapRow::from(swap), Instant::now());
+                    }
+                    Some(Err(e)) => {
+                        skipped += 1;
+                        eprintln!(
+                            "skipping undecodable message at {}/{} (skipped={skipped}): {e}",
+                            msg.partition(),
+                            msg.offset()
+                        );
+                    }
+                    None => skipped += 1,
+                }
+                if !seen_swap && skipped >= MAX_SKIPPED_BEFORE_FIRST_SWAP {
+                    bail!(
+                        "none of the first {skipped} messages on {topic} is a DexSwapV1; \
+                         point KAFKA_SWAPS_TOPIC at the decoder's DexSwapV1 topic (sol_swaps_v2)"
+                    );
+                }
+            }
+            _ = tick.tick() => {}
+            _ = &mut shutdown => {
+                println!("Shutting down, flushing {} pending rows", batch.rows().len());
+                if flush(client, &table, &mut batch).await? {
+                    consumer.commit_consumer_state(CommitMode::Sync)?;
+                }
+                return Ok(());
+            }
+        }
+
+        if batch.is_due(Instant::now()) && flush(client, &table, &mut batch).await? {
+            consumer.commit_consumer_state(CommitMode::Async)?;
+        }
+    }
+}
+
+/// Insert the batch, retrying transient failures. Consumption stops
+/// meanwhile, so offsets never run ahead of what is stored. A schema or access
+/// error, or a batch still failing after `INSERT_MAX_ATTEMPTS`, is returned so
+/// the process exits non-zero with the offsets uncommitted. Returns whether any
+/// rows were written (i.e. there are offsets to commit).
+async fn flush(client: &Client, table: &str, batch: &mut SwapBatch) -> Result<bool> {
+    if batch.is_empty() {
+        return Ok(false);
+    }
+    let rows = batch.rows().len();
+    let mut attempt = 1;
+    loop {
+        match swaps::insert_rows(client, table, batch.rows()).await {
+            Ok(()) => break,
+            Err(e) if InsertFailure::classify(&e) == InsertFailure::Permanent => {
+                return Err(e.context(format!(
+                    "insert into {table} rejected, not retrying (is the schema migrated?)"
+                )));
+            }
+            Err(e) if attempt >= INSERT_MAX_ATTEMPTS => {
+                return Err(e.context(format!(
+                    "insert of {rows} rows into {table} failed {attempt} times"
+                )));
+            }
+            Err(e) => {
+                eprintln!(
+                    "insert of {rows} rows into {table} failed \
+                     (attempt {attempt}/{INSERT_MAX_ATTEMPTS}), retrying: {e:#}"
+                );
+                attempt += 1;
+                tokio::time::sleep(INSERT_RETRY_DELAY).await;
+            }
+        }
+    }
+    batch.clear();
+    Ok(true)
 }
@@ -0,0 +1,245 @@
+//! Gold swap sink: `DexSwapV1` messages from Kafka, batch-inserted into
+//! `solana.dex_swaps`.
+//!
+//! A batch is flushed when it reaches `INDEXER_BATCH_SIZE` rows or its oldest
+//! row is `INDEXER_FLUSH_MS` old, whichever comes first. Offsets are committed
+//! only after the insert succeeded, so a crash replays (never drops) swaps; the
+//! table is a ReplacingMergeTree keyed on the swap identity to absorb replays.
+//! A failed insert is retried only if the error is transient ([`InsertFailure`]).
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use clickhouse::{Client, Row};
+use schema::DexSwapV1;
+use serde::Serialize;
+
+/// One `dex_swaps` row; columns mirror the `DexSwapV1` fields one to one
+#[derive(Debug, Clone, PartialEq, Serialize, Row)]
+pub struct DexSwapRow {
+    pub schema_version: u16,
+    pub chain: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub signature: String,
+    pub index_in_block: u32,
+    pub index_in_tx: u16,
+    pub hop_index: u8,
+    pub venue: String,
+    pub pool_id: Option<String>,
+    pub trader: String,
+    pub in_mint: String,
+    pub in_amount: String,
+    pub out_mint: String,
+    pub out_amount: String,
+    pub fee_mint: Option<String>,
+    pub fee_amount: Option<String>,
+    pub route_id: Option<String>,
+    pub confidence: u8,
+    pub confidence_reasons: u32,
+    pub explain: Option<String>,
+    pub sol_cost_lamports: Option<u64>,
+    pub jito_bundle: bool,
+    pub partial: bool,
+    pub parser_version: u16,
+    pub in_symbol: Option<String>,
+    pub out_symbol: Option<String>,
+    pub in_ui_amount: Option<String>,
+    pub out_ui_amount: Option<String>,
+}
+
+impl From<DexSwapV1> for DexSwapRow {
+    fn from(s: DexSwapV1) -> Self {
+        Self {
+            schema_version: s.schema_version,
+            chain: s.chain,
+            slot: s.slot,
+            block_time: s.block_time,
+            signature: s.signature,
+            index_in_block: s.index_in_block,
+            index_in_tx: s.index_in_tx,
+            hop_index: s.hop_index,
+            venue: s.venue,
+            pool_id: s.pool_id,
+            trader: s.trader,
+            in_mint: s.in_mint,
+            in_amount: s.in_amount.to_string(),
+            out_mint: s.out_mint,
+            out_amount: s.out_amount.to_string(),
+            fee_mint: s.fee_mint,
+            fee_amount: s.fee_amount,
+            route_id: s.route_id,
+            confidence: s.confidence,
+            confidence_reasons: s.confidence_reasons,
+            explain: s.explain,
+            sol_cost_lamports: s.sol_cost_lamports,
+            jito_bundle: s.jito_bundle,
+            partial: s.partial,
+            parser_version: s.parser_version,
+            in_symbol: s.in_symbol,
+            out_symbol: s.out_symbol,
+            in_ui_amount: s.in_ui_amount,
+            out_ui_amount: s.out_ui_amount,
+        }
+    }
+}
+
+/// Rows waiting for the next insert
+#[derive(Debug)]
+pub struct SwapBatch {
+    rows: Vec<DexSwapRow>,
+    max_rows: usize,
+    flush_interval: Duration,
+    /// When the oldest pending row arrived
+    since: Option<Instant>,
+}
+
+impl SwapBatch {
+    pub fn new(max_rows: usize, flush_interval: Duration) -> Self {
+        Self {
+            rows: Vec::with_capacity(max_rows),
+            max_rows: max_rows.max(1),
+            flush_interval,
+            since: None,
+        }
+    }
+
+    pub fn push(&mut self, row: DexSwapRow, now: Instant) {
+        self.since.get_or_insert(now);
+        self.rows.push(row);
+    }
+
+    /// Full, or holding rows older than the flush interval
+    pub fn is_due(&self, now: Instant) -> bool {
+        match self.since {
+            Some(since) => {
+                self.rows.len() >= self.max_rows
+                    || now.duration_since(since) >= self.flush_interval
+            }
+            None => false,
+        }
+    }
+
+    pub fn rows(&self) -> &[DexSwapRow] {
+        &self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.rows.clear();
+        self.since = None;
+    }
+}
+
+/// ClickHouse error codes no retry fixes: the table or a column is missing or
+/// has another type (schema not migrated), or access is denied
+const PERMANENT_INSERT_CODES: &[u32] = &[
+    16,  // NO_SUCH_COLUMN_IN_TABLE
+    27,  // CANNOT_PARSE_INPUT_ASSERTION_FAILED
+    33,  // CANNOT_READ_ALL_DATA (row layout differs from the table)
+    47,  // UNKNOWN_IDENTIFIER
+    53,  // TYPE_MISMATCH
+    60,  // UNKNOWN_TABLE
+    70,  // CANNOT_CONVERT_TYPE
+    81,  // UNKNOWN_DATABASE
+    497, // ACCESS_DENIED
+    516, // AUTHENTICATION_FAILED
+];
+
+/// Whether a failed insert is worth retrying
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertFailure {
+    /// Network error, timeout, overloaded server: retry the batch
+    Transient,
+    /// Schema or access error: every retry fails the same way
+    Permanent,
+}
+
+impl InsertFailure {
+    pub fn classify(error: &anyhow::Error) -> Self {
+        Self::from_message(&format!("{error:#}"))
+    }
+
+    /// By the `Code: N` of the server exception; anything else is transient
+    fn from_message(message: &str) -> Self {
+        let code = message
+            .split("Code: ")
+            .nth(1)
+            .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit()).next())
+            .and_then(|digits| digits.parse::<u32>().ok());
+        match code {
+            Some(code) if PERMANENT_INSERT_CODES.contains(&code) => Self::Permanent,
+            _ => Self::Transient,
+        }
+    }
+}
+
+/// Insert `rows` into `table` as one ClickHouse INSERT
+pub async fn insert_rows(client: &Client, table: &str, rows: &[DexSwapRow]) -> Result<()> {
+    let mut insert = client.insert::<DexSwapRow>(table)?;
+    for row in rows {
+        insert.write(row).await?;
+    }
+    insert.end().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::DexSwapV1Builder;
+
+    fn row(slot: u64) -> DexSwapRow {
+        let swap = DexSwapV1Builder::new()
+            .chain("solana-mainnet")
+            .slot(slot)
+            .signature("Sig")
+            .venue("raydium")
+            .trader("Trader")
+            .in_token("MintA", 100u128)
+            .out_token("MintB", 200u128)
+            .route_id(Some("route".to_string()))
+            .hop_index(1)
+            .build();
+        DexSwapRow::from(swap)
+    }
+
+    #[test]
+    fn test_batch_flushes_on_size_or_age() {
+        let t0 = Instant::now();
+        let mut batch = SwapBatch::new(2, Duration::from_millis(1000));
+        assert!(!batch.is_due(t0 + Duration::from_secs(10)));
+
+        batch.push(row(1), t0);
+        assert!(!batch.is_due(t0 + Duration::from_millis(999)));
+        assert!(batch.is_due(t0 + Duration::from_millis(1000)));
+
+        batch.push(row(2), t0 + Duration::from_millis(10));
+        assert!(batch.is_due(t0 + Duration::from_millis(10)));
+        assert_eq!(batch.rows()[0].route_id.as_deref(), Some("route"));
+        assert_eq!(batch.rows()[0].hop_index, 1);
+
+        batch.clear();
+        assert!(batch.is_empty());
+        assert!(!batch.is_due(t0 + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_schema_errors_are_permanent() {
+        let missing_column = "bad response: Code: 16. DB::Exception: No such column \
+                              route_id in table solana.dex_swaps. (NO_SUCH_COLUMN_IN_TABLE)";
+        assert_eq!(InsertFailure::from_message(missing_column), InsertFailure::Permanent);
+        let missing_table = "bad response: Code: 60. DB::Exception: Table solana.swaps \
+                             does not exist. (UNKNOWN_TABLE)";
+        assert_eq!(InsertFailure::from_message(missing_table), InsertFailure::Permanent);
+
+        let overloaded = "bad response: Code: 202. DB::Exception: Too many simultaneous \
+                          queries. (TOO_MANY_SIMULTANEOUS_QUERIES)";
+        assert_eq!(InsertFailure::from_message(overloaded), InsertFailure::Transient);
+        let network = "network error: connection refused";
+        assert_eq!(InsertFailure::from_message(network), InsertFailure::Transient);
+    }
+}
@@ -1,7 +1,10 @@
 use anyhow::{Result, anyhow};
-use std::{env, time::Duration};
+use schema::ChainPrograms;
+use std::{collections::HashSet, env, time::Duration};
 use yellowstone_grpc_proto::prelude::CommitmentLevel;
 
+use crate::kafka::PartitionKey;
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub geyser_endpoint: String,
@@ -9,13 +12,27 @@ pub struct Config {
 
     pub kafka_broker: String,
     pub kafka_topic: String,
+    pub partition_key: PartitionKey,
+
+    pub chain: String,
+    pub programs: ChainPrograms,
 
     pub required_accounts: Vec<String>,
+    /// Forward only txs invoking one of these programs (empty = all)
+    pub program_allowlist: HashSet<String>,
     pub include_failed: bool,
     pub commitment: CommitmentLevel,
+    pub emit_account_key_counts: bool,
 
     pub reconnect_min_backoff: Duration,
     pub reconnect_max_backoff: Duration,
+    /// Give up after this many consecutive reconnects without a subscribe (0 = never)
+    pub max_reconnects: u32,
+
+    /// Serve `GET /healthz` on this port (off when unset)
+    pub health_port: Option<u16>,
+    /// `/healthz` fails once no tx has been seen for this long
+    pub health_max_tx_age: Duration,
 }
 
 fn parse_bool(v: Option<String>, default: bool) -> bool {
@@ -45,6 +62,18 @@ pub fn load() -> Result<Config> {
 
     let kafka_broker = env::var("KAFKA_BROKER").unwrap_or_else(|_| "localhost:19092".to_string());
     let kafka_topic = env::var("KAFKA_TOPIC").unwrap_or_else(|_| "sol_raw_txs".to_string());
+    // Message key: signature (default) keeps each tx's re-deliveries ordered on
+    // one partition, like the decoder and backfill outputs
+    let partition_key = PartitionKey::parse(
+        &env::var("KAFKA_PARTITION_KEY").unwrap_or_else(|_| "signature".to_string()),
+    )?;
+
+    // Program skip list for main_program is selected per chain (mainnet defaults).
+    // TOKEN_2022_PROGRAM_ID only moves that skip list; the decoder's token
+    // parsing keeps the mainnet token program IDs
+    let chain = env::var("CHAIN").unwrap_or_else(|_| "solana-mainnet".to_string());
+    let programs = ChainPrograms::for_chain(&chain)
+        .with_token_2022(&env::var("TOKEN_2022_PROGRAM_ID").unwrap_or_default());
 
     let required_accounts = env::var("REQUIRED_ACCOUNTS")
         .unwrap_or_else(|_| "".to_string())
@@ -53,20 +82,61 @@ pub fn load() -> Result<Config> {
         .filter(|s| !s.is_empty())
         .collect::<Vec<_>>();
 
+    // Post-filter on the resolved program list (REQUIRED_ACCOUNTS gates at the Geyser level)
+    let program_allowlist = env::var("PROGRAM_ALLOWLIST")
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect::<HashSet<_>>();
+
     let include_failed = parse_bool(env::var("INCLUDE_FAILED").ok(), false);
 
     let commitment =
         parse_commitment(&env::var("COMMITMENT").unwrap_or_else(|_| "processed".to_string()))?;
 
+    // Static / total account key counts on RawTxEvent (v0 + ALT txs have total > static)
+    let emit_account_key_counts = parse_bool(env::var("EMIT_ACCOUNT_KEY_COUNTS").ok(), false);
+
+    // Batch/CI runs: exit after K failed reconnects in a row (0 = reconnect forever)
+    let max_reconnects = env::var("STREAM_MAX_RECONNECTS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
+
+    // Liveness endpoint for orchestrators: 200 while subscribed and txs keep
+    // arriving, 503 once the stream is wedged (off unless a port is set)
+    let health_port = env::var("HEALTH_PORT")
+        .ok()
+        .map(|s| {
+            s.parse::<u16>()
+                .map_err(|_| anyhow!("HEALTH_PORT must be a port number, got {}", s))
+        })
+        .transpose()?;
+    let health_max_tx_age = Duration::from_secs(
+        env::var("HEALTH_MAX_TX_AGE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(60),
+    );
+
     Ok(Config {
         geyser_endpoint,
         geyser_x_token,
         kafka_broker,
         kafka_topic,
+        partition_key,
+        chain,
+        programs,
         required_accounts,
+        program_allowlist,
         include_failed,
         commitment,
+        emit_account_key_counts,
         reconnect_min_backoff: Duration::from_secs(1),
         reconnect_max_backoff: Duration::from_secs(30),
+        max_reconnects,
+        health_port,
+        health_max_tx_age,
     })
 }
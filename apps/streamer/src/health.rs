@@ -0,0 +1,71 @@
+//! Liveness endpoint (`HEALTH_PORT`): `GET /healthz` is 200 while the stream
+//! is subscribed and delivering txs, 503 once none arrived within
+//! `HEALTH_MAX_TX_AGE_SECS`; any other path is a 404.
+
+use anyhow::Result;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use log::{info, warn};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::net::TcpListener;
+
+use crate::metrics::Metrics;
+
+fn respond(req: &Request<Incoming>, m: &Metrics, max_tx_age: Duration) -> Response<Full<Bytes>> {
+    let (status, body) = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/healthz") if m.is_healthy(max_tx_age, SystemTime::now()) => {
+            (StatusCode::OK, "ok\n")
+        }
+        (&Method::GET, "/healthz") => (StatusCode::SERVICE_UNAVAILABLE, "stale\n"),
+        _ => (StatusCode::NOT_FOUND, "not found\n"),
+    };
+    Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response parts")
+}
+
+/// Bind `0.0.0.0:port` and answer health checks in the background until the
+/// process exits.
+///
+/// Binding errors are returned so a taken port fails startup; per-connection
+/// errors are only logged.
+pub async fn serve(port: u16, m: Arc<Metrics>, max_tx_age: Duration) -> Result<()> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let listener = TcpListener::bind(addr).await?;
+    info!("health endpoint listening on http://{addr}/healthz");
+
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("health endpoint accept failed: {e}");
+                    continue;
+                }
+            };
+            let m = m.clone();
+            tokio::spawn(async move {
+                let service = service_fn(|req| {
+                    let m = m.clone();
+                    async move { Ok::<_, Infallible>(respond(&req, &m, max_tx_age)) }
+                });
+                if let Err(e) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    warn!("health endpoint connection error: {e}");
+                }
+            });
+        }
+    });
+    Ok(())
+}
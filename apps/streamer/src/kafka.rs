@@ -3,6 +3,39 @@ use rdkafka::config::ClientConfig;
 use rdkafka::producer::{FutureProducer, FutureRecord};
 use std::time::Duration;
 
+/// What the Kafka message key of a raw tx is (`KAFKA_PARTITION_KEY`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PartitionKey {
+    /// Tx signature: re-deliveries of one tx stay on one partition, in order
+    Signature,
+    /// Slot: a block's txs share a partition
+    Slot,
+    /// No key: the producer spreads txs over partitions
+    None,
+}
+
+impl PartitionKey {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "signature" => Ok(Self::Signature),
+            "slot" => Ok(Self::Slot),
+            "none" => Ok(Self::None),
+            other => Err(anyhow!(
+                "Invalid KAFKA_PARTITION_KEY={other}. Use signature|slot|none"
+            )),
+        }
+    }
+
+    /// Message key of the tx `signature` in `slot`
+    pub fn key_for(self, signature: &str, slot: u64) -> Option<String> {
+        match self {
+            Self::Signature => Some(signature.to_string()),
+            Self::Slot => Some(slot.to_string()),
+            Self::None => None,
+        }
+    }
+}
+
 pub fn create_producer(broker: &str) -> Result<FutureProducer> {
     let producer: FutureProducer = ClientConfig::new()
         .set("bootstrap.servers", broker)
@@ -16,11 +49,43 @@ pub fn create_producer(broker: &str) -> Result<FutureProducer> {
     Ok(producer)
 }
 
-pub async fn send_json(producer: &FutureProducer, topic: &str, json: &str) -> Result<()> {
-    let record = FutureRecord::<(), str>::to(topic).payload(json);
+pub async fn send_json(
+    producer: &FutureProducer,
+    topic: &str,
+    key: Option<&str>,
+    json: &str,
+) -> Result<()> {
+    let mut record = FutureRecord::<str, str>::to(topic).payload(json);
+    if let Some(k) = key {
+        record = record.key(k);
+    }
 
     match producer.send(record, Duration::from_secs(5)).await {
         Ok((_p, _o)) => Ok(()),
         Err((e, _)) => Err(anyhow!("Kafka delivery error: {e:?}")),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition_key_strategies() {
+        assert_eq!(
+            PartitionKey::parse("Signature").unwrap(),
+            PartitionKey::Signature
+        );
+        assert!(PartitionKey::parse("account").is_err());
+
+        assert_eq!(
+            PartitionKey::Signature.key_for("5abc", 42).as_deref(),
+            Some("5abc")
+        );
+        assert_eq!(
+            PartitionKey::Slot.key_for("5abc", 42).as_deref(),
+            Some("42")
+        );
+        assert_eq!(PartitionKey::None.key_for("5abc", 42), None);
+    }
+}
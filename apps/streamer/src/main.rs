@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use log::{info, warn};
 use rdkafka::producer::Producer;
 use std::sync::atomic::Ordering;
@@ -6,6 +6,7 @@ use std::time::Duration;
 use tokio::time::sleep;
 
 mod config;
+mod health;
 mod kafka;
 mod metrics;
 mod stream;
@@ -26,27 +27,55 @@ async fn main() -> Result<()> {
     let cfg: Config = config::load()?;
 
     info!(
-        "streamer starting topic={} broker={}",
-        cfg.kafka_topic, cfg.kafka_broker
+        "streamer starting topic={} broker={} partition_key={:?}",
+        cfg.kafka_topic, cfg.kafka_broker, cfg.partition_key
     );
     info!(
         "endpoint={} commitment={:?} include_failed={} required_accounts={:?}",
         cfg.geyser_endpoint, cfg.commitment, cfg.include_failed, cfg.required_accounts
     );
+    info!("program_allowlist={:?} (empty = all)", cfg.program_allowlist);
+    info!("emit_account_key_counts={}", cfg.emit_account_key_counts);
+    info!("token_2022_program={}", cfg.programs.token_2022_program);
+    info!("max_reconnects={} (0 = unlimited)", cfg.max_reconnects);
+    info!(
+        "health_port={:?} health_max_tx_age={:?}",
+        cfg.health_port, cfg.health_max_tx_age
+    );
 
     let producer = kafka::create_producer(&cfg.kafka_broker)?;
     let m = std::sync::Arc::new(Metrics::new());
 
+    if let Some(port) = cfg.health_port {
+        health::serve(port, m.clone(), cfg.health_max_tx_age).await?;
+    }
+
     // ---- Background metrics logger (prints even when stream is healthy) ----
     {
         let m = m.clone();
         tokio::spawn(async move {
             loop {
                 sleep(Duration::from_secs(5)).await;
-                let (tx_seen, ok, err, reconnects, connected) = m.snapshot();
+                let (
+                    tx_seen,
+                    ok,
+                    err,
+                    reconnects,
+                    connected,
+                    block_time_missing,
+                    v0_tx_seen,
+                    filtered_out,
+                ) = m.snapshot();
                 info!(
-                    "metrics tx_seen={} kafka_ok={} kafka_err={} reconnects={} connected={}",
-                    tx_seen, ok, err, reconnects, connected
+                    "metrics tx_seen={} v0_tx_seen={} filtered_out={} kafka_ok={} kafka_err={} reconnects={} connected={} block_time_missing={}",
+                    tx_seen,
+                    v0_tx_seen,
+                    filtered_out,
+                    ok,
+                    err,
+                    reconnects,
+                    connected,
+                    block_time_missing
                 );
             }
         });
@@ -54,6 +83,7 @@ async fn main() -> Result<()> {
 
     let mut backoff = cfg.reconnect_min_backoff;
     let mut last_connected = 0u64;
+    let mut reconnect_budget = stream::ReconnectBudget::new(cfg.max_reconnects);
 
     info!("starting main loop (Ctrl+C to stop)");
 
@@ -81,11 +111,24 @@ async fn main() -> Result<()> {
 
                 // Reset backoff if we managed to subscribe at least once since last loop
                 let now_connected = m.connected.load(Ordering::Relaxed);
-                if now_connected > last_connected {
+                let subscribed = now_connected > last_connected;
+                if subscribed {
                     backoff = cfg.reconnect_min_backoff;
                     last_connected = now_connected;
                 }
 
+                if reconnect_budget.record(subscribed) {
+                    warn!(
+                        "giving up after {} reconnects without a subscribe (STREAM_MAX_RECONNECTS)",
+                        reconnect_budget.failures()
+                    );
+                    let _ = producer.flush(Duration::from_secs(10));
+                    return Err(anyhow!(
+                        "stream failed to subscribe {} times in a row",
+                        reconnect_budget.failures()
+                    ));
+                }
+
                 warn!("disconnected. reconnecting in {backoff:?}");
                 sleep(backoff).await;
                 backoff = (backoff * 2).min(cfg.reconnect_max_backoff);
@@ -1,4 +1,5 @@
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub struct Metrics {
     pub tx_seen: AtomicU64,
@@ -6,6 +7,14 @@ pub struct Metrics {
     pub send_err: AtomicU64,
     pub reconnects: AtomicU64,
     pub connected: AtomicU64, // increments each time we successfully subscribe
+    pub block_time_missing: AtomicU64, // txs emitted before their slot's block meta arrived
+    pub v0_tx_seen: AtomicU64, // versioned (v0) txs, which may load addresses from ALTs
+    pub filtered_out: AtomicU64, // txs dropped by PROGRAM_ALLOWLIST
+    pub last_tx_unix_ms: AtomicU64, // wall clock of the last tx received (0 = none yet)
+}
+
+fn unix_ms(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
 }
 
 impl Metrics {
@@ -16,16 +25,57 @@ impl Metrics {
             send_err: AtomicU64::new(0),
             reconnects: AtomicU64::new(0),
             connected: AtomicU64::new(0),
+            block_time_missing: AtomicU64::new(0),
+            v0_tx_seen: AtomicU64::new(0),
+            filtered_out: AtomicU64::new(0),
+            last_tx_unix_ms: AtomicU64::new(0),
         }
     }
 
-    pub fn snapshot(&self) -> (u64, u64, u64, u64, u64) {
+    /// Count a received tx and stamp it as the latest
+    pub fn record_tx_seen(&self) {
+        self.tx_seen.fetch_add(1, Ordering::Relaxed);
+        self.last_tx_unix_ms
+            .store(unix_ms(SystemTime::now()), Ordering::Relaxed);
+    }
+
+    /// Subscribed at least once and a tx arrived within `max_tx_age` of `now`
+    pub fn is_healthy(&self, max_tx_age: Duration, now: SystemTime) -> bool {
+        let last_tx = self.last_tx_unix_ms.load(Ordering::Relaxed);
+        self.connected.load(Ordering::Relaxed) > 0
+            && last_tx > 0
+            && unix_ms(now).saturating_sub(last_tx) <= max_tx_age.as_millis() as u64
+    }
+
+    pub fn snapshot(&self) -> (u64, u64, u64, u64, u64, u64, u64, u64) {
         (
             self.tx_seen.load(Ordering::Relaxed),
             self.send_ok.load(Ordering::Relaxed),
             self.send_err.load(Ordering::Relaxed),
             self.reconnects.load(Ordering::Relaxed),
             self.connected.load(Ordering::Relaxed),
+            self.block_time_missing.load(Ordering::Relaxed),
+            self.v0_tx_seen.load(Ordering::Relaxed),
+            self.filtered_out.load(Ordering::Relaxed),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_needs_a_subscribe_and_a_recent_tx() {
+        let m = Metrics::new();
+        let max_age = Duration::from_secs(60);
+        assert!(!m.is_healthy(max_age, SystemTime::now()));
+
+        m.connected.fetch_add(1, Ordering::Relaxed);
+        assert!(!m.is_healthy(max_age, SystemTime::now()));
+
+        m.record_tx_seen();
+        assert!(m.is_healthy(max_age, SystemTime::now()));
+        assert!(!m.is_healthy(max_age, SystemTime::now() + Duration::from_secs(61)));
+    }
+}
@@ -2,15 +2,18 @@ use anyhow::Result;
 use futures::{SinkExt, StreamExt};
 use log::{error, info, warn};
 use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use tonic::transport::ClientTlsConfig;
 use yellowstone_grpc_client::GeyserGrpcClient;
 use yellowstone_grpc_proto::prelude::{
-    SubscribeRequest, SubscribeRequestFilterTransactions, subscribe_update::UpdateOneof,
+    Message, SubscribeRequest, SubscribeRequestFilterBlocksMeta,
+    SubscribeRequestFilterTransactions, SubscribeUpdateTransactionInfo, TransactionStatusMeta,
+    subscribe_update::UpdateOneof,
 };
 
 use crate::{config::Config, kafka, metrics::Metrics};
 use rdkafka::producer::FutureProducer;
+use schema::ChainPrograms;
 
 #[derive(Debug, Serialize)]
 pub struct RawTxEvent {
@@ -26,18 +29,14 @@ pub struct RawTxEvent {
     pub compute_units_consumed: Option<u64>,
     pub main_program: Option<String>,
     pub program_ids: Vec<String>,
-}
-
-fn pick_main_program(program_ids: &[String]) -> Option<String> {
-    let skip = [
-        "ComputeBudget111111111111111111111111111111",
-        "11111111111111111111111111111111",
-        "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
-    ];
-    program_ids
-        .iter()
-        .find(|p| !skip.contains(&p.as_str()))
-        .cloned()
+    /// Message recentBlockhash (base58), for correlating txs to fork points
+    pub recent_blockhash: Option<String>,
+    /// Message account keys, before lookup-table addresses (`EMIT_ACCOUNT_KEY_COUNTS`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub static_account_keys_len: Option<u32>,
+    /// Static keys plus ALT-loaded addresses; above the static count for v0 + ALT txs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_keys_len: Option<u32>,
 }
 
 fn extract_program_ids(
@@ -58,6 +57,147 @@ fn extract_program_ids(
     out
 }
 
+/// Whether a tx invoking `program_ids` passes `PROGRAM_ALLOWLIST` (empty passes all)
+fn allowed_by_programs(allowlist: &HashSet<String>, program_ids: &[String]) -> bool {
+    allowlist.is_empty() || program_ids.iter().any(|p| allowlist.contains(p))
+}
+
+/// (static, total) account key counts: the message's own keys, then those plus
+/// the writable and readonly addresses loaded from lookup tables
+fn account_key_counts(message: &Message, meta: Option<&TransactionStatusMeta>) -> (u32, u32) {
+    let static_len = message.account_keys.len();
+    let loaded_len = meta
+        .map(|mm| mm.loaded_writable_addresses.len() + mm.loaded_readonly_addresses.len())
+        .unwrap_or(0);
+    (static_len as u32, (static_len + loaded_len) as u32)
+}
+
+/// `tx_version` as RPC reports it: `Some(0)` for v0 messages, `None` for legacy
+fn tx_version(message: &Message) -> Option<u8> {
+    message.versioned.then_some(0)
+}
+
+/// Base58 recent blockhash from the Geyser message (raw 32 bytes)
+fn encode_recent_blockhash(bytes: &[u8]) -> Option<String> {
+    if bytes.is_empty() {
+        None
+    } else {
+        Some(bs58::encode(bytes).into_string())
+    }
+}
+
+/// RawTxEvent for one Geyser transaction update; `None` if it carries no message
+fn raw_tx_event(
+    tx_info: &SubscribeUpdateTransactionInfo,
+    slot: u64,
+    block_time: Option<i64>,
+    chain: &str,
+    programs: &ChainPrograms,
+    emit_account_key_counts: bool,
+) -> Option<RawTxEvent> {
+    let message = tx_info.transaction.as_ref()?.message.as_ref()?;
+    let meta = tx_info.meta.as_ref();
+
+    let account_keys: Vec<String> = message
+        .account_keys
+        .iter()
+        .map(|k| bs58::encode(k).into_string())
+        .collect();
+    let outer_indexes = message.instructions.iter().map(|ix| ix.program_id_index);
+    let inner_indexes = meta
+        .into_iter()
+        .flat_map(|mm| mm.inner_instructions.iter())
+        .flat_map(|ii| ii.instructions.iter().map(|ix| ix.program_id_index));
+    let program_ids = extract_program_ids(&account_keys, outer_indexes.chain(inner_indexes));
+
+    let (static_account_keys_len, account_keys_len) = if emit_account_key_counts {
+        let (static_len, total_len) = account_key_counts(message, meta);
+        (Some(static_len), Some(total_len))
+    } else {
+        (None, None)
+    };
+
+    Some(RawTxEvent {
+        schema_version: 1,
+        chain: chain.to_string(),
+        slot,
+        block_time,
+        signature: bs58::encode(&tx_info.signature).into_string(),
+        // Position of the tx within its block, as reported by Geyser
+        index_in_block: u32::try_from(tx_info.index).unwrap_or(u32::MAX),
+        tx_version: tx_version(message),
+        is_success: meta.and_then(|mm| mm.err.as_ref()).is_none(),
+        fee_lamports: meta.map(|mm| mm.fee).unwrap_or(0),
+        compute_units_consumed: meta.and_then(|mm| mm.compute_units_consumed),
+        main_program: programs.pick_main_program(&program_ids),
+        program_ids,
+        recent_blockhash: encode_recent_blockhash(&message.recent_blockhash),
+        static_account_keys_len,
+        account_keys_len,
+    })
+}
+
+/// Slots whose block time is kept; block meta for older slots is not expected
+/// to be looked up again
+const BLOCK_TIME_CACHE_SLOTS: usize = 1024;
+
+/// Slot -> block time (unix seconds) from Geyser block meta updates, keeping
+/// only the most recent `cap` slots
+#[derive(Debug)]
+pub struct BlockTimeCache {
+    cap: usize,
+    times: BTreeMap<u64, i64>,
+}
+
+impl BlockTimeCache {
+    pub fn new(cap: usize) -> Self {
+        Self {
+            cap,
+            times: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, slot: u64, block_time: i64) {
+        self.times.insert(slot, block_time);
+        while self.times.len() > self.cap {
+            self.times.pop_first();
+        }
+    }
+
+    pub fn get(&self, slot: u64) -> Option<i64> {
+        self.times.get(&slot).copied()
+    }
+}
+
+/// Consecutive reconnects that never reached a subscribe (`STREAM_MAX_RECONNECTS`)
+#[derive(Debug)]
+pub struct ReconnectBudget {
+    max: u32,
+    failures: u32,
+}
+
+impl ReconnectBudget {
+    /// `max = 0` never runs out
+    pub fn new(max: u32) -> Self {
+        Self { max, failures: 0 }
+    }
+
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Record one `run_once` attempt; true once `max` attempts in a row failed
+    /// to subscribe. A successful subscribe resets the count.
+    pub fn record(&mut self, subscribed: bool) -> bool {
+        if subscribed {
+            self.failures = 0;
+            return false;
+        }
+        self.failures += 1;
+        self.max > 0 && self.failures >= self.max
+    }
+}
+
 pub async fn run_once(cfg: &Config, producer: &FutureProducer, m: &Metrics) -> Result<()> {
     let mut client = GeyserGrpcClient::build_from_shared(cfg.geyser_endpoint.clone())?
         .x_token(cfg.geyser_x_token.clone())?
@@ -78,9 +218,17 @@ pub async fn run_once(cfg: &Config, producer: &FutureProducer, m: &Metrics) -> R
         },
     );
 
+    // Block meta carries the block time that transaction updates lack
+    let mut blocks_meta_filters = HashMap::new();
+    blocks_meta_filters.insert(
+        "block_meta".to_string(),
+        SubscribeRequestFilterBlocksMeta::default(),
+    );
+
     sub_tx
         .send(SubscribeRequest {
             transactions: tx_filters,
+            blocks_meta: blocks_meta_filters,
             commitment: Some(cfg.commitment as i32),
             ..Default::default()
         })
@@ -90,6 +238,8 @@ pub async fn run_once(cfg: &Config, producer: &FutureProducer, m: &Metrics) -> R
     m.connected
         .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
+    let mut block_times = BlockTimeCache::new(BLOCK_TIME_CACHE_SLOTS);
+
     while let Some(msg) = sub_rx.next().await {
         let msg = match msg {
             Ok(m) => m,
@@ -100,64 +250,47 @@ pub async fn run_once(cfg: &Config, producer: &FutureProducer, m: &Metrics) -> R
         };
 
         match msg.update_oneof {
+            Some(UpdateOneof::BlockMeta(meta)) => {
+                if let Some(block_time) = meta.block_time {
+                    block_times.insert(meta.slot, block_time.timestamp);
+                }
+            }
             Some(UpdateOneof::Transaction(tx)) => {
-                m.tx_seen.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                m.record_tx_seen();
 
                 let Some(tx_info) = tx.transaction else {
                     continue;
                 };
-                let signature = bs58::encode(&tx_info.signature).into_string();
-
                 let slot = tx.slot;
-                let chain = "solana-mainnet".to_string();
-                let meta = tx_info.meta.as_ref();
-                let is_success = meta.and_then(|mm| mm.err.as_ref()).is_none();
-                let fee_lamports = meta.map(|mm| mm.fee).unwrap_or(0);
-
-                let message = match tx_info
-                    .transaction
-                    .as_ref()
-                    .and_then(|t| t.message.as_ref())
-                {
-                    Some(mm) => mm,
-                    None => continue,
-                };
-
-                let account_keys: Vec<String> = message
-                    .account_keys
-                    .iter()
-                    .map(|k| bs58::encode(k).into_string())
-                    .collect();
-
-                let outer_indexes = message.instructions.iter().map(|ix| ix.program_id_index);
-                let inner_indexes = tx_info
-                    .meta
-                    .as_ref()
-                    .into_iter()
-                    .flat_map(|mm| mm.inner_instructions.iter())
-                    .flat_map(|ii| ii.instructions.iter().map(|ix| ix.program_id_index));
-
-                let program_ids =
-                    extract_program_ids(&account_keys, outer_indexes.chain(inner_indexes));
-                let main_program = pick_main_program(&program_ids);
-
-                let event = RawTxEvent {
-                    schema_version: 1,
-                    chain,
+                // Usually known only once the slot's block is complete
+                let block_time = block_times.get(slot);
+                if block_time.is_none() {
+                    m.block_time_missing
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                }
+                let Some(event) = raw_tx_event(
+                    &tx_info,
                     slot,
-                    block_time: None,
-                    signature,
-                    index_in_block: 0,
-                    tx_version: None,
-                    is_success,
-                    fee_lamports,
-                    compute_units_consumed: None,
-                    main_program,
-                    program_ids,
+                    block_time,
+                    &cfg.chain,
+                    &cfg.programs,
+                    cfg.emit_account_key_counts,
+                ) else {
+                    continue;
                 };
+                if !allowed_by_programs(&cfg.program_allowlist, &event.program_ids) {
+                    m.filtered_out
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    continue;
+                }
+                if event.tx_version.is_some() {
+                    m.v0_tx_seen
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                }
 
+                let key = cfg.partition_key.key_for(&event.signature, slot);
                 let json = serde_json::to_string(&event)?;
-                match kafka::send_json(producer, &cfg.kafka_topic, &json).await {
+                match kafka::send_json(producer, &cfg.kafka_topic, key.as_deref(), &json).await {
                     Ok(_) => {
                         m.send_ok.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
                     }
@@ -175,3 +308,161 @@ pub async fn run_once(cfg: &Config, producer: &FutureProducer, m: &Metrics) -> R
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yellowstone_grpc_proto::prelude::{CompiledInstruction, Transaction};
+
+    #[test]
+    fn test_program_allowlist() {
+        let program_ids = vec!["Raydium111".to_string(), "Token111".to_string()];
+        assert!(allowed_by_programs(&HashSet::new(), &program_ids));
+        assert!(allowed_by_programs(
+            &HashSet::from(["Raydium111".to_string(), "Orca111".to_string()]),
+            &program_ids
+        ));
+        assert!(!allowed_by_programs(
+            &HashSet::from(["Orca111".to_string()]),
+            &program_ids
+        ));
+    }
+
+    fn tx_update(recent_blockhash: Vec<u8>) -> SubscribeUpdateTransactionInfo {
+        let message = Message {
+            account_keys: vec![vec![1u8; 32], vec![2u8; 32]],
+            recent_blockhash,
+            instructions: vec![CompiledInstruction {
+                program_id_index: 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        SubscribeUpdateTransactionInfo {
+            signature: vec![9u8; 64],
+            transaction: Some(Transaction {
+                signatures: vec![vec![9u8; 64]],
+                message: Some(message),
+            }),
+            meta: Some(TransactionStatusMeta {
+                fee: 5000,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_raw_tx_event_carries_recent_blockhash() {
+        let programs = ChainPrograms::mainnet();
+        let event = raw_tx_event(
+            &tx_update(vec![7u8; 32]),
+            250_000_000,
+            Some(1_700_000_000),
+            "solana-mainnet",
+            &programs,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            event.recent_blockhash,
+            Some(bs58::encode([7u8; 32]).into_string())
+        );
+        assert_eq!(event.signature, bs58::encode([9u8; 64]).into_string());
+        assert_eq!(
+            event.program_ids,
+            vec![bs58::encode([2u8; 32]).into_string()]
+        );
+        assert_eq!(event.fee_lamports, 5000);
+        assert!(event.is_success);
+
+        // An empty blockhash is left out rather than encoded as ""
+        let event = raw_tx_event(&tx_update(vec![]), 1, None, "c", &programs, false).unwrap();
+        assert_eq!(event.recent_blockhash, None);
+        let json = serde_json::to_value(&event).unwrap();
+        assert!(json["recent_blockhash"].is_null());
+
+        // No message: nothing to emit
+        let mut no_message = tx_update(vec![7u8; 32]);
+        no_message.transaction = None;
+        assert!(raw_tx_event(&no_message, 1, None, "c", &programs, false).is_none());
+    }
+
+    #[test]
+    fn test_raw_tx_event_index_in_block_from_update() {
+        let programs = ChainPrograms::mainnet();
+        let event = |index: u64, signature: u8| {
+            let mut update = tx_update(vec![7u8; 32]);
+            update.index = index;
+            update.signature = vec![signature; 64];
+            raw_tx_event(&update, 250_000_000, None, "solana-mainnet", &programs, false).unwrap()
+        };
+
+        // Two txs of one slot keep their positions within the block
+        let (first, second) = (event(3, 1), event(41, 2));
+        assert_eq!((first.slot, first.index_in_block), (250_000_000, 3));
+        assert_eq!((second.slot, second.index_in_block), (250_000_000, 41));
+        assert_ne!(first.signature, second.signature);
+    }
+
+    #[test]
+    fn test_account_key_counts_for_v0_message() {
+        let message = Message {
+            account_keys: vec![vec![1u8; 32]; 5],
+            versioned: true,
+            ..Default::default()
+        };
+        let meta = TransactionStatusMeta {
+            loaded_writable_addresses: vec![vec![2u8; 32]; 3],
+            loaded_readonly_addresses: vec![vec![3u8; 32]; 2],
+            ..Default::default()
+        };
+        assert_eq!(account_key_counts(&message, Some(&meta)), (5, 10));
+
+        // No meta (or a legacy tx): nothing loaded
+        assert_eq!(account_key_counts(&message, None), (5, 5));
+    }
+
+    #[test]
+    fn test_tx_version_from_message() {
+        let v0 = Message {
+            versioned: true,
+            ..Default::default()
+        };
+        assert_eq!(tx_version(&v0), Some(0));
+        assert_eq!(tx_version(&Message::default()), None);
+    }
+
+    #[test]
+    fn test_block_time_cache_keeps_recent_slots() {
+        let mut cache = BlockTimeCache::new(2);
+        assert_eq!(cache.get(100), None);
+
+        cache.insert(100, 1_700_000_000);
+        cache.insert(102, 1_700_000_001);
+        assert_eq!(cache.get(100), Some(1_700_000_000));
+
+        // Over capacity: the oldest slot goes, even if inserted late
+        cache.insert(101, 1_700_000_000);
+        assert_eq!(cache.get(100), None);
+        assert_eq!(cache.get(101), Some(1_700_000_000));
+        assert_eq!(cache.get(102), Some(1_700_000_001));
+    }
+
+    #[test]
+    fn test_reconnect_budget_resets_on_subscribe() {
+        let mut budget = ReconnectBudget::new(3);
+        assert!(!budget.record(false));
+        assert!(!budget.record(false));
+        // A subscribe in between starts the count over
+        assert!(!budget.record(true));
+        assert_eq!(budget.failures(), 0);
+        assert!(!budget.record(false));
+        assert!(!budget.record(false));
+        assert!(budget.record(false));
+
+        // 0 = unlimited
+        let mut unlimited = ReconnectBudget::new(0);
+        assert!((0..1000).all(|_| !unlimited.record(false)));
+    }
+}
@@ -1,36 +1,91 @@
--- DEX swaps normalized view: one row per swap
+-- Gold DEX swaps (DexSwapV1), written in batches by apps/indexer from Kafka.
+-- Columns mirror the DexSwapV1 fields; one row per swap leg.
+--
+-- Replaced the pre-DexSwapV1 layout (ts/protocol/pool_address/...), which
+-- nothing wrote to. On deployments that still have the old table, re-running
+-- this file adds the new columns (ALTERs below); drop it by hand for the new
+-- engine and sort key (see RUNBOOK).
 
 CREATE TABLE IF NOT EXISTS solana.dex_swaps
 (
-    ts DateTime,                          -- when the swap happened (from block_time)
-    ingested_at DateTime DEFAULT now(),   -- when we stored this row
-
-    -- link back to raw tx
-    slot UInt64,
-    signature String,
-    index_in_block UInt32,
-
-    -- protocol / pool info
-    protocol String,           -- e.g. 'raydium', 'orca'
-    pool_address String,       -- AMM pool account
-
-    -- trader info
-    trader String,             -- user wallet (if we can infer it)
-    side String,               -- 'buy', 'sell', or 'swap'
-
-    -- assets & amounts
-    in_mint String,
-    out_mint String,
-    in_amount Float64,
-    out_amount Float64,
-
-    -- pricing
-    price Float64,             -- out_amount / in_amount
-    usd_value Float64,         -- can be 0 for now, fill later when you add pricing oracles
-
-    -- tx meta
-    is_success Bool,
-    fee_lamports UInt64
+  schema_version UInt16,
+  chain LowCardinality(String),
+
+  slot UInt64,
+  block_time Nullable(Int64),
+  signature String,
+
+  index_in_block UInt32,
+  index_in_tx UInt16,
+  hop_index UInt8 DEFAULT 0,
+
+  venue LowCardinality(String),
+  pool_id Nullable(String),
+
+  trader String,
+
+  in_mint LowCardinality(String),
+  in_amount String,
+
+  out_mint LowCardinality(String),
+  out_amount String,
+
+  fee_mint Nullable(String),
+  fee_amount Nullable(String),
+
+  route_id Nullable(String),
+
+  confidence UInt8,
+  confidence_reasons UInt32 DEFAULT 0,
+  explain Nullable(String),
+
+  sol_cost_lamports Nullable(UInt64),
+  jito_bundle Bool DEFAULT false,
+  partial Bool DEFAULT false,
+  parser_version UInt16 DEFAULT 0,
+
+  in_symbol Nullable(String),
+  out_symbol Nullable(String),
+  in_ui_amount Nullable(String),
+  out_ui_amount Nullable(String),
+
+  ingested_at DateTime DEFAULT now(),
+  version UInt64 DEFAULT toUnixTimestamp(now())
 )
-ENGINE = MergeTree
-ORDER BY (ts, slot, signature, index_in_block);
\ No newline at end of file
+ENGINE = ReplacingMergeTree(version)
+PARTITION BY toYYYYMMDD(fromUnixTimestamp(coalesce(block_time, 0)))
+ORDER BY (slot, signature, index_in_tx, hop_index)
+SETTINGS index_granularity = 8192;
+
+-- Legacy layout (ts/protocol/pool_address/...): give it the DexSwapV1 columns so
+-- the indexer's inserts land instead of failing on a missing column; no-op on
+-- new tables. Engine and sort key can't be altered, so replays only collapse
+-- once the legacy table is dropped and re-created (see RUNBOOK)
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS schema_version UInt16 DEFAULT 0;
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS chain LowCardinality(String) DEFAULT '';
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS block_time Nullable(Int64);
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS index_in_tx UInt16 DEFAULT 0;
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS hop_index UInt8 DEFAULT 0;
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS venue LowCardinality(String) DEFAULT '';
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS pool_id Nullable(String);
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS fee_mint Nullable(String);
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS fee_amount Nullable(String);
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS route_id Nullable(String);
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS confidence UInt8 DEFAULT 0;
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS confidence_reasons UInt32 DEFAULT 0;
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS explain Nullable(String);
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS sol_cost_lamports Nullable(UInt64);
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS jito_bundle Bool DEFAULT false;
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS partial Bool DEFAULT false;
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS parser_version UInt16 DEFAULT 0;
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS in_symbol Nullable(String);
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS out_symbol Nullable(String);
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS in_ui_amount Nullable(String);
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS out_ui_amount Nullable(String);
+ALTER TABLE solana.dex_swaps ADD COLUMN IF NOT EXISTS version UInt64 DEFAULT toUnixTimestamp(now());
+-- Legacy amounts were Float64; base-unit amounts are u128 decimal strings
+ALTER TABLE solana.dex_swaps MODIFY COLUMN in_amount String;
+ALTER TABLE solana.dex_swaps MODIFY COLUMN out_amount String;
+
+-- confidence_reasons grew to 32 bits (MULTI_TRADER = 1 << 16); no-op on new tables
+ALTER TABLE solana.dex_swaps MODIFY COLUMN confidence_reasons UInt32 DEFAULT 0;
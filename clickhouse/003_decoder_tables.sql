@@ -69,9 +69,9 @@ CREATE TABLE IF NOT EXISTS solana.sol_token_balance_deltas_queue
   account_index UInt32,
   mint String,
   decimals Nullable(UInt8),
-  pre_amount UInt64,
-  post_amount UInt64,
-  delta Int64
+  pre_amount UInt128,
+  post_amount UInt128,
+  delta Int128
 )
 ENGINE = Kafka
 SETTINGS
@@ -91,9 +91,9 @@ CREATE TABLE IF NOT EXISTS solana.sol_token_balance_deltas
   account_index UInt32,
   mint String,
   decimals Nullable(UInt8),
-  pre_amount UInt64,
-  post_amount UInt64,
-  delta Int64
+  pre_amount UInt128,
+  post_amount UInt128,
+  delta Int128
 )
 ENGINE = MergeTree
 ORDER BY (mint, slot, signature, account_index);
@@ -113,4 +113,10 @@ SELECT
   pre_amount,
   post_amount,
   delta
-FROM solana.sol_token_balance_deltas_queue;
\ No newline at end of file
+FROM solana.sol_token_balance_deltas_queue;
+
+-- Token amounts grew to 128 bits (high-supply mints overflow u64); no-op on new
+-- tables. The Kafka queue can't be altered: drop it and re-run this file
+ALTER TABLE solana.sol_token_balance_deltas MODIFY COLUMN pre_amount UInt128;
+ALTER TABLE solana.sol_token_balance_deltas MODIFY COLUMN post_amount UInt128;
+ALTER TABLE solana.sol_token_balance_deltas MODIFY COLUMN delta Int128;
@@ -1,5 +1,7 @@
 
 DROP VIEW IF EXISTS solana.sol_swaps_mv;
+-- Re-created below so it selects the queue's current columns
+DROP VIEW IF EXISTS solana.sol_swaps_mv_v2;
 DROP TABLE IF EXISTS solana.sol_swaps_queue;
 
 CREATE TABLE IF NOT EXISTS solana.sol_swaps_queue
@@ -32,8 +34,18 @@ CREATE TABLE IF NOT EXISTS solana.sol_swaps_queue
   route_id Nullable(String),
 
   confidence UInt8,
-  confidence_reasons UInt16 DEFAULT 0,
-  explain Nullable(String)
+  confidence_reasons UInt32 DEFAULT 0,
+  explain Nullable(String),
+
+  sol_cost_lamports Nullable(UInt64),
+  jito_bundle Bool DEFAULT false,
+  partial Bool DEFAULT false,
+  parser_version UInt16 DEFAULT 0,
+
+  in_symbol Nullable(String),
+  out_symbol Nullable(String),
+  in_ui_amount Nullable(String),
+  out_ui_amount Nullable(String)
 )
 ENGINE = Kafka
 SETTINGS
@@ -73,9 +85,19 @@ CREATE TABLE IF NOT EXISTS solana.dex_swaps_v2
   route_id Nullable(String),
 
   confidence UInt8,
-  confidence_reasons UInt16 DEFAULT 0,
+  confidence_reasons UInt32 DEFAULT 0,
   explain Nullable(String),
 
+  sol_cost_lamports Nullable(UInt64),
+  jito_bundle Bool DEFAULT false,
+  partial Bool DEFAULT false,
+  parser_version UInt16 DEFAULT 0,
+
+  in_symbol Nullable(String),
+  out_symbol Nullable(String),
+  in_ui_amount Nullable(String),
+  out_ui_amount Nullable(String),
+
   ingested_at DateTime DEFAULT now(),
   version UInt64 DEFAULT toUnixTimestamp(now())
 )
@@ -84,6 +106,13 @@ PARTITION BY toYYYYMMDD(fromUnixTimestamp(coalesce(block_time, 0)))
 ORDER BY (slot, signature, index_in_block, hop_index)
 SETTINGS index_granularity = 8192;
 
+-- Mint registry enrichment (symbols, whole-token amounts); added before the view
+-- below selects them; no-op on new tables
+ALTER TABLE solana.dex_swaps_v2 ADD COLUMN IF NOT EXISTS in_symbol Nullable(String);
+ALTER TABLE solana.dex_swaps_v2 ADD COLUMN IF NOT EXISTS out_symbol Nullable(String);
+ALTER TABLE solana.dex_swaps_v2 ADD COLUMN IF NOT EXISTS in_ui_amount Nullable(String);
+ALTER TABLE solana.dex_swaps_v2 ADD COLUMN IF NOT EXISTS out_ui_amount Nullable(String);
+
 CREATE MATERIALIZED VIEW IF NOT EXISTS solana.sol_swaps_mv_v2
 TO solana.dex_swaps_v2
 AS
@@ -109,6 +138,14 @@ SELECT
   confidence,
   confidence_reasons,
   explain,
+  sol_cost_lamports,
+  jito_bundle,
+  partial,
+  parser_version,
+  in_symbol,
+  out_symbol,
+  in_ui_amount,
+  out_ui_amount,
   now() AS ingested_at,
   toUnixTimestamp(now()) AS version
 FROM solana.sol_swaps_queue;
@@ -181,6 +218,9 @@ ALTER TABLE solana.dex_swaps_v2 ADD INDEX IF NOT EXISTS idx_pool (pool_id) TYPE
 ALTER TABLE solana.dex_swaps_v2 ADD INDEX IF NOT EXISTS idx_in_mint (in_mint) TYPE bloom_filter GRANULARITY 1;
 ALTER TABLE solana.dex_swaps_v2 ADD INDEX IF NOT EXISTS idx_out_mint (out_mint) TYPE bloom_filter GRANULARITY 1;
 
+-- confidence_reasons grew to 32 bits (MULTI_TRADER = 1 << 16); no-op on new tables
+ALTER TABLE solana.dex_swaps_v2 MODIFY COLUMN confidence_reasons UInt32 DEFAULT 0;
+
 CREATE VIEW IF NOT EXISTS solana.swap_volume_by_venue AS
 SELECT
   venue,
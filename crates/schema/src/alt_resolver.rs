@@ -3,8 +3,8 @@
 /// This module provides utilities to correctly extract program IDs from Solana transactions,
 /// handling both legacy transactions and v0 transactions with Address Lookup Tables.
 
-use serde_json::Value;
-use std::collections::HashSet;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
 
 /// Resolves the full account key list for a transaction, merging accountKeys with
 /// loadedAddresses for v0 transactions.
@@ -156,19 +156,104 @@ pub fn extract_program_ids_from_transaction(tx: &Value) -> Vec<String> {
     out
 }
 
+/// Lookup table accounts a v0 transaction references but whose addresses were not
+/// loaded by the provider (no `meta.loadedAddresses` and no `lookupTable`-sourced keys).
+///
+/// Returns an empty vector when nothing needs resolving.
+pub fn unresolved_lookup_tables(tx: &Value) -> Vec<String> {
+    if tx.pointer("/meta/loadedAddresses").is_some() {
+        return vec![];
+    }
+
+    // jsonParsed responses already list ALT keys in accountKeys
+    let already_loaded = tx
+        .pointer("/transaction/message/accountKeys")
+        .and_then(|v| v.as_array())
+        .is_some_and(|keys| {
+            keys.iter()
+                .any(|k| k.get("source").and_then(|s| s.as_str()) == Some("lookupTable"))
+        });
+    if already_loaded {
+        return vec![];
+    }
+
+    tx.pointer("/transaction/message/addressTableLookups")
+        .and_then(|v| v.as_array())
+        .map(|lookups| {
+            lookups
+                .iter()
+                .filter_map(|l| l.get("accountKey").and_then(|k| k.as_str()))
+                .map(|s| s.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Addresses stored in a lookup table account, from a `getAccountInfo` result
+/// fetched with `jsonParsed` encoding.
+pub fn parse_lookup_table_addresses(account_info: &Value) -> Option<Vec<String>> {
+    account_info
+        .pointer("/value/data/parsed/info/addresses")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|a| a.as_str())
+                .map(|s| s.to_string())
+                .collect()
+        })
+}
+
+/// Resolves `message.addressTableLookups` against fetched lookup tables and writes the
+/// result to `meta.loadedAddresses`, so `resolve_full_account_keys` sees the same layout
+/// as an inline response (all writable, then all readonly, in lookup order).
+///
+/// Returns false (leaving `tx` untouched) if a table is missing or an index is out of range.
+pub fn apply_lookup_tables(tx: &mut Value, tables: &HashMap<String, Vec<String>>) -> bool {
+    let lookups = match tx
+        .pointer("/transaction/message/addressTableLookups")
+        .and_then(|v| v.as_array())
+    {
+        Some(l) => l,
+        None => return false,
+    };
+
+    let mut writable: Vec<String> = Vec::new();
+    let mut readonly: Vec<String> = Vec::new();
+
+    for lookup in lookups {
+        let key = match lookup.get("accountKey").and_then(|k| k.as_str()) {
+            Some(k) => k,
+            None => return false,
+        };
+        let table = match tables.get(key) {
+            Some(t) => t,
+            None => return false,
+        };
+
+        for (field, out) in [("writableIndexes", &mut writable), ("readonlyIndexes", &mut readonly)] {
+            let indexes = lookup.get(field).and_then(|v| v.as_array());
+            for idx in indexes.into_iter().flatten() {
+                match idx.as_u64().and_then(|i| table.get(i as usize)) {
+                    Some(addr) => out.push(addr.clone()),
+                    None => return false,
+                }
+            }
+        }
+    }
+
+    if tx.get("meta").is_none_or(|m| !m.is_object()) {
+        tx["meta"] = json!({});
+    }
+    tx["meta"]["loadedAddresses"] = json!({ "writable": writable, "readonly": readonly });
+    true
+}
+
 /// Picks the "main" program from a list of program IDs by filtering out common system programs.
 ///
 /// Returns the first non-system program, or None if only system programs are present.
+/// Uses the mainnet skip list; see `ChainPrograms::pick_main_program` for other chains.
 pub fn pick_main_program(program_ids: &[String]) -> Option<String> {
-    let skip = [
-        "ComputeBudget111111111111111111111111111111",
-        "11111111111111111111111111111111",
-        "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
-    ];
-    program_ids
-        .iter()
-        .find(|p| !skip.contains(&p.as_str()))
-        .cloned()
+    crate::chain::ChainPrograms::mainnet().pick_main_program(program_ids)
 }
 
 #[cfg(test)]
@@ -312,6 +397,81 @@ mod tests {
         assert!(program_ids.contains(&"675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8".to_string()));
     }
 
+    #[test]
+    fn test_resolve_alt_from_mocked_lookup_table_account() {
+        // v0 transaction from a provider that omits meta.loadedAddresses
+        let mut tx = json!({
+            "transaction": {
+                "message": {
+                    "accountKeys": [
+                        "FeePayer111111111111111111111111111111111",
+                        "11111111111111111111111111111111"
+                    ],
+                    "instructions": [
+                        {"programIdIndex": 1},
+                        {"programIdIndex": 3} // readonly ALT entry
+                    ],
+                    "addressTableLookups": [{
+                        "accountKey": "LookupTab1e111111111111111111111111111111",
+                        "writableIndexes": [2],
+                        "readonlyIndexes": [0]
+                    }]
+                }
+            },
+            "meta": {"err": null}
+        });
+        assert_eq!(
+            unresolved_lookup_tables(&tx),
+            vec!["LookupTab1e111111111111111111111111111111".to_string()]
+        );
+
+        // getAccountInfo (jsonParsed) result for the lookup table
+        let account_info = json!({
+            "context": {"slot": 250000000},
+            "value": {
+                "data": {
+                    "parsed": {
+                        "type": "lookupTable",
+                        "info": {
+                            "addresses": [
+                                "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8",
+                                "SomeOtherAccount11111111111111111111111111",
+                                "PoolVault1111111111111111111111111111111111"
+                            ]
+                        }
+                    },
+                    "program": "address-lookup-table"
+                },
+                "owner": "AddressLookupTab1e1111111111111111111111111"
+            }
+        });
+        let addresses = parse_lookup_table_addresses(&account_info).unwrap();
+        let tables = HashMap::from([(
+            "LookupTab1e111111111111111111111111111111".to_string(),
+            addresses,
+        )]);
+
+        assert!(apply_lookup_tables(&mut tx, &tables));
+        assert!(unresolved_lookup_tables(&tx).is_empty());
+
+        let keys = resolve_full_account_keys(&tx);
+        assert_eq!(keys[2], "PoolVault1111111111111111111111111111111111");
+        assert_eq!(keys[3], "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+
+        let program_ids = extract_program_ids_from_transaction(&tx);
+        assert!(program_ids.contains(&"675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8".to_string()));
+
+        // Missing table or out-of-range index leaves the tx untouched
+        let mut untouched = json!({
+            "transaction": {"message": {"accountKeys": [], "addressTableLookups": [
+                {"accountKey": "LookupTab1e111111111111111111111111111111", "writableIndexes": [9], "readonlyIndexes": []}
+            ]}}
+        });
+        assert!(!apply_lookup_tables(&mut untouched, &tables));
+        assert!(!apply_lookup_tables(&mut untouched, &HashMap::new()));
+        assert!(untouched.pointer("/meta/loadedAddresses").is_none());
+    }
+
     #[test]
     fn test_extract_program_ids_json_parsed_format() {
         let tx = json!({
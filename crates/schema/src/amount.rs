@@ -0,0 +1,174 @@
+//! Token amount in base units.
+//!
+//! On the wire an `Amount` is a decimal string (`"1000000000"`), exactly like
+//! the `String` fields it replaces, so u128 values survive JSON consumers that
+//! read numbers as f64. In Rust it is a `u128` with checked arithmetic.
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Token amount in base units (no decimal adjustment)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(pub u128);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+
+    pub fn get(self) -> u128 {
+        self.0
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn checked_add(self, rhs: Amount) -> Option<Amount> {
+        self.0.checked_add(rhs.0).map(Amount)
+    }
+
+    pub fn checked_sub(self, rhs: Amount) -> Option<Amount> {
+        self.0.checked_sub(rhs.0).map(Amount)
+    }
+
+    pub fn checked_mul(self, rhs: u128) -> Option<Amount> {
+        self.0.checked_mul(rhs).map(Amount)
+    }
+
+    /// Whole-token amount for a mint with `decimals` (`"1.5"` for 1500000 at
+    /// 6), exact and without trailing zeros; `None` past u128's 38 decimals
+    pub fn to_ui_string(self, decimals: u8) -> Option<String> {
+        let scale = 10u128.checked_pow(decimals as u32)?;
+        let (whole, frac) = (self.0 / scale, self.0 % scale);
+        if frac == 0 {
+            return Some(whole.to_string());
+        }
+        let frac = format!("{:0width$}", frac, width = decimals as usize);
+        Some(format!("{}.{}", whole, frac.trim_end_matches('0')))
+    }
+}
+
+impl FromStr for Amount {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Amount)
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<u128> for Amount {
+    fn from(v: u128) -> Self {
+        Amount(v)
+    }
+}
+
+impl From<u64> for Amount {
+    fn from(v: u64) -> Self {
+        Amount(v as u128)
+    }
+}
+
+/// Literal amounts (`.in_token(mint, "1000")`).
+///
+/// Panics if the text is not a u128, so a typo fails loudly instead of turning
+/// into a zero amount; parse with `FromStr` for text that may be malformed.
+impl From<&str> for Amount {
+    fn from(s: &str) -> Self {
+        s.parse()
+            .unwrap_or_else(|_| panic!("amount literal {s:?} is not a u128"))
+    }
+}
+
+impl From<String> for Amount {
+    fn from(s: String) -> Self {
+        Amount::from(s.as_str())
+    }
+}
+
+impl PartialEq<&str> for Amount {
+    fn eq(&self, other: &&str) -> bool {
+        other.parse::<u128>() == Ok(self.0)
+    }
+}
+
+impl PartialEq<u128> for Amount {
+    fn eq(&self, other: &u128) -> bool {
+        self.0 == *other
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(|_| {
+            de::Error::invalid_value(de::Unexpected::Str(&s), &"a u128 amount in base units")
+        })
+    }
+}
+
+/// Described as what is on the wire: a string of decimal digits
+#[cfg(feature = "json-schema")]
+impl schemars::JsonSchema for Amount {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Amount".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "pattern": "^[0-9]+$",
+            "description": "Token amount in base units (u128 as a decimal string)"
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amount_wire_format_and_arithmetic() {
+        let max = Amount(u128::MAX);
+        assert_eq!(
+            serde_json::to_string(&max).unwrap(),
+            "\"340282366920938463463374607431768211455\""
+        );
+        let back: Amount =
+            serde_json::from_str("\"340282366920938463463374607431768211455\"").unwrap();
+        assert_eq!(back, max);
+
+        // One past u128::MAX and non-numbers are rejected, not zeroed
+        let overflow = "\"340282366920938463463374607431768211456\"";
+        assert!(serde_json::from_str::<Amount>(overflow).is_err());
+        assert!(serde_json::from_str::<Amount>("\"not-a-number\"").is_err());
+        assert!("-1".parse::<Amount>().is_err());
+
+        assert_eq!(max.checked_add(Amount(1)), None);
+        assert_eq!(Amount(1).checked_sub(Amount(2)), None);
+        assert_eq!(Amount(5).checked_sub(Amount(2)), Some(Amount(3)));
+        assert_eq!(max.checked_mul(2), None);
+
+        assert_eq!("1000".parse::<Amount>(), Ok(Amount(1000)));
+        assert!("not-a-number".parse::<Amount>().is_err());
+        assert_eq!(Amount::from("1000"), Amount(1000));
+        assert!(std::panic::catch_unwind(|| Amount::from("not-a-number")).is_err());
+
+        assert_eq!(Amount(1_500_000).to_ui_string(6).as_deref(), Some("1.5"));
+        assert_eq!(Amount(1_000_000_000).to_ui_string(9).as_deref(), Some("1"));
+        assert_eq!(Amount(5).to_ui_string(9).as_deref(), Some("0.000000005"));
+        assert_eq!(Amount(42).to_ui_string(0).as_deref(), Some("42"));
+        assert_eq!(max.to_ui_string(39), None);
+    }
+}
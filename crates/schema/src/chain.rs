@@ -0,0 +1,258 @@
+//! Per-chain program ID sets.
+//!
+//! Venue program IDs and the `pick_main_program` skip list differ between
+//! clusters. A `ChainPrograms` set is selected by the `CHAIN` value, falling
+//! back to mainnet for unknown chains.
+
+use crate::dex_swap::{
+    RAYDIUM_AMM_V4_AUTHORITY, RAYDIUM_AMM_V4_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID,
+};
+
+/// System Program ID
+pub const SYSTEM_PROGRAM_ID: &str = "11111111111111111111111111111111";
+
+/// Compute Budget Program ID
+pub const COMPUTE_BUDGET_PROGRAM_ID: &str = "ComputeBudget111111111111111111111111111111";
+
+/// Phoenix v1 order-book program ID (same on mainnet and devnet)
+pub const PHOENIX_PROGRAM_ID: &str = "PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY";
+
+/// Orca Whirlpool (concentrated liquidity) program ID (same on mainnet and devnet)
+pub const ORCA_WHIRLPOOL_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+
+/// Lifinity v2 (proactive market maker) program ID (same on mainnet and devnet)
+pub const LIFINITY_V2_PROGRAM_ID: &str = "2wT8Yq49kHgDzXuPxZSaeLaH1qbmGXtEyPy64bL7aD3c";
+
+/// Meteora DLMM (dynamic liquidity market maker) program ID (same on mainnet and devnet)
+pub const METEORA_DLMM_PROGRAM_ID: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDjUsrcumk1Q";
+
+/// Jupiter v6 aggregator program ID (same on mainnet and devnet)
+pub const JUPITER_V6_PROGRAM_ID: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+
+/// Jito tip accounts (mainnet); SOL sent here marks a bundle-submitted tx
+pub const JITO_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+/// Program IDs used by the pipeline on one chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainPrograms {
+    /// Chain identifier (e.g., "solana-mainnet")
+    pub chain: String,
+    /// Raydium AMM v4 program
+    pub raydium_amm_v4: String,
+    /// Raydium AMM v4 authority (owner of pool vaults)
+    pub raydium_amm_v4_authority: String,
+    /// Phoenix order-book program
+    pub phoenix: String,
+    /// Orca Whirlpool program
+    pub orca_whirlpool: String,
+    /// Lifinity v2 proactive market maker program
+    pub lifinity_v2: String,
+    /// Meteora DLMM (binned liquidity) program
+    pub meteora_dlmm: String,
+    /// Jupiter v6 aggregator program (routes over the venues above)
+    pub jupiter_v6: String,
+    /// SPL Token program
+    pub token_program: String,
+    /// Token-2022 program (mints with extensions; balances reference it as `programId`)
+    pub token_2022_program: String,
+    /// Infrastructure programs never reported as the main program
+    pub main_program_skip: Vec<String>,
+}
+
+impl ChainPrograms {
+    pub fn mainnet() -> Self {
+        Self {
+            chain: "solana-mainnet".to_string(),
+            raydium_amm_v4: RAYDIUM_AMM_V4_PROGRAM_ID.to_string(),
+            raydium_amm_v4_authority: RAYDIUM_AMM_V4_AUTHORITY.to_string(),
+            phoenix: PHOENIX_PROGRAM_ID.to_string(),
+            orca_whirlpool: ORCA_WHIRLPOOL_PROGRAM_ID.to_string(),
+            lifinity_v2: LIFINITY_V2_PROGRAM_ID.to_string(),
+            meteora_dlmm: METEORA_DLMM_PROGRAM_ID.to_string(),
+            jupiter_v6: JUPITER_V6_PROGRAM_ID.to_string(),
+            token_program: TOKEN_PROGRAM_ID.to_string(),
+            token_2022_program: TOKEN_2022_PROGRAM_ID.to_string(),
+            main_program_skip: vec![
+                COMPUTE_BUDGET_PROGRAM_ID.to_string(),
+                SYSTEM_PROGRAM_ID.to_string(),
+                TOKEN_PROGRAM_ID.to_string(),
+                TOKEN_2022_PROGRAM_ID.to_string(),
+            ],
+        }
+    }
+
+    pub fn devnet() -> Self {
+        Self {
+            chain: "solana-devnet".to_string(),
+            raydium_amm_v4: "HWy1jotHpo6UqeQxx49dpYYdQB8wj9Qk9MdxwjLvDHB8".to_string(),
+            raydium_amm_v4_authority: "DbQqP6ehDYmeYjcBaMRuA8tAJY1EjDUz9DpwSLjaQqfC".to_string(),
+            ..Self::mainnet()
+        }
+    }
+
+    /// Select the program set for a `CHAIN` value (mainnet for unknown chains)
+    pub fn for_chain(chain: &str) -> Self {
+        match chain {
+            "solana-devnet" | "devnet" => Self::devnet(),
+            _ => Self {
+                chain: chain.to_string(),
+                ..Self::mainnet()
+            },
+        }
+    }
+
+    /// Override the Raydium AMM v4 program ID (ignored if empty)
+    pub fn with_raydium_amm_v4(mut self, program_id: &str) -> Self {
+        if !program_id.is_empty() {
+            self.raydium_amm_v4 = program_id.to_string();
+        }
+        self
+    }
+
+    /// Override the Phoenix program ID (ignored if empty)
+    pub fn with_phoenix(mut self, program_id: &str) -> Self {
+        if !program_id.is_empty() {
+            self.phoenix = program_id.to_string();
+        }
+        self
+    }
+
+    /// Override the Orca Whirlpool program ID (ignored if empty)
+    pub fn with_orca_whirlpool(mut self, program_id: &str) -> Self {
+        if !program_id.is_empty() {
+            self.orca_whirlpool = program_id.to_string();
+        }
+        self
+    }
+
+    /// Override the Lifinity v2 program ID (ignored if empty)
+    pub fn with_lifinity_v2(mut self, program_id: &str) -> Self {
+        if !program_id.is_empty() {
+            self.lifinity_v2 = program_id.to_string();
+        }
+        self
+    }
+
+    /// Override the Meteora DLMM program ID (ignored if empty)
+    pub fn with_meteora_dlmm(mut self, program_id: &str) -> Self {
+        if !program_id.is_empty() {
+            self.meteora_dlmm = program_id.to_string();
+        }
+        self
+    }
+
+    /// Override the Jupiter v6 program ID (ignored if empty)
+    pub fn with_jupiter_v6(mut self, program_id: &str) -> Self {
+        if !program_id.is_empty() {
+            self.jupiter_v6 = program_id.to_string();
+        }
+        self
+    }
+
+    /// Override the Token-2022 program ID, in the skip list too (ignored if empty)
+    pub fn with_token_2022(mut self, program_id: &str) -> Self {
+        if !program_id.is_empty() {
+            let old = std::mem::replace(&mut self.token_2022_program, program_id.to_string());
+            for p in self.main_program_skip.iter_mut().filter(|p| **p == old) {
+                *p = program_id.to_string();
+            }
+        }
+        self
+    }
+
+    /// First program that is not on this chain's skip list
+    pub fn pick_main_program(&self, program_ids: &[String]) -> Option<String> {
+        program_ids
+            .iter()
+            .find(|p| !self.main_program_skip.contains(p))
+            .cloned()
+    }
+}
+
+impl Default for ChainPrograms {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_chain_selects_devnet_overrides() {
+        let devnet = ChainPrograms::for_chain("solana-devnet");
+        assert_eq!(devnet.chain, "solana-devnet");
+        assert_ne!(devnet.raydium_amm_v4, RAYDIUM_AMM_V4_PROGRAM_ID);
+        assert_ne!(devnet.raydium_amm_v4_authority, RAYDIUM_AMM_V4_AUTHORITY);
+        assert_eq!(
+            devnet.main_program_skip,
+            ChainPrograms::mainnet().main_program_skip
+        );
+
+        // Unknown chains keep mainnet IDs but their own name
+        let other = ChainPrograms::for_chain("solana-localnet");
+        assert_eq!(other.chain, "solana-localnet");
+        assert_eq!(other.raydium_amm_v4, RAYDIUM_AMM_V4_PROGRAM_ID);
+
+        // Explicit override wins, empty override is ignored
+        let custom = ChainPrograms::for_chain("solana-devnet").with_raydium_amm_v4("CustomAmm111");
+        assert_eq!(custom.raydium_amm_v4, "CustomAmm111");
+        assert_eq!(
+            ChainPrograms::devnet()
+                .with_raydium_amm_v4("")
+                .raydium_amm_v4,
+            devnet.raydium_amm_v4
+        );
+    }
+
+    #[test]
+    fn test_pick_main_program_uses_chain_skip_list() {
+        let ids = vec![
+            COMPUTE_BUDGET_PROGRAM_ID.to_string(),
+            "HWy1jotHpo6UqeQxx49dpYYdQB8wj9Qk9MdxwjLvDHB8".to_string(),
+        ];
+        assert_eq!(
+            ChainPrograms::devnet().pick_main_program(&ids).as_deref(),
+            Some("HWy1jotHpo6UqeQxx49dpYYdQB8wj9Qk9MdxwjLvDHB8")
+        );
+
+        let mut programs = ChainPrograms::devnet();
+        programs
+            .main_program_skip
+            .push("HWy1jotHpo6UqeQxx49dpYYdQB8wj9Qk9MdxwjLvDHB8".to_string());
+        assert_eq!(programs.pick_main_program(&ids), None);
+    }
+
+    #[test]
+    fn test_token_2022_skipped_and_overridable() {
+        let ids = vec![
+            TOKEN_2022_PROGRAM_ID.to_string(),
+            RAYDIUM_AMM_V4_PROGRAM_ID.to_string(),
+        ];
+        let mainnet = ChainPrograms::mainnet();
+        assert_eq!(
+            mainnet.pick_main_program(&ids).as_deref(),
+            Some(RAYDIUM_AMM_V4_PROGRAM_ID)
+        );
+
+        // Override swaps the skip-list entry; the default ID is no longer skipped
+        let custom = ChainPrograms::mainnet().with_token_2022("CustomToken2022111");
+        assert_eq!(custom.token_2022_program, "CustomToken2022111");
+        assert!(custom.main_program_skip.contains(&"CustomToken2022111".to_string()));
+        assert_eq!(
+            custom.pick_main_program(&ids).as_deref(),
+            Some(TOKEN_2022_PROGRAM_ID)
+        );
+        assert_eq!(mainnet.clone().with_token_2022(""), mainnet);
+    }
+}
@@ -9,50 +9,79 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+use crate::amount::Amount;
+
 /// Raydium AMM v4 program ID (mainnet)
 pub const RAYDIUM_AMM_V4_PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
 
+/// Raydium AMM v4 authority (owner of every v4 pool vault)
+pub const RAYDIUM_AMM_V4_AUTHORITY: &str = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1";
+
 /// Token Program ID
 pub const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
 
+/// Token-2022 (token extensions) program ID
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+/// Wrapped SOL mint (reported for native SOL legs)
+pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+/// Pseudo-mint (the system program ID) of a WSOL leg normalized to native SOL
+pub const NATIVE_SOL_MINT: &str = "11111111111111111111111111111111";
+
 /// Confidence reasons as bitflags for structured debugging.
 ///
 /// Each bit represents a confidence criterion that was met (1) or failed (0).
 /// Full confidence (1.0) requires all relevant bits set.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
-pub struct ConfidenceReasons(pub u16);
+pub struct ConfidenceReasons(pub u32);
 
 impl ConfidenceReasons {
     /// Program ID gate passed (Raydium instruction found)
-    pub const PROGRAM_GATE: u16 = 1 << 0;
+    pub const PROGRAM_GATE: u32 = 1 << 0;
     /// Pool ID extracted from instruction accounts
-    pub const POOL_ID_FROM_IX: u16 = 1 << 1;
-    /// Pool ID inferred from vault deltas
-    pub const POOL_ID_FROM_VAULT: u16 = 1 << 2;
+    pub const POOL_ID_FROM_IX: u32 = 1 << 1;
+    /// Pool ID looked up from the pool vaults among the instruction accounts
+    pub const POOL_ID_FROM_VAULT: u32 = 1 << 2;
     /// Trader identified from token account owner
-    pub const TRADER_FROM_OWNER: u16 = 1 << 3;
+    pub const TRADER_FROM_OWNER: u32 = 1 << 3;
     /// Trader is instruction signer
-    pub const TRADER_IS_SIGNER: u16 = 1 << 4;
-    /// In/out amounts from user token accounts confirmed
-    pub const AMOUNTS_CONFIRMED: u16 = 1 << 5;
+    pub const TRADER_IS_SIGNER: u32 = 1 << 4;
+    /// In/out amounts confirmed (Raydium: the swap instruction data agrees with the deltas)
+    pub const AMOUNTS_CONFIRMED: u32 = 1 << 5;
     /// Vault balance changes match user balance changes
-    pub const VAULT_MATCH: u16 = 1 << 6;
+    pub const VAULT_MATCH: u32 = 1 << 6;
     /// Single hop (not multi-hop aggregator)
-    pub const SINGLE_HOP: u16 = 1 << 7;
+    pub const SINGLE_HOP: u32 = 1 << 7;
     /// Inner instruction correctly attributed
-    pub const INNER_IX_RESOLVED: u16 = 1 << 8;
+    pub const INNER_IX_RESOLVED: u32 = 1 << 8;
     /// Transaction succeeded (not reverted)
-    pub const TX_SUCCESS: u16 = 1 << 9;
+    pub const TX_SUCCESS: u32 = 1 << 9;
+    /// Penalty: deltas not backed by vault flow and otherwise suspicious (possible fake swap)
+    pub const UNVERIFIED: u32 = 1 << 10;
+    /// One leg inferred from the trader's native SOL balance change (no token balance)
+    pub const SOL_INFERRED: u32 = 1 << 11;
+    /// Penalty: balance arrays were truncated, so the SOL-side inference may be incomplete
+    pub const BALANCES_TRUNCATED: u32 = 1 << 12;
+    /// In/out mints match the pool's mint pair learned from earlier high-confidence swaps
+    pub const POOL_MINTS_MATCH: u32 = 1 << 13;
+    /// A WSOL leg wrapped from / unwrapped into the trader's SOL, reported as native SOL
+    pub const WSOL_NETTED: u32 = 1 << 14;
+    /// Pool fee on the input leg computed from the vault flow (fee_mint/fee_amount set)
+    pub const FEE_COMPUTED: u32 = 1 << 15;
+    /// Penalty: more than one wallet traded in the tx (e.g. an arbitrage bot's
+    /// legs through intermediate owners); each hop is attributed to its own trader
+    pub const MULTI_TRADER: u32 = 1 << 16;
 
     pub fn new() -> Self {
         Self(0)
     }
 
-    pub fn set(&mut self, flag: u16) {
+    pub fn set(&mut self, flag: u32) {
         self.0 |= flag;
     }
 
-    pub fn has(&self, flag: u16) -> bool {
+    pub fn has(&self, flag: u32) -> bool {
         (self.0 & flag) == flag
     }
 
@@ -90,9 +119,9 @@ impl ConfidenceReasons {
             score += 15;
         }
 
-        // Vault match (10 points)
+        // Vault match (10 points; a known pool mint pair is equivalent evidence)
         max_score += 10;
-        if self.has(Self::VAULT_MATCH) {
+        if self.has(Self::VAULT_MATCH) || self.has(Self::POOL_MINTS_MATCH) {
             score += 10;
         }
 
@@ -108,6 +137,21 @@ impl ConfidenceReasons {
             score += 10;
         }
 
+        // Unverified swap penalty (-30 points)
+        if self.has(Self::UNVERIFIED) {
+            score = score.saturating_sub(30);
+        }
+
+        // Truncated SOL deltas behind an inferred leg (-20 points)
+        if self.has(Self::BALANCES_TRUNCATED) {
+            score = score.saturating_sub(20);
+        }
+
+        // Several traders in one tx: per-hop attribution is a heuristic (-10 points)
+        if self.has(Self::MULTI_TRADER) {
+            score = score.saturating_sub(10);
+        }
+
         score as f32 / max_score as f32
     }
 
@@ -152,6 +196,10 @@ impl ConfidenceReasons {
             reasons.push("+vault_match");
         }
 
+        if self.has(Self::POOL_MINTS_MATCH) {
+            reasons.push("+pool_mints");
+        }
+
         if !self.has(Self::SINGLE_HOP) {
             reasons.push("multi_hop");
         }
@@ -162,6 +210,30 @@ impl ConfidenceReasons {
             reasons.push("-tx_fail");
         }
 
+        if self.has(Self::UNVERIFIED) {
+            reasons.push("!unverified");
+        }
+
+        if self.has(Self::SOL_INFERRED) {
+            reasons.push("sol_inferred");
+        }
+
+        if self.has(Self::BALANCES_TRUNCATED) {
+            reasons.push("!balances_truncated");
+        }
+
+        if self.has(Self::WSOL_NETTED) {
+            reasons.push("wsol_netted");
+        }
+
+        if self.has(Self::FEE_COMPUTED) {
+            reasons.push("+fee");
+        }
+
+        if self.has(Self::MULTI_TRADER) {
+            reasons.push("!multi_trader");
+        }
+
         reasons.join(" ")
     }
 }
@@ -178,7 +250,11 @@ impl fmt::Display for ConfidenceReasons {
 /// - `in_amount > 0` and `out_amount > 0` (enforced by constructor)
 /// - `confidence` in [0, 100]
 /// - Required fields for confidence == 100: pool_id, trader, amounts all confirmed
+/// - `(signature, index_in_tx, hop_index)` identifies a swap: no two swaps a
+///   detector emits share it, and reprocessing a tx yields the same triple.
+///   `dedup_key` encodes it for Kafka keys and ReplacingMergeTree ordering.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct DexSwapV1 {
     /// Schema version for forward compatibility
     pub schema_version: u16,
@@ -216,14 +292,14 @@ pub struct DexSwapV1 {
     /// Input token mint address
     pub in_mint: String,
 
-    /// Input amount in base units (as string to preserve precision)
-    pub in_amount: String,
+    /// Input amount in base units (a decimal string on the wire)
+    pub in_amount: Amount,
 
     /// Output token mint address
     pub out_mint: String,
 
-    /// Output amount in base units (as string to preserve precision)
-    pub out_amount: String,
+    /// Output amount in base units (a decimal string on the wire)
+    pub out_amount: Amount,
 
     /// Fee token mint (if known)
     pub fee_mint: Option<String>,
@@ -238,34 +314,99 @@ pub struct DexSwapV1 {
     pub confidence: u8,
 
     /// Structured confidence reasons (bitflags)
-    pub confidence_reasons: u16,
+    pub confidence_reasons: u32,
 
     /// Human-readable explain string for debugging
     pub explain: Option<String>,
+
+    /// Lamports the trader spent in this tx (fees, rent, SOL legs), excluding Jito tips
+    #[serde(default)]
+    pub sol_cost_lamports: Option<u64>,
+
+    /// Transaction tipped a known Jito tip account (submitted as a bundle)
+    #[serde(default)]
+    pub jito_bundle: bool,
+
+    /// Leg of a multi-hop route some of whose other legs could not be parsed
+    #[serde(default)]
+    pub partial: bool,
+
+    /// Detector logic version that produced this event (0 = before versioning)
+    #[serde(default)]
+    pub parser_version: u16,
+
+    /// Input token symbol, when the decoder's mint registry knows it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_symbol: Option<String>,
+
+    /// Output token symbol, when the decoder's mint registry knows it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub out_symbol: Option<String>,
+
+    /// `in_amount` in whole tokens (decimal string), when the mint's decimals are known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_ui_amount: Option<String>,
+
+    /// `out_amount` in whole tokens (decimal string), when the mint's decimals are known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub out_ui_amount: Option<String>,
 }
 
 impl DexSwapV1 {
     pub const SCHEMA_VERSION: u16 = 2;
 
+    /// Bump whenever detector output changes materially (new legs, amounts,
+    /// trader or index rules), so consumers can tell reprocessed data apart.
+    ///
+    /// - 1: first stamped version
+    /// - 2: route legs from pool vault flow, exact-out and fee fields, pool id
+    ///   from vaults, multi-trader routes, rent-free SOL legs, composite
+    ///   `index_in_tx` (opt-in), no same-mint legs from the all-deltas fallback
+    pub const PARSER_VERSION: u16 = 2;
+
+    /// Deterministic identity of this swap, `"{signature}:{index_in_tx}:{hop_index}"`.
+    ///
+    /// Republishing the same tx after a restart (publish done, offset commit
+    /// lost) produces the same key, so compacted topics and dedupe-by-key
+    /// stores keep a single copy.
+    pub fn dedup_key(&self) -> String {
+        format!("{}:{}:{}", self.signature, self.index_in_tx, self.hop_index)
+    }
+
+    /// Raw execution price: out_amount / in_amount in base units (no decimal adjustment)
+    pub fn price(&self) -> Option<f64> {
+        if self.in_amount.is_zero() {
+            return None;
+        }
+        Some(self.out_amount.get() as f64 / self.in_amount.get() as f64)
+    }
+
     /// Validate invariants. Returns error message if invalid.
+    ///
+    /// `in_mint == out_mint` is rejected: one pool swap always trades two
+    /// different mints, so equal mints mean a parser paired the same mint
+    /// twice. See `validate_with` for the arbitrage exception.
     pub fn validate(&self) -> Result<(), &'static str> {
-        // Parse amounts and validate > 0
-        let in_amt: u128 = self
-            .in_amount
-            .parse()
-            .map_err(|_| "in_amount must be valid u128")?;
-        let out_amt: u128 = self
-            .out_amount
-            .parse()
-            .map_err(|_| "out_amount must be valid u128")?;
-
-        if in_amt == 0 {
+        self.validate_with(false)
+    }
+
+    /// Same as `validate`, but with `allow_same_mint_arb` a swap that is part of
+    /// a route (`route_id` set) may have `in_mint == out_mint`: a cyclic
+    /// arbitrage across pools legitimately starts and ends in the same mint.
+    /// Without a route it is still rejected.
+    pub fn validate_with(&self, allow_same_mint_arb: bool) -> Result<(), &'static str> {
+        // Amounts are u128 by construction (malformed wire values fail to deserialize)
+        if self.in_amount.is_zero() {
             return Err("in_amount must be > 0");
         }
-        if out_amt == 0 {
+        if self.out_amount.is_zero() {
             return Err("out_amount must be > 0");
         }
 
+        if self.in_mint == self.out_mint && !(allow_same_mint_arb && self.route_id.is_some()) {
+            return Err("in_mint must differ from out_mint");
+        }
+
         if self.confidence > 100 {
             return Err("confidence must be in [0, 100]");
         }
@@ -280,7 +421,38 @@ impl DexSwapV1 {
 
     /// Check if this is a high-confidence swap
     pub fn is_high_confidence(&self) -> bool {
-        self.confidence >= 80
+        self.meets_confidence(80)
+    }
+
+    /// Check if `confidence` is at least `min` (0 admits every swap)
+    pub fn meets_confidence(&self, min: u8) -> bool {
+        self.confidence >= min
+    }
+
+    /// Set a confidence reason after build, recomputing `confidence` and the
+    /// reasons part of `explain` (anything appended after it is kept)
+    pub fn add_confidence_reason(&mut self, flag: u32) {
+        let mut reasons = ConfidenceReasons(self.confidence_reasons);
+        reasons.set(flag);
+        self.set_confidence_reasons(reasons);
+    }
+
+    /// Clear a confidence reason after build (see `add_confidence_reason`)
+    pub fn remove_confidence_reason(&mut self, flag: u32) {
+        self.set_confidence_reasons(ConfidenceReasons(self.confidence_reasons & !flag));
+    }
+
+    fn set_confidence_reasons(&mut self, reasons: ConfidenceReasons) {
+        self.confidence_reasons = reasons.0;
+        self.confidence = reasons.to_confidence_u8();
+
+        if let Some(explain) = self.explain.as_mut() {
+            let suffix = explain
+                .find(" | ")
+                .map(|i| explain[i..].to_string())
+                .unwrap_or_default();
+            *explain = reasons.explain() + &suffix;
+        }
     }
 }
 
@@ -298,14 +470,16 @@ pub struct DexSwapV1Builder {
     pool_id: Option<String>,
     trader: String,
     in_mint: String,
-    in_amount: String,
+    in_amount: Amount,
     out_mint: String,
-    out_amount: String,
+    out_amount: Amount,
     fee_mint: Option<String>,
     fee_amount: Option<String>,
     route_id: Option<String>,
     confidence_reasons: ConfidenceReasons,
     explain_enabled: bool,
+    sol_cost_lamports: Option<u64>,
+    jito_bundle: bool,
 }
 
 impl DexSwapV1Builder {
@@ -363,13 +537,13 @@ impl DexSwapV1Builder {
         self
     }
 
-    pub fn in_token(mut self, mint: impl Into<String>, amount: impl Into<String>) -> Self {
+    pub fn in_token(mut self, mint: impl Into<String>, amount: impl Into<Amount>) -> Self {
         self.in_mint = mint.into();
         self.in_amount = amount.into();
         self
     }
 
-    pub fn out_token(mut self, mint: impl Into<String>, amount: impl Into<String>) -> Self {
+    pub fn out_token(mut self, mint: impl Into<String>, amount: impl Into<Amount>) -> Self {
         self.out_mint = mint.into();
         self.out_amount = amount.into();
         self
@@ -386,11 +560,11 @@ impl DexSwapV1Builder {
         self
     }
 
-    pub fn add_confidence_reason(&mut self, reason: u16) {
+    pub fn add_confidence_reason(&mut self, reason: u32) {
         self.confidence_reasons.set(reason);
     }
 
-    pub fn with_confidence_reason(mut self, reason: u16) -> Self {
+    pub fn with_confidence_reason(mut self, reason: u32) -> Self {
         self.confidence_reasons.set(reason);
         self
     }
@@ -400,6 +574,16 @@ impl DexSwapV1Builder {
         self
     }
 
+    pub fn sol_cost_lamports(mut self, lamports: Option<u64>) -> Self {
+        self.sol_cost_lamports = lamports;
+        self
+    }
+
+    pub fn jito_bundle(mut self, jito_bundle: bool) -> Self {
+        self.jito_bundle = jito_bundle;
+        self
+    }
+
     pub fn build(self) -> DexSwapV1 {
         let confidence = self.confidence_reasons.to_confidence_u8();
         let explain = if self.explain_enabled {
@@ -430,6 +614,14 @@ impl DexSwapV1Builder {
             confidence,
             confidence_reasons: self.confidence_reasons.0,
             explain,
+            sol_cost_lamports: self.sol_cost_lamports,
+            jito_bundle: self.jito_bundle,
+            partial: false,
+            parser_version: DexSwapV1::PARSER_VERSION,
+            in_symbol: None,
+            out_symbol: None,
+            in_ui_amount: None,
+            out_ui_amount: None,
         }
     }
 }
@@ -462,6 +654,36 @@ mod tests {
         assert!(conf > 0 && conf < 100);
     }
 
+    #[test]
+    fn test_confidence_reasons_unverified_penalty() {
+        let mut reasons = ConfidenceReasons::new();
+        reasons.set(ConfidenceReasons::PROGRAM_GATE);
+        reasons.set(ConfidenceReasons::POOL_ID_FROM_IX);
+        reasons.set(ConfidenceReasons::TRADER_FROM_OWNER);
+        reasons.set(ConfidenceReasons::AMOUNTS_CONFIRMED);
+        reasons.set(ConfidenceReasons::TX_SUCCESS);
+        let verified = reasons.to_confidence_u8();
+
+        reasons.set(ConfidenceReasons::UNVERIFIED);
+        assert_eq!(reasons.to_confidence_u8(), verified - 30);
+        assert!(reasons.explain().contains("!unverified"));
+    }
+
+    #[test]
+    fn test_confidence_reasons_multi_trader_penalty() {
+        let mut reasons = ConfidenceReasons::new();
+        reasons.set(ConfidenceReasons::PROGRAM_GATE);
+        reasons.set(ConfidenceReasons::POOL_ID_FROM_IX);
+        reasons.set(ConfidenceReasons::TRADER_FROM_OWNER);
+        reasons.set(ConfidenceReasons::VAULT_MATCH);
+        reasons.set(ConfidenceReasons::TX_SUCCESS);
+        let single = reasons.to_confidence_u8();
+
+        reasons.set(ConfidenceReasons::MULTI_TRADER);
+        assert_eq!(reasons.to_confidence_u8(), single - 10);
+        assert!(reasons.explain().contains("!multi_trader"));
+    }
+
     #[test]
     fn test_confidence_reasons_explain() {
         let mut reasons = ConfidenceReasons::new();
@@ -483,8 +705,8 @@ mod tests {
             .signature("test_sig")
             .venue("raydium")
             .trader("wallet123")
-            .in_token("mint_a", "1000000")
-            .out_token("mint_b", "500000")
+            .in_token("mint_a", 1_000_000u128)
+            .out_token("mint_b", 500_000u128)
             .with_confidence_reason(ConfidenceReasons::PROGRAM_GATE)
             .with_confidence_reason(ConfidenceReasons::TX_SUCCESS)
             .build();
@@ -500,13 +722,55 @@ mod tests {
             .signature("test_sig")
             .venue("raydium")
             .trader("wallet123")
-            .in_token("mint_a", "0") // Invalid
-            .out_token("mint_b", "500000")
+            .in_token("mint_a", 0u128) // Invalid
+            .out_token("mint_b", 500_000u128)
             .build();
 
         assert!(swap.validate().is_err());
     }
 
+    #[test]
+    fn test_dex_swap_v1_validation_same_mint() {
+        let swap = |route_id: Option<&str>| {
+            DexSwapV1Builder::new()
+                .signature("test_sig")
+                .trader("wallet123")
+                .in_token("mint_a", 1_000_000u128)
+                .out_token("mint_a", 1_003_000u128)
+                .route_id(route_id.map(String::from))
+                .build()
+        };
+
+        // Same delta paired twice
+        assert_eq!(swap(None).validate(), Err("in_mint must differ from out_mint"));
+        assert!(swap(None).validate_with(true).is_err());
+
+        // Cyclic arbitrage route: only kept when allowed
+        let arb = swap(Some("test_sig:0"));
+        assert!(arb.validate().is_err());
+        assert!(arb.validate_with(true).is_ok());
+    }
+
+    #[test]
+    fn test_dex_swap_v1_dedup_key() {
+        let swap = |index_in_tx: u16, hop_index: u8| {
+            DexSwapV1Builder::new()
+                .signature("test_sig")
+                .index_in_tx(index_in_tx)
+                .hop_index(hop_index)
+                .trader("wallet123")
+                .in_token("mint_a", 1_000_000u128)
+                .out_token("mint_b", 2_000_000u128)
+                .build()
+        };
+
+        assert_eq!(swap(3, 1).dedup_key(), "test_sig:3:1");
+        // Same swap decoded again: same key
+        assert_eq!(swap(3, 1).dedup_key(), swap(3, 1).dedup_key());
+        assert_ne!(swap(3, 0).dedup_key(), swap(3, 1).dedup_key());
+        assert_ne!(swap(2, 1).dedup_key(), swap(3, 1).dedup_key());
+    }
+
     #[test]
     fn test_builder_pattern() {
         let swap = DexSwapV1Builder::new()
@@ -520,8 +784,8 @@ mod tests {
             .venue("raydium")
             .pool_id(Some("pool_abc".into()))
             .trader("trader123")
-            .in_token("SOL", "1000000000")
-            .out_token("USDC", "50000000")
+            .in_token("SOL", 1_000_000_000u128)
+            .out_token("USDC", 50_000_000u128)
             .route_id(None)
             .explain_enabled(true)
             .with_confidence_reason(ConfidenceReasons::PROGRAM_GATE)
@@ -536,4 +800,46 @@ mod tests {
         assert!(swap.explain.is_some());
         assert!(swap.confidence >= 80);
     }
+
+    #[test]
+    fn test_parser_version_populated_and_round_trips() {
+        let swap = DexSwapV1Builder::new()
+            .signature("sig_parser_version")
+            .in_token("SOL", 1_000_000_000u128)
+            .out_token("USDC", 50_000_000u128)
+            .build();
+        assert_eq!(swap.parser_version, DexSwapV1::PARSER_VERSION);
+        assert!(swap.parser_version > 0);
+
+        let mut json = serde_json::to_value(&swap).unwrap();
+        assert_eq!(json["parser_version"], DexSwapV1::PARSER_VERSION);
+        let back: DexSwapV1 = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(back.parser_version, DexSwapV1::PARSER_VERSION);
+
+        // Events written before the field existed read back as version 0
+        json.as_object_mut().unwrap().remove("parser_version");
+        let legacy: DexSwapV1 = serde_json::from_value(json).unwrap();
+        assert_eq!(legacy.parser_version, 0);
+    }
+
+    #[test]
+    fn test_amounts_are_strings_on_the_wire() {
+        let swap = DexSwapV1Builder::new()
+            .in_token("SOL", 1_000_000_000u128)
+            .out_token("USDC", 340_282_366_920_938_463_463_374_607_431_768_211_455u128)
+            .build();
+        assert_eq!(swap.in_amount.get(), 1_000_000_000);
+        assert_eq!(swap.out_amount, u128::MAX);
+
+        let json = serde_json::to_value(&swap).unwrap();
+        assert_eq!(json["in_amount"], "1000000000");
+        assert_eq!(json["out_amount"], "340282366920938463463374607431768211455");
+        let back: DexSwapV1 = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(back.in_amount, swap.in_amount);
+
+        // One past u128::MAX is a deserialization error, not a zero amount
+        let mut overflow = json;
+        overflow["in_amount"] = "340282366920938463463374607431768211456".into();
+        assert!(serde_json::from_value::<DexSwapV1>(overflow).is_err());
+    }
 }
@@ -1,20 +1,56 @@
 pub mod alt_resolver;
+pub mod amount;
+pub mod chain;
 pub mod dex_swap;
+pub mod liquidity;
+pub mod pairing;
+pub mod retraction;
+pub mod stable_swap;
 pub mod swap;
 pub mod tx_facts;
+pub mod tx_summary;
 
 // Legacy swap event (deprecated, use DexSwapV1)
 pub use swap::SwapEvent;
 
 // ALT resolution utilities
 pub use alt_resolver::{
-    extract_program_ids_from_transaction, pick_main_program, resolve_full_account_keys,
+    apply_lookup_tables, extract_program_ids_from_transaction, parse_lookup_table_addresses,
+    pick_main_program, resolve_full_account_keys, unresolved_lookup_tables,
+};
+
+// Base-unit token amount (decimal string on the wire)
+pub use amount::Amount;
+
+// Per-chain program ID sets
+pub use chain::{
+    ChainPrograms, JITO_TIP_ACCOUNTS, JUPITER_V6_PROGRAM_ID, LIFINITY_V2_PROGRAM_ID,
+    METEORA_DLMM_PROGRAM_ID, ORCA_WHIRLPOOL_PROGRAM_ID, PHOENIX_PROGRAM_ID,
 };
 
 // Gold swap contract (v2)
 pub use dex_swap::{
-    ConfidenceReasons, DexSwapV1, DexSwapV1Builder, RAYDIUM_AMM_V4_PROGRAM_ID, TOKEN_PROGRAM_ID,
+    ConfidenceReasons, DexSwapV1, DexSwapV1Builder, NATIVE_SOL_MINT, RAYDIUM_AMM_V4_AUTHORITY,
+    RAYDIUM_AMM_V4_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID, WSOL_MINT,
 };
 
+// Liquidity event contract (zero legs allowed, unlike swaps)
+pub use liquidity::{LiquidityEventV1, LiquidityKind};
+
+// Trader delta pairing shared by venue detectors
+pub use pairing::{pair_in_out, InOut, PairingStrategy};
+
+// Undo marker for swaps from rolled-back txs
+pub use retraction::SwapRetraction;
+
+// Multi-asset (3+ token) pool interactions; two-asset swaps stay on DexSwapV1
+pub use stable_swap::{MintDelta, StableSwapV1};
+
 // TxFacts layer
-pub use tx_facts::{ParsedInstruction, TokenBalance, TokenBalanceDelta, TxFacts};
+pub use tx_facts::{
+    BalanceLengths, KeyResolutionReport, ParsedInstruction, TokenBalance, TokenBalanceDelta,
+    TxFacts,
+};
+
+// Per-tx monitoring heartbeat
+pub use tx_summary::TxSummaryV1;
@@ -0,0 +1,160 @@
+//! Liquidity Event Contract: `LiquidityEventV1`
+//!
+//! Pool deposits and withdrawals, kept separate from `DexSwapV1` so the swap
+//! invariant (both legs > 0) never has to be relaxed. Single-sided adds and
+//! removes legitimately carry a zero leg; whether those are kept is decided by
+//! the caller through `validate(allow_zero_amount)`.
+
+use serde::{Deserialize, Serialize};
+
+/// Direction of a liquidity event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LiquidityKind {
+    /// Tokens deposited into the pool
+    #[default]
+    Add,
+    /// Tokens withdrawn from the pool
+    Remove,
+}
+
+/// Gold-layer liquidity event (v1 schema).
+///
+/// Invariants:
+/// - Both leg amounts are valid u128 and at least one is > 0
+/// - Zero legs only pass with `allow_zero_amount`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LiquidityEventV1 {
+    /// Schema version for forward compatibility
+    pub schema_version: u16,
+
+    /// Chain identifier (e.g., "solana-mainnet")
+    pub chain: String,
+
+    /// Slot number
+    pub slot: u64,
+
+    /// Block timestamp (Unix seconds)
+    pub block_time: Option<i64>,
+
+    /// Transaction signature
+    pub signature: String,
+
+    /// Index within the transaction (outer instruction)
+    pub index_in_tx: u16,
+
+    /// DEX venue (e.g., "raydium")
+    pub venue: String,
+
+    /// Pool/market address (AMM pool account)
+    pub pool_id: Option<String>,
+
+    /// Wallet that added or removed liquidity
+    pub provider: String,
+
+    /// Add or remove
+    pub kind: LiquidityKind,
+
+    /// First pool token mint
+    pub token_a_mint: String,
+
+    /// First pool token amount in base units (as string to preserve precision)
+    pub token_a_amount: String,
+
+    /// Second pool token mint
+    pub token_b_mint: String,
+
+    /// Second pool token amount in base units (as string to preserve precision)
+    pub token_b_amount: String,
+
+    /// LP token mint (if known)
+    pub lp_mint: Option<String>,
+
+    /// LP tokens minted (add) or burned (remove), in base units
+    pub lp_amount: Option<String>,
+}
+
+impl LiquidityEventV1 {
+    pub const SCHEMA_VERSION: u16 = 1;
+
+    /// Validate invariants. Returns error message if invalid.
+    ///
+    /// With `allow_zero_amount`, single-sided events (one zero leg) are kept;
+    /// an event where nothing moved is always rejected.
+    pub fn validate(&self, allow_zero_amount: bool) -> Result<(), &'static str> {
+        let a: u128 = self
+            .token_a_amount
+            .parse()
+            .map_err(|_| "token_a_amount must be valid u128")?;
+        let b: u128 = self
+            .token_b_amount
+            .parse()
+            .map_err(|_| "token_b_amount must be valid u128")?;
+
+        if a == 0 && b == 0 {
+            return Err("at least one leg must be > 0");
+        }
+        if !allow_zero_amount && (a == 0 || b == 0) {
+            return Err("zero-amount leg (set allow_zero_amount to keep)");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DexSwapV1Builder;
+
+    const SOL: &str = "So11111111111111111111111111111111111111112";
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn single_sided_add() -> LiquidityEventV1 {
+        LiquidityEventV1 {
+            schema_version: LiquidityEventV1::SCHEMA_VERSION,
+            chain: "solana-mainnet".into(),
+            slot: 250000000,
+            signature: "sig_liq".into(),
+            venue: "raydium".into(),
+            pool_id: Some("PoolAccount123".into()),
+            provider: "ProviderWallet111".into(),
+            kind: LiquidityKind::Add,
+            token_a_mint: SOL.into(),
+            token_a_amount: "500000000".into(),
+            token_b_mint: USDC.into(),
+            token_b_amount: "0".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_zero_leg_allowed_for_liquidity_not_swaps() {
+        // Swap path: zero amounts stay a hard reject
+        let swap = DexSwapV1Builder::new()
+            .chain("solana-mainnet")
+            .slot(250000000)
+            .signature("sig_swap")
+            .venue("raydium")
+            .trader("TraderWallet111")
+            .in_token(SOL, 500_000_000u128)
+            .out_token(USDC, 0u128)
+            .build();
+        assert_eq!(swap.validate(), Err("out_amount must be > 0"));
+
+        // Liquidity path: the zero leg passes only when allowed
+        let event = single_sided_add();
+        assert!(event.validate(true).is_ok());
+        assert!(event.validate(false).is_err());
+
+        // Nothing moved: rejected either way
+        let empty = LiquidityEventV1 {
+            token_a_amount: "0".into(),
+            ..single_sided_add()
+        };
+        assert!(empty.validate(true).is_err());
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["kind"], "add");
+    }
+}
@@ -0,0 +1,219 @@
+//! Pairing of a trader's token balance deltas into a swap's input and output legs.
+//!
+//! Shared by the venue detectors so every venue resolves in/out the same way.
+//! The input leg is always a negative delta and the output leg a positive one;
+//! strategies differ in how they choose when there are several candidates.
+
+use crate::tx_facts::TokenBalanceDelta;
+
+/// How to choose between multiple candidate deltas
+#[derive(Debug, Clone, Copy)]
+pub enum PairingStrategy<'a> {
+    /// First negative delta and first positive delta of another mint, in balance order
+    FirstMatch,
+    /// Largest outflow and largest inflow (distinct mints)
+    LargestMagnitude,
+    /// Prefer pairs with exactly one side in a quote mint, then by magnitude
+    QuotePreferred,
+    /// Legs whose mints a pool vault received (in) and sent (out), preferring
+    /// exact amount matches
+    VaultMatched(&'a [&'a TokenBalanceDelta]),
+}
+
+/// Input and output legs of a swap
+#[derive(Debug, Clone, Copy)]
+pub struct InOut<'a> {
+    pub in_delta: &'a TokenBalanceDelta,
+    pub out_delta: &'a TokenBalanceDelta,
+}
+
+pub fn pair_in_out<'a>(
+    deltas: &[&'a TokenBalanceDelta],
+    strategy: PairingStrategy<'_>,
+    quote_mints: &[String],
+) -> Option<InOut<'a>> {
+    let outflows = deltas.iter().copied().filter(|d| d.delta < 0);
+    let inflows = deltas.iter().copied().filter(|d| d.delta > 0);
+
+    let (in_delta, out_delta) = match strategy {
+        PairingStrategy::FirstMatch => {
+            let in_delta = outflows.clone().next()?;
+            let out_delta = inflows.clone().find(|d| d.mint != in_delta.mint)?;
+            (in_delta, out_delta)
+        }
+        PairingStrategy::LargestMagnitude => {
+            let in_delta = outflows.min_by_key(|d| d.delta)?;
+            let out_delta = inflows
+                .filter(|d| d.mint != in_delta.mint)
+                .max_by_key(|d| d.delta)?;
+            (in_delta, out_delta)
+        }
+        PairingStrategy::QuotePreferred => {
+            let is_quote = |d: &TokenBalanceDelta| quote_mints.contains(&d.mint);
+            let mut best: Option<((bool, i128, i128), &TokenBalanceDelta, &TokenBalanceDelta)> =
+                None;
+            for i in outflows {
+                for o in inflows.clone().filter(|o| o.mint != i.mint) {
+                    let score = (is_quote(i) != is_quote(o), -i.delta, o.delta);
+                    if best.as_ref().is_none_or(|(s, _, _)| score > *s) {
+                        best = Some((score, i, o));
+                    }
+                }
+            }
+            let (_, i, o) = best?;
+            (i, o)
+        }
+        PairingStrategy::VaultMatched(vaults) => {
+            let in_delta = pick_vault_matched(outflows, vaults, |v| v.delta > 0)?;
+            let out_delta =
+                pick_vault_matched(inflows.filter(|d| d.mint != in_delta.mint), vaults, |v| {
+                    v.delta < 0
+                })?;
+            (in_delta, out_delta)
+        }
+    };
+
+    Some(InOut {
+        in_delta,
+        out_delta,
+    })
+}
+
+/// First candidate whose mint moved in a vault in the wanted direction,
+/// preferring one whose amount matches the vault's exactly
+fn pick_vault_matched<'a>(
+    candidates: impl Iterator<Item = &'a TokenBalanceDelta>,
+    vaults: &[&TokenBalanceDelta],
+    direction: impl Fn(&TokenBalanceDelta) -> bool,
+) -> Option<&'a TokenBalanceDelta> {
+    let mut fallback = None;
+    for d in candidates {
+        let mut matching = vaults.iter().filter(|v| v.mint == d.mint && direction(v));
+        match matching.clone().next() {
+            None => continue,
+            Some(_) if matching.any(|v| v.delta.unsigned_abs() == d.delta.unsigned_abs()) => {
+                return Some(d);
+            }
+            Some(_) => {
+                fallback.get_or_insert(d);
+            }
+        }
+    }
+    fallback
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOL: &str = "So11111111111111111111111111111111111111112";
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const BONK: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+
+    fn delta(account_index: u32, mint: &str, delta: i128) -> TokenBalanceDelta {
+        TokenBalanceDelta {
+            account_index,
+            mint: mint.to_string(),
+            owner: Some("TraderWallet111".to_string()),
+            pre_amount: 0,
+            post_amount: 0,
+            delta,
+            decimals: None,
+            token_program: None,
+        }
+    }
+
+    fn quotes() -> Vec<String> {
+        vec![SOL.to_string(), USDC.to_string()]
+    }
+
+    #[test]
+    fn test_first_match() {
+        let a = delta(1, BONK, 300);
+        let b = delta(2, USDC, -50);
+        let c = delta(3, SOL, -900);
+        let pair = pair_in_out(&[&a, &b, &c], PairingStrategy::FirstMatch, &[]).unwrap();
+        assert_eq!(pair.in_delta.account_index, 2);
+        assert_eq!(pair.out_delta.account_index, 1);
+
+        // An inflow of the input's own mint (e.g. a refund) is never the output
+        let refund = delta(0, USDC, 10);
+        let pair = pair_in_out(&[&refund, &b, &a], PairingStrategy::FirstMatch, &[]).unwrap();
+        assert_eq!(pair.out_delta.account_index, 1);
+
+        // Needs both an outflow and an inflow
+        assert!(pair_in_out(&[&a], PairingStrategy::FirstMatch, &[]).is_none());
+    }
+
+    #[test]
+    fn test_largest_magnitude() {
+        let dust_in = delta(1, USDC, -5);
+        let big_in = delta(2, SOL, -900);
+        let small_out = delta(3, BONK, 10);
+        let big_out = delta(4, USDC, 500);
+        let pair = pair_in_out(
+            &[&dust_in, &big_in, &small_out, &big_out],
+            PairingStrategy::LargestMagnitude,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(pair.in_delta.mint, SOL);
+        assert_eq!(pair.out_delta.mint, USDC);
+
+        // Inflow of the same mint as the input leg is never the output
+        let refund = delta(5, SOL, 1_000_000);
+        let pair = pair_in_out(
+            &[&big_in, &refund, &small_out],
+            PairingStrategy::LargestMagnitude,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(pair.out_delta.mint, BONK);
+    }
+
+    #[test]
+    fn test_quote_preferred() {
+        // Pays USDC for BONK, but also gets a larger wSOL refund
+        let paid = delta(1, USDC, -1_000_000);
+        let bought = delta(2, BONK, 42);
+        let refund = delta(3, SOL, 2_039_280);
+        let deltas = [&paid, &refund, &bought];
+
+        let pair = pair_in_out(&deltas, PairingStrategy::QuotePreferred, &quotes()).unwrap();
+        assert_eq!(pair.in_delta.mint, USDC);
+        assert_eq!(pair.out_delta.mint, BONK);
+
+        // Largest magnitude picks the quote/quote pair instead
+        let pair = pair_in_out(&deltas, PairingStrategy::LargestMagnitude, &quotes()).unwrap();
+        assert_eq!(pair.out_delta.mint, SOL);
+    }
+
+    #[test]
+    fn test_vault_matched() {
+        let paid = delta(1, USDC, -5_000_000);
+        let decoy_out = delta(2, SOL, 7_777);
+        let bought = delta(3, SOL, 48_123_457);
+        let vault_usdc = delta(10, USDC, 5_000_000);
+        let vault_sol = delta(11, SOL, -48_123_457);
+        let vaults = [&vault_usdc, &vault_sol];
+
+        let pair = pair_in_out(
+            &[&paid, &decoy_out, &bought],
+            PairingStrategy::VaultMatched(&vaults),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(pair.in_delta.account_index, 1);
+        // Exact vault amount beats the earlier same-mint candidate
+        assert_eq!(pair.out_delta.account_index, 3);
+
+        // No vault sent the output mint
+        let only_usdc = [&vault_usdc];
+        assert!(pair_in_out(
+            &[&paid, &bought],
+            PairingStrategy::VaultMatched(&only_usdc),
+            &[]
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,43 @@
+//! Swap Retraction Contract: `SwapRetraction`
+//!
+//! Swaps read at `processed` commitment can belong to a transaction that is
+//! later rolled back with its fork. A retraction tells downstream to undo the
+//! swap identified by `(signature, index_in_tx, hop_index)`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dex_swap::DexSwapV1;
+use crate::swap::SwapEvent;
+
+/// Undo marker for a previously emitted swap
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwapRetraction {
+    /// Transaction signature of the retracted swap
+    pub signature: String,
+
+    /// Index within the transaction (outer instruction)
+    pub index_in_tx: u16,
+
+    /// Hop index for multi-hop routes (0 for single swaps)
+    pub hop_index: u8,
+}
+
+impl From<&DexSwapV1> for SwapRetraction {
+    fn from(swap: &DexSwapV1) -> Self {
+        Self {
+            signature: swap.signature.clone(),
+            index_in_tx: swap.index_in_tx,
+            hop_index: swap.hop_index,
+        }
+    }
+}
+
+impl From<&SwapEvent> for SwapRetraction {
+    fn from(swap: &SwapEvent) -> Self {
+        Self {
+            signature: swap.signature.clone(),
+            index_in_tx: swap.index_in_tx,
+            hop_index: 0,
+        }
+    }
+}
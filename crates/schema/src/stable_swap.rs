@@ -0,0 +1,150 @@
+//! Multi-Asset Pool Contract: `StableSwapV1`
+//!
+//! Interactions with curve/stable pools that hold three or more tokens. One
+//! call can move several of them at once (e.g. pay USDC, receive USDT and
+//! DAI), which the one-in/one-out `DexSwapV1` cannot describe, so every mint
+//! the trader's side moved is listed with its signed delta. Two-asset swaps
+//! stay on `DexSwapV1`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// One token moved by a pool interaction, from the trader's side
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MintDelta {
+    /// Token mint address
+    pub mint: String,
+
+    /// Signed amount in base units (negative = paid into the pool, positive =
+    /// received), as string to preserve precision
+    pub delta: String,
+}
+
+/// Gold-layer multi-asset pool interaction (v1 schema).
+///
+/// Invariants:
+/// - At least two legs, each with a distinct mint and a non-zero i128 delta
+/// - At least one leg paid in and one received
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StableSwapV1 {
+    /// Schema version for forward compatibility
+    pub schema_version: u16,
+
+    /// Chain identifier (e.g., "solana-mainnet")
+    pub chain: String,
+
+    /// Slot number
+    pub slot: u64,
+
+    /// Block timestamp (Unix seconds)
+    pub block_time: Option<i64>,
+
+    /// Transaction signature
+    pub signature: String,
+
+    /// Index of transaction within block
+    pub index_in_block: u32,
+
+    /// Index within the transaction (same encoding as `DexSwapV1::index_in_tx`)
+    pub index_in_tx: u16,
+
+    /// Venue name from `STABLE_POOL_PROGRAMS` (e.g., "saber")
+    pub venue: String,
+
+    /// Owner of the pool's token vaults (pool authority), if they share one
+    pub pool_authority: Option<String>,
+
+    /// Wallet on the other side of the pool
+    pub trader: String,
+
+    /// Every mint moved for the trader, in first-seen order
+    pub deltas: Vec<MintDelta>,
+}
+
+impl StableSwapV1 {
+    pub const SCHEMA_VERSION: u16 = 1;
+
+    /// Validate invariants. Returns error message if invalid.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.deltas.len() < 2 {
+            return Err("at least two legs required");
+        }
+
+        let mut mints = HashSet::new();
+        let (mut paid, mut received) = (false, false);
+        for leg in &self.deltas {
+            if !mints.insert(leg.mint.as_str()) {
+                return Err("duplicate mint in deltas");
+            }
+            let delta: i128 = leg.delta.parse().map_err(|_| "delta must be valid i128")?;
+            match delta.signum() {
+                -1 => paid = true,
+                1 => received = true,
+                _ => return Err("delta must be non-zero"),
+            }
+        }
+
+        if !(paid && received) {
+            return Err("needs a leg paid in and a leg received");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const USDT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+    const PAI: &str = "Ea5SjE2Y6yvCeW5dYTn7PYMuW5ikXkvbGdcmSnXeaLjS";
+
+    fn leg(mint: &str, delta: &str) -> MintDelta {
+        MintDelta {
+            mint: mint.into(),
+            delta: delta.into(),
+        }
+    }
+
+    #[test]
+    fn test_three_asset_interaction_validates() {
+        let event = StableSwapV1 {
+            schema_version: StableSwapV1::SCHEMA_VERSION,
+            chain: "solana-mainnet".into(),
+            slot: 250000000,
+            signature: "sig_stable".into(),
+            venue: "saber".into(),
+            trader: "TraderWallet111".into(),
+            deltas: vec![
+                leg(USDC, "-1000000000"),
+                leg(USDT, "600000000"),
+                leg(PAI, "399000000"),
+            ],
+            ..Default::default()
+        };
+        assert!(event.validate().is_ok());
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["deltas"][0]["mint"], USDC);
+        assert_eq!(json["deltas"][0]["delta"], "-1000000000");
+
+        // Only outflows: nothing was received for them
+        let one_sided = StableSwapV1 {
+            deltas: vec![leg(USDC, "-1"), leg(USDT, "-2"), leg(PAI, "-3")],
+            ..event.clone()
+        };
+        assert!(one_sided.validate().is_err());
+
+        let zero_leg = StableSwapV1 {
+            deltas: vec![leg(USDC, "-1"), leg(USDT, "0"), leg(PAI, "3")],
+            ..event.clone()
+        };
+        assert_eq!(zero_leg.validate(), Err("delta must be non-zero"));
+
+        let duplicate = StableSwapV1 {
+            deltas: vec![leg(USDC, "-1"), leg(USDC, "2")],
+            ..event
+        };
+        assert!(duplicate.validate().is_err());
+    }
+}
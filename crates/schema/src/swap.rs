@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::dex_swap::DexSwapV1;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SwapEvent {
     pub schema_version: u16,
@@ -21,3 +23,34 @@ pub struct SwapEvent {
     pub confidence: u8,
     pub explain: Option<String>,
 }
+
+impl SwapEvent {
+    pub const SCHEMA_VERSION: u16 = 1;
+}
+
+/// Adapter from the gold contract to the legacy event, used while consumers
+/// migrate off `SwapEvent`.
+impl From<&DexSwapV1> for SwapEvent {
+    fn from(swap: &DexSwapV1) -> Self {
+        Self {
+            schema_version: Self::SCHEMA_VERSION,
+            chain: swap.chain.clone(),
+            slot: swap.slot,
+            block_time: swap.block_time,
+            signature: swap.signature.clone(),
+            index_in_tx: swap.index_in_tx,
+            venue: swap.venue.clone(),
+            market_or_pool: swap.pool_id.clone(),
+            trader: swap.trader.clone(),
+            in_mint: swap.in_mint.clone(),
+            in_amount: swap.in_amount.to_string(),
+            out_mint: swap.out_mint.clone(),
+            out_amount: swap.out_amount.to_string(),
+            fee_mint: swap.fee_mint.clone(),
+            fee_amount: swap.fee_amount.clone(),
+            route_id: swap.route_id.clone(),
+            confidence: swap.confidence,
+            explain: swap.explain.clone(),
+        }
+    }
+}
@@ -6,9 +6,11 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 
 use crate::alt_resolver::resolve_full_account_keys;
+use crate::dex_swap::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID};
 
 /// Parsed instruction from a transaction
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,11 +24,23 @@ pub struct ParsedInstruction {
     /// Base58-encoded instruction data (if available)
     pub data: Option<String>,
 
-    /// Index of the outer instruction this belongs to (for inner ix)
+    /// `parsed` object of a jsonParsed instruction (e.g. SPL Token
+    /// `{"type": "transfer", "info": {"source", "destination", "amount", ...}}`)
+    pub parsed: Option<Value>,
+
+    /// Index of the outer instruction (its own index for outer ix, the parent's for inner ix)
     pub outer_ix_index: Option<usize>,
 
-    /// Stack depth (0 = outer, 1+ = inner/CPI)
+    /// Position within the parent's inner instruction group (inner ix only)
+    pub inner_ix_index: Option<usize>,
+
+    /// CPI nesting level: 0 = outer, 1 = invoked by the outer ix, 2+ = invoked
+    /// by another inner ix (RPC `stackHeight` - 1; 1 when the RPC omits it)
     pub stack_depth: u8,
+
+    /// Inner ix (position in the same group) that invoked this one; None for
+    /// outer ixs and for direct CPIs of the outer ix
+    pub parent_inner_ix_index: Option<usize>,
 }
 
 /// Token balance for a specific account
@@ -46,6 +60,10 @@ pub struct TokenBalance {
 
     /// Decimals
     pub decimals: Option<u8>,
+
+    /// Token program owning the account (`programId`: SPL Token or Token-2022);
+    /// None if the RPC response omits it
+    pub token_program: Option<String>,
 }
 
 /// Token balance delta (change between pre and post)
@@ -71,6 +89,9 @@ pub struct TokenBalanceDelta {
 
     /// Decimals
     pub decimals: Option<u8>,
+
+    /// Token program owning the account (see `TokenBalance::token_program`)
+    pub token_program: Option<String>,
 }
 
 /// SOL balance delta
@@ -92,15 +113,84 @@ pub struct SolBalanceDelta {
     pub delta: i64,
 }
 
+/// Lengths of the per-account arrays that SOL deltas are zipped from.
+///
+/// Per spec `preBalances`/`postBalances` align with the full account key list
+/// (static keys + loaded addresses). A mismatch means a malformed response;
+/// `parse_sol_deltas` then only covers the shortest array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceLengths {
+    pub account_keys: usize,
+    pub pre_balances: usize,
+    pub post_balances: usize,
+}
+
+impl BalanceLengths {
+    pub fn from_json(tx: &Value) -> Self {
+        Self::with_account_keys(tx, resolve_full_account_keys(tx).len())
+    }
+
+    /// Same as `from_json`, for callers that already resolved the full key list
+    pub fn with_account_keys(tx: &Value, account_keys: usize) -> Self {
+        let len = |ptr: &str| {
+            tx.pointer(ptr)
+                .and_then(|v| v.as_array())
+                .map(|a| a.len())
+                .unwrap_or(0)
+        };
+        Self {
+            account_keys,
+            pre_balances: len("/meta/preBalances"),
+            post_balances: len("/meta/postBalances"),
+        }
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.pre_balances == self.post_balances && self.pre_balances == self.account_keys
+    }
+}
+
+impl fmt::Display for BalanceLengths {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "accountKeys={} preBalances={} postBalances={}",
+            self.account_keys, self.pre_balances, self.post_balances
+        )
+    }
+}
+
+/// How `TxFacts::full_account_keys` was assembled (`TxFacts::resolution_report`).
+///
+/// For auditing whether a transaction (or fixture) exercises lookup-table
+/// resolution at all.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyResolutionReport {
+    /// `message.accountKeys`
+    pub static_keys: usize,
+    /// `meta.loadedAddresses.writable`, appended after the static keys
+    pub loaded_writable: usize,
+    /// `meta.loadedAddresses.readonly`, appended after the writable ones
+    pub loaded_readonly: usize,
+    /// Some instruction's program index is past the static keys, so the tx
+    /// could not be parsed without its lookup tables
+    pub alt_required: bool,
+    /// (index, program) of every program resolved past the static keys
+    pub loaded_programs: Vec<(usize, String)>,
+}
+
 /// Pre-computed facts about a transaction.
 ///
 /// All fields are computed once from the transaction JSON.
 /// Parsers receive this struct and produce outputs without side effects.
 #[derive(Debug, Clone)]
 pub struct TxFacts {
-    /// Transaction signature
+    /// Transaction signature (primary, used for dedup)
     pub signature: String,
 
+    /// All transaction signatures; the primary `signature` is first
+    pub signatures: Vec<String>,
+
     /// Slot number
     pub slot: u64,
 
@@ -125,6 +215,13 @@ pub struct TxFacts {
     /// Number of static account keys (before loadedAddresses)
     pub static_account_keys_len: usize,
 
+    /// Writable and readonly `loadedAddresses` counts (0 for legacy txs)
+    pub loaded_writable_len: usize,
+    pub loaded_readonly_len: usize,
+
+    /// Signer pubkeys in message order (fee payer first)
+    pub signers: Vec<String>,
+
     /// Outer instructions (top-level)
     pub outer_instructions: Vec<ParsedInstruction>,
 
@@ -148,6 +245,13 @@ pub struct TxFacts {
 
     /// Whether this is a v0 transaction with loaded addresses
     pub has_loaded_addresses: bool,
+
+    /// Balance array lengths disagreed, so `sol_balance_deltas` may miss accounts
+    pub sol_deltas_truncated: bool,
+
+    /// Token balances whose `accountIndex` is past `full_account_keys` (malformed
+    /// response); excluded from the balances and deltas above
+    pub out_of_range_token_balances: usize,
 }
 
 impl TxFacts {
@@ -181,6 +285,28 @@ impl TxFacts {
             .map(|a| a.len())
             .unwrap_or(0);
 
+        let loaded_len = |kind: &str| {
+            tx.pointer(&format!("/meta/loadedAddresses/{kind}"))
+                .and_then(|v| v.as_array())
+                .map(|a| a.len())
+                .unwrap_or(0)
+        };
+        let loaded_writable_len = loaded_len("writable");
+        let loaded_readonly_len = loaded_len("readonly");
+
+        let signers = Self::parse_signers(tx, &full_account_keys);
+
+        // Keep the caller-provided signature as primary, append the rest
+        let mut signatures = vec![signature.to_string()];
+        if let Some(arr) = tx.pointer("/transaction/signatures").and_then(|v| v.as_array()) {
+            signatures.extend(
+                arr.iter()
+                    .filter_map(|v| v.as_str())
+                    .filter(|s| *s != signature)
+                    .map(|s| s.to_string()),
+            );
+        }
+
         let has_loaded_addresses = tx.pointer("/meta/loadedAddresses").is_some();
 
         // Parse outer instructions
@@ -189,9 +315,34 @@ impl TxFacts {
         // Parse all instructions (outer + inner)
         let all_instructions = Self::parse_all_instructions(tx, &full_account_keys);
 
-        // Parse token balances
-        let pre_token_balances = Self::parse_token_balances(tx, "/meta/preTokenBalances");
-        let post_token_balances = Self::parse_token_balances(tx, "/meta/postTokenBalances");
+        // Parse token balances; an index past the key list matches no vault or
+        // instruction account, so such balances are dropped (and counted)
+        let mut pre_token_balances = Self::parse_token_balances(tx, "/meta/preTokenBalances");
+        let mut post_token_balances = Self::parse_token_balances(tx, "/meta/postTokenBalances");
+        let parsed_token_balances = pre_token_balances.len() + post_token_balances.len();
+        let in_range = |b: &TokenBalance| (b.account_index as usize) < full_account_keys.len();
+        pre_token_balances.retain(in_range);
+        post_token_balances.retain(in_range);
+        let out_of_range_token_balances =
+            parsed_token_balances - pre_token_balances.len() - post_token_balances.len();
+
+        // Pre-ATA token accounts can report the token program as `owner`: take the
+        // signer that authorized a transfer out of the account, else leave it unknown.
+        // The balance's own `programId` counts too (non-default Token-2022 deployments)
+        let transfer_authorities = Self::token_transfer_authorities(tx, &full_account_keys);
+        for b in pre_token_balances.iter_mut().chain(post_token_balances.iter_mut()) {
+            let owned_by_program = b
+                .owner
+                .as_deref()
+                .is_some_and(|o| is_token_program(o) || b.token_program.as_deref() == Some(o));
+            if owned_by_program {
+                b.owner = full_account_keys
+                    .get(b.account_index as usize)
+                    .and_then(|account| transfer_authorities.get(account))
+                    .filter(|authority| signers.contains(authority))
+                    .cloned();
+            }
+        }
 
         // Compute token balance deltas
         let token_balance_deltas =
@@ -199,6 +350,8 @@ impl TxFacts {
 
         // Parse SOL balance deltas
         let sol_balance_deltas = Self::parse_sol_deltas(tx, &full_account_keys);
+        let sol_deltas_truncated =
+            !BalanceLengths::with_account_keys(tx, full_account_keys.len()).is_consistent();
 
         // Parse logs
         let logs = tx
@@ -213,6 +366,7 @@ impl TxFacts {
 
         Self {
             signature: signature.to_string(),
+            signatures,
             slot,
             block_time,
             version,
@@ -221,6 +375,9 @@ impl TxFacts {
             compute_units,
             full_account_keys,
             static_account_keys_len,
+            loaded_writable_len,
+            loaded_readonly_len,
+            signers,
             outer_instructions,
             all_instructions,
             pre_token_balances,
@@ -229,9 +386,38 @@ impl TxFacts {
             sol_balance_deltas,
             logs,
             has_loaded_addresses,
+            sol_deltas_truncated,
+            out_of_range_token_balances,
         }
     }
 
+    fn parse_signers(tx: &Value, account_keys: &[String]) -> Vec<String> {
+        // jsonParsed: accountKeys entries carry an explicit signer flag
+        let flagged: Vec<String> = tx
+            .pointer("/transaction/message/accountKeys")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter(|k| k.get("signer").and_then(|s| s.as_bool()) == Some(true))
+                    .filter_map(|k| k.get("pubkey").and_then(|p| p.as_str()))
+                    .map(|s| s.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !flagged.is_empty() {
+            return flagged;
+        }
+
+        // Raw format: the first numRequiredSignatures static keys are signers
+        let n = tx
+            .pointer("/transaction/message/header/numRequiredSignatures")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(1);
+
+        account_keys.iter().take(n).cloned().collect()
+    }
+
     fn parse_outer_instructions(tx: &Value, account_keys: &[String]) -> Vec<ParsedInstruction> {
         let mut out = Vec::new();
 
@@ -241,7 +427,7 @@ impl TxFacts {
         };
 
         for (idx, ix) in instructions.iter().enumerate() {
-            if let Some(parsed) = Self::parse_single_instruction(ix, account_keys, None, 0, idx) {
+            if let Some(parsed) = Self::parse_single_instruction(ix, account_keys, idx, None, 0) {
                 out.push(parsed);
             }
         }
@@ -259,7 +445,7 @@ impl TxFacts {
         };
 
         for (idx, ix) in outer.iter().enumerate() {
-            if let Some(parsed) = Self::parse_single_instruction(ix, account_keys, None, 0, idx) {
+            if let Some(parsed) = Self::parse_single_instruction(ix, account_keys, idx, None, 0) {
                 out.push(parsed);
             }
         }
@@ -280,17 +466,32 @@ impl TxFacts {
                 .cloned()
                 .unwrap_or_default();
 
+            // Innermost open invocation per depth: (depth, inner index)
+            let mut callers: Vec<(u8, usize)> = Vec::new();
             for (inner_idx, ix) in inner_ixs.iter().enumerate() {
-                // Determine stack depth from stackHeight if available
+                // stackHeight counts the outer ix as 1; without it, assume a direct CPI
                 let stack_depth = ix
                     .get("stackHeight")
                     .and_then(|v| v.as_u64())
-                    .map(|h| h as u8)
+                    .map(|h| h.saturating_sub(1).clamp(1, u8::MAX as u64) as u8)
                     .unwrap_or(1);
 
-                if let Some(parsed) =
-                    Self::parse_single_instruction(ix, account_keys, Some(outer_idx), stack_depth, inner_idx)
-                {
+                // Parent: nearest preceding inner ix one level up
+                callers.retain(|&(depth, _)| depth < stack_depth);
+                let parent = callers
+                    .last()
+                    .filter(|&&(depth, _)| depth + 1 == stack_depth)
+                    .map(|&(_, idx)| idx);
+                callers.push((stack_depth, inner_idx));
+
+                if let Some(mut parsed) = Self::parse_single_instruction(
+                    ix,
+                    account_keys,
+                    outer_idx,
+                    Some(inner_idx),
+                    stack_depth,
+                ) {
+                    parsed.parent_inner_ix_index = parent;
                     out.push(parsed);
                 }
             }
@@ -302,9 +503,9 @@ impl TxFacts {
     fn parse_single_instruction(
         ix: &Value,
         account_keys: &[String],
-        outer_ix_index: Option<usize>,
+        outer_ix_index: usize,
+        inner_ix_index: Option<usize>,
         stack_depth: u8,
-        _ix_index: usize,
     ) -> Option<ParsedInstruction> {
         // Get program ID
         let program_id = if let Some(pid) = ix.get("programId").and_then(|v| v.as_str()) {
@@ -328,18 +529,84 @@ impl TxFacts {
             })
             .unwrap_or_default();
 
-        // Get data
+        // Get data (raw), or the decoded form the RPC returns for known programs
         let data = ix.get("data").and_then(|v| v.as_str()).map(|s| s.to_string());
+        let parsed = ix.get("parsed").filter(|v| !v.is_null()).cloned();
 
         Some(ParsedInstruction {
             program_id,
             accounts,
             data,
-            outer_ix_index,
+            parsed,
+            outer_ix_index: Some(outer_ix_index),
+            inner_ix_index,
             stack_depth,
+            parent_inner_ix_index: None,
         })
     }
 
+    /// Token account -> authority of an SPL Token `transfer`/`transferChecked` out
+    /// of it, from outer and inner instructions (raw or jsonParsed)
+    fn token_transfer_authorities(tx: &Value, account_keys: &[String]) -> HashMap<String, String> {
+        let outer = tx
+            .pointer("/transaction/message/instructions")
+            .and_then(|v| v.as_array());
+        let inner = tx
+            .pointer("/meta/innerInstructions")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|group| group.get("instructions")?.as_array())
+            .flatten();
+
+        outer
+            .into_iter()
+            .flatten()
+            .chain(inner)
+            .filter_map(|ix| Self::transfer_source_and_authority(ix, account_keys))
+            .collect()
+    }
+
+    /// `(source, authority)` of a token transfer instruction
+    fn transfer_source_and_authority(
+        ix: &Value,
+        account_keys: &[String],
+    ) -> Option<(String, String)> {
+        // Raw instructions reference accounts by index, jsonParsed ones by pubkey
+        let key = |v: &Value| match v.as_str() {
+            Some(pubkey) => Some(pubkey.to_string()),
+            None => account_keys.get(v.as_u64()? as usize).cloned(),
+        };
+        let program_id = match ix.get("programId") {
+            Some(pid) => key(pid)?,
+            None => key(ix.get("programIdIndex")?)?,
+        };
+        if !is_token_program(&program_id) {
+            return None;
+        }
+
+        if let Some(parsed) = ix.get("parsed") {
+            let kind = parsed.get("type")?.as_str()?;
+            if kind != "transfer" && kind != "transferChecked" {
+                return None;
+            }
+            let info = parsed.get("info")?;
+            let authority = info.get("authority").or_else(|| info.get("multisigAuthority"))?;
+            return Some((key(info.get("source")?)?, key(authority)?));
+        }
+
+        // Raw: tag 3 = Transfer [source, dest, authority],
+        // tag 12 = TransferChecked [source, mint, dest, authority]
+        let data = bs58::decode(ix.get("data")?.as_str()?).into_vec().ok()?;
+        let authority_pos = match data.first()? {
+            3 => 2,
+            12 => 3,
+            _ => return None,
+        };
+        let accounts = ix.get("accounts")?.as_array()?;
+        Some((key(accounts.first()?)?, key(accounts.get(authority_pos)?)?))
+    }
+
     fn parse_token_balances(tx: &Value, path: &str) -> Vec<TokenBalance> {
         let balances = tx.pointer(path).and_then(|v| v.as_array());
 
@@ -359,6 +626,8 @@ impl TxFacts {
                         .pointer("/uiTokenAmount/decimals")
                         .and_then(|v| v.as_u64())
                         .map(|d| d as u8);
+                    let token_program =
+                        b.get("programId").and_then(|v| v.as_str()).map(|s| s.to_string());
 
                     Some(TokenBalance {
                         account_index,
@@ -366,6 +635,7 @@ impl TxFacts {
                         owner,
                         amount,
                         decimals,
+                        token_program,
                     })
                 })
                 .collect(),
@@ -425,6 +695,10 @@ impl TxFacts {
                 .and_then(|b| b.decimals)
                 .or_else(|| pre_bal.and_then(|b| b.decimals));
 
+            let token_program = post_bal
+                .and_then(|b| b.token_program.clone())
+                .or_else(|| pre_bal.and_then(|b| b.token_program.clone()));
+
             deltas.push(TokenBalanceDelta {
                 account_index,
                 mint,
@@ -433,6 +707,7 @@ impl TxFacts {
                 post_amount,
                 delta,
                 decimals,
+                token_program,
             });
         }
 
@@ -486,6 +761,13 @@ impl TxFacts {
             .collect()
     }
 
+    /// Token balance delta of the account at `account_index` (None if it did not change)
+    pub fn token_delta_at(&self, account_index: usize) -> Option<&TokenBalanceDelta> {
+        self.token_balance_deltas
+            .iter()
+            .find(|d| d.account_index as usize == account_index)
+    }
+
     /// Get instructions for a specific program
     pub fn instructions_for_program(&self, program_id: &str) -> Vec<&ParsedInstruction> {
         self.all_instructions
@@ -494,11 +776,33 @@ impl TxFacts {
             .collect()
     }
 
+    /// Inner instructions executed under outer instruction `outer_ix_index`,
+    /// grouped by the instruction that invoked them: `None` holds the outer ix's
+    /// direct CPIs, `Some(i)` those invoked by inner ix `i` (e.g. an aggregator
+    /// route's Raydium swaps under `None`, each swap's token transfers under it).
+    pub fn cpi_children_of(
+        &self,
+        outer_ix_index: usize,
+    ) -> BTreeMap<Option<usize>, Vec<&ParsedInstruction>> {
+        let mut children: BTreeMap<Option<usize>, Vec<&ParsedInstruction>> = BTreeMap::new();
+        for ix in self.all_instructions.iter().filter(|ix| {
+            ix.inner_ix_index.is_some() && ix.outer_ix_index == Some(outer_ix_index)
+        }) {
+            children.entry(ix.parent_inner_ix_index).or_default().push(ix);
+        }
+        children
+    }
+
     /// Get the fee payer (first account key)
     pub fn fee_payer(&self) -> Option<&str> {
         self.full_account_keys.first().map(|s| s.as_str())
     }
 
+    /// Check if an account signed this transaction
+    pub fn is_signer(&self, account: &str) -> bool {
+        self.signers.iter().any(|s| s == account)
+    }
+
     /// Check if a program was invoked in this transaction
     pub fn has_program(&self, program_id: &str) -> bool {
         self.all_instructions.iter().any(|ix| ix.program_id == program_id)
@@ -508,6 +812,91 @@ impl TxFacts {
     pub fn account_at(&self, index: usize) -> Option<&str> {
         self.full_account_keys.get(index).map(|s| s.as_str())
     }
+
+    /// Diagnostic breakdown of the account key resolution (static vs loaded keys,
+    /// programs only reachable through a lookup table)
+    pub fn resolution_report(&self) -> KeyResolutionReport {
+        let mut loaded_programs: Vec<(usize, String)> = self
+            .all_instructions
+            .iter()
+            .filter_map(|ix| {
+                let index = self.full_account_keys.iter().position(|k| *k == ix.program_id)?;
+                (index >= self.static_account_keys_len).then(|| (index, ix.program_id.clone()))
+            })
+            .collect();
+        loaded_programs.sort();
+        loaded_programs.dedup();
+
+        KeyResolutionReport {
+            static_keys: self.static_account_keys_len,
+            loaded_writable: self.loaded_writable_len,
+            loaded_readonly: self.loaded_readonly_len,
+            alt_required: !loaded_programs.is_empty(),
+            loaded_programs,
+        }
+    }
+
+    /// Lamports received by any of `tip_accounts` (e.g. Jito tips)
+    pub fn tip_lamports(&self, tip_accounts: &HashSet<String>) -> u64 {
+        self.sol_balance_deltas
+            .iter()
+            .filter(|d| d.delta > 0 && tip_accounts.contains(&d.account))
+            .map(|d| d.delta as u64)
+            .sum()
+    }
+
+    /// Lamports `account` spent in this tx (negative SOL delta), not counting tips
+    /// paid to `tip_accounts`
+    pub fn sol_cost(&self, account: &str, tip_accounts: &HashSet<String>) -> u64 {
+        let spent = self
+            .sol_balance_deltas
+            .iter()
+            .filter(|d| d.account == account && d.delta < 0)
+            .map(|d| d.delta.unsigned_abs())
+            .sum::<u64>();
+        spent.saturating_sub(self.tip_lamports(tip_accounts))
+    }
+
+    /// `Program log:` lines emitted while `program_id` is the innermost invocation,
+    /// tracked via the `invoke [n]` / `success` / `failed` markers (at most `max_lines`)
+    pub fn logs_for_program(&self, program_id: &str, max_lines: usize) -> Vec<&str> {
+        let mut stack: Vec<&str> = Vec::new();
+        let mut out = Vec::new();
+
+        for line in &self.logs {
+            if out.len() >= max_lines {
+                break;
+            }
+            if line.starts_with("Program log: ") {
+                if stack.last() == Some(&program_id) {
+                    out.push(line.as_str());
+                }
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            if parts.next() != Some("Program") {
+                continue;
+            }
+            let (Some(pid), Some(marker)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            match marker {
+                "invoke" => stack.push(pid),
+                "success" | "failed:" if stack.last() == Some(&pid) => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+
+        out
+    }
+}
+
+/// The mainnet token programs; `TOKEN_2022_PROGRAM_ID` overrides only the
+/// streamer's `main_program` skip list, not this
+fn is_token_program(program_id: &str) -> bool {
+    program_id == TOKEN_PROGRAM_ID || program_id == TOKEN_2022_PROGRAM_ID
 }
 
 #[cfg(test)]
@@ -603,6 +992,30 @@ mod tests {
         assert_eq!(delta.delta, -500000000);
     }
 
+    #[test]
+    fn test_out_of_range_token_balance_excluded() {
+        let mut tx = sample_tx_json();
+        let stray = json!({
+            "accountIndex": 7,
+            "mint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+            "owner": "TraderWallet111",
+            "uiTokenAmount": {"amount": "50000000", "decimals": 6}
+        });
+        tx["meta"]["postTokenBalances"]
+            .as_array_mut()
+            .unwrap()
+            .push(stray);
+
+        let facts = TxFacts::from_json(&tx, "sig123", 250000000);
+        assert_eq!(facts.out_of_range_token_balances, 1);
+        assert_eq!(facts.post_token_balances.len(), 1);
+        assert_eq!(facts.token_balance_deltas.len(), 1);
+        assert_eq!(facts.token_balance_deltas[0].account_index, 1);
+
+        let clean = TxFacts::from_json(&sample_tx_json(), "sig123", 250000000);
+        assert_eq!(clean.out_of_range_token_balances, 0);
+    }
+
     #[test]
     fn test_tx_facts_sol_deltas() {
         let tx = sample_tx_json();
@@ -613,6 +1026,31 @@ mod tests {
         assert_eq!(delta.delta, -5000); // Fee paid
     }
 
+    #[test]
+    fn test_balance_length_mismatch() {
+        let tx = sample_tx_json();
+        assert!(BalanceLengths::from_json(&tx).is_consistent());
+
+        // postBalances one short: flagged, deltas cover only the aligned prefix
+        let mut tx = sample_tx_json();
+        tx["meta"]["preBalances"] = json!([1000000000, 500000000, 7]);
+        tx["meta"]["postBalances"] = json!([999995000]);
+        let lengths = BalanceLengths::from_json(&tx);
+        assert!(!lengths.is_consistent());
+        assert_eq!(
+            lengths.to_string(),
+            "accountKeys=2 preBalances=3 postBalances=1"
+        );
+        let facts = TxFacts::from_json(&tx, "sig123", 250000000);
+        assert_eq!(facts.sol_balance_deltas.len(), 1);
+        assert!(facts.sol_deltas_truncated);
+
+        // v0: balances cover loaded addresses too
+        tx["meta"]["loadedAddresses"] = json!({"writable": ["WritableAddr"], "readonly": []});
+        tx["meta"]["postBalances"] = json!([999995000, 500000000, 7]);
+        assert!(BalanceLengths::from_json(&tx).is_consistent());
+    }
+
     #[test]
     fn test_tx_facts_deltas_for_owner() {
         let tx = sample_tx_json();
@@ -622,6 +1060,162 @@ mod tests {
         assert_eq!(deltas.len(), 1);
     }
 
+    #[test]
+    fn test_token_program_owner_resolved_from_parsed_transfer() {
+        let mut tx = sample_tx_json();
+        for path in ["/meta/preTokenBalances", "/meta/postTokenBalances"] {
+            for b in tx.pointer_mut(path).unwrap().as_array_mut().unwrap() {
+                b["owner"] = json!(TOKEN_PROGRAM_ID);
+            }
+        }
+        let transfer = json!({
+            "program": "spl-token",
+            "programId": TOKEN_PROGRAM_ID,
+            "parsed": {
+                "type": "transfer",
+                "info": {
+                    "source": "TokenAccount111",
+                    "destination": "Vault111",
+                    "authority": "FeePayer111",
+                    "amount": "500000000"
+                }
+            }
+        });
+
+        // No transfer out of the account: the token program is not reported as owner
+        let facts = TxFacts::from_json(&tx, "sig123", 250000000);
+        assert_eq!(facts.token_balance_deltas[0].owner, None);
+
+        // jsonParsed transfer signed by the wallet names the real owner
+        tx["meta"]["innerInstructions"] = json!([{"index": 0, "instructions": [transfer]}]);
+        let facts = TxFacts::from_json(&tx, "sig123", 250000000);
+        assert_eq!(facts.token_balance_deltas[0].owner.as_deref(), Some("FeePayer111"));
+
+        // A balance owned by its own `programId` is treated the same way
+        for path in ["/meta/preTokenBalances", "/meta/postTokenBalances"] {
+            for b in tx.pointer_mut(path).unwrap().as_array_mut().unwrap() {
+                b["owner"] = json!("CustomToken2022111");
+                b["programId"] = json!("CustomToken2022111");
+            }
+        }
+        tx["meta"]["innerInstructions"] = json!([]);
+        let facts = TxFacts::from_json(&tx, "sig123", 250000000);
+        let delta = &facts.token_balance_deltas[0];
+        assert_eq!(delta.owner, None);
+        assert_eq!(delta.token_program.as_deref(), Some("CustomToken2022111"));
+    }
+
+    #[test]
+    fn test_cpi_children_grouped_by_parent() {
+        let mut tx = sample_tx_json();
+        let accounts = tx["transaction"]["message"]["accountKeys"].as_array_mut().unwrap();
+        accounts.extend([json!("JUP6Router111"), json!("RaydiumAmm111"), json!(TOKEN_PROGRAM_ID)]);
+        let (jup, ray, token) = (accounts.len() - 3, accounts.len() - 2, accounts.len() - 1);
+        let ix = |program: usize, height: u64| {
+            json!({"programIdIndex": program, "accounts": [], "data": "", "stackHeight": height})
+        };
+        // Jupiter (outer 1) -> two Raydium swaps, each paying out through Token transfers
+        tx["transaction"]["message"]["instructions"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({"programIdIndex": jup, "accounts": [], "data": ""}));
+        tx["meta"]["innerInstructions"] = json!([{"index": 1, "instructions": [
+            ix(ray, 2), ix(token, 3), ix(token, 3), ix(ray, 2), ix(token, 3)
+        ]}]);
+        let facts = TxFacts::from_json(&tx, "sig123", 250000000);
+
+        let depths: Vec<u8> = facts.all_instructions.iter().map(|ix| ix.stack_depth).collect();
+        assert_eq!(depths, vec![0, 0, 1, 2, 2, 1, 2]);
+
+        let children = facts.cpi_children_of(1);
+        let inner = |parent: Option<usize>| -> Vec<usize> {
+            children[&parent].iter().map(|ix| ix.inner_ix_index.unwrap()).collect()
+        };
+        assert_eq!(children.len(), 3);
+        assert_eq!(inner(None), vec![0, 3]);
+        assert_eq!(inner(Some(0)), vec![1, 2]);
+        assert_eq!(inner(Some(3)), vec![4]);
+        assert!(children[&None].iter().all(|ix| ix.program_id == "RaydiumAmm111"));
+        assert!(facts.cpi_children_of(0).is_empty());
+    }
+
+    #[test]
+    fn test_parsed_instruction_keeps_json_parsed_transfer() {
+        let mut tx = sample_tx_json();
+        tx["meta"]["innerInstructions"] = json!([{"index": 0, "instructions": [{
+            "program": "spl-token",
+            "programId": TOKEN_PROGRAM_ID,
+            "parsed": {
+                "type": "transfer",
+                "info": {
+                    "source": "TokenAccount111",
+                    "destination": "Vault111",
+                    "authority": "FeePayer111",
+                    "amount": "500000000"
+                }
+            },
+            "stackHeight": 2
+        }]}]);
+        let facts = TxFacts::from_json(&tx, "sig123", 250000000);
+
+        let transfer = facts.instructions_for_program(TOKEN_PROGRAM_ID)[0];
+        assert_eq!(transfer.data, None);
+        let parsed = transfer.parsed.as_ref().unwrap();
+        assert_eq!(parsed["type"], "transfer");
+        assert_eq!(parsed["info"]["source"], "TokenAccount111");
+        assert_eq!(parsed["info"]["destination"], "Vault111");
+        assert_eq!(parsed["info"]["amount"], "500000000");
+
+        // Raw instructions carry only base58 data
+        let outer = &facts.outer_instructions[0];
+        assert!(outer.data.is_some());
+        assert!(outer.parsed.is_none());
+    }
+
+    #[test]
+    fn test_tx_facts_multiple_signatures() {
+        let tx = json!({
+            "meta": {"err": null, "fee": 10000},
+            "transaction": {
+                "message": {
+                    "header": {"numRequiredSignatures": 2},
+                    "accountKeys": ["FeePayer111", "CoSigner222"],
+                    "instructions": []
+                },
+                "signatures": ["sigPrimary", "sigSecond"]
+            }
+        });
+
+        let facts = TxFacts::from_json(&tx, "sigPrimary", 1);
+        assert_eq!(facts.signature, "sigPrimary");
+        assert_eq!(facts.signatures, vec!["sigPrimary", "sigSecond"]);
+        assert_eq!(facts.signers, vec!["FeePayer111", "CoSigner222"]);
+    }
+
+    #[test]
+    fn test_tx_facts_signers() {
+        let tx = sample_tx_json();
+        let facts = TxFacts::from_json(&tx, "sig123", 250000000);
+        assert_eq!(facts.signers, vec!["FeePayer111".to_string()]);
+
+        let parsed = json!({
+            "transaction": {
+                "message": {
+                    "accountKeys": [
+                        {"pubkey": "FeePayer111", "signer": true, "writable": true},
+                        {"pubkey": "CoSigner222", "signer": true, "writable": false},
+                        {"pubkey": "Account333", "signer": false, "writable": true}
+                    ],
+                    "instructions": []
+                }
+            }
+        });
+        let facts = TxFacts::from_json(&parsed, "sig_parsed", 1);
+        assert_eq!(facts.signers, vec!["FeePayer111", "CoSigner222"]);
+        assert!(facts.is_signer("CoSigner222"));
+        assert!(!facts.is_signer("Account333"));
+    }
+
     #[test]
     fn test_tx_facts_v0_with_alt() {
         let tx = json!({
@@ -668,5 +1262,60 @@ mod tests {
         assert_eq!(facts.full_account_keys[1], "Account2");
         assert_eq!(facts.full_account_keys[2], "WritableAddr");
         assert_eq!(facts.full_account_keys[3], "ReadonlyAddr");
+
+        // The only instruction's program came from the readonly loaded addresses
+        let report = facts.resolution_report();
+        assert_eq!(
+            (report.static_keys, report.loaded_writable, report.loaded_readonly),
+            (2, 1, 1)
+        );
+        assert!(report.alt_required);
+        assert_eq!(report.loaded_programs, vec![(3, "ReadonlyAddr".to_string())]);
+
+        let legacy = TxFacts::from_json(&sample_tx_json(), "sig123", 250000000).resolution_report();
+        assert!(!legacy.alt_required);
+        assert_eq!((legacy.loaded_writable, legacy.loaded_readonly), (0, 0));
+    }
+
+    #[test]
+    fn test_logs_for_program_follows_invocation_markers() {
+        let amm = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+        let token = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+        let tx = json!({
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "logMessages": [
+                    "Program ComputeBudget111111111111111111111111111111 invoke [1]",
+                    "Program ComputeBudget111111111111111111111111111111 success",
+                    format!("Program {amm} invoke [1]"),
+                    "Program log: ray_log: AwDh9QUAAAAA",
+                    format!("Program {token} invoke [2]"),
+                    "Program log: Instruction: Transfer",
+                    format!("Program {token} consumed 4645 of 180000 compute units"),
+                    format!("Program {token} success"),
+                    "Program log: swap done",
+                    format!("Program {amm} consumed 31000 of 200000 compute units"),
+                    format!("Program {amm} success"),
+                    "Program log: outside any venue"
+                ]
+            },
+            "transaction": {"message": {"accountKeys": ["FeePayer"], "instructions": []}}
+        });
+        let facts = TxFacts::from_json(&tx, "sig_logs", 250000000);
+
+        // The token program's nested log is excluded
+        assert_eq!(
+            facts.logs_for_program(amm, 10),
+            vec!["Program log: ray_log: AwDh9QUAAAAA", "Program log: swap done"]
+        );
+        assert_eq!(
+            facts.logs_for_program(token, 10),
+            vec!["Program log: Instruction: Transfer"]
+        );
+
+        // Truncated to max_lines
+        assert_eq!(facts.logs_for_program(amm, 1).len(), 1);
+        assert!(facts.logs_for_program(amm, 0).is_empty());
     }
 }
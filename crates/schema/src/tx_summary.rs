@@ -0,0 +1,123 @@
+//! Tx Summary Contract: `TxSummaryV1`
+//!
+//! One compact event per processed transaction, for monitoring without the
+//! full delta streams.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::tx_facts::TxFacts;
+
+/// Per-transaction heartbeat (v1 schema)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxSummaryV1 {
+    /// Schema version for forward compatibility
+    pub schema_version: u16,
+
+    /// Transaction signature
+    pub signature: String,
+
+    /// Slot number
+    pub slot: u64,
+
+    /// Block timestamp (Unix seconds)
+    pub block_time: Option<i64>,
+
+    /// Whether the transaction succeeded
+    pub is_success: bool,
+
+    /// Fee paid (lamports)
+    pub fee_lamports: u64,
+
+    /// Compute units consumed
+    pub compute_units: Option<u64>,
+
+    /// Distinct venues among the detected swaps
+    pub venue_count: u32,
+
+    /// Swaps detected in this transaction (before emission filters)
+    pub swap_count: u32,
+}
+
+impl TxSummaryV1 {
+    pub const SCHEMA_VERSION: u16 = 1;
+
+    /// Summarize a transaction from its facts and the venues of the swaps detected in it
+    pub fn from_facts<'a>(facts: &TxFacts, swap_venues: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut venues = HashSet::new();
+        let mut swap_count = 0u32;
+        for venue in swap_venues {
+            venues.insert(venue);
+            swap_count += 1;
+        }
+
+        Self {
+            schema_version: Self::SCHEMA_VERSION,
+            signature: facts.signature.clone(),
+            slot: facts.slot,
+            block_time: facts.block_time,
+            is_success: facts.is_success,
+            fee_lamports: facts.fee,
+            compute_units: facts.compute_units,
+            venue_count: venues.len() as u32,
+            swap_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DexSwapV1Builder;
+    use serde_json::json;
+
+    #[test]
+    fn test_summary_from_facts_and_detections() {
+        let tx = json!({
+            "blockTime": 1703001234,
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "computeUnitsConsumed": 64000,
+                "preBalances": [1000000000],
+                "postBalances": [999995000]
+            },
+            "transaction": {
+                "message": {"accountKeys": ["FeePayer111"], "instructions": []},
+                "signatures": ["sig_summary"]
+            }
+        });
+        let facts = TxFacts::from_json(&tx, "sig_summary", 250000000);
+
+        let swap = |venue: &str, hop: u8| {
+            DexSwapV1Builder::new()
+                .signature("sig_summary")
+                .hop_index(hop)
+                .venue(venue)
+                .in_token("MintA", 1u128)
+                .out_token("MintB", 2u128)
+                .build()
+        };
+        let swaps = [swap("raydium", 0), swap("raydium", 1), swap("phoenix", 2)];
+
+        let summary = TxSummaryV1::from_facts(&facts, swaps.iter().map(|s| s.venue.as_str()));
+        assert_eq!(
+            summary,
+            TxSummaryV1 {
+                schema_version: 1,
+                signature: "sig_summary".into(),
+                slot: 250000000,
+                block_time: Some(1703001234),
+                is_success: true,
+                fee_lamports: 5000,
+                compute_units: Some(64000),
+                venue_count: 2,
+                swap_count: 3,
+            }
+        );
+
+        // No detections: still a heartbeat
+        let empty = TxSummaryV1::from_facts(&facts, []);
+        assert_eq!((empty.venue_count, empty.swap_count), (0, 0));
+    }
+}